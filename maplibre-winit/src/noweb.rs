@@ -145,9 +145,16 @@ pub fn run_headed_map<P>(
                 Box::new(maplibre::sdf::SdfPlugin::<
                     maplibre::vector::DefaultVectorTransferables,
                 >::default()),
-                // Box::new(maplibre::raster::RasterPlugin::<
-                //     maplibre::raster::DefaultRasterTransferables,
-                // >::default()),
+                Box::new(maplibre::circle::CirclePlugin),
+                Box::new(maplibre::fill_extrusion::FillExtrusionPlugin),
+                Box::new(maplibre::heatmap::HeatmapPlugin),
+                // Fetches the elevations of the hillshade, color-relief and terrain plugins
+                Box::new(maplibre::raster::RasterPlugin::<
+                    maplibre::raster::DefaultRasterTransferables,
+                >::default()),
+                Box::new(maplibre::hillshade::HillshadePlugin),
+                Box::new(maplibre::color_relief::ColorReliefPlugin),
+                Box::new(maplibre::terrain::TerrainPlugin),
                 #[cfg(debug_assertions)]
                 Box::new(maplibre::debug::DebugPlugin::default()),
             ],
@@ -3,7 +3,10 @@ use wgpu::util::DeviceExt;
 use crate::{
     context::MapContext,
     render::{
+        eventually::{Eventually, Eventually::Initialized},
+        image_atlas::{ImageAtlas, ImagePosition},
         render_phase::{DrawState, LayerItem, RenderPhase},
+        resource::ImageAtlasTexture,
         shaders::BackgroundLayerMetadata,
     },
     style::layer::LayerPaint,
@@ -12,6 +15,8 @@ use crate::{
 
 pub struct BackgroundBuffers {
     pub metadata_buffer: wgpu::Buffer,
+    /// The ids of the style layers, in the order of their metadata in the buffer.
+    pub layers: Vec<String>,
 }
 
 use super::render_commands::DrawBackground;
@@ -20,30 +25,66 @@ pub fn queue_system(
     MapContext {
         world,
         style,
+        view_state,
         renderer,
         ..
     }: &mut MapContext,
 ) -> SystemResult {
-    let Some(mut layer_item_phase) = world.resources.get_mut::<RenderPhase<LayerItem>>() else {
+    let Some((layer_item_phase, Initialized(image_atlas_texture), image_atlas)) =
+        world.resources.query_mut::<(
+            &mut RenderPhase<LayerItem>,
+            &mut Eventually<ImageAtlasTexture>,
+            &mut ImageAtlas,
+        )>()
+    else {
         return Err(SystemError::Dependencies);
     };
 
     let mut metadatas = Vec::new();
+    let mut layers = Vec::new();
+    let zoom = view_state.zoom().level();
+    // Patterns are drawn at their display size at integer zoom levels and scaled in between
+    let pattern_scale = 2f64.powf((zoom - zoom.floor()) as f64);
+    // The top left corner of the viewport in world coordinates
+    let camera = view_state.camera().position();
+    let origin = [
+        camera.x - view_state.width() / 2.0,
+        camera.y - view_state.height() / 2.0,
+    ];
 
     // Note: Background layer is uniquely not tied to any tiles.
     // We just iterate through the style layers and issue a single quad draw for each background layer.
     for layer in &style.layers {
-        if layer.type_ == "background" {
+        if layer.type_ == "background" && layer.is_visible_at(zoom) {
             let c: [f32; 4] = match &layer.paint {
-                Some(paint @ LayerPaint::Background(_)) => paint
-                    .get_color()
-                    .map(|c| c.into())
+                Some(LayerPaint::Background(paint)) => paint
+                    .background_color
+                    .as_ref()
+                    .and_then(|color| color.evaluate_at_zoom(zoom))
+                    .map(|c| [c.r as f32, c.g as f32, c.b as f32, c.a as f32])
                     .unwrap_or([0.0, 0.0, 0.0, 1.0]),
                 _ => [0.0, 0.0, 0.0, 1.0],
             };
             let color = [c[0] as f32, c[1] as f32, c[2] as f32, c[3] as f32];
             let z_index = layer.index as f32;
-            metadatas.push(BackgroundLayerMetadata { color, z_index });
+            let pattern = match &layer.paint {
+                Some(LayerPaint::Background(paint)) => paint
+                    .background_pattern
+                    .as_ref()
+                    .and_then(|pattern| pattern.evaluate_at_zoom(zoom))
+                    .and_then(|id| image_atlas.get_pattern(&id)),
+                _ => None,
+            };
+            metadatas.push(BackgroundLayerMetadata {
+                color,
+                z_index,
+                pattern: pattern.map(|pattern| pattern.rect()).unwrap_or_default(),
+                pattern_placement: pattern
+                    .map(|pattern| pattern_placement(&pattern, origin, pattern_scale))
+                    .unwrap_or_default(),
+                pattern_scale: pattern_scale as f32,
+            });
+            layers.push(layer.id.clone());
 
             layer_item_phase.add(LayerItem {
                 draw_function: Box::new(DrawState::<LayerItem, DrawBackground>::new())
@@ -61,6 +102,10 @@ pub fn queue_system(
         }
     }
 
+    if let Some(data) = image_atlas.take_changes() {
+        image_atlas_texture.upload(&renderer.queue, data);
+    }
+
     if !metadatas.is_empty() {
         let buffer = renderer
             .device
@@ -71,8 +116,22 @@ pub fn queue_system(
             });
         world.resources.insert(BackgroundBuffers {
             metadata_buffer: buffer,
+            layers,
         });
     }
 
     Ok(())
 }
+
+/// Returns the display size of the `pattern` and the offset of the top left corner of the
+/// viewport at `origin` within the pattern.
+fn pattern_placement(pattern: &ImagePosition, origin: [f64; 2], scale: f64) -> [f32; 4] {
+    let [width, height] = pattern.display_size;
+    let offset = |origin: f64, size: f32| (origin / scale).rem_euclid(size as f64) as f32;
+    [
+        width,
+        height,
+        offset(origin[0], width),
+        offset(origin[1], height),
+    ]
+}
@@ -1,9 +1,9 @@
 use crate::{
-    background::resource_system::BackgroundRenderPipeline,
+    background::{queue_system::BackgroundBuffers, resource_system::BackgroundRenderPipeline},
     render::{
         eventually::Eventually::{self, Initialized},
-        render_phase::{PhaseItem, RenderCommand, RenderCommandResult},
-        resource::TrackedRenderPass,
+        render_phase::{LayerItem, PhaseItem, RenderCommand, RenderCommandResult},
+        resource::{ImageAtlasTexture, TrackedRenderPass},
     },
     tcs::world::World,
 };
@@ -15,44 +15,53 @@ impl<P: PhaseItem> RenderCommand<P> for SetBackgroundPipeline {
         _item: &P,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        let Some(Initialized(BackgroundRenderPipeline(pipeline))) = world
-            .resources
-            .get::<Eventually<BackgroundRenderPipeline>>()
+        let Some((
+            Initialized(BackgroundRenderPipeline(pipeline)),
+            Initialized(image_atlas_texture),
+        )) = world.resources.query::<(
+            &Eventually<BackgroundRenderPipeline>,
+            &Eventually<ImageAtlasTexture>,
+        )>()
         else {
             return RenderCommandResult::Failure;
         };
 
         pass.set_render_pipeline(pipeline);
+        pass.set_bind_group(0, &image_atlas_texture.bind_group, &[]);
         RenderCommandResult::Success
     }
 }
 
 pub struct DrawBackgroundQuad;
-impl<P: PhaseItem> RenderCommand<P> for DrawBackgroundQuad {
+impl RenderCommand<LayerItem> for DrawBackgroundQuad {
     fn render<'w>(
         world: &'w World,
-        item: &P,
+        item: &LayerItem,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        if let Some(buf) = world
-            .resources
-            .get::<crate::background::queue_system::BackgroundBuffers>()
-        {
-            pass.set_vertex_buffer(0, buf.metadata_buffer.slice(..));
+        let Some(buffers) = world.resources.get::<BackgroundBuffers>() else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(instance) = buffers
+            .layers
+            .iter()
+            .position(|layer| *layer == item.style_layer)
+        else {
+            return RenderCommandResult::Failure;
+        };
 
-            // Simplified drawing for now, assuming a single background layer or that the first one is sufficient for the test.
-            pass.draw(0..6, 0..1);
-            return RenderCommandResult::Success;
-        }
-        RenderCommandResult::Failure
+        pass.set_vertex_buffer(0, buffers.metadata_buffer.slice(..));
+        let instance = instance as u32;
+        pass.draw(0..6, instance..instance + 1);
+        RenderCommandResult::Success
     }
 }
 
 pub struct DrawBackground;
-impl<P: PhaseItem> RenderCommand<P> for DrawBackground {
+impl RenderCommand<LayerItem> for DrawBackground {
     fn render<'w>(
         world: &'w World,
-        item: &P,
+        item: &LayerItem,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
         let mut result = SetBackgroundPipeline::render(world, item, pass);
@@ -42,7 +42,7 @@ pub fn resource_system(
             true,  // debug stencil (Always pass stencil)
             false, // wireframe
             surface.is_multisampling_supported(settings.msaa), // multisampling
-            false, // raster
+            true,  // The image atlas is bound like a raster texture
             false, // glyph
         )
         .describe_render_pipeline()
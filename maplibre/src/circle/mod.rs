@@ -0,0 +1,48 @@
+//! Draws the point features of `circle` style layers as screen-space circles.
+//!
+//! The features are fetched and tessellated by the [`VectorPlugin`](crate::vector::VectorPlugin),
+//! which therefore needs to be added as well.
+
+use std::rc::Rc;
+
+use crate::{
+    circle::{
+        queue_system::queue_system, resource_system::resource_system, upload_system::upload_system,
+    },
+    environment::Environment,
+    kernel::Kernel,
+    plugin::Plugin,
+    render::{eventually::Eventually, graph::RenderGraph, RenderStageLabel},
+    schedule::Schedule,
+    tcs::world::World,
+};
+
+mod queue_system;
+mod render_commands;
+mod resource;
+mod resource_system;
+pub mod tessellation;
+mod upload_system;
+
+pub use resource::{CircleBuffers, CircleResources};
+
+#[derive(Default)]
+pub struct CirclePlugin;
+
+impl<E: Environment> Plugin<E> for CirclePlugin {
+    fn build(
+        &self,
+        schedule: &mut Schedule,
+        _kernel: Rc<Kernel<E>>,
+        world: &mut World,
+        _graph: &mut RenderGraph,
+    ) {
+        world
+            .resources
+            .insert(Eventually::<CircleResources>::Uninitialized);
+
+        schedule.add_system_to_stage(RenderStageLabel::Prepare, resource_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, upload_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, queue_system);
+    }
+}
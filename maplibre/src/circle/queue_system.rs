@@ -0,0 +1,64 @@
+//! Queues [PhaseItems](crate::render::render_phase::PhaseItem) for rendering.
+use crate::{
+    circle::{render_commands::DrawCircles, resource::CircleResources},
+    context::MapContext,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{DrawState, LayerItem, RenderPhase},
+        tile_view_pattern::WgpuTileViewPattern,
+    },
+    style::layer::LayerPaint,
+    tcs::{
+        system::{SystemError, SystemResult},
+        tiles::Tile,
+    },
+};
+
+pub fn queue_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some((Initialized(tile_view_pattern), Initialized(circle_resources), layer_item_phase)) =
+        world.resources.query_mut::<(
+            &mut Eventually<WgpuTileViewPattern>,
+            &mut Eventually<CircleResources>,
+            &mut RenderPhase<LayerItem>,
+        )>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+
+    for view_tile in tile_view_pattern.iter() {
+        view_tile.render(|source_shape| {
+            let coords = source_shape.coords();
+
+            for style_layer in &style.layers {
+                if !matches!(style_layer.paint, Some(LayerPaint::Circle(_)))
+                    || !style_layer.is_visible_at(zoom)
+                    || circle_resources
+                        .get_buffers(&coords, &style_layer.id)
+                        .is_none()
+                {
+                    continue;
+                }
+
+                layer_item_phase.add(LayerItem {
+                    draw_function: Box::new(DrawState::<LayerItem, DrawCircles>::new()),
+                    index: style_layer.index,
+                    is_line: false,
+                    style_layer: style_layer.id.clone(),
+                    tile: Tile { coords },
+                    source_shape: source_shape.clone(),
+                });
+            }
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Specifies the instructions which are going to be sent to the GPU. Render commands can be concatenated
+//! into a new render command which executes multiple instruction sets.
+use crate::{
+    circle::resource::CircleResources,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{LayerItem, PhaseItem, RenderCommand, RenderCommandResult},
+        resource::TrackedRenderPass,
+        tile_view_pattern::WgpuTileViewPattern,
+        INDEX_FORMAT,
+    },
+    tcs::world::World,
+};
+
+pub struct SetCirclePipeline;
+impl<P: PhaseItem> RenderCommand<P> for SetCirclePipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &P,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(circle_resources)) =
+            world.resources.get::<Eventually<CircleResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_render_pipeline(circle_resources.pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawCircle;
+impl RenderCommand<LayerItem> for DrawCircle {
+    fn render<'w>(
+        world: &'w World,
+        item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some((Initialized(circle_resources), Initialized(tile_view_pattern))) =
+            world.resources.query::<(
+                &Eventually<CircleResources>,
+                &Eventually<WgpuTileViewPattern>,
+            )>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let Some(buffers) = circle_resources.get_buffers(&item.tile.coords, &item.style_layer)
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let source_shape = &item.source_shape;
+
+        // Uses stencil value of requested tile and the shape of the requested tile
+        let reference = source_shape.coords().stencil_reference_value_3d() as u32;
+        pass.set_stencil_reference(reference);
+
+        pass.set_index_buffer(buffers.indices.slice(..), INDEX_FORMAT);
+        pass.set_vertex_buffer(0, buffers.vertices.slice(..));
+        let tile_view_pattern_buffer = source_shape
+            .buffer_range()
+            .expect("tile_view_pattern needs to be uploaded first"); // FIXME tcs
+        pass.set_vertex_buffer(
+            1,
+            tile_view_pattern.buffer().slice(tile_view_pattern_buffer),
+        );
+        pass.set_vertex_buffer(2, buffers.layer_metadata.slice(..));
+        pass.draw_indexed(0..buffers.num_indices, 0, 0..1);
+
+        RenderCommandResult::Success
+    }
+}
+
+pub type DrawCircles = (SetCirclePipeline, DrawCircle);
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use crate::coords::WorldTileCoords;
+
+/// The GPU buffers of the circles of a style layer within a tile.
+pub struct CircleBuffers {
+    pub vertices: wgpu::Buffer,
+    pub indices: wgpu::Buffer,
+    pub layer_metadata: wgpu::Buffer,
+    pub num_indices: u32,
+    /// The zoom level at which the paint properties were evaluated. `None` if none of them
+    /// depends on the zoom level.
+    pub evaluated_zoom: Option<f32>,
+}
+
+/// Holds the resources necessary for drawing circles such as the
+/// * pipeline
+/// * buffers of each tile
+pub struct CircleResources {
+    pipeline: wgpu::RenderPipeline,
+    /// The buffers of each tile, keyed by the id of the style layer.
+    buffers: HashMap<WorldTileCoords, HashMap<String, CircleBuffers>>,
+}
+
+impl CircleResources {
+    pub fn new(pipeline: wgpu::RenderPipeline) -> Self {
+        Self {
+            pipeline,
+            buffers: Default::default(),
+        }
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn get_buffers(
+        &self,
+        coords: &WorldTileCoords,
+        style_layer_id: &str,
+    ) -> Option<&CircleBuffers> {
+        self.buffers.get(coords)?.get(style_layer_id)
+    }
+
+    pub fn insert_buffers(
+        &mut self,
+        coords: WorldTileCoords,
+        style_layer_id: &str,
+        buffers: CircleBuffers,
+    ) {
+        self.buffers
+            .entry(coords)
+            .or_default()
+            .insert(style_layer_id.to_string(), buffers);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+    }
+}
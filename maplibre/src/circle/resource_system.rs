@@ -0,0 +1,59 @@
+//! Prepares GPU-owned resources by initializing them if they are uninitialized or out-of-date.
+use crate::{
+    circle::resource::CircleResources,
+    context::MapContext,
+    render::{
+        eventually::Eventually,
+        resource::{RenderPipeline, TilePipeline},
+        shaders,
+        shaders::Shader,
+        RenderResources, Renderer,
+    },
+    tcs::system::{SystemError, SystemResult},
+};
+
+pub fn resource_system(
+    MapContext {
+        world,
+        renderer:
+            Renderer {
+                device,
+                resources: RenderResources { surface, .. },
+                settings,
+                ..
+            },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(circle_resources) = world
+        .resources
+        .query_mut::<&mut Eventually<CircleResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    circle_resources.initialize(|| {
+        let shader = shaders::CircleShader {
+            format: surface.surface_format(),
+        };
+
+        CircleResources::new(
+            TilePipeline::new(
+                "circle_pipeline".into(),
+                *settings,
+                shader.describe_vertex(),
+                shader.describe_fragment(),
+                true,
+                false,
+                false,
+                false,
+                surface.is_multisampling_supported(settings.msaa),
+                false,
+                false,
+            )
+            .describe_render_pipeline()
+            .initialize(device),
+        )
+    });
+    Ok(())
+}
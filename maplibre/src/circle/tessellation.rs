@@ -0,0 +1,179 @@
+//! Collects the points of features which are drawn as circles.
+
+use geozero::{ColumnValue, FeatureProcessor, GeomProcessor, PropertyProcessor};
+use lyon::tessellation::VertexBuffers;
+
+use crate::{
+    coords::EXTENT,
+    render::ShaderVertex,
+    style::{
+        expression::{EvaluationContext, FeatureProperties, Value},
+        layer::{CirclePaint, HeatmapPaint, StyleProperty},
+    },
+    vector::tessellation::IndexDataType,
+};
+
+type GeoResult<T> = geozero::error::Result<T>;
+
+struct CircleFeature {
+    sort_key: f32,
+    centers: Vec<ShaderVertex>,
+    properties: FeatureProperties,
+}
+
+/// Emits a vertex for every point of the processed geometries, such that each of them can be
+/// drawn as a circle. Like in maplibre-gl, every vertex of lines and polygons is a circle, too.
+///
+/// Points which are outside of the tile are skipped, because they are drawn by the neighbouring
+/// tile.
+pub struct CircleTessellator {
+    sort_key: Option<StyleProperty<f32>>,
+    /// Whether the properties of the features are needed to evaluate the paint properties.
+    retain_properties: bool,
+    zoom: f64,
+
+    features: Vec<CircleFeature>,
+    current_centers: Vec<ShaderVertex>,
+    current_properties: FeatureProperties,
+}
+
+impl CircleTessellator {
+    pub fn new(paint: &CirclePaint, zoom: f64) -> Self {
+        Self {
+            sort_key: paint.circle_sort_key.clone(),
+            retain_properties: !paint.is_feature_constant(),
+            zoom,
+            features: Vec::new(),
+            current_centers: Vec::new(),
+            current_properties: FeatureProperties::new(),
+        }
+    }
+
+    /// Collects the centers of the kernels of a heatmap, which have no sort key.
+    pub fn for_heatmap(paint: &HeatmapPaint, zoom: f64) -> Self {
+        Self {
+            sort_key: None,
+            retain_properties: !paint.is_feature_constant(),
+            zoom,
+            features: Vec::new(),
+            current_centers: Vec::new(),
+            current_properties: FeatureProperties::new(),
+        }
+    }
+
+    /// Returns the centers of the circles ordered by their `circle-sort-key`, the count of
+    /// circles for each feature and the properties of each feature. The properties are only
+    /// retained if some paint property depends on them.
+    pub fn finish(
+        mut self,
+    ) -> (
+        VertexBuffers<ShaderVertex, IndexDataType>,
+        Vec<u32>,
+        Vec<FeatureProperties>,
+    ) {
+        // Bare GeoJSON geometries are not wrapped in a feature
+        if !self.current_centers.is_empty() {
+            let _ = self.feature_end(0);
+        }
+
+        // The sort is stable, so circles with the same key keep the order of the source
+        self.features
+            .sort_by(|a, b| a.sort_key.total_cmp(&b.sort_key));
+
+        let mut buffer = VertexBuffers::new();
+        let mut feature_indices = Vec::with_capacity(self.features.len());
+        let mut feature_properties = Vec::new();
+        for feature in self.features {
+            feature_indices.push(feature.centers.len() as u32);
+            for center in feature.centers {
+                buffer.indices.push(buffer.vertices.len() as IndexDataType);
+                buffer.vertices.push(center);
+            }
+            if self.retain_properties {
+                feature_properties.push(feature.properties);
+            }
+        }
+        (buffer, feature_indices, feature_properties)
+    }
+}
+
+impl GeomProcessor for CircleTessellator {
+    fn xy(&mut self, x: f64, y: f64, _idx: usize) -> GeoResult<()> {
+        if (0.0..EXTENT).contains(&x) && (0.0..EXTENT).contains(&y) {
+            self.current_centers
+                .push(ShaderVertex::new([x as f32, y as f32], [0.0, 0.0]));
+        }
+        Ok(())
+    }
+}
+
+impl PropertyProcessor for CircleTessellator {
+    fn property(&mut self, _idx: usize, name: &str, value: &ColumnValue) -> GeoResult<bool> {
+        self.current_properties
+            .insert(name.to_string(), Value::from(value));
+        Ok(false)
+    }
+}
+
+impl FeatureProcessor for CircleTessellator {
+    fn feature_end(&mut self, _idx: u64) -> GeoResult<()> {
+        let properties = std::mem::take(&mut self.current_properties);
+        let sort_key = self
+            .sort_key
+            .as_ref()
+            .and_then(|sort_key| {
+                sort_key.evaluate(
+                    &EvaluationContext::default()
+                        .with_zoom(self.zoom)
+                        .with_properties(&properties),
+                )
+            })
+            .unwrap_or(0.0);
+
+        self.features.push(CircleFeature {
+            sort_key,
+            centers: std::mem::take(&mut self.current_centers),
+            properties,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geozero::GeozeroDatasource;
+
+    use super::*;
+
+    #[test]
+    fn test_sort_key_orders_circles() {
+        let paint: CirclePaint = serde_json::from_value(serde_json::json!({
+            "circle-color": ["get", "color"],
+            "circle-sort-key": ["get", "key"]
+        }))
+        .unwrap();
+        let mut tessellator = CircleTessellator::new(&paint, 0.0);
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"key": 2, "color": "red"},
+             "geometry": {"type": "Point", "coordinates": [10, 10]}},
+            {"type": "Feature", "properties": {"key": 1, "color": "blue"},
+             "geometry": {"type": "MultiPoint", "coordinates": [[20, 20], [-5, 20]]}}
+        ]}"#;
+        geozero::geojson::GeoJson(geojson)
+            .process(&mut tessellator)
+            .unwrap();
+
+        let (buffer, feature_indices, feature_properties) = tessellator.finish();
+
+        // The point outside of the tile is skipped
+        assert_eq!(feature_indices, vec![1, 1]);
+        assert_eq!(buffer.indices, vec![0, 1]);
+        assert_eq!(buffer.vertices[0].position, [20.0, 20.0]);
+        assert_eq!(buffer.vertices[1].position, [10.0, 10.0]);
+        assert_eq!(
+            feature_properties[0].get("color"),
+            Some(&Value::String("blue".to_string()))
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Uploads data to the GPU which is needed for rendering.
+
+use csscolorparser::Color;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    circle::resource::{CircleBuffers, CircleResources},
+    context::MapContext,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        shaders::{ShaderCircleLayerMetadata, ShaderCircleVertex, Vec4f32},
+        tile_view_pattern::DEFAULT_TILE_SIZE,
+        view_state::ViewStatePadding,
+        Renderer,
+    },
+    style::{
+        expression::{EvaluationContext, FeatureProperties},
+        layer::{CirclePaint, LayerPaint, Reference, StyleLayer, StyleProperty},
+    },
+    tcs::system::{SystemError, SystemResult},
+    vector::{
+        tessellation::IndexDataType, AvailableVectorLayerBucket, VectorLayerBucket,
+        VectorLayerBucketComponent,
+    },
+};
+
+/// The corners of the quad of a circle.
+const EXTRUDES: [[f32; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+pub fn upload_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        renderer: Renderer { device, .. },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(Initialized(circle_resources)) = world
+        .resources
+        .query_mut::<&mut Eventually<CircleResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+    let Some(view_region) = view_state.create_view_region(
+        view_state.zoom().zoom_level(DEFAULT_TILE_SIZE),
+        ViewStatePadding::Loose,
+    ) else {
+        return Ok(());
+    };
+
+    for coords in view_region.iter() {
+        let Some(vector_layers) = world.tiles.query::<&VectorLayerBucketComponent>(coords) else {
+            continue;
+        };
+
+        for style_layer in &style.layers {
+            let Some(LayerPaint::Circle(paint)) = &style_layer.paint else {
+                continue;
+            };
+
+            // Zoom-dependent properties are re-evaluated whenever the zoom level changes
+            if circle_resources
+                .get_buffers(&coords, &style_layer.id)
+                .is_some_and(|buffers| buffers.evaluated_zoom.is_none_or(|z| z == zoom))
+            {
+                continue;
+            }
+
+            let Some(bucket) = vector_layers.layers.iter().find_map(|layer| match layer {
+                VectorLayerBucket::AvailableLayer(bucket)
+                    if bucket.style_layer_id == style_layer.id =>
+                {
+                    Some(bucket)
+                }
+                _ => None,
+            }) else {
+                continue;
+            };
+
+            if bucket.buffer.buffer.indices.is_empty() {
+                continue;
+            }
+
+            let (vertices, indices) = circle_vertices(paint, bucket, zoom);
+
+            log::debug!("Allocating circles at {}", bucket.coords);
+            circle_resources.insert_buffers(
+                coords,
+                &style_layer.id,
+                CircleBuffers {
+                    vertices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Circle Vertex Buffer"),
+                        contents: bytemuck::cast_slice(&vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                    indices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Circle Index Buffer"),
+                        contents: bytemuck::cast_slice(&indices),
+                        usage: wgpu::BufferUsages::INDEX,
+                    }),
+                    layer_metadata: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Circle Layer Metadata Buffer"),
+                        contents: bytemuck::cast_slice(&[layer_metadata(style_layer, paint, zoom)]),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                    num_indices: indices.len() as u32,
+                    evaluated_zoom: (!paint.is_zoom_constant()).then_some(zoom),
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn layer_metadata(
+    style_layer: &StyleLayer,
+    paint: &CirclePaint,
+    zoom: f32,
+) -> ShaderCircleLayerMetadata {
+    ShaderCircleLayerMetadata {
+        z_index: style_layer.index as f32,
+        translate: paint
+            .circle_translate
+            .as_ref()
+            .and_then(|translate| translate.evaluate_at_zoom(zoom))
+            .unwrap_or([0.0, 0.0]),
+        translate_viewport: (paint.circle_translate_anchor == Some(Reference::Viewport)) as u32,
+        scale_with_map: (paint.circle_pitch_scale != Some(Reference::Viewport)) as u32,
+        pitch_with_map: (paint.circle_pitch_alignment == Some(Reference::Map)) as u32,
+    }
+}
+
+/// Expands every circle of the bucket into a quad. The paint properties are evaluated for each
+/// feature at `zoom`.
+fn circle_vertices(
+    paint: &CirclePaint,
+    bucket: &AvailableVectorLayerBucket,
+    zoom: f32,
+) -> (Vec<ShaderCircleVertex>, Vec<IndexDataType>) {
+    let centers = &bucket.buffer.buffer.vertices;
+    let mut vertices = Vec::with_capacity(centers.len() * 4);
+    let mut indices = Vec::with_capacity(centers.len() * 6);
+
+    let no_properties = FeatureProperties::new();
+    let mut centers = centers.iter();
+    for (idx, &count) in bucket.feature_indices.iter().enumerate() {
+        let properties = bucket.feature_properties.get(idx).unwrap_or(&no_properties);
+        let context = EvaluationContext::default()
+            .with_zoom(zoom as f64)
+            .with_properties(properties);
+
+        let radius = evaluate_number(&paint.circle_radius, &context, 5.0);
+        let blur = evaluate_number(&paint.circle_blur, &context, 0.0);
+        let stroke_width = evaluate_number(&paint.circle_stroke_width, &context, 0.0);
+        let fill_color = evaluate_color(
+            &paint.circle_color,
+            &context,
+            evaluate_number(&paint.circle_opacity, &context, 1.0),
+        );
+        let stroke_color = evaluate_color(
+            &paint.circle_stroke_color,
+            &context,
+            evaluate_number(&paint.circle_stroke_opacity, &context, 1.0),
+        );
+
+        for center in centers.by_ref().take(count as usize) {
+            let first = vertices.len() as IndexDataType;
+            indices.extend([0, 1, 2, 0, 2, 3].map(|offset| first + offset));
+            vertices.extend(EXTRUDES.map(|extrude| ShaderCircleVertex {
+                position: center.position,
+                extrude,
+                color: fill_color,
+                stroke_color,
+                radius,
+                blur,
+                stroke_width,
+            }));
+        }
+    }
+
+    (vertices, indices)
+}
+
+fn evaluate_number(
+    property: &Option<StyleProperty<f32>>,
+    context: &EvaluationContext,
+    default: f32,
+) -> f32 {
+    property
+        .as_ref()
+        .and_then(|property| property.evaluate(context))
+        .unwrap_or(default)
+}
+
+/// Evaluates a color and premultiplies it with its alpha and the given `opacity`. Black is the
+/// default.
+fn evaluate_color(
+    property: &Option<StyleProperty<Color>>,
+    context: &EvaluationContext,
+    opacity: f32,
+) -> Vec4f32 {
+    let color = property
+        .as_ref()
+        .and_then(|property| property.evaluate(context))
+        .unwrap_or_else(|| Color::new(0.0, 0.0, 0.0, 1.0));
+    let alpha = color.a as f32 * opacity;
+    [
+        color.r as f32 * alpha,
+        color.g as f32 * alpha,
+        color.b as f32 * alpha,
+        alpha,
+    ]
+}
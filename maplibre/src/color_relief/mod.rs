@@ -0,0 +1,49 @@
+//! Draws `color-relief` style layers, which color each tile by the elevations of its raster-dem
+//! source. The `color-relief-color` is evaluated into a color ramp, which is looked up by the
+//! elevations during the main pass.
+//!
+//! The elevations are fetched and decoded by the [`RasterPlugin`](crate::raster::RasterPlugin),
+//! which therefore needs to be added as well.
+
+use std::rc::Rc;
+
+use crate::{
+    color_relief::{
+        queue_system::queue_system, resource_system::resource_system, upload_system::upload_system,
+    },
+    environment::Environment,
+    kernel::Kernel,
+    plugin::Plugin,
+    render::{eventually::Eventually, graph::RenderGraph, RenderStageLabel},
+    schedule::Schedule,
+    tcs::world::World,
+};
+
+mod queue_system;
+mod render_commands;
+mod resource;
+mod resource_system;
+mod upload_system;
+
+pub use resource::{ColorReliefLayer, ColorReliefResources, ColorReliefTile};
+
+#[derive(Default)]
+pub struct ColorReliefPlugin;
+
+impl<E: Environment> Plugin<E> for ColorReliefPlugin {
+    fn build(
+        &self,
+        schedule: &mut Schedule,
+        _kernel: Rc<Kernel<E>>,
+        world: &mut World,
+        _graph: &mut RenderGraph,
+    ) {
+        world
+            .resources
+            .insert(Eventually::<ColorReliefResources>::Uninitialized);
+
+        schedule.add_system_to_stage(RenderStageLabel::Prepare, resource_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, upload_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, queue_system);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{
+    color_relief::resource::ColorReliefResources,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{LayerItem, PhaseItem, RenderCommand, RenderCommandResult},
+        resource::TrackedRenderPass,
+        tile_view_pattern::WgpuTileViewPattern,
+    },
+    tcs::world::World,
+};
+
+pub struct SetColorReliefPipeline;
+impl<P: PhaseItem> RenderCommand<P> for SetColorReliefPipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &P,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(color_relief_resources)) =
+            world.resources.get::<Eventually<ColorReliefResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_render_pipeline(color_relief_resources.pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+/// Binds the elevations of the tile and the color ramp and metadata of the layer.
+pub struct SetColorReliefBindGroups;
+impl RenderCommand<LayerItem> for SetColorReliefBindGroups {
+    fn render<'w>(
+        world: &'w World,
+        item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(color_relief_resources)) =
+            world.resources.get::<Eventually<ColorReliefResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let (Some(tile), Some(layer)) = (
+            color_relief_resources.get_tile(&item.tile.coords, &item.style_layer),
+            color_relief_resources.get_layer(&item.style_layer),
+        ) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_bind_group(0, &tile.dem_bind_group, &[]);
+        pass.set_bind_group(1, &layer.color_ramp_bind_group, &[]);
+        pass.set_bind_group(2, &layer.bind_group, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawColorReliefTile;
+impl RenderCommand<LayerItem> for DrawColorReliefTile {
+    fn render<'w>(
+        world: &'w World,
+        item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(tile_view_pattern)) =
+            world.resources.get::<Eventually<WgpuTileViewPattern>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let source_shape = &item.source_shape;
+
+        let reference = source_shape.coords().stencil_reference_value_3d() as u32;
+
+        pass.set_stencil_reference(reference);
+
+        let tile_view_pattern_buffer = source_shape
+            .buffer_range()
+            .expect("tile_view_pattern needs to be uploaded first"); // FIXME tcs
+        pass.set_vertex_buffer(
+            0,
+            tile_view_pattern.buffer().slice(tile_view_pattern_buffer),
+        );
+
+        const TILE_SHADER_VERTICES: u32 = 6;
+        pass.draw(0..TILE_SHADER_VERTICES, 0..1);
+
+        RenderCommandResult::Success
+    }
+}
+
+pub type DrawColorReliefTiles = (
+    SetColorReliefPipeline,
+    SetColorReliefBindGroups,
+    DrawColorReliefTile,
+);
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::{
+    coords::WorldTileCoords, raster::DemData, render::shaders::ShaderColorReliefLayerMetadata,
+};
+
+/// Width of the color ramp textures, which map the elevations between the first and the last
+/// stop of the `color-relief-color` to colors.
+pub const COLOR_RAMP_SIZE: u32 = 256;
+
+/// The elevations of a tile, as they are drawn by a style layer.
+pub struct ColorReliefTile {
+    /// The [`DemData::revision`] of the uploaded elevations.
+    pub dem_revision: u32,
+    pub dem_bind_group: wgpu::BindGroup,
+}
+
+/// The color ramp and uniform buffer of a color-relief layer, which are shared by all tiles.
+pub struct ColorReliefLayer {
+    /// The premultiplied colors of the layer, which are written every frame.
+    pub color_ramp: wgpu::Texture,
+    pub color_ramp_bind_group: wgpu::BindGroup,
+    /// The [`ShaderColorReliefLayerMetadata`] of the layer, which is written every frame.
+    pub metadata: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+/// Holds the resources necessary for drawing color reliefs such as the
+/// * pipeline
+/// * elevations of each tile
+/// * color ramps and uniform buffers of each layer
+pub struct ColorReliefResources {
+    pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    /// The elevations of each tile, keyed by the id of the style layer.
+    tiles: HashMap<WorldTileCoords, HashMap<String, ColorReliefTile>>,
+    /// The color ramps and uniform buffers of each layer, keyed by the id of the style layer.
+    layers: HashMap<String, ColorReliefLayer>,
+}
+
+impl ColorReliefResources {
+    pub fn new(device: &wgpu::Device, pipeline: wgpu::RenderPipeline) -> Self {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Color relief color ramp sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            sampler,
+            tiles: Default::default(),
+            layers: Default::default(),
+        }
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn get_tile(
+        &self,
+        coords: &WorldTileCoords,
+        style_layer_id: &str,
+    ) -> Option<&ColorReliefTile> {
+        self.tiles.get(coords)?.get(style_layer_id)
+    }
+
+    /// Uploads the elevations of a tile, including the border which is filled by its neighbors.
+    pub fn insert_tile(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        coords: WorldTileCoords,
+        style_layer_id: &str,
+        dem: &DemData,
+    ) {
+        let dem_size = wgpu::Extent3d {
+            width: dem.stride(),
+            height: dem.stride(),
+            depth_or_array_layers: 1,
+        };
+        let dem_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Color relief DEM texture"),
+            size: dem_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &dem_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            dem.as_bytes(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * dem.stride()),
+                rows_per_image: Some(dem.stride()),
+            },
+            dem_size,
+        );
+        let dem_view = dem_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let tile = ColorReliefTile {
+            dem_revision: dem.revision(),
+            dem_bind_group: device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Color relief DEM bind group"),
+                layout: &self.pipeline.get_bind_group_layout(0),
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&dem_view),
+                }],
+            }),
+        };
+
+        self.tiles
+            .entry(coords)
+            .or_default()
+            .insert(style_layer_id.to_string(), tile);
+    }
+
+    pub fn get_layer(&self, style_layer_id: &str) -> Option<&ColorReliefLayer> {
+        self.layers.get(style_layer_id)
+    }
+
+    /// Returns the color ramp and uniform buffer of a layer, which are created if the layer has
+    /// none yet.
+    pub fn layer_or_insert(
+        &mut self,
+        device: &wgpu::Device,
+        style_layer_id: &str,
+    ) -> &ColorReliefLayer {
+        if !self.layers.contains_key(style_layer_id) {
+            let color_ramp = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Color relief color ramp texture"),
+                size: wgpu::Extent3d {
+                    width: COLOR_RAMP_SIZE,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let color_ramp_view = color_ramp.create_view(&wgpu::TextureViewDescriptor::default());
+            let color_ramp_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Color relief color ramp bind group"),
+                layout: &self.pipeline.get_bind_group_layout(1),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&color_ramp_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+
+            let metadata = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Color Relief Layer Metadata Buffer"),
+                size: std::mem::size_of::<ShaderColorReliefLayerMetadata>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Color relief layer bind group"),
+                layout: &self.pipeline.get_bind_group_layout(2),
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: metadata.as_entire_binding(),
+                }],
+            });
+
+            self.layers.insert(
+                style_layer_id.to_string(),
+                ColorReliefLayer {
+                    color_ramp,
+                    color_ramp_bind_group,
+                    metadata,
+                    bind_group,
+                },
+            );
+        }
+        &self.layers[style_layer_id]
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.layers.clear();
+    }
+}
@@ -0,0 +1,73 @@
+//! Prepares GPU-owned resources by initializing them if they are uninitialized or out-of-date.
+use crate::{
+    color_relief::resource::ColorReliefResources,
+    context::MapContext,
+    render::{
+        eventually::Eventually,
+        resource::{RenderPipeline, TilePipeline},
+        shaders,
+        shaders::Shader,
+        RenderResources, Renderer,
+    },
+    tcs::system::{SystemError, SystemResult},
+};
+
+pub fn resource_system(
+    MapContext {
+        world,
+        renderer:
+            Renderer {
+                device,
+                resources: RenderResources { surface, .. },
+                settings,
+                ..
+            },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(color_relief_resources) = world
+        .resources
+        .query_mut::<&mut Eventually<ColorReliefResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    color_relief_resources.initialize(|| {
+        let shader = shaders::ColorReliefShader {
+            format: surface.surface_format(),
+        };
+        let mut descriptor = TilePipeline::new(
+            "color_relief_pipeline".into(),
+            *settings,
+            shader.describe_vertex(),
+            shader.describe_fragment(),
+            true,
+            false,
+            false,
+            false,
+            surface.is_multisampling_supported(settings.msaa),
+            true,
+            false,
+        )
+        .with_texture_bind_groups(2)
+        .with_uniform_bind_group()
+        .describe_render_pipeline();
+
+        // The elevations can not be filtered, so they are loaded texel by texel
+        if let Some(layout) = descriptor.layout.as_mut() {
+            layout[0] = vec![wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }];
+        }
+
+        ColorReliefResources::new(device, descriptor.initialize(device))
+    });
+    Ok(())
+}
@@ -0,0 +1,213 @@
+//! Uploads data to the GPU which is needed for rendering.
+
+use csscolorparser::Color;
+
+use crate::{
+    color_relief::resource::{ColorReliefResources, COLOR_RAMP_SIZE},
+    context::MapContext,
+    raster::DemDataComponent,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        shaders::ShaderColorReliefLayerMetadata,
+        tile_view_pattern::DEFAULT_TILE_SIZE,
+        view_state::ViewStatePadding,
+        Renderer,
+    },
+    style::{
+        expression::EvaluationContext,
+        layer::{ColorReliefPaint, LayerPaint, StyleProperty},
+    },
+    tcs::system::{SystemError, SystemResult},
+};
+
+pub fn upload_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        renderer: Renderer { device, queue, .. },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(Initialized(color_relief_resources)) = world
+        .resources
+        .query_mut::<&mut Eventually<ColorReliefResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+
+    // The color ramp is written every frame, because the colors may depend on the zoom level
+    for style_layer in &style.layers {
+        let Some(LayerPaint::ColorRelief(paint)) = &style_layer.paint else {
+            continue;
+        };
+        let (metadata, color_ramp) = layer_metadata(paint, zoom);
+        let layer = color_relief_resources.layer_or_insert(device, &style_layer.id);
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &layer.color_ramp,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &color_ramp,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(COLOR_RAMP_SIZE * 4),
+                rows_per_image: Some(1),
+            },
+            wgpu::Extent3d {
+                width: COLOR_RAMP_SIZE,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.write_buffer(&layer.metadata, 0, bytemuck::cast_slice(&[metadata]));
+    }
+
+    let Some(view_region) = view_state.create_view_region(
+        view_state.zoom().zoom_level(DEFAULT_TILE_SIZE),
+        ViewStatePadding::Loose,
+    ) else {
+        return Ok(());
+    };
+
+    for coords in view_region.iter() {
+        let Some(dem_data) = world.tiles.query::<&DemDataComponent>(coords) else {
+            continue;
+        };
+
+        for style_layer in &style.layers {
+            if !matches!(style_layer.paint, Some(LayerPaint::ColorRelief(_))) {
+                continue;
+            }
+            let Some(dem) = style_layer
+                .source
+                .as_ref()
+                .and_then(|source| dem_data.sources.get(source))
+            else {
+                continue;
+            };
+
+            // The elevations are uploaded again once the borders were filled by the neighbors
+            if color_relief_resources
+                .get_tile(&coords, &style_layer.id)
+                .is_some_and(|tile| tile.dem_revision == dem.revision())
+            {
+                continue;
+            }
+
+            log::debug!("Uploading color relief at {coords}");
+            color_relief_resources.insert_tile(device, queue, coords, &style_layer.id, dem);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the metadata of the layer and the premultiplied RGBA texels of its color ramp. The
+/// ramp spans the elevations between the first and the last stop of the `color-relief-color`.
+fn layer_metadata(
+    paint: &ColorReliefPaint,
+    zoom: f32,
+) -> (ShaderColorReliefLayerMetadata, Vec<u8>) {
+    let (first_stop, max_elevation) = paint
+        .color_relief_color
+        .as_ref()
+        .and_then(|color| match color {
+            StyleProperty::Constant(_) => None,
+            StyleProperty::Expression(expression) => expression.stop_range(),
+        })
+        .unwrap_or_default();
+    // The first texel lies below the first stop, where a `step` has a color of its own
+    let spacing = if max_elevation > first_stop {
+        (max_elevation - first_stop) / (COLOR_RAMP_SIZE - 2) as f64
+    } else {
+        1.0
+    };
+    let min_elevation = first_stop - spacing;
+
+    let mut color_ramp = Vec::with_capacity(COLOR_RAMP_SIZE as usize * 4);
+    for x in 0..COLOR_RAMP_SIZE {
+        let elevation = min_elevation
+            + (max_elevation - min_elevation) * x as f64 / (COLOR_RAMP_SIZE - 1) as f64;
+        // Like in the style specification, the color relief is transparent by default
+        let Color { r, g, b, a } = paint
+            .color_relief_color
+            .as_ref()
+            .and_then(|color| {
+                color.evaluate(
+                    &EvaluationContext::default()
+                        .with_zoom(zoom as f64)
+                        .with_elevation(elevation),
+                )
+            })
+            .unwrap_or_else(|| Color::new(0.0, 0.0, 0.0, 0.0));
+        color_ramp.extend([r * a, g * a, b * a, a].map(|c| (c * 255.0).round() as u8));
+    }
+
+    let metadata = ShaderColorReliefLayerMetadata {
+        min_elevation: min_elevation as f32,
+        max_elevation: max_elevation as f32,
+        opacity: paint
+            .color_relief_opacity
+            .as_ref()
+            .and_then(|opacity| opacity.evaluate_at_zoom(zoom))
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0),
+        _padding: 0.0,
+    };
+    (metadata, color_ramp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_metadata() {
+        let paint: ColorReliefPaint = serde_json::from_str(
+            r##"{
+                "color-relief-opacity": 0.5,
+                "color-relief-color": [
+                    "interpolate", ["linear"], ["elevation"], 400, "#F00", 800, "#00F8"
+                ]
+            }"##,
+        )
+        .unwrap();
+        let (metadata, color_ramp) = layer_metadata(&paint, 11.0);
+
+        assert!(metadata.min_elevation < 400.0);
+        assert_eq!(metadata.max_elevation, 800.0);
+        assert_eq!(metadata.opacity, 0.5);
+        assert_eq!(color_ramp.len(), COLOR_RAMP_SIZE as usize * 4);
+        // The second texel lies on the first stop
+        assert_eq!(color_ramp[4..8], [255, 0, 0, 255]);
+        assert_eq!(color_ramp[color_ramp.len() - 4..], [0, 0, 136, 136]);
+    }
+
+    #[test]
+    fn test_layer_metadata_step() {
+        let paint: ColorReliefPaint = serde_json::from_str(
+            r#"{"color-relief-color": ["step", ["elevation"], "black", 100, "white"]}"#,
+        )
+        .unwrap();
+        let (metadata, color_ramp) = layer_metadata(&paint, 0.0);
+
+        // Elevations below the only stop are black and above it white
+        assert!(metadata.min_elevation < 100.0);
+        assert_eq!(metadata.max_elevation, 100.0);
+        assert_eq!(color_ramp[..4], [0, 0, 0, 255]);
+        assert_eq!(color_ramp[color_ramp.len() - 4..], [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_layer_metadata_defaults() {
+        let (metadata, color_ramp) = layer_metadata(&ColorReliefPaint::default(), 0.0);
+
+        assert_eq!(metadata.opacity, 1.0);
+        assert!(color_ramp.iter().all(|c| *c == 0));
+    }
+}
@@ -164,15 +164,9 @@ impl LatLon {
         Self::EARTH_CIRCUMFRENCE * (self.latitude * PI / 180.0).cos()
     }
 
-    fn mercator_x_from_lng(&self) -> f64 {
-        (180.0 + self.longitude) / 360.0
-    }
-
-    fn mercator_y_from_lat(&self) -> f64 {
-        (180.0 - (180.0 / PI * ((PI / 4.0 + self.latitude * PI / 360.0).tan()).ln())) / 360.0
-    }
-
-    fn mercator_z_from_altitude(&self, altitude: f64) -> f64 {
+    /// Converts an altitude in meters at this latitude to units of the mercator projection, in
+    /// which the world has a size of 1.
+    pub fn mercator_z_from_altitude(&self, altitude: f64) -> f64 {
         altitude / self.circumference_at_latitude()
     }
 }
@@ -600,6 +594,17 @@ impl WorldCoords {
         Self { x, y }
     }
 
+    /// Inverse of [`WorldCoords::from_lat_lon`].
+    pub fn into_lat_lon(self, zoom: Zoom) -> LatLon {
+        let tile_size = TILE_SIZE * 2.0_f64.powf(zoom.0);
+        let longitude = self.x * 360.0 / tile_size - 180.0;
+
+        let merc_n = PI - 2.0 * PI * self.y / tile_size;
+        let latitude = merc_n.sinh().atan() * 180.0 / PI;
+
+        LatLon::new(latitude, longitude)
+    }
+
     pub fn into_world_tile(self, z: ZoomLevel, zoom: Zoom) -> WorldTileCoords {
         let tile_scale = zoom.scale_to_zoom_level(z) / TILE_SIZE; // TODO: Deduplicate
         let x = self.x * tile_scale;
@@ -734,7 +739,7 @@ mod tests {
 
     use crate::{
         coords::{
-            Quadkey, TileCoords, ViewRegion, WorldCoords, WorldTileCoords, Zoom, ZoomLevel,
+            LatLon, Quadkey, TileCoords, ViewRegion, WorldCoords, WorldTileCoords, Zoom, ZoomLevel,
             BOTTOM_RIGHT_EXTENT, TOP_LEFT_EXTENT,
         },
         render::tile_view_pattern::DEFAULT_TILE_SIZE,
@@ -755,6 +760,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_lat_lon_round_trip() {
+        let zoom = Zoom::new(3.5);
+        let world = WorldCoords::from_lat_lon(LatLon::new(52.52, 13.405), zoom);
+        let lat_lon = world.into_lat_lon(zoom);
+        assert!((lat_lon.latitude - 52.52).abs() < 1e-9);
+        assert!((lat_lon.longitude - 13.405).abs() < 1e-9);
+    }
+
     #[test]
     fn world_coords_tests() {
         to_from_world((1, 0, ZoomLevel::from(1)), Zoom::new(1.0));
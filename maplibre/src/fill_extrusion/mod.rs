@@ -0,0 +1,49 @@
+//! Draws the polygon features of `fill-extrusion` style layers as extruded 3D shapes, which are
+//! shaded by the light of the style.
+//!
+//! The features are fetched and tessellated by the [`VectorPlugin`](crate::vector::VectorPlugin),
+//! which therefore needs to be added as well.
+
+use std::rc::Rc;
+
+use crate::{
+    environment::Environment,
+    fill_extrusion::{
+        queue_system::queue_system, resource_system::resource_system, upload_system::upload_system,
+    },
+    kernel::Kernel,
+    plugin::Plugin,
+    render::{eventually::Eventually, graph::RenderGraph, RenderStageLabel},
+    schedule::Schedule,
+    tcs::world::World,
+};
+
+mod queue_system;
+mod render_commands;
+mod resource;
+mod resource_system;
+pub mod tessellation;
+mod upload_system;
+
+pub use resource::{FillExtrusionBuffers, FillExtrusionResources};
+
+#[derive(Default)]
+pub struct FillExtrusionPlugin;
+
+impl<E: Environment> Plugin<E> for FillExtrusionPlugin {
+    fn build(
+        &self,
+        schedule: &mut Schedule,
+        _kernel: Rc<Kernel<E>>,
+        world: &mut World,
+        _graph: &mut RenderGraph,
+    ) {
+        world
+            .resources
+            .insert(Eventually::<FillExtrusionResources>::Uninitialized);
+
+        schedule.add_system_to_stage(RenderStageLabel::Prepare, resource_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, upload_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, queue_system);
+    }
+}
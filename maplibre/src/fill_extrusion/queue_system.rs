@@ -0,0 +1,93 @@
+//! Queues [PhaseItems](crate::render::render_phase::PhaseItem) for rendering.
+use crate::{
+    context::MapContext,
+    fill_extrusion::{
+        render_commands::{DrawFillExtrusionDepths, DrawFillExtrusions},
+        resource::FillExtrusionResources,
+    },
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{DrawState, RenderPhase, TranslucentItem},
+        tile_view_pattern::WgpuTileViewPattern,
+    },
+    style::layer::LayerPaint,
+    tcs::{
+        system::{SystemError, SystemResult},
+        tiles::Tile,
+    },
+};
+
+pub fn queue_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some((
+        Initialized(tile_view_pattern),
+        Initialized(fill_extrusion_resources),
+        translucent_phase,
+    )) = world.resources.query_mut::<(
+        &mut Eventually<WgpuTileViewPattern>,
+        &mut Eventually<FillExtrusionResources>,
+        &mut RenderPhase<TranslucentItem>,
+    )>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+
+    let mut source_shapes = Vec::new();
+    for view_tile in tile_view_pattern.iter() {
+        view_tile.render(|source_shape| source_shapes.push(source_shape.clone()));
+    }
+
+    for style_layer in &style.layers {
+        if !matches!(style_layer.paint, Some(LayerPaint::FillExtrusion(_)))
+            || !style_layer.is_visible_at(zoom)
+        {
+            continue;
+        }
+
+        let source_shapes = source_shapes
+            .iter()
+            .filter(|source_shape| {
+                fill_extrusion_resources
+                    .get_buffers(&source_shape.coords(), &style_layer.id)
+                    .is_some()
+            })
+            .collect::<Vec<_>>();
+
+        // The depth of the extrusions of all tiles is drawn before their colors, such that only
+        // the closest faces are visible. The phase is sorted stably by the index of the layer.
+        for source_shape in &source_shapes {
+            translucent_phase.add(TranslucentItem {
+                draw_function: Box::new(
+                    DrawState::<TranslucentItem, DrawFillExtrusionDepths>::new(),
+                ),
+                index: style_layer.index,
+                style_layer: style_layer.id.clone(),
+                tile: Tile {
+                    coords: source_shape.coords(),
+                },
+                source_shape: (*source_shape).clone(),
+            });
+        }
+        for source_shape in &source_shapes {
+            translucent_phase.add(TranslucentItem {
+                draw_function: Box::new(DrawState::<TranslucentItem, DrawFillExtrusions>::new()),
+                index: style_layer.index,
+                style_layer: style_layer.id.clone(),
+                tile: Tile {
+                    coords: source_shape.coords(),
+                },
+                source_shape: (*source_shape).clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Specifies the instructions which are going to be sent to the GPU. Render commands can be concatenated
+//! into a new render command which executes multiple instruction sets.
+use crate::{
+    fill_extrusion::resource::FillExtrusionResources,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{PhaseItem, RenderCommand, RenderCommandResult, TranslucentItem},
+        resource::TrackedRenderPass,
+        tile_view_pattern::WgpuTileViewPattern,
+        INDEX_FORMAT,
+    },
+    tcs::world::World,
+};
+
+pub struct SetFillExtrusionDepthPipeline;
+impl<P: PhaseItem> RenderCommand<P> for SetFillExtrusionDepthPipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &P,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(fill_extrusion_resources)) =
+            world.resources.get::<Eventually<FillExtrusionResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_render_pipeline(fill_extrusion_resources.depth_pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+pub struct SetFillExtrusionPipeline;
+impl<P: PhaseItem> RenderCommand<P> for SetFillExtrusionPipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &P,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(fill_extrusion_resources)) =
+            world.resources.get::<Eventually<FillExtrusionResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_render_pipeline(fill_extrusion_resources.pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawFillExtrusion;
+impl RenderCommand<TranslucentItem> for DrawFillExtrusion {
+    fn render<'w>(
+        world: &'w World,
+        item: &TranslucentItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some((Initialized(fill_extrusion_resources), Initialized(tile_view_pattern))) =
+            world.resources.query::<(
+                &Eventually<FillExtrusionResources>,
+                &Eventually<WgpuTileViewPattern>,
+            )>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let Some(buffers) =
+            fill_extrusion_resources.get_buffers(&item.tile.coords, &item.style_layer)
+        else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(layer_metadata) = fill_extrusion_resources.get_layer_metadata(&item.style_layer)
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let source_shape = &item.source_shape;
+
+        pass.set_index_buffer(buffers.indices.slice(..), INDEX_FORMAT);
+        pass.set_vertex_buffer(0, buffers.vertices.slice(..));
+        let tile_view_pattern_buffer = source_shape
+            .buffer_range()
+            .expect("tile_view_pattern needs to be uploaded first"); // FIXME tcs
+        pass.set_vertex_buffer(
+            1,
+            tile_view_pattern.buffer().slice(tile_view_pattern_buffer),
+        );
+        pass.set_vertex_buffer(2, layer_metadata.slice(..));
+        pass.draw_indexed(0..buffers.num_indices, 0, 0..1);
+
+        RenderCommandResult::Success
+    }
+}
+
+pub type DrawFillExtrusionDepths = (SetFillExtrusionDepthPipeline, DrawFillExtrusion);
+pub type DrawFillExtrusions = (SetFillExtrusionPipeline, DrawFillExtrusion);
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use crate::coords::WorldTileCoords;
+
+/// The GPU buffers of the extrusions of a style layer within a tile.
+pub struct FillExtrusionBuffers {
+    pub vertices: wgpu::Buffer,
+    pub indices: wgpu::Buffer,
+    pub num_indices: u32,
+    /// The zoom level at which the paint properties were evaluated. `None` if none of them
+    /// depends on the zoom level.
+    pub evaluated_zoom: Option<f32>,
+}
+
+/// Holds the resources necessary for drawing extrusions such as the
+/// * pipeline which fills the depth buffer and the pipeline which draws the colors
+/// * buffers of each tile
+/// * metadata of each style layer
+pub struct FillExtrusionResources {
+    depth_pipeline: wgpu::RenderPipeline,
+    pipeline: wgpu::RenderPipeline,
+    /// The buffers of each tile, keyed by the id of the style layer.
+    buffers: HashMap<WorldTileCoords, HashMap<String, FillExtrusionBuffers>>,
+    /// The metadata of each style layer, which is shared by all tiles.
+    layer_metadata: HashMap<String, wgpu::Buffer>,
+}
+
+impl FillExtrusionResources {
+    pub fn new(depth_pipeline: wgpu::RenderPipeline, pipeline: wgpu::RenderPipeline) -> Self {
+        Self {
+            depth_pipeline,
+            pipeline,
+            buffers: Default::default(),
+            layer_metadata: Default::default(),
+        }
+    }
+
+    pub fn depth_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.depth_pipeline
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn get_buffers(
+        &self,
+        coords: &WorldTileCoords,
+        style_layer_id: &str,
+    ) -> Option<&FillExtrusionBuffers> {
+        self.buffers.get(coords)?.get(style_layer_id)
+    }
+
+    pub fn insert_buffers(
+        &mut self,
+        coords: WorldTileCoords,
+        style_layer_id: &str,
+        buffers: FillExtrusionBuffers,
+    ) {
+        self.buffers
+            .entry(coords)
+            .or_default()
+            .insert(style_layer_id.to_string(), buffers);
+    }
+
+    pub fn get_layer_metadata(&self, style_layer_id: &str) -> Option<&wgpu::Buffer> {
+        self.layer_metadata.get(style_layer_id)
+    }
+
+    /// Returns the metadata buffer of the style layer, which is created by `create` if the layer
+    /// has none yet.
+    pub fn layer_metadata_or_insert_with(
+        &mut self,
+        style_layer_id: &str,
+        create: impl FnOnce() -> wgpu::Buffer,
+    ) -> &wgpu::Buffer {
+        self.layer_metadata
+            .entry(style_layer_id.to_string())
+            .or_insert_with(create)
+    }
+
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+    }
+}
@@ -0,0 +1,74 @@
+//! Prepares GPU-owned resources by initializing them if they are uninitialized or out-of-date.
+use crate::{
+    context::MapContext,
+    fill_extrusion::resource::FillExtrusionResources,
+    render::{
+        eventually::Eventually,
+        resource::{RenderPipeline, TilePipeline},
+        shaders,
+        shaders::Shader,
+        RenderResources, Renderer,
+    },
+    tcs::system::{SystemError, SystemResult},
+};
+
+pub fn resource_system(
+    MapContext {
+        world,
+        renderer:
+            Renderer {
+                device,
+                resources: RenderResources { surface, .. },
+                settings,
+                ..
+            },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(fill_extrusion_resources) = world
+        .resources
+        .query_mut::<&mut Eventually<FillExtrusionResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    fill_extrusion_resources.initialize(|| {
+        let shader = shaders::FillExtrusionShader {
+            format: surface.surface_format(),
+        };
+        let pipeline = |name: &'static str, fragment_state| {
+            TilePipeline::new(
+                name.into(),
+                *settings,
+                shader.describe_vertex(),
+                fragment_state,
+                true,
+                false,
+                true,
+                false,
+                surface.is_multisampling_supported(settings.msaa),
+                false,
+                false,
+            )
+        };
+
+        // Translucent extrusions only show their closest faces: the depth of all faces is drawn
+        // first, then the colors of the faces which have this depth.
+        let mut depth_fragment = shader.describe_fragment();
+        for target in depth_fragment.targets.iter_mut().flatten() {
+            target.write_mask = wgpu::ColorWrites::empty();
+        }
+
+        FillExtrusionResources::new(
+            pipeline("fill_extrusion_depth_pipeline", depth_fragment)
+                .with_depth_test(wgpu::CompareFunction::Less, true)
+                .describe_render_pipeline()
+                .initialize(device),
+            pipeline("fill_extrusion_pipeline", shader.describe_fragment())
+                .with_depth_test(wgpu::CompareFunction::LessEqual, false)
+                .describe_render_pipeline()
+                .initialize(device),
+        )
+    });
+    Ok(())
+}
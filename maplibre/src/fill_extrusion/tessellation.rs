@@ -0,0 +1,257 @@
+//! Tessellates the roofs and collects the walls of polygons which are drawn as extrusions.
+
+use geozero::{ColumnValue, FeatureProcessor, GeomProcessor, PropertyProcessor};
+use lyon::{
+    geom,
+    path::Path,
+    tessellation::{BuffersBuilder, FillOptions, FillRule, FillTessellator, VertexBuffers},
+};
+
+use crate::{
+    coords::EXTENT,
+    render::ShaderVertex,
+    style::{
+        expression::{FeatureProperties, Value},
+        layer::FillExtrusionPaint,
+    },
+    vector::tessellation::{IndexDataType, VertexConstructor},
+};
+
+type GeoResult<T> = geozero::error::Result<T>;
+
+const DEFAULT_TOLERANCE: f32 = 0.02;
+
+/// Emits the triangles of the roofs and the edges of the walls of the processed polygons. Points
+/// and lines are skipped.
+///
+/// The vertices of roofs have a normal of zero and are indexed as triangles. Every wall is indexed
+/// as a pair of vertices, which carry the outward normal of the wall. The walls are expanded to
+/// quads when they are uploaded.
+pub struct FillExtrusionTessellator {
+    /// Whether the properties of the features are needed to evaluate the paint properties.
+    retain_properties: bool,
+
+    buffer: VertexBuffers<ShaderVertex, IndexDataType>,
+    feature_indices: Vec<u32>,
+    feature_properties: Vec<FeatureProperties>,
+
+    current_properties: FeatureProperties,
+    current_index: usize,
+    /// The rings of the polygon which is processed, the first one is the exterior ring.
+    rings: Vec<Vec<[f32; 2]>>,
+    ring: Vec<[f32; 2]>,
+    in_polygon: bool,
+}
+
+impl FillExtrusionTessellator {
+    pub fn new(paint: &FillExtrusionPaint) -> Self {
+        Self {
+            retain_properties: !paint.is_feature_constant(),
+            buffer: VertexBuffers::new(),
+            feature_indices: Vec::new(),
+            feature_properties: Vec::new(),
+            current_properties: FeatureProperties::new(),
+            current_index: 0,
+            rings: Vec::new(),
+            ring: Vec::new(),
+            in_polygon: false,
+        }
+    }
+
+    /// Returns the roofs and walls, the count of indices for each feature and the properties of
+    /// each feature. The properties are only retained if some paint property depends on them.
+    pub fn finish(
+        mut self,
+    ) -> (
+        VertexBuffers<ShaderVertex, IndexDataType>,
+        Vec<u32>,
+        Vec<FeatureProperties>,
+    ) {
+        // Bare GeoJSON geometries are not wrapped in a feature
+        if self.buffer.indices.len() > self.current_index {
+            let _ = self.feature_end(0);
+        }
+        (self.buffer, self.feature_indices, self.feature_properties)
+    }
+
+    fn tessellate_polygon(&mut self) {
+        let rings = std::mem::take(&mut self.rings);
+
+        let mut builder = Path::builder();
+        for ring in &rings {
+            let Some((first, rest)) = ring.split_first() else {
+                continue;
+            };
+            builder.begin(geom::point(first[0], first[1]));
+            for point in rest {
+                builder.line_to(geom::point(point[0], point[1]));
+            }
+            builder.end(true);
+        }
+        if let Err(e) = FillTessellator::new().tessellate_path(
+            &builder.build(),
+            &FillOptions::tolerance(DEFAULT_TOLERANCE).with_fill_rule(FillRule::NonZero),
+            &mut BuffersBuilder::new(&mut self.buffer, VertexConstructor::default()),
+        ) {
+            log::warn!("failed to tessellate the roof of an extrusion: {e:?}");
+        }
+
+        for (i, ring) in rings.iter().enumerate() {
+            if ring.len() < 3 {
+                continue;
+            }
+
+            // Walls of the exterior ring face away from it, walls of holes face into them
+            let area = signed_area(ring);
+            let sign = if i == 0 {
+                -area.signum()
+            } else {
+                area.signum()
+            };
+
+            for (start, end) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+                let length = (dx * dx + dy * dy).sqrt();
+                if length == 0.0 || is_boundary_edge(start, end) {
+                    continue;
+                }
+                let normal = [sign * -dy / length, sign * dx / length];
+
+                let first = self.buffer.vertices.len() as IndexDataType;
+                self.buffer.vertices.extend([
+                    ShaderVertex::new(*start, normal),
+                    ShaderVertex::new(*end, normal),
+                ]);
+                self.buffer.indices.extend([first, first + 1]);
+            }
+        }
+    }
+}
+
+/// Returns twice the signed area of the ring, which is positive if the ring is counterclockwise
+/// with the y axis pointing up.
+fn signed_area(ring: &[[f32; 2]]) -> f32 {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum()
+}
+
+/// Edges along the clipped border outside of the tile are not walls of the polygon.
+fn is_boundary_edge(start: &[f32; 2], end: &[f32; 2]) -> bool {
+    let outside = |v: f32| v < 0.0 || v > EXTENT as f32;
+    (start[0] == end[0] && outside(start[0])) || (start[1] == end[1] && outside(start[1]))
+}
+
+impl GeomProcessor for FillExtrusionTessellator {
+    fn xy(&mut self, x: f64, y: f64, _idx: usize) -> GeoResult<()> {
+        if self.in_polygon {
+            self.ring.push([x as f32, y as f32]);
+        }
+        Ok(())
+    }
+
+    fn linestring_end(&mut self, _tagged: bool, _idx: usize) -> GeoResult<()> {
+        if self.in_polygon {
+            let mut ring = std::mem::take(&mut self.ring);
+            // GeoJSON rings repeat their first point at the end
+            if ring.len() > 1 && ring.first() == ring.last() {
+                ring.pop();
+            }
+            self.rings.push(ring);
+        }
+        Ok(())
+    }
+
+    fn polygon_begin(&mut self, _tagged: bool, _size: usize, _idx: usize) -> GeoResult<()> {
+        self.in_polygon = true;
+        Ok(())
+    }
+
+    fn polygon_end(&mut self, _tagged: bool, _idx: usize) -> GeoResult<()> {
+        self.in_polygon = false;
+        self.tessellate_polygon();
+        Ok(())
+    }
+}
+
+impl PropertyProcessor for FillExtrusionTessellator {
+    fn property(&mut self, _idx: usize, name: &str, value: &ColumnValue) -> GeoResult<bool> {
+        self.current_properties
+            .insert(name.to_string(), Value::from(value));
+        Ok(false)
+    }
+}
+
+impl FeatureProcessor for FillExtrusionTessellator {
+    fn feature_end(&mut self, _idx: u64) -> GeoResult<()> {
+        let next_index = self.buffer.indices.len();
+        self.feature_indices
+            .push((next_index - self.current_index) as u32);
+        self.current_index = next_index;
+
+        let properties = std::mem::take(&mut self.current_properties);
+        if self.retain_properties {
+            self.feature_properties.push(properties);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geozero::GeozeroDatasource;
+
+    use super::*;
+
+    #[test]
+    fn test_roof_and_walls() {
+        let paint: FillExtrusionPaint = serde_json::from_value(serde_json::json!({
+            "fill-extrusion-height": ["get", "height"]
+        }))
+        .unwrap();
+        let mut tessellator = FillExtrusionTessellator::new(&paint);
+
+        // The square is clipped at the left border of the tile
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"height": 10},
+             "geometry": {"type": "Polygon",
+                          "coordinates": [[[-10, 0], [10, 0], [10, 10], [-10, 10], [-10, 0]]]}},
+            {"type": "Feature", "properties": {"height": 20},
+             "geometry": {"type": "Point", "coordinates": [10, 10]}}
+        ]}"#;
+        geozero::geojson::GeoJson(geojson)
+            .process(&mut tessellator)
+            .unwrap();
+
+        let (buffer, feature_indices, feature_properties) = tessellator.finish();
+
+        // Two roof triangles and three walls, the point is skipped
+        assert_eq!(feature_indices, vec![12, 0]);
+        assert_eq!(feature_properties.len(), 2);
+        assert_eq!(
+            feature_properties[0].get("height"),
+            Some(&Value::Number(10.0))
+        );
+
+        let walls: Vec<_> = buffer.indices[6..]
+            .chunks(2)
+            .map(|wall| {
+                let start = buffer.vertices[wall[0] as usize];
+                let end = buffer.vertices[wall[1] as usize];
+                (start.position, end.position, start.normal)
+            })
+            .collect();
+        assert_eq!(
+            walls,
+            vec![
+                ([-10.0, 0.0], [10.0, 0.0], [0.0, -1.0]),
+                ([10.0, 0.0], [10.0, 10.0], [1.0, 0.0]),
+                ([10.0, 10.0], [-10.0, 10.0], [0.0, 1.0]),
+            ]
+        );
+        assert!(buffer.indices[..6]
+            .iter()
+            .all(|&i| buffer.vertices[i as usize].normal == [0.0, 0.0]));
+    }
+}
@@ -0,0 +1,264 @@
+//! Uploads data to the GPU which is needed for rendering.
+
+use std::collections::HashMap;
+
+use cgmath::Deg;
+use csscolorparser::Color;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    context::MapContext,
+    fill_extrusion::resource::{FillExtrusionBuffers, FillExtrusionResources},
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        shaders::{ShaderFillExtrusionLayerMetadata, ShaderFillExtrusionVertex},
+        tile_view_pattern::DEFAULT_TILE_SIZE,
+        view_state::ViewStatePadding,
+        Renderer,
+    },
+    style::{
+        expression::{EvaluationContext, FeatureProperties},
+        layer::{FillExtrusionPaint, LayerPaint, Reference, StyleProperty},
+        Light,
+    },
+    tcs::system::{SystemError, SystemResult},
+    vector::{
+        tessellation::IndexDataType, AvailableVectorLayerBucket, VectorLayerBucket,
+        VectorLayerBucketComponent,
+    },
+};
+
+pub fn upload_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        renderer: Renderer { device, queue, .. },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(Initialized(fill_extrusion_resources)) = world
+        .resources
+        .query_mut::<&mut Eventually<FillExtrusionResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+    let light = style.light.clone().unwrap_or_default();
+    let bearing = Deg::from(view_state.camera().get_roll()).0;
+
+    // The metadata is written every frame, because the light follows the bearing of the map
+    for style_layer in &style.layers {
+        let Some(LayerPaint::FillExtrusion(paint)) = &style_layer.paint else {
+            continue;
+        };
+        let buffer =
+            fill_extrusion_resources.layer_metadata_or_insert_with(&style_layer.id, || {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Fill Extrusion Layer Metadata Buffer"),
+                    size: std::mem::size_of::<ShaderFillExtrusionLayerMetadata>() as u64,
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            });
+        queue.write_buffer(
+            buffer,
+            0,
+            bytemuck::cast_slice(&[layer_metadata(paint, &light, bearing, zoom)]),
+        );
+    }
+
+    let Some(view_region) = view_state.create_view_region(
+        view_state.zoom().zoom_level(DEFAULT_TILE_SIZE),
+        ViewStatePadding::Loose,
+    ) else {
+        return Ok(());
+    };
+
+    for coords in view_region.iter() {
+        let Some(vector_layers) = world.tiles.query::<&VectorLayerBucketComponent>(coords) else {
+            continue;
+        };
+
+        for style_layer in &style.layers {
+            let Some(LayerPaint::FillExtrusion(paint)) = &style_layer.paint else {
+                continue;
+            };
+
+            // Zoom-dependent properties are re-evaluated whenever the zoom level changes
+            if fill_extrusion_resources
+                .get_buffers(&coords, &style_layer.id)
+                .is_some_and(|buffers| buffers.evaluated_zoom.is_none_or(|z| z == zoom))
+            {
+                continue;
+            }
+
+            let Some(bucket) = vector_layers.layers.iter().find_map(|layer| match layer {
+                VectorLayerBucket::AvailableLayer(bucket)
+                    if bucket.style_layer_id == style_layer.id =>
+                {
+                    Some(bucket)
+                }
+                _ => None,
+            }) else {
+                continue;
+            };
+
+            if bucket.buffer.usable_indices == 0 {
+                continue;
+            }
+
+            let (vertices, indices) = fill_extrusion_vertices(paint, bucket, zoom);
+
+            log::debug!("Allocating extrusions at {}", bucket.coords);
+            fill_extrusion_resources.insert_buffers(
+                coords,
+                &style_layer.id,
+                FillExtrusionBuffers {
+                    vertices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Fill Extrusion Vertex Buffer"),
+                        contents: bytemuck::cast_slice(&vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                    indices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Fill Extrusion Index Buffer"),
+                        contents: bytemuck::cast_slice(&indices),
+                        usage: wgpu::BufferUsages::INDEX,
+                    }),
+                    num_indices: indices.len() as u32,
+                    evaluated_zoom: (!paint.is_feature_zoom_constant()).then_some(zoom),
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn layer_metadata(
+    paint: &FillExtrusionPaint,
+    light: &Light,
+    bearing: f64,
+    zoom: f32,
+) -> ShaderFillExtrusionLayerMetadata {
+    ShaderFillExtrusionLayerMetadata {
+        translate: paint
+            .fill_extrusion_translate
+            .as_ref()
+            .and_then(|translate| translate.evaluate_at_zoom(zoom))
+            .unwrap_or([0.0, 0.0]),
+        translate_viewport: (paint.fill_extrusion_translate_anchor == Some(Reference::Viewport))
+            as u32 as f32,
+        opacity: paint
+            .fill_extrusion_opacity
+            .as_ref()
+            .and_then(|opacity| opacity.evaluate_at_zoom(zoom))
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0),
+        vertical_gradient: paint.fill_extrusion_vertical_gradient.unwrap_or(true) as u32 as f32,
+        light_direction: light.direction(bearing),
+        light_intensity: light.intensity.clamp(0.0, 1.0),
+        light_color: [
+            light.color.r as f32,
+            light.color.g as f32,
+            light.color.b as f32,
+        ],
+    }
+}
+
+/// Expands the walls of the bucket into quads and copies the roofs. The paint properties are
+/// evaluated for each feature at `zoom`.
+fn fill_extrusion_vertices(
+    paint: &FillExtrusionPaint,
+    bucket: &AvailableVectorLayerBucket,
+    zoom: f32,
+) -> (Vec<ShaderFillExtrusionVertex>, Vec<IndexDataType>) {
+    let source = &bucket.buffer.buffer;
+    let mut vertices = Vec::with_capacity(source.vertices.len() * 2);
+    let mut indices = Vec::with_capacity(bucket.buffer.usable_indices as usize * 2);
+
+    let no_properties = FeatureProperties::new();
+    let mut start = 0;
+    for (idx, &count) in bucket.feature_indices.iter().enumerate() {
+        let properties = bucket.feature_properties.get(idx).unwrap_or(&no_properties);
+        let context = EvaluationContext::default()
+            .with_zoom(zoom as f64)
+            .with_properties(properties);
+
+        let height = evaluate_number(&paint.fill_extrusion_height, &context, 0.0);
+        let base = evaluate_number(&paint.fill_extrusion_base, &context, 0.0);
+        let color = paint
+            .fill_extrusion_color
+            .as_ref()
+            .and_then(|color| color.evaluate(&context))
+            .unwrap_or_else(|| Color::new(0.0, 0.0, 0.0, 1.0));
+        // The color is premultiplied, but its alpha is ignored like in maplibre-gl
+        let alpha = color.a as f32;
+        let color = [
+            color.r as f32 * alpha,
+            color.g as f32 * alpha,
+            color.b as f32 * alpha,
+            1.0,
+        ];
+        let vertex = |position, normal, top: bool| ShaderFillExtrusionVertex {
+            position,
+            normal,
+            color,
+            top: top as u32 as f32,
+            base,
+            height,
+        };
+
+        let feature = &source.indices[start..start + count as usize];
+        start += count as usize;
+
+        // Vertices of roofs are shared between their triangles
+        let mut roof_vertices = HashMap::new();
+        let mut i = 0;
+        while i < feature.len() {
+            let first = &source.vertices[feature[i] as usize];
+            if first.normal == [0.0, 0.0] {
+                for &index in &feature[i..(i + 3).min(feature.len())] {
+                    let roof_index = *roof_vertices.entry(index).or_insert_with(|| {
+                        vertices.push(vertex(
+                            source.vertices[index as usize].position,
+                            [0.0, 0.0],
+                            true,
+                        ));
+                        (vertices.len() - 1) as IndexDataType
+                    });
+                    indices.push(roof_index);
+                }
+                i += 3;
+            } else {
+                let Some(&end) = feature.get(i + 1) else {
+                    break;
+                };
+                let end = &source.vertices[end as usize];
+                let first_index = vertices.len() as IndexDataType;
+                vertices.extend([
+                    vertex(first.position, first.normal, false),
+                    vertex(first.position, first.normal, true),
+                    vertex(end.position, end.normal, false),
+                    vertex(end.position, end.normal, true),
+                ]);
+                indices.extend([0, 1, 2, 1, 3, 2].map(|offset| first_index + offset));
+                i += 2;
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+fn evaluate_number(
+    property: &Option<StyleProperty<f32>>,
+    context: &EvaluationContext,
+    default: f32,
+) -> f32 {
+    property
+        .as_ref()
+        .and_then(|property| property.evaluate(context))
+        .unwrap_or(default)
+}
@@ -7,7 +7,9 @@ use geozero::{FeatureProcessor, GeomProcessor, GeozeroDatasource, PropertyProces
 use thiserror::Error;
 
 use crate::{
+    circle::tessellation::CircleTessellator,
     coords::{WorldTileCoords, EXTENT},
+    fill_extrusion::tessellation::FillExtrusionTessellator,
     io::apc::{Context, SendError},
     sdf::{tessellation::TextTessellator, tessellation_new::TextTessellatorNew},
     style::layer::{LayerPaint, StyleLayer},
@@ -188,6 +190,9 @@ pub struct GeoJsonTileRequest {
     pub source_name: String,
     /// If true, applies Web Mercator projection. Tests use false.
     pub project: bool,
+    /// Whether the progress along lines is calculated, see
+    /// [`GeoJsonSource::line_metrics`](crate::style::source::GeoJsonSource::line_metrics).
+    pub line_metrics: bool,
 }
 
 /// Process inline GeoJSON data and tessellate features for each matching style layer.
@@ -224,10 +229,16 @@ pub fn process_geojson_features<T: VectorTransferables, C: Context>(
         match paint {
             LayerPaint::Fill(_) | LayerPaint::Line(_) | LayerPaint::Background(_) => {
                 let mut tessellator = ZeroTessellator::<IndexDataType>::default();
+                tessellator.zoom = u8::from(coords.z) as f64;
                 match paint {
-                    LayerPaint::Fill(p) => tessellator.style_property = p.fill_color.clone(),
+                    LayerPaint::Fill(p) => {
+                        tessellator.style_property = p.fill_color.clone();
+                        tessellator.fill_paint = Some(p.clone());
+                    }
                     LayerPaint::Line(p) => {
                         tessellator.style_property = p.line_color.clone();
+                        tessellator.line_paint = Some(p.clone());
+                        tessellator.line_metrics = request.line_metrics;
                         tessellator.is_line_layer = true;
                     }
                     LayerPaint::Background(p) => {
@@ -272,6 +283,89 @@ pub fn process_geojson_features<T: VectorTransferables, C: Context>(
                         inner.buffer.into(),
                         inner.feature_indices,
                         inner.feature_colors,
+                        inner.retained_feature_properties,
+                        synthetic_layer,
+                        style_layer.id.clone(),
+                    ))
+                    .map_err(ProcessGeoJsonError::SendError)?;
+            }
+            LayerPaint::Circle(_) | LayerPaint::Heatmap(_) => {
+                let zoom = u8::from(coords.z) as f64;
+                // The kernels of heatmaps are centered at the same points as circles
+                let tessellator = match paint {
+                    LayerPaint::Circle(p) => CircleTessellator::new(p, zoom),
+                    LayerPaint::Heatmap(p) => CircleTessellator::for_heatmap(p, zoom),
+                    _ => unreachable!(),
+                };
+                let mut projecting =
+                    ProjectingTessellator::new(coords, request.project, tessellator);
+
+                let mut geojson_src = geozero::geojson::GeoJson(json_str.as_str());
+                if let Err(e) = geojson_src.process(&mut projecting) {
+                    log::warn!(
+                        "GeoJSON point tessellation for layer {} failed: {e:?}",
+                        style_layer.id
+                    );
+                    context
+                        .send_back(T::LayerMissing::build_from(coords, style_layer.id.clone()))
+                        .map_err(ProcessGeoJsonError::SendError)?;
+                    continue;
+                }
+
+                let (buffer, feature_indices, feature_properties) =
+                    projecting.into_inner().finish();
+
+                let synthetic_layer = geozero::mvt::tile::Layer {
+                    version: 2,
+                    name: style_layer.id.clone(),
+                    ..Default::default()
+                };
+
+                context
+                    .send_back(T::LayerTessellated::build_from(
+                        coords,
+                        buffer.into(),
+                        feature_indices,
+                        Vec::new(),
+                        feature_properties,
+                        synthetic_layer,
+                        style_layer.id.clone(),
+                    ))
+                    .map_err(ProcessGeoJsonError::SendError)?;
+            }
+            LayerPaint::FillExtrusion(fill_extrusion_paint) => {
+                let tessellator = FillExtrusionTessellator::new(fill_extrusion_paint);
+                let mut projecting =
+                    ProjectingTessellator::new(coords, request.project, tessellator);
+
+                let mut geojson_src = geozero::geojson::GeoJson(json_str.as_str());
+                if let Err(e) = geojson_src.process(&mut projecting) {
+                    log::warn!(
+                        "GeoJSON fill-extrusion tessellation for layer {} failed: {e:?}",
+                        style_layer.id
+                    );
+                    context
+                        .send_back(T::LayerMissing::build_from(coords, style_layer.id.clone()))
+                        .map_err(ProcessGeoJsonError::SendError)?;
+                    continue;
+                }
+
+                let (buffer, feature_indices, feature_properties) =
+                    projecting.into_inner().finish();
+
+                let synthetic_layer = geozero::mvt::tile::Layer {
+                    version: 2,
+                    name: style_layer.id.clone(),
+                    ..Default::default()
+                };
+
+                context
+                    .send_back(T::LayerTessellated::build_from(
+                        coords,
+                        buffer.into(),
+                        feature_indices,
+                        Vec::new(),
+                        feature_properties,
                         synthetic_layer,
                         style_layer.id.clone(),
                     ))
@@ -279,11 +373,8 @@ pub fn process_geojson_features<T: VectorTransferables, C: Context>(
             }
             LayerPaint::Symbol(symbol_paint) => {
                 let mut tessellator = TextTessellator::<IndexDataType>::default();
-                let text_field = symbol_paint
-                    .text_field
-                    .clone()
-                    .unwrap_or_else(|| "name".to_string());
-                let mut tessellator_new = TextTessellatorNew::new(text_field);
+                let tessellator_new =
+                    TextTessellatorNew::new(symbol_paint, u8::from(coords.z) as f64);
                 let mut projecting =
                     ProjectingTessellator::new(coords, request.project, tessellator_new);
 
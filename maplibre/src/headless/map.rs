@@ -1,33 +1,63 @@
-use std::{cell::RefCell, ops::Deref, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, ops::Deref, rc::Rc};
+
+use thiserror::Error;
 
 use crate::{
+    circle::CircleResources,
+    color_relief::ColorReliefResources,
     context::MapContext,
     coords::{WorldCoords, WorldTileCoords, Zoom, ZoomLevel, TILE_SIZE},
+    fill_extrusion::FillExtrusionResources,
     geojson::{process_geojson_features, GeoJsonTileRequest},
     headless::environment::HeadlessEnvironment,
+    heatmap::HeatmapResources,
+    hillshade::HillshadeResources,
     io::{
         apc::{Context, IntoMessage, Message, SendError},
         source_client::SourceFetchError,
-        source_type::{SourceType, TessellateSource},
+        source_type::{SourceType, TemplateSource, TessellateSource},
+        sprite::{fetch_sprite, SpriteError},
+        tile_json::resolve_sources,
     },
     kernel::Kernel,
     map::MapError,
     plugin::Plugin,
-    render::{eventually::Eventually, view_state::ViewState, Renderer},
+    raster::{DemData, DemDataComponent},
+    render::{eventually::Eventually, image_atlas::ImageAtlas, view_state::ViewState, Renderer},
     schedule::{Schedule, Stage},
-    style::{layer::StyleLayer, Style},
+    sdf::{IconResources, SymbolBufferPool, SymbolLayerData, SymbolLayersDataComponent},
+    style::{layer::StyleLayer, source::RasterDemSource, Style},
     tcs::world::World,
+    terrain::TerrainResources,
     vector::{
         process_vector_tile, AvailableVectorLayerBucket, DefaultVectorTransferables,
-        LayerTessellated, ProcessVectorContext, VectorBufferPool, VectorLayerBucket,
-        VectorLayerBucketComponent, VectorTileRequest, VectorTransferables,
+        LayerTessellated, ProcessVectorContext, SymbolLayerTessellated, VectorBufferPool,
+        VectorLayerBucket, VectorLayerBucketComponent, VectorTileRequest, VectorTransferables,
     },
 };
 
+#[derive(Error, Debug)]
+pub enum DemLoadError {
+    #[error("the raster-dem source does not list any tile URLs")]
+    MissingTiles,
+    #[error("fetching the tile failed")]
+    Fetch(#[from] SourceFetchError),
+    #[error("decoding the tile failed")]
+    Decoding(#[from] image::ImageError),
+    #[error("the tile of a raster-dem source is not square")]
+    InvalidDem,
+    #[error("the raster-dem source does not provide tile 0/0/0")]
+    UnsupportedZoom,
+}
+
 pub struct HeadlessMap {
     kernel: Rc<Kernel<HeadlessEnvironment>>,
     schedule: Schedule,
     map_context: MapContext,
+    /// Symbol layers of processed GeoJSON, which are drawn by the next [`Self::render_tile`].
+    symbol_layers: Vec<SymbolLayerData>,
+    /// Elevations of raster-dem sources, which are drawn by the next [`Self::render_tile`].
+    dem_sources: HashMap<String, DemData>,
 }
 
 impl HeadlessMap {
@@ -69,6 +99,8 @@ impl HeadlessMap {
                 renderer,
             },
             schedule,
+            symbol_layers: Vec::new(),
+            dem_sources: HashMap::new(),
         })
     }
 
@@ -82,6 +114,12 @@ impl HeadlessMap {
         tiles
             .spawn_mut((0, 0, ZoomLevel::default()).into())
             .expect("unable to spawn tile")
+            .insert(SymbolLayersDataComponent {
+                layers: std::mem::take(&mut self.symbol_layers),
+            })
+            .insert(DemDataComponent {
+                sources: std::mem::take(&mut self.dem_sources),
+            })
             .insert(VectorLayerBucketComponent {
                 done: true,
                 layers: layers
@@ -94,6 +132,7 @@ impl HeadlessMap {
                             buffer: layer.buffer,
                             feature_indices: layer.feature_indices,
                             feature_colors: layer.feature_colors,
+                            feature_properties: layer.feature_properties,
                         })
                     })
                     .collect::<Vec<_>>(),
@@ -112,6 +151,111 @@ impl HeadlessMap {
             .expect_initialized_mut("VectorBufferPool not initialized");
 
         pool.clear();
+
+        if let Some(Eventually::Initialized(symbol_buffer_pool)) =
+            resources.query_mut::<&mut Eventually<SymbolBufferPool>>()
+        {
+            symbol_buffer_pool.clear();
+        }
+
+        if let Some(Eventually::Initialized(icon_resources)) =
+            resources.query_mut::<&mut Eventually<IconResources>>()
+        {
+            icon_resources.clear();
+        }
+
+        if let Some(Eventually::Initialized(circle_resources)) =
+            resources.query_mut::<&mut Eventually<CircleResources>>()
+        {
+            circle_resources.clear();
+        }
+
+        if let Some(Eventually::Initialized(fill_extrusion_resources)) =
+            resources.query_mut::<&mut Eventually<FillExtrusionResources>>()
+        {
+            fill_extrusion_resources.clear();
+        }
+
+        if let Some(Eventually::Initialized(heatmap_resources)) =
+            resources.query_mut::<&mut Eventually<HeatmapResources>>()
+        {
+            heatmap_resources.clear();
+        }
+
+        if let Some(Eventually::Initialized(hillshade_resources)) =
+            resources.query_mut::<&mut Eventually<HillshadeResources>>()
+        {
+            hillshade_resources.clear();
+        }
+
+        if let Some(Eventually::Initialized(color_relief_resources)) =
+            resources.query_mut::<&mut Eventually<ColorReliefResources>>()
+        {
+            color_relief_resources.clear();
+        }
+
+        if let Some(Eventually::Initialized(terrain_resources)) =
+            resources.query_mut::<&mut Eventually<TerrainResources>>()
+        {
+            terrain_resources.clear();
+        }
+    }
+
+    pub fn style(&self) -> &Style {
+        &self.map_context.style
+    }
+
+    /// Resolves the TileJSON documents of sources which reference them through their `url`. This
+    /// needs to happen before tiles of these sources are loaded.
+    pub async fn load_tile_json(&mut self) {
+        resolve_sources(
+            &mut self.map_context.style.sources,
+            self.kernel.source_client(),
+        )
+        .await;
+    }
+
+    /// Fetches the sprite of the style, whose images are used for patterns and icons. Styles without a
+    /// sprite are left untouched.
+    pub async fn load_sprite(&mut self) -> Result<(), SpriteError> {
+        let Some(sprite) = &self.map_context.style.sprite else {
+            return Ok(());
+        };
+        let sprite = fetch_sprite(sprite, 1.0, self.kernel.source_client()).await?;
+        self.map_context
+            .world
+            .resources
+            .get_or_init_mut::<ImageAtlas>()
+            .set_sprite(sprite);
+        Ok(())
+    }
+
+    /// Fetches and decodes the elevations of a raster-dem source at tile 0/0/0, which are drawn by
+    /// the next [`Self::render_tile`].
+    ///
+    /// Only zoom level 0 is supported, because [`Self::render_tile`] always draws tile 0/0/0.
+    /// Sources which do not provide this tile are rejected.
+    pub async fn load_dem(
+        &mut self,
+        source_name: &str,
+        source: &RasterDemSource,
+    ) -> Result<(), DemLoadError> {
+        let coords = WorldTileCoords::default();
+        if !source.raster.has_tile(&coords) {
+            return Err(DemLoadError::UnsupportedZoom);
+        }
+        let template_source =
+            TemplateSource::from_raster_source(&source.raster).ok_or(DemLoadError::MissingTiles)?;
+        let data = self
+            .kernel
+            .source_client()
+            .fetch(&coords, &SourceType::Template(template_source))
+            .await?;
+        let image = image::load_from_memory(&data)?.to_rgba8();
+        let dem =
+            DemData::decode(&image, &source.unpack_factors()).ok_or(DemLoadError::InvalidDem)?;
+        self.dem_sources.insert(source_name.to_string(), dem);
+        Ok(())
     }
 
     pub async fn fetch_tile(&self, coords: WorldTileCoords) -> Result<Box<[u8]>, SourceFetchError> {
@@ -157,11 +301,13 @@ impl HeadlessMap {
 
     /// Process inline GeoJSON data for the given style layers and tile coordinates.
     ///
-    /// Returns tessellated layers ready to be passed to [`Self::render_tile`].
+    /// Returns tessellated layers ready to be passed to [`Self::render_tile`]. Tessellated symbol
+    /// layers are kept by the map and drawn by the next [`Self::render_tile`].
     pub fn process_geojson(
         &mut self,
         geojson_value: &serde_json::Value,
         source_name: &str,
+        line_metrics: bool,
         matching_layers: Vec<StyleLayer>,
         target_coords: WorldTileCoords,
         project: bool,
@@ -175,12 +321,26 @@ impl HeadlessMap {
                 layers: matching_layers,
                 source_name: source_name.to_owned(),
                 project,
+                line_metrics,
             },
             &context,
         )
         .expect("Failed to process GeoJSON");
 
-        let messages = context.messages.deref().take();
+        let (symbol_messages, messages): (Vec<_>, Vec<_>) =
+            context.messages.deref().take().into_iter().partition(|message| {
+                message.tag()
+                    == <DefaultVectorTransferables as VectorTransferables>::SymbolLayerTessellated::message_tag()
+            });
+        self.symbol_layers
+            .extend(symbol_messages.into_iter().map(|message| {
+                message
+                .into_transferable::<
+                    <DefaultVectorTransferables as VectorTransferables>::SymbolLayerTessellated,
+                >()
+                .to_bucket()
+            }));
+
         messages
             .into_iter()
             .filter(|message| {
@@ -67,7 +67,7 @@ mod draw_graph {
     pub mod input {}
     // Labels for non-input nodes
     pub mod node {
-        pub const MAIN_PASS: &str = "main_pass";
+        pub const TRANSLUCENT_PASS: &str = "translucent_pass";
         pub const COPY: &str = "copy_pass";
     }
 }
@@ -96,8 +96,9 @@ impl Plugin<HeadlessEnvironment> for HeadlessPlugin {
             .get_sub_graph_mut(draw_graph::NAME)
             .expect("Subgraph does not exist");
         draw_graph.add_node(draw_graph::node::COPY, CopySurfaceBufferNode::default());
+        // The surface is copied after the translucent pass, which draws after the main pass
         draw_graph
-            .add_node_edge(draw_graph::node::MAIN_PASS, draw_graph::node::COPY)
+            .add_node_edge(draw_graph::node::TRANSLUCENT_PASS, draw_graph::node::COPY)
             .unwrap(); // TODO: remove unwrap
 
         schedule.add_system_to_stage(
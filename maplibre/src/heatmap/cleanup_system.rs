@@ -0,0 +1,16 @@
+use crate::{
+    context::MapContext,
+    heatmap::HeatmapItem,
+    render::render_phase::RenderPhase,
+    tcs::system::{SystemError, SystemResult},
+};
+
+pub fn cleanup_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
+    let Some(heatmap_phase) = world.resources.query_mut::<&mut RenderPhase<HeatmapItem>>() else {
+        return Err(SystemError::Dependencies);
+    };
+
+    heatmap_phase.clear();
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use wgpu::StoreOp;
+
+use crate::{
+    heatmap::{HeatmapItem, HeatmapResources},
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        graph::{Node, NodeRunError, RenderContext, RenderGraphContext, SlotInfo},
+        render_phase::RenderPhase,
+        resource::TrackedRenderPass,
+        RenderResources,
+    },
+    tcs::world::World,
+};
+
+/// Pass which sums up the kernels of each heatmap layer in its density texture. The textures are
+/// colored later on by the main pass.
+pub struct HeatmapPassNode {}
+
+impl HeatmapPassNode {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Node for HeatmapPassNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![]
+    }
+
+    fn update(&mut self, _state: &mut RenderResources) {}
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        _resources: &RenderResources,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let Some((Initialized(heatmap_resources), heatmap_items)) = world
+            .resources
+            .query::<(&Eventually<HeatmapResources>, &RenderPhase<HeatmapItem>)>()
+        else {
+            return Ok(());
+        };
+
+        for (style_layer_id, layer) in heatmap_resources.layers() {
+            let render_pass =
+                render_context
+                    .command_encoder
+                    .begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("heatmap_pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &layer.density_view,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                                store: StoreOp::Store,
+                            },
+                            resolve_target: None,
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+
+            let mut tracked_pass = TrackedRenderPass::new(render_pass);
+
+            for item in heatmap_items
+                .into_iter()
+                .filter(|item| item.style_layer == *style_layer_id)
+            {
+                item.draw_function.draw(&mut tracked_pass, world, item);
+            }
+        }
+
+        Ok(())
+    }
+}
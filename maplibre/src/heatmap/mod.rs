@@ -0,0 +1,101 @@
+//! Draws the point features of `heatmap` style layers. The kernels of the points are summed up
+//! in an offscreen density texture by the [`HeatmapPassNode`], which is colored with the
+//! `heatmap-color` ramp during the main pass.
+//!
+//! The features are fetched and tessellated by the [`VectorPlugin`](crate::vector::VectorPlugin),
+//! which therefore needs to be added as well.
+
+use std::rc::Rc;
+
+use crate::{
+    environment::Environment,
+    heatmap::{
+        cleanup_system::cleanup_system, heatmap_pass::HeatmapPassNode, queue_system::queue_system,
+        resource_system::resource_system, upload_system::upload_system,
+    },
+    kernel::Kernel,
+    plugin::Plugin,
+    render::{
+        eventually::Eventually,
+        graph::RenderGraph,
+        render_phase::{Draw, PhaseItem, RenderPhase},
+        tile_view_pattern::TileShape,
+        RenderStageLabel,
+    },
+    schedule::Schedule,
+    tcs::{tiles::Tile, world::World},
+};
+
+mod cleanup_system;
+mod heatmap_pass;
+mod queue_system;
+mod render_commands;
+mod resource;
+mod resource_system;
+mod upload_system;
+
+pub use resource::{HeatmapBuffers, HeatmapLayer, HeatmapResources};
+
+/// Labels for the "draw" graph
+mod draw_graph {
+    pub const NAME: &str = "draw";
+    // Labels for input nodes
+    pub mod input {}
+    // Labels for non-input nodes
+    pub mod node {
+        pub const MAIN_PASS: &str = "main_pass";
+        pub const HEATMAP_PASS: &str = "heatmap_pass";
+    }
+}
+
+/// The kernels of a heatmap layer within a tile, which are drawn into the density texture of the
+/// layer.
+pub struct HeatmapItem {
+    pub draw_function: Box<dyn Draw<HeatmapItem>>,
+    pub index: u32,
+    pub style_layer: String,
+    pub tile: Tile,
+    pub source_shape: TileShape,
+}
+
+impl PhaseItem for HeatmapItem {
+    type SortKey = u32;
+
+    fn sort_key(&self) -> Self::SortKey {
+        self.index
+    }
+
+    fn draw_function(&self) -> &dyn Draw<HeatmapItem> {
+        self.draw_function.as_ref()
+    }
+}
+
+#[derive(Default)]
+pub struct HeatmapPlugin;
+
+impl<E: Environment> Plugin<E> for HeatmapPlugin {
+    fn build(
+        &self,
+        schedule: &mut Schedule,
+        _kernel: Rc<Kernel<E>>,
+        world: &mut World,
+        graph: &mut RenderGraph,
+    ) {
+        let resources = &mut world.resources;
+
+        let draw_graph = graph.get_sub_graph_mut(draw_graph::NAME).unwrap();
+        draw_graph.add_node(draw_graph::node::HEATMAP_PASS, HeatmapPassNode::new());
+        // The density textures are sampled by the main pass
+        draw_graph
+            .add_node_edge(draw_graph::node::HEATMAP_PASS, draw_graph::node::MAIN_PASS)
+            .unwrap();
+
+        resources.init::<RenderPhase<HeatmapItem>>();
+        resources.insert(Eventually::<HeatmapResources>::Uninitialized);
+
+        schedule.add_system_to_stage(RenderStageLabel::Prepare, resource_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, upload_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, queue_system);
+        schedule.add_system_to_stage(RenderStageLabel::Cleanup, cleanup_system);
+    }
+}
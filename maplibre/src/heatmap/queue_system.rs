@@ -0,0 +1,89 @@
+//! Queues [PhaseItems](crate::render::render_phase::PhaseItem) for rendering.
+use crate::{
+    context::MapContext,
+    coords::WorldTileCoords,
+    heatmap::{
+        render_commands::{DrawHeatmapKernels, DrawHeatmapTextures},
+        resource::HeatmapResources,
+        HeatmapItem,
+    },
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{DrawState, LayerItem, RenderPhase},
+        tile_view_pattern::{TileShape, WgpuTileViewPattern},
+    },
+    style::layer::LayerPaint,
+    tcs::{
+        system::{SystemError, SystemResult},
+        tiles::Tile,
+    },
+};
+
+pub fn queue_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some((
+        Initialized(tile_view_pattern),
+        Initialized(heatmap_resources),
+        heatmap_phase,
+        layer_item_phase,
+    )) = world.resources.query_mut::<(
+        &mut Eventually<WgpuTileViewPattern>,
+        &mut Eventually<HeatmapResources>,
+        &mut RenderPhase<HeatmapItem>,
+        &mut RenderPhase<LayerItem>,
+    )>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+
+    for style_layer in &style.layers {
+        if !matches!(style_layer.paint, Some(LayerPaint::Heatmap(_)))
+            || !style_layer.is_visible_at(zoom)
+            || heatmap_resources.get_layer(&style_layer.id).is_none()
+        {
+            continue;
+        }
+
+        for view_tile in tile_view_pattern.iter() {
+            view_tile.render(|source_shape| {
+                let coords = source_shape.coords();
+                if heatmap_resources
+                    .get_buffers(&coords, &style_layer.id)
+                    .is_none()
+                {
+                    return;
+                }
+
+                heatmap_phase.add(HeatmapItem {
+                    draw_function: Box::new(DrawState::<HeatmapItem, DrawHeatmapKernels>::new()),
+                    index: style_layer.index,
+                    style_layer: style_layer.id.clone(),
+                    tile: Tile { coords },
+                    source_shape: source_shape.clone(),
+                });
+            });
+        }
+
+        // Like backgrounds, the density texture covers the whole viewport
+        layer_item_phase.add(LayerItem {
+            draw_function: Box::new(DrawState::<LayerItem, DrawHeatmapTextures>::new()),
+            index: style_layer.index,
+            is_line: false,
+            style_layer: style_layer.id.clone(),
+            tile: Tile {
+                coords: WorldTileCoords::default(),
+            },
+            source_shape: TileShape::default(),
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Specifies the instructions which are going to be sent to the GPU. Render commands can be concatenated
+//! into a new render command which executes multiple instruction sets.
+use crate::{
+    heatmap::{resource::HeatmapResources, HeatmapItem},
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{LayerItem, RenderCommand, RenderCommandResult},
+        resource::TrackedRenderPass,
+        tile_view_pattern::WgpuTileViewPattern,
+        INDEX_FORMAT,
+    },
+    tcs::world::World,
+};
+
+pub struct SetHeatmapPipeline;
+impl RenderCommand<HeatmapItem> for SetHeatmapPipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &HeatmapItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(heatmap_resources)) =
+            world.resources.get::<Eventually<HeatmapResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_render_pipeline(heatmap_resources.kernel_pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawHeatmapKernel;
+impl RenderCommand<HeatmapItem> for DrawHeatmapKernel {
+    fn render<'w>(
+        world: &'w World,
+        item: &HeatmapItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some((Initialized(heatmap_resources), Initialized(tile_view_pattern))) =
+            world.resources.query::<(
+                &Eventually<HeatmapResources>,
+                &Eventually<WgpuTileViewPattern>,
+            )>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let (Some(buffers), Some(layer)) = (
+            heatmap_resources.get_buffers(&item.tile.coords, &item.style_layer),
+            heatmap_resources.get_layer(&item.style_layer),
+        ) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_index_buffer(buffers.indices.slice(..), INDEX_FORMAT);
+        pass.set_vertex_buffer(0, buffers.vertices.slice(..));
+        let tile_view_pattern_buffer = item
+            .source_shape
+            .buffer_range()
+            .expect("tile_view_pattern needs to be uploaded first"); // FIXME tcs
+        pass.set_vertex_buffer(
+            1,
+            tile_view_pattern.buffer().slice(tile_view_pattern_buffer),
+        );
+        pass.set_vertex_buffer(2, layer.metadata.slice(..));
+        pass.draw_indexed(0..buffers.num_indices, 0, 0..1);
+
+        RenderCommandResult::Success
+    }
+}
+
+pub type DrawHeatmapKernels = (SetHeatmapPipeline, DrawHeatmapKernel);
+
+pub struct SetHeatmapTexturePipeline;
+impl RenderCommand<LayerItem> for SetHeatmapTexturePipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(heatmap_resources)) =
+            world.resources.get::<Eventually<HeatmapResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_render_pipeline(heatmap_resources.texture_pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawHeatmapTexture;
+impl RenderCommand<LayerItem> for DrawHeatmapTexture {
+    fn render<'w>(
+        world: &'w World,
+        item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(heatmap_resources)) =
+            world.resources.get::<Eventually<HeatmapResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let Some(layer) = heatmap_resources.get_layer(&item.style_layer) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_bind_group(0, &layer.density_bind_group, &[]);
+        pass.set_bind_group(1, &layer.color_ramp_bind_group, &[]);
+        pass.set_vertex_buffer(0, layer.metadata.slice(..));
+        pass.draw(0..6, 0..1);
+
+        RenderCommandResult::Success
+    }
+}
+
+pub type DrawHeatmapTextures = (SetHeatmapTexturePipeline, DrawHeatmapTexture);
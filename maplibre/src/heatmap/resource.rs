@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::{
+    coords::WorldTileCoords,
+    render::{resource::TilePipeline, shaders::ShaderHeatmapLayerMetadata},
+};
+
+/// Format of the density textures. Densities are summed up beyond 1, so a float format is needed.
+pub const DENSITY_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Like in maplibre-gl, the density is drawn at a quarter of the resolution of the surface.
+pub const DENSITY_RESOLUTION_SCALING: u32 = 4;
+
+/// Count of texels of the color ramp.
+pub const COLOR_RAMP_SIZE: u32 = 256;
+
+/// The GPU buffers of the kernels of a style layer within a tile.
+pub struct HeatmapBuffers {
+    pub vertices: wgpu::Buffer,
+    pub indices: wgpu::Buffer,
+    pub num_indices: u32,
+    /// The zoom level at which the weights and radii were evaluated. `None` if none of them
+    /// depends on the zoom level.
+    pub evaluated_zoom: Option<f32>,
+}
+
+/// The textures of a heatmap layer, which are shared by all tiles.
+pub struct HeatmapLayer {
+    /// Width and height of the density texture.
+    size: (u32, u32),
+    pub density_view: wgpu::TextureView,
+    pub density_bind_group: wgpu::BindGroup,
+    pub color_ramp_bind_group: wgpu::BindGroup,
+    /// The [`ShaderHeatmapLayerMetadata`] of the layer, which is written every frame.
+    pub metadata: wgpu::Buffer,
+}
+
+impl HeatmapLayer {
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+/// Holds the resources necessary for drawing heatmaps such as the
+/// * pipelines
+/// * buffers of each tile
+/// * textures of each layer
+pub struct HeatmapResources {
+    kernel_pipeline: wgpu::RenderPipeline,
+    texture_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    /// The buffers of each tile, keyed by the id of the style layer.
+    buffers: HashMap<WorldTileCoords, HashMap<String, HeatmapBuffers>>,
+    /// The textures of each layer, keyed by the id of the style layer.
+    layers: HashMap<String, HeatmapLayer>,
+}
+
+impl HeatmapResources {
+    pub fn new(
+        device: &wgpu::Device,
+        kernel_pipeline: wgpu::RenderPipeline,
+        texture_pipeline: wgpu::RenderPipeline,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Heatmap bind group layout"),
+            entries: &TilePipeline::texture_bind_group_layout_entries(),
+        });
+
+        // Clamps the densities above 1 to the end of the color ramp
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Heatmap sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            kernel_pipeline,
+            texture_pipeline,
+            bind_group_layout,
+            sampler,
+            buffers: Default::default(),
+            layers: Default::default(),
+        }
+    }
+
+    pub fn kernel_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.kernel_pipeline
+    }
+
+    pub fn texture_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.texture_pipeline
+    }
+
+    pub fn get_buffers(
+        &self,
+        coords: &WorldTileCoords,
+        style_layer_id: &str,
+    ) -> Option<&HeatmapBuffers> {
+        self.buffers.get(coords)?.get(style_layer_id)
+    }
+
+    pub fn insert_buffers(
+        &mut self,
+        coords: WorldTileCoords,
+        style_layer_id: &str,
+        buffers: HeatmapBuffers,
+    ) {
+        self.buffers
+            .entry(coords)
+            .or_default()
+            .insert(style_layer_id.to_string(), buffers);
+    }
+
+    pub fn get_layer(&self, style_layer_id: &str) -> Option<&HeatmapLayer> {
+        self.layers.get(style_layer_id)
+    }
+
+    /// Returns the textures of all layers, keyed by the id of the style layer.
+    pub fn layers(&self) -> impl Iterator<Item = (&String, &HeatmapLayer)> {
+        self.layers.iter()
+    }
+
+    /// Creates the textures of a layer with a density texture of `size` and the premultiplied
+    /// RGBA texels of its color ramp.
+    pub fn insert_layer(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        style_layer_id: &str,
+        size: (u32, u32),
+        color_ramp: &[u8],
+    ) {
+        let density_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Heatmap density texture"),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DENSITY_TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let density_view = density_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let color_ramp_size = wgpu::Extent3d {
+            width: COLOR_RAMP_SIZE,
+            height: 1,
+            depth_or_array_layers: 1,
+        };
+        let color_ramp_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Heatmap color ramp texture"),
+            size: color_ramp_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &color_ramp_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            color_ramp,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(COLOR_RAMP_SIZE * 4),
+                rows_per_image: Some(1),
+            },
+            color_ramp_size,
+        );
+        let color_ramp_view =
+            color_ramp_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let layer = HeatmapLayer {
+            size,
+            density_bind_group: self.create_bind_group(device, &density_view),
+            color_ramp_bind_group: self.create_bind_group(device, &color_ramp_view),
+            density_view,
+            metadata: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Heatmap Layer Metadata Buffer"),
+                size: std::mem::size_of::<ShaderHeatmapLayerMetadata>() as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+        };
+        self.layers.insert(style_layer_id.to_string(), layer);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+        self.layers.clear();
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Heatmap bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        })
+    }
+}
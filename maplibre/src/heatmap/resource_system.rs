@@ -0,0 +1,80 @@
+//! Prepares GPU-owned resources by initializing them if they are uninitialized or out-of-date.
+use crate::{
+    context::MapContext,
+    heatmap::resource::{HeatmapResources, DENSITY_TEXTURE_FORMAT},
+    render::{
+        eventually::Eventually,
+        resource::{RenderPipeline, TilePipeline},
+        shaders,
+        shaders::Shader,
+        RenderResources, Renderer,
+    },
+    tcs::system::{SystemError, SystemResult},
+};
+
+pub fn resource_system(
+    MapContext {
+        world,
+        renderer:
+            Renderer {
+                device,
+                resources: RenderResources { surface, .. },
+                settings,
+                ..
+            },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(heatmap_resources) = world
+        .resources
+        .query_mut::<&mut Eventually<HeatmapResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    heatmap_resources.initialize(|| {
+        let kernel_shader = shaders::HeatmapShader {
+            format: DENSITY_TEXTURE_FORMAT,
+        };
+        // The density texture has neither a depth stencil nor multiple samples
+        let kernel_pipeline = TilePipeline::new(
+            "heatmap_pipeline".into(),
+            *settings,
+            kernel_shader.describe_vertex(),
+            kernel_shader.describe_fragment(),
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+        )
+        .describe_render_pipeline()
+        .initialize(device);
+
+        let texture_shader = shaders::HeatmapTextureShader {
+            format: surface.surface_format(),
+        };
+        // Binds the density texture and the color ramp
+        let texture_pipeline = TilePipeline::new(
+            "heatmap_texture_pipeline".into(),
+            *settings,
+            texture_shader.describe_vertex(),
+            texture_shader.describe_fragment(),
+            true,
+            false,
+            true,
+            false,
+            surface.is_multisampling_supported(settings.msaa),
+            false,
+            false,
+        )
+        .with_texture_bind_groups(2)
+        .describe_render_pipeline()
+        .initialize(device);
+
+        HeatmapResources::new(device, kernel_pipeline, texture_pipeline)
+    });
+    Ok(())
+}
@@ -0,0 +1,279 @@
+//! Uploads data to the GPU which is needed for rendering.
+
+use csscolorparser::Color;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    context::MapContext,
+    heatmap::resource::{
+        HeatmapBuffers, HeatmapResources, COLOR_RAMP_SIZE, DENSITY_RESOLUTION_SCALING,
+    },
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        shaders::{ShaderHeatmapLayerMetadata, ShaderHeatmapVertex},
+        tile_view_pattern::DEFAULT_TILE_SIZE,
+        view_state::ViewStatePadding,
+        RenderResources, Renderer,
+    },
+    style::{
+        expression::{EvaluationContext, FeatureProperties},
+        layer::{HeatmapPaint, LayerPaint, StyleProperty},
+    },
+    tcs::system::{SystemError, SystemResult},
+    vector::{
+        tessellation::IndexDataType, AvailableVectorLayerBucket, VectorLayerBucket,
+        VectorLayerBucketComponent,
+    },
+};
+
+/// The corners of the quad of a kernel.
+const EXTRUDES: [[f32; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+pub fn upload_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        renderer:
+            Renderer {
+                device,
+                queue,
+                resources: RenderResources { surface, .. },
+                ..
+            },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(Initialized(heatmap_resources)) = world
+        .resources
+        .query_mut::<&mut Eventually<HeatmapResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+    let size = surface.size();
+    let density_size = (
+        (size.width() / DENSITY_RESOLUTION_SCALING).max(1),
+        (size.height() / DENSITY_RESOLUTION_SCALING).max(1),
+    );
+
+    for style_layer in &style.layers {
+        let Some(LayerPaint::Heatmap(paint)) = &style_layer.paint else {
+            continue;
+        };
+
+        // The density texture follows the size of the surface
+        if heatmap_resources
+            .get_layer(&style_layer.id)
+            .is_none_or(|layer| layer.size() != density_size)
+        {
+            let Some(color_ramp) = color_ramp(paint) else {
+                log::warn!(
+                    "the heatmap-color of layer {} can not be evaluated",
+                    style_layer.id
+                );
+                continue;
+            };
+            heatmap_resources.insert_layer(
+                device,
+                queue,
+                &style_layer.id,
+                density_size,
+                &color_ramp,
+            );
+        }
+
+        if let Some(layer) = heatmap_resources.get_layer(&style_layer.id) {
+            queue.write_buffer(
+                &layer.metadata,
+                0,
+                bytemuck::cast_slice(&[layer_metadata(paint, zoom)]),
+            );
+        }
+    }
+
+    let Some(view_region) = view_state.create_view_region(
+        view_state.zoom().zoom_level(DEFAULT_TILE_SIZE),
+        ViewStatePadding::Loose,
+    ) else {
+        return Ok(());
+    };
+
+    for coords in view_region.iter() {
+        let Some(vector_layers) = world.tiles.query::<&VectorLayerBucketComponent>(coords) else {
+            continue;
+        };
+
+        for style_layer in &style.layers {
+            let Some(LayerPaint::Heatmap(paint)) = &style_layer.paint else {
+                continue;
+            };
+
+            // Zoom-dependent properties are re-evaluated whenever the zoom level changes
+            if heatmap_resources
+                .get_buffers(&coords, &style_layer.id)
+                .is_some_and(|buffers| buffers.evaluated_zoom.is_none_or(|z| z == zoom))
+            {
+                continue;
+            }
+
+            let Some(bucket) = vector_layers.layers.iter().find_map(|layer| match layer {
+                VectorLayerBucket::AvailableLayer(bucket)
+                    if bucket.style_layer_id == style_layer.id =>
+                {
+                    Some(bucket)
+                }
+                _ => None,
+            }) else {
+                continue;
+            };
+
+            let (vertices, indices) = heatmap_vertices(paint, bucket, zoom);
+            if indices.is_empty() {
+                continue;
+            }
+
+            log::debug!("Allocating heatmap kernels at {}", bucket.coords);
+            heatmap_resources.insert_buffers(
+                coords,
+                &style_layer.id,
+                HeatmapBuffers {
+                    vertices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Heatmap Vertex Buffer"),
+                        contents: bytemuck::cast_slice(&vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                    indices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Heatmap Index Buffer"),
+                        contents: bytemuck::cast_slice(&indices),
+                        usage: wgpu::BufferUsages::INDEX,
+                    }),
+                    num_indices: indices.len() as u32,
+                    evaluated_zoom: (!paint.is_feature_zoom_constant()).then_some(zoom),
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn layer_metadata(paint: &HeatmapPaint, zoom: f32) -> ShaderHeatmapLayerMetadata {
+    ShaderHeatmapLayerMetadata {
+        intensity: paint
+            .heatmap_intensity
+            .as_ref()
+            .and_then(|intensity| intensity.evaluate_at_zoom(zoom))
+            .unwrap_or(1.0),
+        opacity: paint
+            .heatmap_opacity
+            .as_ref()
+            .and_then(|opacity| opacity.evaluate_at_zoom(zoom))
+            .unwrap_or(1.0),
+    }
+}
+
+/// Returns the premultiplied RGBA texels of the `heatmap-color` of the layer, which maps
+/// densities between 0 and 1 to colors.
+fn color_ramp(paint: &HeatmapPaint) -> Option<Vec<u8>> {
+    let default_color = paint.heatmap_color.is_none().then(default_heatmap_color);
+    let color = paint.heatmap_color.as_ref().or(default_color.as_ref())?;
+
+    let mut texels = Vec::with_capacity(COLOR_RAMP_SIZE as usize * 4);
+    for x in 0..COLOR_RAMP_SIZE {
+        let density = x as f64 / (COLOR_RAMP_SIZE - 1) as f64;
+        let Color { r, g, b, a } =
+            color.evaluate(&EvaluationContext::default().with_heatmap_density(density))?;
+        texels.extend([r * a, g * a, b * a, a].map(|c| (c * 255.0).round() as u8));
+    }
+    Some(texels)
+}
+
+/// The default `heatmap-color` of the style specification.
+fn default_heatmap_color() -> StyleProperty<Color> {
+    serde_json::from_value(serde_json::json!([
+        "interpolate",
+        ["linear"],
+        ["heatmap-density"],
+        0,
+        "rgba(0, 0, 255, 0)",
+        0.1,
+        "royalblue",
+        0.3,
+        "cyan",
+        0.5,
+        "lime",
+        0.7,
+        "yellow",
+        1,
+        "red"
+    ]))
+    .expect("the default heatmap-color is valid")
+}
+
+/// Expands every point of the bucket into the quad of a kernel. The weight and radius are
+/// evaluated for each feature at `zoom`. Points without weight have no kernel.
+fn heatmap_vertices(
+    paint: &HeatmapPaint,
+    bucket: &AvailableVectorLayerBucket,
+    zoom: f32,
+) -> (Vec<ShaderHeatmapVertex>, Vec<IndexDataType>) {
+    let centers = &bucket.buffer.buffer.vertices;
+    let mut vertices = Vec::with_capacity(centers.len() * 4);
+    let mut indices = Vec::with_capacity(centers.len() * 6);
+
+    let no_properties = FeatureProperties::new();
+    let mut centers = centers.iter();
+    for (idx, &count) in bucket.feature_indices.iter().enumerate() {
+        let properties = bucket.feature_properties.get(idx).unwrap_or(&no_properties);
+        let context = EvaluationContext::default()
+            .with_zoom(zoom as f64)
+            .with_properties(properties);
+
+        let weight = evaluate_number(&paint.heatmap_weight, &context, 1.0);
+        let radius = evaluate_number(&paint.heatmap_radius, &context, 30.0);
+
+        for center in centers.by_ref().take(count as usize) {
+            if weight <= 0.0 || radius <= 0.0 {
+                continue;
+            }
+
+            let first = vertices.len() as IndexDataType;
+            indices.extend([0, 1, 2, 0, 2, 3].map(|offset| first + offset));
+            vertices.extend(EXTRUDES.map(|extrude| ShaderHeatmapVertex {
+                position: center.position,
+                extrude,
+                weight,
+                radius,
+            }));
+        }
+    }
+
+    (vertices, indices)
+}
+
+fn evaluate_number(
+    property: &Option<StyleProperty<f32>>,
+    context: &EvaluationContext,
+    default: f32,
+) -> f32 {
+    property
+        .as_ref()
+        .and_then(|property| property.evaluate(context))
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_color_ramp() {
+        let texels = color_ramp(&HeatmapPaint::default()).unwrap();
+        assert_eq!(texels.len(), COLOR_RAMP_SIZE as usize * 4);
+        // Transparent at a density of 0 and red at a density of 1
+        assert_eq!(texels[..4], [0, 0, 0, 0]);
+        assert_eq!(texels[texels.len() - 4..], [255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,20 @@
+use crate::{
+    context::MapContext,
+    hillshade::HillshadeResources,
+    render::eventually::{Eventually, Eventually::Initialized},
+    tcs::system::{SystemError, SystemResult},
+};
+
+pub fn cleanup_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
+    let Some(Initialized(hillshade_resources)) = world
+        .resources
+        .query_mut::<&mut Eventually<HillshadeResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    // The slopes of the pending tiles were calculated by the prepare pass of this frame
+    hillshade_resources.clear_pending();
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! Draws `hillshade` style layers. The slopes of each tile are calculated from the elevations of
+//! its raster-dem source by the [`HillshadePrepareNode`] and shaded during the main pass.
+//!
+//! The elevations are fetched and decoded by the [`RasterPlugin`](crate::raster::RasterPlugin),
+//! which therefore needs to be added as well.
+
+use std::rc::Rc;
+
+use crate::{
+    environment::Environment,
+    hillshade::{
+        cleanup_system::cleanup_system, prepare_pass::HillshadePrepareNode,
+        queue_system::queue_system, resource_system::resource_system, upload_system::upload_system,
+    },
+    kernel::Kernel,
+    plugin::Plugin,
+    render::{eventually::Eventually, graph::RenderGraph, RenderStageLabel},
+    schedule::Schedule,
+    tcs::world::World,
+};
+
+mod cleanup_system;
+mod prepare_pass;
+mod queue_system;
+mod render_commands;
+mod resource;
+mod resource_system;
+mod upload_system;
+
+pub use resource::{HillshadeLayer, HillshadeResources, HillshadeTile};
+
+/// Labels for the "draw" graph
+mod draw_graph {
+    pub const NAME: &str = "draw";
+    // Labels for input nodes
+    pub mod input {}
+    // Labels for non-input nodes
+    pub mod node {
+        pub const MAIN_PASS: &str = "main_pass";
+        pub const HILLSHADE_PREPARE_PASS: &str = "hillshade_prepare_pass";
+    }
+}
+
+#[derive(Default)]
+pub struct HillshadePlugin;
+
+impl<E: Environment> Plugin<E> for HillshadePlugin {
+    fn build(
+        &self,
+        schedule: &mut Schedule,
+        _kernel: Rc<Kernel<E>>,
+        world: &mut World,
+        graph: &mut RenderGraph,
+    ) {
+        let draw_graph = graph.get_sub_graph_mut(draw_graph::NAME).unwrap();
+        draw_graph.add_node(
+            draw_graph::node::HILLSHADE_PREPARE_PASS,
+            HillshadePrepareNode::new(),
+        );
+        // The slopes are sampled by the main pass
+        draw_graph
+            .add_node_edge(
+                draw_graph::node::HILLSHADE_PREPARE_PASS,
+                draw_graph::node::MAIN_PASS,
+            )
+            .unwrap();
+
+        world
+            .resources
+            .insert(Eventually::<HillshadeResources>::Uninitialized);
+
+        schedule.add_system_to_stage(RenderStageLabel::Prepare, resource_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, upload_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, queue_system);
+        schedule.add_system_to_stage(RenderStageLabel::Cleanup, cleanup_system);
+    }
+}
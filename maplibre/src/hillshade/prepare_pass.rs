@@ -0,0 +1,69 @@
+use wgpu::StoreOp;
+
+use crate::{
+    hillshade::HillshadeResources,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        graph::{Node, NodeRunError, RenderContext, RenderGraphContext, SlotInfo},
+        RenderResources,
+    },
+    tcs::world::World,
+};
+
+/// Pass which calculates the slopes of the tiles whose elevations were uploaded in this frame.
+/// The slopes are shaded later on by the main pass.
+pub struct HillshadePrepareNode {}
+
+impl HillshadePrepareNode {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Node for HillshadePrepareNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![]
+    }
+
+    fn update(&mut self, _state: &mut RenderResources) {}
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        _resources: &RenderResources,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let Some(Initialized(hillshade_resources)) =
+            world.resources.get::<Eventually<HillshadeResources>>()
+        else {
+            return Ok(());
+        };
+
+        for tile in hillshade_resources.pending_tiles() {
+            let mut pass =
+                render_context
+                    .command_encoder
+                    .begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("hillshade_prepare_pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &tile.derivative_view,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                                store: StoreOp::Store,
+                            },
+                            resolve_target: None,
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+
+            pass.set_pipeline(hillshade_resources.prepare_pipeline());
+            pass.set_bind_group(0, &tile.prepare_bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+//! Queues [PhaseItems](crate::render::render_phase::PhaseItem) for rendering.
+
+use crate::{
+    context::MapContext,
+    hillshade::{render_commands::DrawHillshadeTiles, resource::HillshadeResources},
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_commands::DrawMasks,
+        render_phase::{DrawState, LayerItem, RenderPhase, TileMaskItem},
+        tile_view_pattern::WgpuTileViewPattern,
+    },
+    style::layer::LayerPaint,
+    tcs::{
+        system::{SystemError, SystemResult},
+        tiles::Tile,
+    },
+};
+
+pub fn queue_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some((Initialized(tile_view_pattern), Initialized(hillshade_resources))) =
+        world.resources.query::<(
+            &Eventually<WgpuTileViewPattern>,
+            &Eventually<HillshadeResources>,
+        )>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+    let hillshade_layers = style
+        .layers
+        .iter()
+        .filter(|layer| {
+            matches!(layer.paint, Some(LayerPaint::Hillshade(_))) && layer.is_visible_at(zoom)
+        })
+        .collect::<Vec<_>>();
+
+    let mut items = Vec::new();
+
+    for view_tile in tile_view_pattern.iter() {
+        view_tile.render(|source_shape| {
+            let source_coords = source_shape.coords();
+            for style_layer in &hillshade_layers {
+                if hillshade_resources
+                    .get_tile(&source_coords, &style_layer.id)
+                    .is_none()
+                {
+                    continue;
+                }
+
+                items.push((
+                    LayerItem {
+                        draw_function: Box::new(DrawState::<LayerItem, DrawHillshadeTiles>::new()),
+                        index: style_layer.index,
+                        is_line: false,
+                        style_layer: style_layer.id.clone(),
+                        tile: Tile {
+                            coords: source_coords,
+                        },
+                        source_shape: source_shape.clone(),
+                    },
+                    // FIXME tsc: Tile masks are currently drawn twice by each plugin
+                    TileMaskItem {
+                        draw_function: Box::new(DrawState::<TileMaskItem, DrawMasks>::new()),
+                        source_shape: source_shape.clone(),
+                    },
+                ));
+            }
+        });
+    }
+
+    let Some((layer_item_phase, tile_mask_phase)) = world
+        .resources
+        .query_mut::<(&mut RenderPhase<LayerItem>, &mut RenderPhase<TileMaskItem>)>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    for (layer, mask) in items {
+        layer_item_phase.add(layer);
+        tile_mask_phase.add(mask);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use crate::{
+    hillshade::resource::HillshadeResources,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{LayerItem, PhaseItem, RenderCommand, RenderCommandResult},
+        resource::TrackedRenderPass,
+        tile_view_pattern::WgpuTileViewPattern,
+    },
+    tcs::world::World,
+};
+
+pub struct SetHillshadePipeline;
+impl<P: PhaseItem> RenderCommand<P> for SetHillshadePipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &P,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(hillshade_resources)) =
+            world.resources.get::<Eventually<HillshadeResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_render_pipeline(hillshade_resources.pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+/// Binds the slopes of the tile and the metadata of the layer.
+pub struct SetHillshadeBindGroups;
+impl RenderCommand<LayerItem> for SetHillshadeBindGroups {
+    fn render<'w>(
+        world: &'w World,
+        item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(hillshade_resources)) =
+            world.resources.get::<Eventually<HillshadeResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let (Some(tile), Some(layer)) = (
+            hillshade_resources.get_tile(&item.tile.coords, &item.style_layer),
+            hillshade_resources.get_layer(&item.style_layer),
+        ) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_bind_group(0, &tile.derivative_bind_group, &[]);
+        pass.set_bind_group(1, &layer.bind_group, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawHillshadeTile;
+impl RenderCommand<LayerItem> for DrawHillshadeTile {
+    fn render<'w>(
+        world: &'w World,
+        item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(tile_view_pattern)) =
+            world.resources.get::<Eventually<WgpuTileViewPattern>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let source_shape = &item.source_shape;
+
+        let reference = source_shape.coords().stencil_reference_value_3d() as u32;
+
+        pass.set_stencil_reference(reference);
+
+        let tile_view_pattern_buffer = source_shape
+            .buffer_range()
+            .expect("tile_view_pattern needs to be uploaded first"); // FIXME tcs
+        pass.set_vertex_buffer(
+            0,
+            tile_view_pattern.buffer().slice(tile_view_pattern_buffer),
+        );
+
+        const TILE_SHADER_VERTICES: u32 = 6;
+        pass.draw(0..TILE_SHADER_VERTICES, 0..1);
+
+        RenderCommandResult::Success
+    }
+}
+
+pub type DrawHillshadeTiles = (
+    SetHillshadePipeline,
+    SetHillshadeBindGroups,
+    DrawHillshadeTile,
+);
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    coords::WorldTileCoords,
+    raster::DemData,
+    render::shaders::{ShaderHillshadeLayerMetadata, ShaderHillshadeTileMetadata},
+};
+
+/// Format of the textures with the slopes of the tiles. The slopes are signed and not limited to
+/// a range, so a float format is needed.
+pub const DERIVATIVE_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// The textures of a style layer within a tile.
+pub struct HillshadeTile {
+    /// The [`DemData::revision`] of the elevations the slopes were calculated from.
+    pub dem_revision: u32,
+    /// Binds the elevations and the [`ShaderHillshadeTileMetadata`] of the prepare pass.
+    pub prepare_bind_group: wgpu::BindGroup,
+    pub derivative_view: wgpu::TextureView,
+    pub derivative_bind_group: wgpu::BindGroup,
+}
+
+/// The uniform buffer of a hillshade layer, which is shared by all tiles.
+pub struct HillshadeLayer {
+    /// The [`ShaderHillshadeLayerMetadata`] of the layer, which is written every frame.
+    pub metadata: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+/// Holds the resources necessary for drawing hillshades such as the
+/// * pipelines
+/// * textures of each tile
+/// * uniform buffers of each layer
+pub struct HillshadeResources {
+    prepare_pipeline: wgpu::RenderPipeline,
+    pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    /// The textures of each tile, keyed by the id of the style layer.
+    tiles: HashMap<WorldTileCoords, HashMap<String, HillshadeTile>>,
+    /// The uniform buffers of each layer, keyed by the id of the style layer.
+    layers: HashMap<String, HillshadeLayer>,
+    /// Tiles whose slopes are calculated by the next prepare pass.
+    pending: Vec<(WorldTileCoords, String)>,
+}
+
+impl HillshadeResources {
+    pub fn new(
+        device: &wgpu::Device,
+        prepare_pipeline: wgpu::RenderPipeline,
+        pipeline: wgpu::RenderPipeline,
+    ) -> Self {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Hillshade sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            prepare_pipeline,
+            pipeline,
+            sampler,
+            tiles: Default::default(),
+            layers: Default::default(),
+            pending: Default::default(),
+        }
+    }
+
+    pub fn prepare_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.prepare_pipeline
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn get_tile(
+        &self,
+        coords: &WorldTileCoords,
+        style_layer_id: &str,
+    ) -> Option<&HillshadeTile> {
+        self.tiles.get(coords)?.get(style_layer_id)
+    }
+
+    /// Uploads the elevations of a tile and queues the calculation of its slopes for the next
+    /// prepare pass.
+    pub fn insert_tile(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        coords: WorldTileCoords,
+        style_layer_id: &str,
+        dem: &DemData,
+    ) {
+        let dem_size = wgpu::Extent3d {
+            width: dem.stride(),
+            height: dem.stride(),
+            depth_or_array_layers: 1,
+        };
+        let dem_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Hillshade DEM texture"),
+            size: dem_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &dem_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            dem.as_bytes(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * dem.stride()),
+                rows_per_image: Some(dem.stride()),
+            },
+            dem_size,
+        );
+        let dem_view = dem_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let metadata = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Hillshade Tile Metadata Buffer"),
+            contents: bytemuck::cast_slice(&[tile_metadata(&coords)]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let derivative_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Hillshade derivative texture"),
+            size: wgpu::Extent3d {
+                width: dem.dim(),
+                height: dem.dim(),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DERIVATIVE_TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let derivative_view =
+            derivative_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let tile = HillshadeTile {
+            dem_revision: dem.revision(),
+            prepare_bind_group: device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Hillshade prepare bind group"),
+                layout: &self.prepare_pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&dem_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: metadata.as_entire_binding(),
+                    },
+                ],
+            }),
+            derivative_bind_group: device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Hillshade derivative bind group"),
+                layout: &self.pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&derivative_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            }),
+            derivative_view,
+        };
+
+        self.tiles
+            .entry(coords)
+            .or_default()
+            .insert(style_layer_id.to_string(), tile);
+        self.pending.push((coords, style_layer_id.to_string()));
+    }
+
+    /// Returns the tiles whose slopes are calculated by the next prepare pass.
+    pub fn pending_tiles(&self) -> impl Iterator<Item = &HillshadeTile> {
+        self.pending
+            .iter()
+            .flat_map(|(coords, style_layer_id)| self.get_tile(coords, style_layer_id))
+    }
+
+    pub fn clear_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn get_layer(&self, style_layer_id: &str) -> Option<&HillshadeLayer> {
+        self.layers.get(style_layer_id)
+    }
+
+    /// Returns the uniform buffer of a layer, which is created if the layer has none yet.
+    pub fn layer_metadata_or_insert(
+        &mut self,
+        device: &wgpu::Device,
+        style_layer_id: &str,
+    ) -> &wgpu::Buffer {
+        if !self.layers.contains_key(style_layer_id) {
+            let metadata = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Hillshade Layer Metadata Buffer"),
+                size: std::mem::size_of::<ShaderHillshadeLayerMetadata>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Hillshade layer bind group"),
+                layout: &self.pipeline.get_bind_group_layout(1),
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: metadata.as_entire_binding(),
+                }],
+            });
+            self.layers.insert(
+                style_layer_id.to_string(),
+                HillshadeLayer {
+                    metadata,
+                    bind_group,
+                },
+            );
+        }
+        &self.layers[style_layer_id].metadata
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.layers.clear();
+        self.pending.clear();
+    }
+}
+
+fn tile_metadata(coords: &WorldTileCoords) -> ShaderHillshadeTileMetadata {
+    let z = u8::from(coords.z);
+    ShaderHillshadeTileMetadata {
+        latitudes: [
+            tile_latitude(coords.y, z) as f32,
+            tile_latitude(coords.y + 1, z) as f32,
+        ],
+        zoom: f32::from(z),
+        _padding: 0.0,
+    }
+}
+
+/// Returns the latitude in degrees of the northern edge of the tiles in row `y`.
+fn tile_latitude(y: i32, z: u8) -> f64 {
+    let n = std::f64::consts::PI * (1.0 - 2.0 * f64::from(y) / f64::from(1u32 << z));
+    n.sinh().atan().to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_latitude() {
+        assert!((tile_latitude(0, 0) - 85.051129).abs() < 1e-6);
+        assert!(tile_latitude(1, 1).abs() < 1e-9);
+        assert!((tile_latitude(1, 0) + 85.051129).abs() < 1e-6);
+    }
+}
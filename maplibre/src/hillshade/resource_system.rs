@@ -0,0 +1,98 @@
+//! Prepares GPU-owned resources by initializing them if they are uninitialized or out-of-date.
+use crate::{
+    context::MapContext,
+    hillshade::resource::{HillshadeResources, DERIVATIVE_TEXTURE_FORMAT},
+    render::{
+        eventually::Eventually,
+        resource::{RenderPipeline, RenderPipelineDescriptor, TilePipeline},
+        shaders,
+        shaders::Shader,
+        RenderResources, Renderer,
+    },
+    tcs::system::{SystemError, SystemResult},
+};
+
+pub fn resource_system(
+    MapContext {
+        world,
+        renderer:
+            Renderer {
+                device,
+                resources: RenderResources { surface, .. },
+                settings,
+                ..
+            },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(hillshade_resources) = world
+        .resources
+        .query_mut::<&mut Eventually<HillshadeResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    hillshade_resources.initialize(|| {
+        let prepare_shader = shaders::HillshadePrepareShader {
+            format: DERIVATIVE_TEXTURE_FORMAT,
+        };
+        // Binds the elevations, which are loaded texel by texel, and the metadata of the tile
+        let prepare_pipeline = RenderPipelineDescriptor {
+            label: Some("hillshade_prepare_pipeline".into()),
+            layout: Some(vec![vec![
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ]]),
+            vertex: prepare_shader.describe_vertex(),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: prepare_shader.describe_fragment(),
+        }
+        .initialize(device);
+
+        let shader = shaders::HillshadeShader {
+            format: surface.surface_format(),
+        };
+        let pipeline = TilePipeline::new(
+            "hillshade_pipeline".into(),
+            *settings,
+            shader.describe_vertex(),
+            shader.describe_fragment(),
+            true,
+            false,
+            false,
+            false,
+            surface.is_multisampling_supported(settings.msaa),
+            true,
+            false,
+        )
+        .with_uniform_bind_group()
+        .describe_render_pipeline()
+        .initialize(device);
+
+        HillshadeResources::new(device, prepare_pipeline, pipeline)
+    });
+    Ok(())
+}
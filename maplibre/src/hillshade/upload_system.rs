@@ -0,0 +1,223 @@
+//! Uploads data to the GPU which is needed for rendering.
+
+use cgmath::Deg;
+use csscolorparser::Color;
+
+use crate::{
+    context::MapContext,
+    hillshade::resource::HillshadeResources,
+    raster::DemDataComponent,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        shaders::{ShaderHillshadeLayerMetadata, Vec4f32},
+        tile_view_pattern::DEFAULT_TILE_SIZE,
+        view_state::ViewStatePadding,
+        Renderer,
+    },
+    style::{
+        expression::FromValue,
+        layer::{HillshadePaint, LayerPaint, OneOrMany, Reference, StyleProperty},
+    },
+    tcs::system::{SystemError, SystemResult},
+};
+
+/// The number of light sources of the `multidirectional` method which are supported.
+const MAX_LIGHTS: usize = 4;
+
+pub fn upload_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        renderer: Renderer { device, queue, .. },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(Initialized(hillshade_resources)) = world
+        .resources
+        .query_mut::<&mut Eventually<HillshadeResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let zoom = view_state.zoom().level();
+    let bearing = Deg::from(view_state.camera().get_roll()).0;
+
+    // The metadata is written every frame, because the light may follow the bearing of the map
+    for style_layer in &style.layers {
+        let Some(LayerPaint::Hillshade(paint)) = &style_layer.paint else {
+            continue;
+        };
+        let buffer = hillshade_resources.layer_metadata_or_insert(device, &style_layer.id);
+        queue.write_buffer(
+            buffer,
+            0,
+            bytemuck::cast_slice(&[layer_metadata(paint, bearing, zoom)]),
+        );
+    }
+
+    let Some(view_region) = view_state.create_view_region(
+        view_state.zoom().zoom_level(DEFAULT_TILE_SIZE),
+        ViewStatePadding::Loose,
+    ) else {
+        return Ok(());
+    };
+
+    for coords in view_region.iter() {
+        let Some(dem_data) = world.tiles.query::<&DemDataComponent>(coords) else {
+            continue;
+        };
+
+        for style_layer in &style.layers {
+            if !matches!(style_layer.paint, Some(LayerPaint::Hillshade(_))) {
+                continue;
+            }
+            let Some(dem) = style_layer
+                .source
+                .as_ref()
+                .and_then(|source| dem_data.sources.get(source))
+            else {
+                continue;
+            };
+
+            // The slopes are calculated again once the borders were filled by the neighbors
+            if hillshade_resources
+                .get_tile(&coords, &style_layer.id)
+                .is_some_and(|tile| tile.dem_revision == dem.revision())
+            {
+                continue;
+            }
+
+            log::debug!("Preparing hillshade at {coords}");
+            hillshade_resources.insert_tile(device, queue, coords, &style_layer.id, dem);
+        }
+    }
+
+    Ok(())
+}
+
+fn layer_metadata(paint: &HillshadePaint, bearing: f64, zoom: f32) -> ShaderHillshadeLayerMetadata {
+    let directions = evaluate_lights(&paint.hillshade_illumination_direction, zoom, 335.0);
+    let altitudes = evaluate_lights(&paint.hillshade_illumination_altitude, zoom, 45.0);
+    let shadows = evaluate_lights(
+        &paint.hillshade_shadow_color,
+        zoom,
+        Color::new(0.0, 0.0, 0.0, 1.0),
+    );
+    let highlights = evaluate_lights(
+        &paint.hillshade_highlight_color,
+        zoom,
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    );
+    let light_count = [
+        directions.len(),
+        altitudes.len(),
+        shadows.len(),
+        highlights.len(),
+    ]
+    .into_iter()
+    .max()
+    .unwrap_or(1)
+    .min(MAX_LIGHTS);
+
+    // Like the light of fill extrusions, the illumination follows the viewport by default
+    let bearing = match paint
+        .hillshade_illumination_anchor
+        .unwrap_or(Reference::Viewport)
+    {
+        Reference::Map => 0.0,
+        Reference::Viewport => bearing as f32,
+    };
+
+    ShaderHillshadeLayerMetadata {
+        accent: premultiply(
+            paint
+                .hillshade_accent_color
+                .as_ref()
+                .and_then(|color| color.evaluate_at_zoom(zoom))
+                .unwrap_or_else(|| Color::new(0.0, 0.0, 0.0, 1.0)),
+        ),
+        shadows: pad_lights(&shadows).map(premultiply),
+        highlights: pad_lights(&highlights).map(premultiply),
+        azimuths: pad_lights(&directions).map(|direction| (direction + bearing).to_radians()),
+        altitudes: pad_lights(&altitudes).map(|altitude| altitude.to_radians()),
+        method: paint.hillshade_method.unwrap_or_default() as u32,
+        light_count: light_count as u32,
+        exaggeration: paint
+            .hillshade_exaggeration
+            .as_ref()
+            .and_then(|exaggeration| exaggeration.evaluate_at_zoom(zoom))
+            .unwrap_or(0.5)
+            .clamp(0.0, 1.0),
+        _padding: 0.0,
+    }
+}
+
+/// Evaluates a property with a value for each light source. Falls back to `default` if the
+/// property is missing or has no values.
+fn evaluate_lights<T: FromValue + Clone>(
+    property: &Option<StyleProperty<OneOrMany<T>>>,
+    zoom: f32,
+    default: T,
+) -> Vec<T> {
+    property
+        .as_ref()
+        .and_then(|property| property.evaluate_at_zoom(zoom))
+        .map(OneOrMany::into_vec)
+        .filter(|values| !values.is_empty())
+        .unwrap_or_else(|| vec![default])
+}
+
+/// Repeats the last value for the light sources which have no value of their own.
+fn pad_lights<T: Clone>(values: &[T]) -> [T; MAX_LIGHTS] {
+    std::array::from_fn(|i| values[i.min(values.len() - 1)].clone())
+}
+
+fn premultiply(color: Color) -> Vec4f32 {
+    let alpha = color.a as f32;
+    [
+        color.r as f32 * alpha,
+        color.g as f32 * alpha,
+        color.b as f32 * alpha,
+        alpha,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::layer::HillshadeMethod;
+
+    #[test]
+    fn test_layer_metadata_defaults() {
+        let metadata = layer_metadata(&HillshadePaint::default(), 10.0, 0.0);
+
+        assert_eq!(metadata.method, HillshadeMethod::Standard as u32);
+        assert_eq!(metadata.light_count, 1);
+        assert_eq!(metadata.exaggeration, 0.5);
+        assert!((metadata.azimuths[0] - 345f32.to_radians()).abs() < 1e-6);
+        assert_eq!(metadata.shadows[0], [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(metadata.highlights[3], [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_layer_metadata_multidirectional() {
+        let paint: HillshadePaint = serde_json::from_str(
+            r#"{
+                "hillshade-method": "multidirectional",
+                "hillshade-illumination-anchor": "map",
+                "hillshade-illumination-direction": [90, 180],
+                "hillshade-shadow-color": ["rgba(255, 0, 0, 0.5)", "blue"]
+            }"#,
+        )
+        .unwrap();
+        let metadata = layer_metadata(&paint, 10.0, 0.0);
+
+        assert_eq!(metadata.method, HillshadeMethod::Multidirectional as u32);
+        assert_eq!(metadata.light_count, 2);
+        assert!((metadata.azimuths[1] - 180f32.to_radians()).abs() < 1e-6);
+        assert_eq!(metadata.azimuths[1], metadata.azimuths[3]);
+        assert_eq!(metadata.shadows[0], [0.5, 0.0, 0.0, 0.5]);
+        assert_eq!(metadata.shadows[2], [0.0, 0.0, 1.0, 1.0]);
+    }
+}
@@ -19,7 +19,7 @@ use crate::{
     coords::WorldTileCoords,
     define_label,
     environment::{OffscreenKernel, OffscreenKernelConfig},
-    io::scheduler::Scheduler,
+    io::{scheduler::Scheduler, source_client::SourceBackends},
     style::Style,
 };
 
@@ -71,10 +71,15 @@ pub trait IntoMessage {
 
 /// Inputs for an [`AsyncProcedure`]
 #[derive(Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum Input {
     TileRequest {
         coords: WorldTileCoords,
         style: Style, // TODO
+        /// The source backends of the [Kernel](crate::kernel::Kernel). They can not be sent to
+        /// web workers, which use the backends of their offscreen kernel instead.
+        #[serde(skip)]
+        source_backends: SourceBackends,
     },
     NotYetImplemented, // TODO: Placeholder, should be removed when second input is added
 }
@@ -4,8 +4,12 @@ pub use geozero::mvt::tile::Layer as RawLayer;
 
 pub mod apc;
 pub mod geometry_index;
+#[cfg(feature = "pmtiles")]
+pub mod pmtiles;
 pub mod scheduler;
 pub mod source_client;
 pub mod source_type;
+pub mod sprite;
 #[cfg(feature = "embed-static-tiles")]
 pub mod static_tile_fetcher;
+pub mod tile_json;
@@ -0,0 +1,558 @@
+//! Reads tiles from [PMTiles v3](https://github.com/protomaps/PMTiles/blob/main/spec/v3/spec.md)
+//! archives, either over HTTP range requests or from local files.
+
+use std::{
+    collections::HashMap,
+    io::Read,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use thiserror::Error;
+
+use crate::{
+    coords::WorldTileCoords,
+    io::{
+        source_client::{HttpClient, SourceBackend, SourceFetchError},
+        tile_json::{TileJson, TileJsonError},
+    },
+    style::source::TileAddressingScheme,
+};
+
+/// The URL scheme of sources which are read from PMTiles archives. The scheme is followed by
+/// either a HTTP(S) url or a local path, e.g. `pmtiles://https://example.com/basemap.pmtiles`
+/// or `pmtiles:///data/basemap.pmtiles`.
+pub const PMTILES_SCHEME: &str = "pmtiles://";
+
+const HEADER_LENGTH: u64 = 127;
+/// The root directory is guaranteed to be within the first 16 KiB of an archive.
+const INITIAL_FETCH_LENGTH: u64 = 16384;
+/// Leaf directories can be nested, but not deeper than this.
+const MAX_DIRECTORY_DEPTH: usize = 4;
+
+#[derive(Error, Debug)]
+pub enum PmtilesError {
+    #[error("reading the archive failed")]
+    Fetch(#[from] SourceFetchError),
+    #[error("reading the archive file failed")]
+    Io(#[from] std::io::Error),
+    #[error("the archive is not a PMTiles v3 archive")]
+    InvalidHeader,
+    #[error("a directory of the archive is invalid")]
+    InvalidDirectory,
+    #[error("the compression {0} is not supported")]
+    UnsupportedCompression(u8),
+    #[error("the tile {0} does not exist")]
+    TileNotFound(WorldTileCoords),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    None,
+    Gzip,
+    Other(u8),
+}
+
+impl From<u8> for Compression {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Compression::None,
+            2 => Compression::Gzip,
+            other => Compression::Other(other),
+        }
+    }
+}
+
+impl Compression {
+    fn decompress(self, data: Vec<u8>) -> Result<Vec<u8>, PmtilesError> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Gzip => {
+                let mut decompressed = Vec::new();
+                GzDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+            Compression::Other(value) => Err(PmtilesError::UnsupportedCompression(value)),
+        }
+    }
+}
+
+/// The fixed-size header at the start of every archive.
+#[derive(Debug, Clone)]
+pub struct Header {
+    root_directory_offset: u64,
+    root_directory_length: u64,
+    leaf_directories_offset: u64,
+    tile_data_offset: u64,
+    internal_compression: Compression,
+    tile_compression: Compression,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    /// Bounds in the order west, south, east, north.
+    pub bounds: (f64, f64, f64, f64),
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, PmtilesError> {
+        if data.len() < HEADER_LENGTH as usize || &data[0..7] != b"PMTiles" || data[7] != 3 {
+            return Err(PmtilesError::InvalidHeader);
+        }
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let coordinate_at = |offset: usize| {
+            f64::from(i32::from_le_bytes(
+                data[offset..offset + 4].try_into().unwrap(),
+            )) / 1e7
+        };
+
+        Ok(Self {
+            root_directory_offset: u64_at(8),
+            root_directory_length: u64_at(16),
+            leaf_directories_offset: u64_at(40),
+            tile_data_offset: u64_at(56),
+            internal_compression: Compression::from(data[97]),
+            tile_compression: Compression::from(data[98]),
+            min_zoom: data[100],
+            max_zoom: data[101],
+            bounds: (
+                coordinate_at(102),
+                coordinate_at(106),
+                coordinate_at(110),
+                coordinate_at(114),
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    tile_id: u64,
+    offset: u64,
+    length: u32,
+    /// A run length of zero marks a pointer to a leaf directory.
+    run_length: u32,
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64, PmtilesError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let Some((&byte, rest)) = data.split_first() else {
+            return Err(PmtilesError::InvalidDirectory);
+        };
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(PmtilesError::InvalidDirectory)
+}
+
+/// Decodes an uncompressed directory. The columns of the entries are stored one after another.
+fn parse_directory(mut data: &[u8]) -> Result<Vec<Entry>, PmtilesError> {
+    let data = &mut data;
+    let count = read_varint(data)? as usize;
+    let mut entries = vec![
+        Entry {
+            tile_id: 0,
+            offset: 0,
+            length: 0,
+            run_length: 0,
+        };
+        count
+    ];
+
+    let mut last_id = 0;
+    for entry in &mut entries {
+        last_id += read_varint(data)?;
+        entry.tile_id = last_id;
+    }
+    for entry in &mut entries {
+        entry.run_length = read_varint(data)? as u32;
+    }
+    for entry in &mut entries {
+        entry.length = read_varint(data)? as u32;
+    }
+    for i in 0..count {
+        let value = read_varint(data)?;
+        // Zero means the data directly follows the previous entry
+        entries[i].offset = if value == 0 && i > 0 {
+            entries[i - 1].offset + u64::from(entries[i - 1].length)
+        } else {
+            value.checked_sub(1).ok_or(PmtilesError::InvalidDirectory)?
+        };
+    }
+    Ok(entries)
+}
+
+fn find_entry(entries: &[Entry], tile_id: u64) -> Option<&Entry> {
+    let index = match entries.binary_search_by_key(&tile_id, |entry| entry.tile_id) {
+        Ok(index) => index,
+        Err(0) => return None,
+        Err(index) => index - 1,
+    };
+    let entry = &entries[index];
+    if entry.run_length == 0 || tile_id < entry.tile_id + u64::from(entry.run_length) {
+        Some(entry)
+    } else {
+        None
+    }
+}
+
+/// Returns the id of a tile, which is its position along the Hilbert curves of all zoom levels.
+fn tile_id(z: u8, x: u64, y: u64) -> u64 {
+    let base: u64 = (0..z).map(|z| 1u64 << (2 * z)).sum();
+    let n = 1u64 << z;
+    let (mut x, mut y) = (x, y);
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+        // Rotate the quadrant
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    base + d
+}
+
+/// Directories by the archive location and their offset within the archive.
+type DirectoryCache = HashMap<(String, u64), Arc<Vec<Entry>>>;
+
+/// Reads tiles from PMTiles archives. Headers and directories are cached and shared between
+/// clones.
+#[derive(Clone)]
+pub struct PmtilesClient<HC: HttpClient> {
+    http_client: HC,
+    headers: Arc<Mutex<HashMap<String, Arc<Header>>>>,
+    directories: Arc<Mutex<DirectoryCache>>,
+}
+
+impl<HC: HttpClient> PmtilesClient<HC> {
+    pub fn new(http_client: HC) -> Self {
+        Self {
+            http_client,
+            headers: Default::default(),
+            directories: Default::default(),
+        }
+    }
+
+    async fn read(
+        &self,
+        location: &str,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>, PmtilesError> {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            return Ok(self
+                .http_client
+                .fetch_range(location, offset, length)
+                .await?);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use std::io::{Seek, SeekFrom};
+
+            let mut file = std::fs::File::open(location)?;
+            file.seek(SeekFrom::Start(offset))?;
+            let mut data = Vec::new();
+            file.take(length).read_to_end(&mut data)?;
+            Ok(data)
+        }
+        #[cfg(target_arch = "wasm32")]
+        Err(PmtilesError::Io(std::io::ErrorKind::Unsupported.into()))
+    }
+
+    async fn header(&self, location: &str) -> Result<Arc<Header>, PmtilesError> {
+        if let Some(header) = self.headers.lock().unwrap().get(location) {
+            return Ok(header.clone());
+        }
+
+        let data = self.read(location, 0, INITIAL_FETCH_LENGTH).await?;
+        let header = Arc::new(Header::parse(&data)?);
+
+        // The root directory is usually part of the initial fetch
+        let root_end = header.root_directory_offset + header.root_directory_length;
+        if root_end <= data.len() as u64 {
+            let directory = header.internal_compression.decompress(
+                data[header.root_directory_offset as usize..root_end as usize].to_vec(),
+            )?;
+            self.directories.lock().unwrap().insert(
+                (location.to_string(), header.root_directory_offset),
+                Arc::new(parse_directory(&directory)?),
+            );
+        }
+
+        self.headers
+            .lock()
+            .unwrap()
+            .insert(location.to_string(), header.clone());
+        Ok(header)
+    }
+
+    async fn directory(
+        &self,
+        location: &str,
+        header: &Header,
+        offset: u64,
+        length: u64,
+    ) -> Result<Arc<Vec<Entry>>, PmtilesError> {
+        let key = (location.to_string(), offset);
+        if let Some(directory) = self.directories.lock().unwrap().get(&key) {
+            return Ok(directory.clone());
+        }
+
+        let data = self.read(location, offset, length).await?;
+        let directory = Arc::new(parse_directory(
+            &header.internal_compression.decompress(data)?,
+        )?);
+        self.directories
+            .lock()
+            .unwrap()
+            .insert(key, directory.clone());
+        Ok(directory)
+    }
+
+    /// Reads the tile at `coords` from the archive at `location`. Gzip compressed tiles are
+    /// decompressed.
+    pub async fn read_tile(
+        &self,
+        location: &str,
+        coords: &WorldTileCoords,
+    ) -> Result<Vec<u8>, PmtilesError> {
+        let tile = coords
+            .into_tile(TileAddressingScheme::XYZ)
+            .ok_or(PmtilesError::TileNotFound(*coords))?;
+        let tile_id = tile_id(u8::from(tile.z), u64::from(tile.x), u64::from(tile.y));
+
+        let header = self.header(location).await?;
+        let (mut offset, mut length) = (header.root_directory_offset, header.root_directory_length);
+
+        for _ in 0..MAX_DIRECTORY_DEPTH {
+            let directory = self.directory(location, &header, offset, length).await?;
+            let entry =
+                *find_entry(&directory, tile_id).ok_or(PmtilesError::TileNotFound(*coords))?;
+
+            if entry.run_length > 0 {
+                let data = self
+                    .read(
+                        location,
+                        header.tile_data_offset + entry.offset,
+                        u64::from(entry.length),
+                    )
+                    .await?;
+                return header.tile_compression.decompress(data);
+            }
+
+            offset = header.leaf_directories_offset + entry.offset;
+            length = u64::from(entry.length);
+        }
+
+        Err(PmtilesError::InvalidDirectory)
+    }
+
+    /// Describes the archive at `location` as TileJSON by reading its header. The tiles of the
+    /// returned document point back to the archive.
+    pub async fn read_tile_json(&self, location: &str) -> Result<TileJson, PmtilesError> {
+        let header = self.header(location).await?;
+        Ok(TileJson {
+            tiles: vec![format!("{PMTILES_SCHEME}{location}")],
+            attribution: None,
+            bounds: Some(header.bounds),
+            maxzoom: Some(header.max_zoom),
+            minzoom: Some(header.min_zoom),
+            scheme: Some(TileAddressingScheme::XYZ),
+        })
+    }
+}
+
+#[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+#[cfg_attr(feature = "thread-safe-futures", async_trait)]
+impl<HC: HttpClient> SourceBackend for PmtilesClient<HC> {
+    async fn fetch_tile(
+        &self,
+        url: &str,
+        coords: &WorldTileCoords,
+    ) -> Result<Vec<u8>, SourceFetchError> {
+        let location = url.strip_prefix(PMTILES_SCHEME).unwrap_or(url);
+        self.read_tile(location, coords)
+            .await
+            .map_err(|e| SourceFetchError(Box::new(e)))
+    }
+
+    async fn fetch_tile_json(&self, url: &str) -> Result<TileJson, TileJsonError> {
+        let location = url.strip_prefix(PMTILES_SCHEME).unwrap_or(url);
+        self.read_tile_json(location)
+            .await
+            .map_err(|e| SourceFetchError(Box::new(e)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::ZoomLevel;
+
+    fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            buffer.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        buffer.push(value as u8);
+    }
+
+    fn write_directory(entries: &[Entry]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        write_varint(&mut buffer, entries.len() as u64);
+        let mut last_id = 0;
+        for entry in entries {
+            write_varint(&mut buffer, entry.tile_id - last_id);
+            last_id = entry.tile_id;
+        }
+        for entry in entries {
+            write_varint(&mut buffer, u64::from(entry.run_length));
+        }
+        for entry in entries {
+            write_varint(&mut buffer, u64::from(entry.length));
+        }
+        for entry in entries {
+            write_varint(&mut buffer, entry.offset + 1);
+        }
+        buffer
+    }
+
+    /// Builds an uncompressed archive which stores the tiles in a leaf directory.
+    fn write_archive(tiles: &[(u64, &[u8])]) -> Vec<u8> {
+        let mut tile_data = Vec::new();
+        let mut leaf_entries = Vec::new();
+        for (tile_id, data) in tiles {
+            leaf_entries.push(Entry {
+                tile_id: *tile_id,
+                offset: tile_data.len() as u64,
+                length: data.len() as u32,
+                run_length: 1,
+            });
+            tile_data.extend_from_slice(data);
+        }
+        let leaf_directory = write_directory(&leaf_entries);
+        let root_directory = write_directory(&[Entry {
+            tile_id: 0,
+            offset: 0,
+            length: leaf_directory.len() as u32,
+            run_length: 0,
+        }]);
+
+        let root_offset = HEADER_LENGTH;
+        let leaf_offset = root_offset + root_directory.len() as u64;
+        let tile_data_offset = leaf_offset + leaf_directory.len() as u64;
+
+        let mut archive = b"PMTiles".to_vec();
+        archive.push(3);
+        for value in [
+            root_offset,
+            root_directory.len() as u64,
+            0, // metadata offset
+            0, // metadata length
+            leaf_offset,
+            leaf_directory.len() as u64,
+            tile_data_offset,
+            tile_data.len() as u64,
+            tiles.len() as u64,
+            tiles.len() as u64,
+            tiles.len() as u64,
+        ] {
+            archive.extend_from_slice(&value.to_le_bytes());
+        }
+        archive.extend_from_slice(&[1, 1, 1, 1, 0, 2]); // clustered, compressions, type, zooms
+        for coordinate in [-180.0, -85.0, 180.0, 85.0f64] {
+            archive.extend_from_slice(&((coordinate * 1e7) as i32).to_le_bytes());
+        }
+        archive.resize(HEADER_LENGTH as usize, 0);
+        archive.extend(root_directory);
+        archive.extend(leaf_directory);
+        archive.extend(tile_data);
+        archive
+    }
+
+    /// Serves an archive from memory and only supports range requests.
+    #[derive(Clone)]
+    struct RangeServer(Arc<Vec<u8>>);
+
+    #[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+    #[cfg_attr(feature = "thread-safe-futures", async_trait)]
+    impl HttpClient for RangeServer {
+        async fn fetch(&self, _url: &str) -> Result<Vec<u8>, SourceFetchError> {
+            panic!("archives must be read with range requests")
+        }
+
+        async fn fetch_range(
+            &self,
+            _url: &str,
+            offset: u64,
+            length: u64,
+        ) -> Result<Vec<u8>, SourceFetchError> {
+            let start = (offset as usize).min(self.0.len());
+            let end = (start + length as usize).min(self.0.len());
+            Ok(self.0[start..end].to_vec())
+        }
+    }
+
+    #[test]
+    fn test_tile_id() {
+        assert_eq!(tile_id(0, 0, 0), 0);
+        assert_eq!(tile_id(1, 0, 0), 1);
+        assert_eq!(tile_id(1, 0, 1), 2);
+        assert_eq!(tile_id(1, 1, 1), 3);
+        assert_eq!(tile_id(1, 1, 0), 4);
+        assert_eq!(tile_id(2, 0, 0), 5);
+        assert_eq!(tile_id(12, 3423, 1763), 19078479);
+    }
+
+    #[tokio::test]
+    async fn test_read_archive() {
+        let archive = write_archive(&[(0, b"world"), (3, b"tile")]);
+        let client = PmtilesClient::new(RangeServer(Arc::new(archive.clone())));
+
+        let tile = client
+            .read_tile(
+                "https://example.com/test.pmtiles",
+                &WorldTileCoords::from((1, 1, ZoomLevel::new(1))),
+            )
+            .await
+            .unwrap();
+        assert_eq!(tile, b"tile");
+        assert!(matches!(
+            client
+                .read_tile(
+                    "https://example.com/test.pmtiles",
+                    &WorldTileCoords::from((0, 0, ZoomLevel::new(1))),
+                )
+                .await,
+            Err(PmtilesError::TileNotFound(_))
+        ));
+
+        let path =
+            std::env::temp_dir().join(format!("maplibre-test-{}.pmtiles", std::process::id()));
+        std::fs::write(&path, &archive).unwrap();
+        let path_str = path.to_str().unwrap();
+        let tile = client
+            .read_tile(path_str, &WorldTileCoords::from((0, 0, ZoomLevel::new(0))))
+            .await
+            .unwrap();
+        assert_eq!(tile, b"world");
+        let tile_json = client.read_tile_json(path_str).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(tile_json.maxzoom, Some(2));
+        assert_eq!(tile_json.tiles, vec![format!("pmtiles://{path_str}")]);
+    }
+}
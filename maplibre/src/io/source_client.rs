@@ -1,9 +1,17 @@
-//! HTTP client.
+//! Clients which fetch tiles from sources.
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
 
 use async_trait::async_trait;
 use thiserror::Error;
 
-use crate::{coords::WorldTileCoords, io::source_type::SourceType};
+use crate::{
+    coords::WorldTileCoords,
+    io::{
+        source_type::SourceType,
+        tile_json::{TileJson, TileJsonError},
+    },
+};
 
 /// A closure that returns a HTTP client.
 pub type HTTPClientFactory<HC> = dyn Fn() -> HC;
@@ -18,6 +26,20 @@ pub type HTTPClientFactory<HC> = dyn Fn() -> HC;
 #[cfg_attr(feature = "thread-safe-futures", async_trait)]
 pub trait HttpClient: Clone + Sync + Send + 'static {
     async fn fetch(&self, url: &str) -> Result<Vec<u8>, SourceFetchError>;
+
+    /// Fetches `length` bytes of the document at `url`, starting at `offset`. Clients which do
+    /// not support range requests fall back to fetching the whole document.
+    async fn fetch_range(
+        &self,
+        url: &str,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>, SourceFetchError> {
+        let data = self.fetch(url).await?;
+        let start = (offset as usize).min(data.len());
+        let end = start.saturating_add(length as usize).min(data.len());
+        Ok(data[start..end].to_vec())
+    }
 }
 
 /// Gives access to the HTTP client which can be of multiple types,
@@ -34,14 +56,74 @@ where
 #[error("failed to fetch from source")]
 pub struct SourceFetchError(#[source] pub Box<dyn std::error::Error>);
 
-/// Defines the different types of HTTP clients such as basic HTTP and Mbtiles.
-/// More types might be coming such as S3 and other cloud http clients.
+#[derive(Error, Debug)]
+#[error("no source backend is registered for the url {0}")]
+pub struct UnsupportedSchemeError(pub String);
+
+/// Provides the tiles of urls with a specific scheme, e.g. `mbtiles://`. Custom backends can be
+/// registered with [`KernelBuilder::with_source_backend`](crate::kernel::KernelBuilder::with_source_backend).
+#[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+#[cfg_attr(feature = "thread-safe-futures", async_trait)]
+pub trait SourceBackend: Send + Sync + 'static {
+    /// Fetches the tile at `coords`. The `url` is the tile url of the source, with the
+    /// placeholders already replaced.
+    async fn fetch_tile(
+        &self,
+        url: &str,
+        coords: &WorldTileCoords,
+    ) -> Result<Vec<u8>, SourceFetchError>;
+
+    /// Fetches the TileJSON document which describes the source at `url`.
+    async fn fetch_tile_json(&self, url: &str) -> Result<TileJson, TileJsonError>;
+
+    /// Fetches a resource of the style which is not a tile, e.g. the sprite. Backends which only
+    /// provide tiles do not support this.
+    async fn fetch_resource(&self, url: &str) -> Result<Vec<u8>, SourceFetchError> {
+        Err(SourceFetchError(Box::new(UnsupportedSchemeError(
+            url.to_string(),
+        ))))
+    }
+}
+
+/// Source backends by the scheme of the urls they handle, e.g. `https`.
+#[derive(Clone, Default)]
+pub struct SourceBackends(HashMap<String, Arc<dyn SourceBackend>>);
+
+impl SourceBackends {
+    /// Registers `backend` for urls with the given `scheme`, replacing a previously registered
+    /// backend.
+    pub fn insert(&mut self, scheme: &str, backend: Arc<dyn SourceBackend>) {
+        self.0.insert(scheme.to_string(), backend);
+    }
+
+    /// Registers all backends of `other`, replacing backends for the same schemes.
+    pub fn extend(&mut self, other: &SourceBackends) {
+        self.0.extend(
+            other
+                .0
+                .iter()
+                .map(|(scheme, backend)| (scheme.clone(), backend.clone())),
+        );
+    }
+
+    pub fn get(&self, url: &str) -> Result<&dyn SourceBackend, UnsupportedSchemeError> {
+        url.split_once("://")
+            .and_then(|(scheme, _)| self.0.get(scheme))
+            .map(|backend| backend.as_ref())
+            .ok_or_else(|| UnsupportedSchemeError(url.to_string()))
+    }
+}
+
+/// Fetches tiles and TileJSON documents through the backend which is registered for the scheme
+/// of the url. By default backends for `http(s)://`, `file://` on native platforms and, depending
+/// on the enabled features, `mbtiles://` and `pmtiles://` are registered.
 #[derive(Clone)]
 pub struct SourceClient<HC>
 where
     HC: HttpClient,
 {
-    http: HttpSourceClient<HC>,
+    backends: SourceBackends,
+    phantom_hc: PhantomData<HC>,
 }
 
 impl<HC> SourceClient<HC>
@@ -49,7 +131,43 @@ where
     HC: HttpClient,
 {
     pub fn new(http: HttpSourceClient<HC>) -> Self {
-        Self { http }
+        let mut backends = SourceBackends::default();
+
+        #[cfg(feature = "pmtiles")]
+        backends.insert(
+            "pmtiles",
+            Arc::new(crate::io::pmtiles::PmtilesClient::new(
+                http.inner_client.clone(),
+            )),
+        );
+        #[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+        backends.insert(
+            "mbtiles",
+            Arc::new(crate::platform::mbtiles::MbtilesClient::default()),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        backends.insert(
+            "file",
+            Arc::new(crate::platform::file_client::FileSourceClient),
+        );
+
+        let http = Arc::new(http);
+        backends.insert("http", http.clone());
+        backends.insert("https", http);
+
+        Self {
+            backends,
+            phantom_hc: PhantomData,
+        }
+    }
+
+    /// Registers additional backends, which replace the backends for the same schemes.
+    pub fn register_backends(&mut self, backends: &SourceBackends) {
+        self.backends.extend(backends);
+    }
+
+    pub fn backends(&self) -> &SourceBackends {
+        &self.backends
     }
 
     pub async fn fetch(
@@ -57,7 +175,30 @@ where
         coords: &WorldTileCoords,
         source_type: &SourceType,
     ) -> Result<Vec<u8>, SourceFetchError> {
-        self.http.fetch(coords, source_type).await
+        let url = source_type.format(coords);
+        let backend = match self.backends.get(&url) {
+            Ok(backend) => backend,
+            Err(e) => return Err(SourceFetchError(Box::new(e))),
+        };
+        backend.fetch_tile(&url, coords).await
+    }
+
+    /// Fetches the TileJSON document at `url`.
+    pub async fn fetch_tile_json(&self, url: &str) -> Result<TileJson, TileJsonError> {
+        let backend = match self.backends.get(url) {
+            Ok(backend) => backend,
+            Err(e) => return Err(SourceFetchError(Box::new(e)).into()),
+        };
+        backend.fetch_tile_json(url).await
+    }
+
+    /// Fetches the resource at `url`, e.g. the sprite of the style.
+    pub async fn fetch_resource(&self, url: &str) -> Result<Vec<u8>, SourceFetchError> {
+        let backend = match self.backends.get(url) {
+            Ok(backend) => backend,
+            Err(e) => return Err(SourceFetchError(Box::new(e))),
+        };
+        backend.fetch_resource(url).await
     }
 }
 
@@ -81,3 +222,91 @@ where
             .await
     }
 }
+
+#[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+#[cfg_attr(feature = "thread-safe-futures", async_trait)]
+impl<HC> SourceBackend for HttpSourceClient<HC>
+where
+    HC: HttpClient,
+{
+    async fn fetch_tile(
+        &self,
+        url: &str,
+        _coords: &WorldTileCoords,
+    ) -> Result<Vec<u8>, SourceFetchError> {
+        self.inner_client.fetch(url).await
+    }
+
+    async fn fetch_tile_json(&self, url: &str) -> Result<TileJson, TileJsonError> {
+        TileJson::parse(&self.inner_client.fetch(url).await?)
+    }
+
+    async fn fetch_resource(&self, url: &str) -> Result<Vec<u8>, SourceFetchError> {
+        self.inner_client.fetch(url).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        coords::ZoomLevel, io::source_type::TemplateSource, style::source::TileAddressingScheme,
+    };
+
+    #[derive(Clone)]
+    struct UnreachableHttpClient;
+
+    #[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+    #[cfg_attr(feature = "thread-safe-futures", async_trait)]
+    impl HttpClient for UnreachableHttpClient {
+        async fn fetch(&self, url: &str) -> Result<Vec<u8>, SourceFetchError> {
+            panic!("unexpected request to {url}")
+        }
+    }
+
+    /// Serves tiles from memory, the tile data is the url of the tile.
+    struct MemoryBackend;
+
+    #[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+    #[cfg_attr(feature = "thread-safe-futures", async_trait)]
+    impl SourceBackend for MemoryBackend {
+        async fn fetch_tile(
+            &self,
+            url: &str,
+            _coords: &WorldTileCoords,
+        ) -> Result<Vec<u8>, SourceFetchError> {
+            Ok(url.as_bytes().to_vec())
+        }
+
+        async fn fetch_tile_json(&self, _url: &str) -> Result<TileJson, TileJsonError> {
+            Err(TileJsonError::NoTiles)
+        }
+    }
+
+    fn source_type(template: &str) -> SourceType {
+        SourceType::Template(TemplateSource {
+            tiles: vec![template.to_string()],
+            scheme: TileAddressingScheme::XYZ,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_by_scheme() {
+        let mut client = SourceClient::new(HttpSourceClient::new(UnreachableHttpClient));
+        let mut backends = SourceBackends::default();
+        backends.insert("memory", Arc::new(MemoryBackend));
+        client.register_backends(&backends);
+
+        let coords = WorldTileCoords::from((1, 2, ZoomLevel::new(3)));
+        let tile = client
+            .fetch(&coords, &source_type("memory://basemap/{z}/{x}/{y}"))
+            .await
+            .unwrap();
+        assert_eq!(tile, b"memory://basemap/3/1/2");
+
+        assert!(client
+            .fetch(&coords, &source_type("s3://bucket/{z}/{x}/{y}"))
+            .await
+            .is_err());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{coords::WorldTileCoords, style::source::TileAddressingScheme};
+use crate::{
+    coords::WorldTileCoords,
+    style::source::{RasterSource, TileAddressingScheme, TileUrl, VectorSource},
+};
 
 /// Represents a source from which the vector tile are fetched.
 #[derive(Clone)]
@@ -35,59 +38,118 @@ impl Default for TessellateSource {
     }
 }
 
-/// Represents a source from which the raster tile are fetched.
+/// Represents a source of the style, whose tiles are fetched from its URL templates.
 #[derive(Clone)]
-pub struct RasterSource {
-    pub url: String,
-    pub filetype: String,
-    pub key: String,
+pub struct TemplateSource {
+    pub tiles: Vec<TileUrl>,
+    pub scheme: TileAddressingScheme,
 }
 
-impl RasterSource {
-    pub fn new(url: &str, filetype: &str, key: &str) -> Self {
-        Self {
-            url: url.to_string(),
-            filetype: filetype.to_string(),
-            key: key.to_string(),
-        }
+impl TemplateSource {
+    /// Returns `None` if the source does not list any tile URLs.
+    pub fn from_vector_source(source: &VectorSource) -> Option<Self> {
+        Self::from_tiles(source.tiles.as_ref(), source.scheme)
     }
 
-    pub fn format(&self, coords: &WorldTileCoords) -> String {
-        let tile_coords = coords.into_tile(TileAddressingScheme::XYZ).unwrap();
-        format!(
-            "{url}/{z}/{x}/{y}.{filetype}?key={key}",
-            url = self.url,
-            z = tile_coords.z,
-            x = tile_coords.x,
-            y = tile_coords.y,
-            filetype = self.filetype,
-            key = self.key,
-        )
+    /// Returns `None` if the source does not list any tile URLs.
+    pub fn from_raster_source(source: &RasterSource) -> Option<Self> {
+        Self::from_tiles(source.tiles.as_ref(), source.scheme)
     }
-}
 
-impl Default for RasterSource {
-    fn default() -> Self {
-        Self::new(
-            "https://api.maptiler.com/tiles/satellite-v2",
-            "jpg",
-            "qnePkfbGpMsLCi3KFBs3",
-        )
+    fn from_tiles(
+        tiles: Option<&Vec<TileUrl>>,
+        scheme: Option<TileAddressingScheme>,
+    ) -> Option<Self> {
+        let tiles = tiles.filter(|tiles| !tiles.is_empty())?.clone();
+        Some(Self {
+            tiles,
+            scheme: scheme.unwrap_or_default(),
+        })
+    }
+
+    /// Expands the `{z}`, `{x}`, `{y}` and `{quadkey}` placeholders of one of the templates. If
+    /// there are multiple templates, the tiles are distributed among them.
+    pub fn format(&self, coords: &WorldTileCoords) -> String {
+        let tile_coords = coords.into_tile(self.scheme).unwrap();
+        let template = &self.tiles[(tile_coords.x + tile_coords.y) as usize % self.tiles.len()];
+
+        let mut url = template
+            .replace("{z}", &tile_coords.z.to_string())
+            .replace("{x}", &tile_coords.x.to_string())
+            .replace("{y}", &tile_coords.y.to_string());
+        if url.contains("{quadkey}") {
+            let quadkey = (1..=u8::from(tile_coords.z))
+                .rev()
+                .map(|z| {
+                    let mask = 1 << (z - 1);
+                    let digit = u8::from(tile_coords.x & mask != 0)
+                        + 2 * u8::from(tile_coords.y & mask != 0);
+                    char::from(b'0' + digit)
+                })
+                .collect::<String>();
+            url = url.replace("{quadkey}", &quadkey);
+        }
+        url
     }
 }
 
 /// Represents the tiles' different types of source.
 #[derive(Clone)]
 pub enum SourceType {
-    Raster(RasterSource),
     Tessellate(TessellateSource),
+    Template(TemplateSource),
 }
 
 impl SourceType {
     pub fn format(&self, coords: &WorldTileCoords) -> String {
         match self {
-            SourceType::Raster(raster_source) => raster_source.format(coords),
             SourceType::Tessellate(tessellate_source) => tessellate_source.format(coords),
+            SourceType::Template(template_source) => template_source.format(coords),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::ZoomLevel;
+
+    fn template_source(tiles: &[&str], scheme: TileAddressingScheme) -> TemplateSource {
+        TemplateSource {
+            tiles: tiles.iter().map(|tile| tile.to_string()).collect(),
+            scheme,
+        }
+    }
+
+    #[test]
+    fn test_format_template() {
+        let coords = WorldTileCoords::from((3, 1, ZoomLevel::new(2)));
+
+        let source = template_source(
+            &["https://example.com/{z}/{x}/{y}.pbf"],
+            TileAddressingScheme::XYZ,
+        );
+        assert_eq!(source.format(&coords), "https://example.com/2/3/1.pbf");
+
+        let source = template_source(
+            &["https://example.com/{z}/{x}/{y}.pbf"],
+            TileAddressingScheme::TMS,
+        );
+        assert_eq!(source.format(&coords), "https://example.com/2/3/2.pbf");
+
+        let source = template_source(
+            &["https://example.com/{quadkey}.pbf"],
+            TileAddressingScheme::XYZ,
+        );
+        assert_eq!(source.format(&coords), "https://example.com/13.pbf");
+
+        let source = template_source(
+            &[
+                "https://a.example.com/{z}.pbf",
+                "https://b.example.com/{z}.pbf",
+            ],
+            TileAddressingScheme::XYZ,
+        );
+        assert_eq!(source.format(&coords), "https://a.example.com/2.pbf");
+    }
+}
@@ -0,0 +1,288 @@
+//! Loading of the [sprite](https://maplibre.org/maplibre-style-spec/sprite/) of a style, which
+//! contains the images of patterns and icons.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    io::source_client::{HttpClient, SourceClient, SourceFetchError},
+    style::Sprite,
+};
+
+#[derive(Error, Debug)]
+pub enum SpriteError {
+    #[error("fetching the sprite failed")]
+    Fetch(#[from] SourceFetchError),
+    #[error("the index of the sprite is invalid")]
+    Index(#[from] serde_json::Error),
+    #[error("the image of the sprite is invalid")]
+    Image(#[from] png::DecodingError),
+    #[error("the image of the sprite has the unsupported color type {0:?}")]
+    ColorType(png::ColorType),
+    #[error("the image {0} exceeds the bounds of the sprite sheet")]
+    OutOfBounds(String),
+    #[error("the image {0} is empty or has an invalid pixel ratio")]
+    InvalidImage(String),
+}
+
+/// The location of an image within the sprite sheet, as listed in the index of the sprite.
+#[derive(Deserialize, Debug, Clone, Copy)]
+struct SpriteIndexEntry {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    #[serde(rename = "pixelRatio", default = "default_pixel_ratio")]
+    pixel_ratio: f32,
+    #[serde(default)]
+    sdf: bool,
+}
+
+fn default_pixel_ratio() -> f32 {
+    1.0
+}
+
+/// An image of a sprite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteImage {
+    pub width: u32,
+    pub height: u32,
+    /// Ratio of the texels of the image to the pixels it covers on the screen.
+    pub pixel_ratio: f32,
+    /// Whether the image is a signed distance field, which is colored when drawn.
+    pub sdf: bool,
+    /// The RGBA texels of the image, row by row and with non-premultiplied alpha.
+    pub data: Vec<u8>,
+}
+
+impl SpriteImage {
+    /// Returns the size of the image in pixels on the screen.
+    pub fn display_size(&self) -> [f32; 2] {
+        [
+            self.width as f32 / self.pixel_ratio,
+            self.height as f32 / self.pixel_ratio,
+        ]
+    }
+}
+
+/// The images of one or multiple sprites by their id.
+#[derive(Debug, Clone, Default)]
+pub struct SpriteSheet {
+    images: HashMap<String, SpriteImage>,
+}
+
+impl SpriteSheet {
+    /// Cuts the images listed in the JSON `index` out of the PNG `image`.
+    pub fn parse(index: &[u8], image: &[u8]) -> Result<Self, SpriteError> {
+        let index: HashMap<String, SpriteIndexEntry> = serde_json::from_slice(index)?;
+        let (width, height, texels) = decode_png(image)?;
+
+        let mut images = HashMap::with_capacity(index.len());
+        for (id, entry) in index {
+            // The atlas wraps around the edges of the images and their size is divided by the
+            // pixel ratio, so neither may be zero
+            if entry.width == 0
+                || entry.height == 0
+                || !entry.pixel_ratio.is_finite()
+                || entry.pixel_ratio <= 0.0
+            {
+                return Err(SpriteError::InvalidImage(id));
+            }
+            let in_bounds = |start: u32, size: u32, bound: u32| {
+                start.checked_add(size).is_some_and(|end| end <= bound)
+            };
+            if !in_bounds(entry.x, entry.width, width) || !in_bounds(entry.y, entry.height, height)
+            {
+                return Err(SpriteError::OutOfBounds(id));
+            }
+
+            let row_bytes = entry.width as usize * 4;
+            let mut data = Vec::with_capacity(row_bytes * entry.height as usize);
+            for y in entry.y..entry.y + entry.height {
+                let start = (y as usize * width as usize + entry.x as usize) * 4;
+                data.extend_from_slice(&texels[start..start + row_bytes]);
+            }
+
+            images.insert(
+                id,
+                SpriteImage {
+                    width: entry.width,
+                    height: entry.height,
+                    pixel_ratio: entry.pixel_ratio,
+                    sdf: entry.sdf,
+                    data,
+                },
+            );
+        }
+        Ok(Self { images })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SpriteImage> {
+        self.images.get(id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Adds the images of `other`, with their ids prefixed by `prefix`.
+    pub fn extend(&mut self, prefix: &str, other: SpriteSheet) {
+        self.images.extend(
+            other
+                .images
+                .into_iter()
+                .map(|(id, image)| (format!("{prefix}{id}"), image)),
+        );
+    }
+}
+
+/// Decodes a PNG into its width, height and RGBA texels.
+fn decode_png(data: &[u8]) -> Result<(u32, u32, Vec<u8>), SpriteError> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(
+        png::Transformations::normalize_to_color8() | png::Transformations::ALPHA,
+    );
+    let mut reader = decoder.read_info()?;
+    let mut texels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut texels)?;
+    texels.truncate(info.buffer_size());
+
+    let texels = match info.color_type {
+        png::ColorType::Rgba => texels,
+        png::ColorType::GrayscaleAlpha => texels
+            .chunks_exact(2)
+            .flat_map(|texel| [texel[0], texel[0], texel[0], texel[1]])
+            .collect(),
+        color_type => return Err(SpriteError::ColorType(color_type)),
+    };
+    Ok((info.width, info.height, texels))
+}
+
+/// Returns the url of the index or the image of a sprite, which is selected by the `extension`.
+/// Displays with a pixel ratio above 1 use the `@2x` variant of the sprite.
+pub fn sprite_url(url: &str, pixel_ratio: f64, extension: &str) -> String {
+    let suffix = if pixel_ratio > 1.0 { "@2x" } else { "" };
+    match url.split_once('?') {
+        Some((path, query)) => format!("{path}{suffix}.{extension}?{query}"),
+        None => format!("{url}{suffix}.{extension}"),
+    }
+}
+
+/// Fetches the index and the image of every sprite of the style and merges them into one
+/// [`SpriteSheet`].
+pub async fn fetch_sprite<HC: HttpClient>(
+    sprite: &Sprite,
+    pixel_ratio: f64,
+    client: &SourceClient<HC>,
+) -> Result<SpriteSheet, SpriteError> {
+    let mut sheet = SpriteSheet::default();
+    for (prefix, url) in sprite.sources() {
+        let index = client
+            .fetch_resource(&sprite_url(url, pixel_ratio, "json"))
+            .await?;
+        let image = client
+            .fetch_resource(&sprite_url(url, pixel_ratio, "png"))
+            .await?;
+        sheet.extend(&prefix, SpriteSheet::parse(&index, &image)?);
+    }
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a 4x2 image, whose left half is red and whose right half is blue.
+    fn sprite_png() -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 4, 2);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let row = [red, red, blue, blue].concat();
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[row.clone(), row].concat())
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_sprite() {
+        let index = br#"{
+            "red": {"x": 0, "y": 0, "width": 2, "height": 2, "pixelRatio": 2},
+            "blue": {"x": 2, "y": 1, "width": 2, "height": 1, "sdf": true}
+        }"#;
+        let sheet = SpriteSheet::parse(index, &sprite_png()).unwrap();
+
+        let red = sheet.get("red").unwrap();
+        assert_eq!(red.data, [255, 0, 0, 255].repeat(4));
+        assert_eq!(red.display_size(), [1.0, 1.0]);
+        let blue = sheet.get("blue").unwrap();
+        assert_eq!(blue.data, [0, 0, 255, 255].repeat(2));
+        assert!(blue.sdf);
+
+        let index = br#"{"wide": {"x": 2, "y": 0, "width": 4, "height": 2}}"#;
+        assert!(matches!(
+            SpriteSheet::parse(index, &sprite_png()),
+            Err(SpriteError::OutOfBounds(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_overflowing_bounds() {
+        let index = br#"{"far": {"x": 4294967295, "y": 0, "width": 2, "height": 1}}"#;
+        assert!(matches!(
+            SpriteSheet::parse(index, &sprite_png()),
+            Err(SpriteError::OutOfBounds(_))
+        ));
+        let index = br#"{"far": {"x": 0, "y": 1, "width": 1, "height": 4294967295}}"#;
+        assert!(matches!(
+            SpriteSheet::parse(index, &sprite_png()),
+            Err(SpriteError::OutOfBounds(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_empty_images() {
+        let index = br#"{"empty": {"x": 0, "y": 0, "width": 0, "height": 2}}"#;
+        assert!(matches!(
+            SpriteSheet::parse(index, &sprite_png()),
+            Err(SpriteError::InvalidImage(_))
+        ));
+        let index = br#"{"empty": {"x": 0, "y": 0, "width": 2, "height": 0}}"#;
+        assert!(matches!(
+            SpriteSheet::parse(index, &sprite_png()),
+            Err(SpriteError::InvalidImage(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_invalid_pixel_ratio() {
+        for pixel_ratio in ["0", "-1"] {
+            let index = format!(
+                r#"{{"red": {{"x": 0, "y": 0, "width": 2, "height": 2, "pixelRatio": {pixel_ratio}}}}}"#
+            );
+            assert!(matches!(
+                SpriteSheet::parse(index.as_bytes(), &sprite_png()),
+                Err(SpriteError::InvalidImage(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_sprite_url() {
+        assert_eq!(
+            sprite_url("https://example.com/sprite", 1.0, "json"),
+            "https://example.com/sprite.json"
+        );
+        assert_eq!(
+            sprite_url("https://example.com/sprite?key=abc", 2.0, "png"),
+            "https://example.com/sprite@2x.png?key=abc"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Resolution of [TileJSON](https://github.com/mapbox/tilejson-spec) documents which are
+//! referenced by the `url` of a source.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    io::source_client::{HttpClient, SourceClient, SourceFetchError},
+    style::source::{
+        RasterDemSource, RasterSource, Source, TileAddressingScheme, TileUrl, VectorSource,
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum TileJsonError {
+    #[error("fetching the TileJSON document failed")]
+    Fetch(#[from] SourceFetchError),
+    #[error("the TileJSON document is invalid")]
+    Parse(#[from] serde_json::Error),
+    #[error("the TileJSON document does not list any tiles")]
+    NoTiles,
+}
+
+/// The subset of a TileJSON document which is merged into a source.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TileJson {
+    pub tiles: Vec<TileUrl>,
+    #[serde(default)]
+    pub attribution: Option<String>,
+    #[serde(default)]
+    pub bounds: Option<(f64, f64, f64, f64)>,
+    #[serde(default)]
+    pub maxzoom: Option<u8>,
+    #[serde(default)]
+    pub minzoom: Option<u8>,
+    #[serde(default)]
+    pub scheme: Option<TileAddressingScheme>,
+}
+
+impl TileJson {
+    pub fn parse(data: &[u8]) -> Result<Self, TileJsonError> {
+        let tile_json: TileJson = serde_json::from_slice(data)?;
+        if tile_json.tiles.is_empty() {
+            return Err(TileJsonError::NoTiles);
+        }
+        Ok(tile_json)
+    }
+}
+
+/// A source of the style which can reference a TileJSON document through its `url`.
+pub trait TileJsonSource {
+    fn tile_json_url(&self) -> Option<&str>;
+
+    /// Merges the properties of a TileJSON document into this source. Properties which are set
+    /// in the style take precedence.
+    fn merge_tile_json(&mut self, tile_json: TileJson);
+}
+
+macro_rules! impl_tile_json_source {
+    ($source:ty) => {
+        impl TileJsonSource for $source {
+            fn tile_json_url(&self) -> Option<&str> {
+                self.url.as_deref()
+            }
+
+            fn merge_tile_json(&mut self, tile_json: TileJson) {
+                self.tiles = self.tiles.take().or(Some(tile_json.tiles));
+                self.attribution = self.attribution.take().or(tile_json.attribution);
+                self.bounds = self.bounds.or(tile_json.bounds);
+                self.maxzoom = self.maxzoom.or(tile_json.maxzoom);
+                self.minzoom = self.minzoom.or(tile_json.minzoom);
+                self.scheme = self.scheme.or(tile_json.scheme);
+            }
+        }
+    };
+}
+
+impl_tile_json_source!(VectorSource);
+impl_tile_json_source!(RasterSource);
+
+impl TileJsonSource for RasterDemSource {
+    fn tile_json_url(&self) -> Option<&str> {
+        self.raster.tile_json_url()
+    }
+
+    fn merge_tile_json(&mut self, tile_json: TileJson) {
+        self.raster.merge_tile_json(tile_json)
+    }
+}
+
+/// Fetches the TileJSON document of `source` and merges it into the source. For `mbtiles://`
+/// and `pmtiles://` urls the document is derived from the metadata of the file. Sources without
+/// a `url` are left untouched.
+pub async fn resolve_tile_json<HC: HttpClient, S: TileJsonSource>(
+    source: &mut S,
+    client: &SourceClient<HC>,
+) -> Result<(), TileJsonError> {
+    let Some(url) = source.tile_json_url() else {
+        return Ok(());
+    };
+    let tile_json = client.fetch_tile_json(url).await?;
+    source.merge_tile_json(tile_json);
+    Ok(())
+}
+
+/// Resolves the TileJSON documents of all `sources`. Sources whose document can not be resolved
+/// are logged and left untouched.
+pub async fn resolve_sources<HC: HttpClient>(
+    sources: &mut HashMap<String, Source>,
+    client: &SourceClient<HC>,
+) {
+    for (id, source) in sources {
+        let result = match source {
+            Source::Vector(source) => resolve_tile_json(source, client).await,
+            Source::Raster(source) => resolve_tile_json(source, client).await,
+            Source::RasterDem(source) => resolve_tile_json(source, client).await,
+            Source::GeoJson(_) => Ok(()),
+        };
+        if let Err(e) = result {
+            log::error!("resolving the TileJSON of source {id} failed: {e:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_tile_json() {
+        let tile_json = TileJson::parse(
+            br#"{
+                "tilejson": "3.0.0",
+                "tiles": ["https://example.com/{z}/{x}/{y}.pbf"],
+                "attribution": "OpenStreetMap contributors",
+                "bounds": [5.8, 47.2, 15.1, 55.1],
+                "minzoom": 0,
+                "maxzoom": 14,
+                "scheme": "tms"
+            }"#,
+        )
+        .unwrap();
+
+        let mut source: VectorSource = serde_json::from_value(serde_json::json!({
+            "url": "https://example.com/tiles.json",
+            "maxzoom": 12
+        }))
+        .unwrap();
+        source.merge_tile_json(tile_json);
+
+        assert_eq!(
+            source.tiles,
+            Some(vec!["https://example.com/{z}/{x}/{y}.pbf".to_string()])
+        );
+        assert_eq!(source.bounds, Some((5.8, 47.2, 15.1, 55.1)));
+        assert_eq!(source.minzoom, Some(0));
+        assert_eq!(source.maxzoom, Some(12));
+        assert_eq!(source.scheme, Some(TileAddressingScheme::TMS));
+    }
+
+    #[test]
+    fn test_invalid_tile_json() {
+        assert!(matches!(
+            TileJson::parse(b"<html></html>"),
+            Err(TileJsonError::Parse(_))
+        ));
+        assert!(matches!(
+            TileJson::parse(br#"{"tiles": []}"#),
+            Err(TileJsonError::NoTiles)
+        ));
+    }
+}
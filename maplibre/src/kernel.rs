@@ -1,6 +1,8 @@
+use std::sync::Arc;
+
 use crate::{
     environment::Environment,
-    io::source_client::{HttpSourceClient, SourceClient},
+    io::source_client::{HttpSourceClient, SourceBackend, SourceBackends, SourceClient},
 };
 
 /// Holds references to core constructs of maplibre. Based on the compile-time initialization
@@ -42,6 +44,7 @@ pub struct KernelBuilder<E: Environment> {
     apc: Option<E::AsyncProcedureCall>,
     scheduler: Option<E::Scheduler>,
     http_client: Option<E::HttpClient>,
+    source_backends: SourceBackends,
 }
 
 impl<E: Environment> Default for KernelBuilder<E> {
@@ -57,6 +60,7 @@ impl<E: Environment> KernelBuilder<E> {
             apc: None,
             http_client: None,
             map_window_config: None,
+            source_backends: SourceBackends::default(),
         }
     }
 
@@ -80,11 +84,21 @@ impl<E: Environment> KernelBuilder<E> {
         self
     }
 
+    /// Registers a backend which fetches the tiles of sources with urls of the given `scheme`,
+    /// e.g. `memory` for `memory://basemap`. Backends for built-in schemes can be replaced.
+    pub fn with_source_backend(mut self, scheme: &str, backend: impl SourceBackend) -> Self {
+        self.source_backends.insert(scheme, Arc::new(backend));
+        self
+    }
+
     pub fn build(self) -> Kernel<E> {
+        let mut source_client = SourceClient::new(HttpSourceClient::new(self.http_client.unwrap())); // TODO: Remove unwrap
+        source_client.register_backends(&self.source_backends);
+
         Kernel {
             scheduler: self.scheduler.unwrap(), // TODO: Remove unwrap
             apc: self.apc.unwrap(),             // TODO: Remove unwrap
-            source_client: SourceClient::new(HttpSourceClient::new(self.http_client.unwrap())), // TODO: Remove unwrap
+            source_client,
             map_window_config: self.map_window_config.unwrap(), // TODO: Remove unwrap
         }
     }
@@ -55,9 +55,15 @@ pub mod plugin;
 pub mod tcs;
 
 // Plugins
+pub mod circle;
+pub mod color_relief;
 pub mod debug;
+pub mod fill_extrusion;
 pub mod geojson;
+pub mod heatmap;
+pub mod hillshade;
 pub mod raster;
+pub mod terrain;
 pub mod vector;
 
 mod legacy;
@@ -6,6 +6,7 @@ use crate::{
     context::MapContext,
     coords::{LatLon, WorldCoords, Zoom},
     environment::Environment,
+    io::{sprite::fetch_sprite, tile_json::resolve_sources},
     kernel::Kernel,
     plugin::Plugin,
     render::{
@@ -14,6 +15,7 @@ use crate::{
         },
         error::RenderError,
         graph::RenderGraphError,
+        image_atlas::ImageAtlas,
         view_state::ViewState,
     },
     schedule::{Schedule, Stage, StageError},
@@ -40,10 +42,10 @@ pub enum MapError {
 }
 
 pub enum CurrentMapContext {
-    Ready(MapContext),
+    Ready(Box<MapContext>),
     Pending {
-        style: Style,
-        renderer_builder: RendererBuilder,
+        style: Box<Style>,
+        renderer_builder: Box<RendererBuilder>,
     },
 }
 
@@ -76,8 +78,8 @@ where
             kernel,
             schedule,
             map_context: CurrentMapContext::Pending {
-                style,
-                renderer_builder,
+                style: Box::new(style),
+                renderer_builder: Box::new(renderer_builder),
             },
             window,
             plugins,
@@ -99,6 +101,20 @@ where
                     .await
                     .map_err(MapError::DeviceInit)?;
 
+                // Sources referencing TileJSON documents need to be resolved before tiles can
+                // be requested
+                resolve_sources(&mut style.sources, self.kernel.source_client()).await;
+
+                // The renderer does not scale sizes by the pixel ratio of the display yet, so the
+                // sprite is loaded for a pixel ratio of 1
+                let sprite = match &style.sprite {
+                    Some(sprite) => fetch_sprite(sprite, 1.0, self.kernel.source_client())
+                        .await
+                        .map_err(|e| log::error!("loading the sprite failed: {e:?}"))
+                        .ok(),
+                    None => None,
+                };
+
                 let window_size = self.window.size();
 
                 let center = style.center.unwrap_or_default();
@@ -126,12 +142,19 @@ where
                             );
                         }
 
-                        self.map_context = CurrentMapContext::Ready(MapContext {
+                        if let Some(sprite) = sprite {
+                            world
+                                .resources
+                                .get_or_init_mut::<ImageAtlas>()
+                                .set_sprite(sprite);
+                        }
+
+                        self.map_context = CurrentMapContext::Ready(Box::new(MapContext {
                             world,
                             view_state,
-                            style: std::mem::take(style),
+                            style: *std::mem::take(style),
                             renderer,
-                        });
+                        }));
                     }
                     InitializationResult::Uninitialized(UninitializedRenderer { .. }) => {}
                     _ => panic!("Rendering context gone"),
@@ -162,10 +185,12 @@ where
         match &self.map_context {
             CurrentMapContext::Ready(c) => {
                 self.map_context = CurrentMapContext::Pending {
-                    style: c.style.clone(),
-                    renderer_builder: RendererBuilder::new()
-                        .with_renderer_settings(c.renderer.settings.clone())
-                        .with_wgpu_settings(c.renderer.wgpu_settings.clone()),
+                    style: Box::new(c.style.clone()),
+                    renderer_builder: Box::new(
+                        RendererBuilder::new()
+                            .with_renderer_settings(c.renderer.settings.clone())
+                            .with_wgpu_settings(c.renderer.wgpu_settings.clone()),
+                    ),
                 }
             }
             CurrentMapContext::Pending { .. } => {}
@@ -185,14 +210,14 @@ where
 
     pub fn context(&self) -> Result<&MapContext, MapError> {
         match &self.map_context {
-            CurrentMapContext::Ready(map_context) => Ok(map_context),
+            CurrentMapContext::Ready(map_context) => Ok(map_context.as_ref()),
             CurrentMapContext::Pending { .. } => Err(MapError::RendererNotReady),
         }
     }
 
     pub fn context_mut(&mut self) -> Result<&mut MapContext, MapError> {
         match &mut self.map_context {
-            CurrentMapContext::Ready(map_context) => Ok(map_context),
+            CurrentMapContext::Ready(map_context) => Ok(map_context.as_mut()),
             CurrentMapContext::Pending { .. } => Err(MapError::RendererNotReady),
         }
     }
@@ -17,6 +17,19 @@ pub mod http_client {
     pub use super::noweb::http_client::*;
 }
 
+/// File client for non-web targets.
+pub mod file_client {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use super::noweb::file_client::*;
+}
+
+/// MBTiles client for non-web targets.
+#[cfg(feature = "mbtiles")]
+pub mod mbtiles {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use super::noweb::mbtiles::*;
+}
+
 /// Scheduler for non-web targets.
 pub mod scheduler {
     #[cfg(not(target_arch = "wasm32"))]
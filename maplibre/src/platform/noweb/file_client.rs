@@ -0,0 +1,45 @@
+//! Reads tiles and other resources from the local file system.
+
+use async_trait::async_trait;
+
+use crate::{
+    coords::WorldTileCoords,
+    io::{
+        source_client::{SourceBackend, SourceFetchError},
+        tile_json::{TileJson, TileJsonError},
+    },
+};
+
+/// The URL scheme of files, e.g. `file:///data/tiles/{z}/{x}/{y}.pbf`.
+pub const FILE_SCHEME: &str = "file://";
+
+/// Reads tiles, TileJSON documents and other resources from `file://` urls.
+#[derive(Clone, Default)]
+pub struct FileSourceClient;
+
+impl FileSourceClient {
+    fn read(url: &str) -> Result<Vec<u8>, SourceFetchError> {
+        let path = url.strip_prefix(FILE_SCHEME).unwrap_or(url);
+        std::fs::read(path).map_err(|e| SourceFetchError(Box::new(e)))
+    }
+}
+
+#[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+#[cfg_attr(feature = "thread-safe-futures", async_trait)]
+impl SourceBackend for FileSourceClient {
+    async fn fetch_tile(
+        &self,
+        url: &str,
+        _coords: &WorldTileCoords,
+    ) -> Result<Vec<u8>, SourceFetchError> {
+        Self::read(url)
+    }
+
+    async fn fetch_tile_json(&self, url: &str) -> Result<TileJson, TileJsonError> {
+        TileJson::parse(&Self::read(url)?)
+    }
+
+    async fn fetch_resource(&self, url: &str) -> Result<Vec<u8>, SourceFetchError> {
+        Self::read(url)
+    }
+}
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use async_trait::async_trait;
 use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache, HttpCacheOptions};
-use reqwest::{Client, StatusCode};
+use reqwest::{header::RANGE, Client, StatusCode};
 use reqwest_middleware::ClientWithMiddleware;
 
 use crate::io::source_client::{HttpClient, SourceFetchError};
@@ -65,4 +65,39 @@ impl HttpClient for ReqwestHttpClient {
             Err(e) => Err(SourceFetchError(Box::new(e))),
         }
     }
+
+    async fn fetch_range(
+        &self,
+        url: &str,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>, SourceFetchError> {
+        // An empty range can not be expressed by a Range header
+        if length == 0 {
+            return Ok(Vec::new());
+        }
+
+        let response = self
+            .client
+            .get(url)
+            .header(RANGE, format!("bytes={}-{}", offset, offset + length - 1))
+            .send()
+            .await?;
+        match response.error_for_status() {
+            Ok(response) => {
+                // Servers or the cache may ignore the range and respond with the whole document
+                let is_partial = response.status() == StatusCode::PARTIAL_CONTENT;
+                let body = response.bytes().await?;
+
+                if is_partial {
+                    Ok(Vec::from(body.as_ref()))
+                } else {
+                    let start = (offset as usize).min(body.len());
+                    let end = start.saturating_add(length as usize).min(body.len());
+                    Ok(Vec::from(&body[start..end]))
+                }
+            }
+            Err(e) => Err(SourceFetchError(Box::new(e))),
+        }
+    }
 }
@@ -0,0 +1,216 @@
+//! Reads tiles from [MBTiles](https://github.com/mapbox/mbtiles-spec) files at runtime.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::Read,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use thiserror::Error;
+
+use crate::{
+    coords::WorldTileCoords,
+    io::{
+        source_client::{SourceBackend, SourceFetchError},
+        tile_json::{TileJson, TileJsonError},
+    },
+    style::source::TileAddressingScheme,
+};
+
+/// The URL scheme of sources which are read from MBTiles files, e.g. `mbtiles:///data/berlin.mbtiles`.
+pub const MBTILES_SCHEME: &str = "mbtiles://";
+
+#[derive(Error, Debug)]
+pub enum MbtilesError {
+    #[error("accessing the MBTiles database failed")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("decompressing the tile failed")]
+    Decompression(#[from] std::io::Error),
+    #[error("the tile {0} does not exist")]
+    TileNotFound(WorldTileCoords),
+    #[error("the metadata value {0} is invalid")]
+    InvalidMetadata(String),
+}
+
+/// Reads tiles from MBTiles files. The connections to the files are kept open and shared between
+/// clones.
+#[derive(Clone, Default)]
+pub struct MbtilesClient {
+    connections: Arc<Mutex<HashMap<String, Connection>>>,
+}
+
+impl MbtilesClient {
+    fn with_connection<T>(
+        &self,
+        path: &str,
+        f: impl FnOnce(&Connection) -> Result<T, MbtilesError>,
+    ) -> Result<T, MbtilesError> {
+        let mut connections = self.connections.lock().unwrap();
+        let connection = match connections.entry(path.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Connection::open_with_flags(
+                path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?),
+        };
+        f(connection)
+    }
+
+    /// Reads the tile at `coords` from the file at `path`. Gzip compressed tiles are decompressed.
+    pub fn read_tile(&self, path: &str, coords: &WorldTileCoords) -> Result<Vec<u8>, MbtilesError> {
+        // MBTiles stores the rows according to the TMS scheme
+        let tile = coords
+            .into_tile(TileAddressingScheme::TMS)
+            .ok_or(MbtilesError::TileNotFound(*coords))?;
+
+        let data: Vec<u8> = self
+            .with_connection(path, |connection| {
+                // language=SQL
+                Ok(connection
+                    .query_row(
+                        "SELECT tile_data FROM tiles
+                         WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+                        params![u8::from(tile.z), tile.x, tile.y],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })?
+            .ok_or(MbtilesError::TileNotFound(*coords))?;
+
+        if data.starts_with(&[0x1f, 0x8b]) {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+            Ok(decompressed)
+        } else {
+            Ok(data)
+        }
+    }
+
+    /// Describes the file at `path` as TileJSON by reading its `metadata` table. The tiles of the
+    /// returned document point back to the file.
+    pub fn read_tile_json(&self, path: &str) -> Result<TileJson, MbtilesError> {
+        let metadata: HashMap<String, String> = self.with_connection(path, |connection| {
+            // language=SQL
+            let mut statement = connection.prepare("SELECT name, value FROM metadata")?;
+            let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            Ok(rows.collect::<Result<_, _>>()?)
+        })?;
+
+        let zoom = |name: &str| {
+            metadata
+                .get(name)
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<u8>()
+                        .map_err(|_| MbtilesError::InvalidMetadata(name.to_string()))
+                })
+                .transpose()
+        };
+
+        let bounds = metadata
+            .get("bounds")
+            .map(|value| {
+                match value
+                    .split(',')
+                    .map(|part| part.trim().parse::<f64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .as_deref()
+                {
+                    Ok([west, south, east, north]) => Ok((*west, *south, *east, *north)),
+                    _ => Err(MbtilesError::InvalidMetadata("bounds".to_string())),
+                }
+            })
+            .transpose()?;
+
+        Ok(TileJson {
+            tiles: vec![format!("{MBTILES_SCHEME}{path}")],
+            attribution: metadata.get("attribution").cloned(),
+            bounds,
+            maxzoom: zoom("maxzoom")?,
+            minzoom: zoom("minzoom")?,
+            // Tiles are addressed by their world coordinates, the rows are flipped while reading
+            scheme: Some(TileAddressingScheme::XYZ),
+        })
+    }
+}
+
+#[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+#[cfg_attr(feature = "thread-safe-futures", async_trait)]
+impl SourceBackend for MbtilesClient {
+    async fn fetch_tile(
+        &self,
+        url: &str,
+        coords: &WorldTileCoords,
+    ) -> Result<Vec<u8>, SourceFetchError> {
+        let path = url.strip_prefix(MBTILES_SCHEME).unwrap_or(url);
+        self.read_tile(path, coords)
+            .map_err(|e| SourceFetchError(Box::new(e)))
+    }
+
+    async fn fetch_tile_json(&self, url: &str) -> Result<TileJson, TileJsonError> {
+        let path = url.strip_prefix(MBTILES_SCHEME).unwrap_or(url);
+        self.read_tile_json(path)
+            .map_err(|e| SourceFetchError(Box::new(e)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+    use crate::coords::ZoomLevel;
+
+    fn create_mbtiles(path: &std::path::Path) {
+        let connection = Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE metadata (name text, value text);
+                 CREATE TABLE tiles (zoom_level integer, tile_column integer, tile_row integer, tile_data blob);
+                 INSERT INTO metadata VALUES ('bounds', '13.0,52.3,13.8,52.7'), ('minzoom', '0'), ('maxzoom', '14');",
+            )
+            .unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"tile").unwrap();
+        // The tile x=1, y=0 at zoom level 1 is stored in row 1
+        connection
+            .execute(
+                "INSERT INTO tiles VALUES (1, 1, 1, ?1)",
+                params![encoder.finish().unwrap()],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_read_mbtiles() {
+        let path =
+            std::env::temp_dir().join(format!("maplibre-test-{}.mbtiles", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        create_mbtiles(&path);
+        let path_str = path.to_str().unwrap();
+
+        let client = MbtilesClient::default();
+        let tile = client
+            .read_tile(path_str, &WorldTileCoords::from((1, 0, ZoomLevel::new(1))))
+            .unwrap();
+        assert_eq!(tile, b"tile");
+        assert!(matches!(
+            client.read_tile(path_str, &WorldTileCoords::from((1, 1, ZoomLevel::new(1)))),
+            Err(MbtilesError::TileNotFound(_))
+        ));
+
+        let tile_json = client.read_tile_json(path_str).unwrap();
+        assert_eq!(tile_json.tiles, vec![format!("mbtiles://{path_str}")]);
+        assert_eq!(tile_json.bounds, Some((13.0, 52.3, 13.8, 52.7)));
+        assert_eq!(tile_json.maxzoom, Some(14));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
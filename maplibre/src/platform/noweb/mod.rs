@@ -11,7 +11,10 @@ use crate::{
     platform::http_client::ReqwestHttpClient,
 };
 
+pub mod file_client;
 pub mod http_client;
+#[cfg(feature = "mbtiles")]
+pub mod mbtiles;
 pub mod scheduler;
 pub mod trace;
 
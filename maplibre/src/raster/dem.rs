@@ -0,0 +1,185 @@
+//! Elevation grids decoded from the tiles of raster-dem sources.
+
+use image::RgbaImage;
+
+use crate::style::source::DemUnpackFactors;
+
+/// The elevations of a tile in meters. The grid has a border of one sample around the tile,
+/// so that the slope can be calculated at its edges. Initially the border repeats the edge of
+/// the tile; it is backfilled from the neighbouring tiles once they are loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemData {
+    /// Width and height of the tile without the border.
+    dim: u32,
+    /// The `(dim + 2) * (dim + 2)` elevations including the border, row by row.
+    elevations: Vec<f32>,
+    /// Increases whenever the border is backfilled, so that data derived from the elevations
+    /// can be updated.
+    revision: u32,
+}
+
+impl DemData {
+    /// Decodes the elevations of the square `image` of a tile. Returns `None` if the image is
+    /// not square.
+    pub fn decode(image: &RgbaImage, factors: &DemUnpackFactors) -> Option<Self> {
+        let (width, height) = image.dimensions();
+        if width != height || width == 0 {
+            return None;
+        }
+
+        let mut dem = Self {
+            dim: width,
+            elevations: vec![0.0; ((width + 2) * (width + 2)) as usize],
+            revision: 0,
+        };
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let [r, g, b, _] = pixel.0;
+            dem.set(x as i32, y as i32, factors.unpack([r, g, b]));
+        }
+
+        let dim = dem.dim as i32;
+        for i in -1..=dim {
+            let inner = i.clamp(0, dim - 1);
+            dem.set(-1, i, dem.get(0, inner));
+            dem.set(dim, i, dem.get(dim - 1, inner));
+            dem.set(i, -1, dem.get(inner, 0));
+            dem.set(i, dim, dem.get(inner, dim - 1));
+        }
+        Some(dem)
+    }
+
+    /// Restores the elevations from an image created with [`DemData::to_image`].
+    pub fn from_image(image: &RgbaImage) -> Option<Self> {
+        let (width, height) = image.dimensions();
+        if width != height || width < 3 {
+            return None;
+        }
+
+        Some(Self {
+            dim: width - 2,
+            elevations: image
+                .pixels()
+                .map(|pixel| f32::from_ne_bytes(pixel.0))
+                .collect(),
+            revision: 0,
+        })
+    }
+
+    /// Stores the elevations including the border losslessly in the texels of an image, so
+    /// that they can be transferred like the images of raster tiles.
+    pub fn to_image(&self) -> RgbaImage {
+        let stride = self.stride();
+        RgbaImage::from_vec(stride, stride, self.as_bytes().to_vec())
+            .expect("the image has the size of the grid")
+    }
+
+    /// Width and height of the tile without the border.
+    pub fn dim(&self) -> u32 {
+        self.dim
+    }
+
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+
+    /// Width and height of the grid including the border.
+    pub fn stride(&self) -> u32 {
+        self.dim + 2
+    }
+
+    /// The elevations including the border as `f32`s in native byte order, row by row.
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.elevations)
+    }
+
+    /// Returns the elevation at `x` and `y`, which range from -1 to `dim` including the border.
+    pub fn get(&self, x: i32, y: i32) -> f32 {
+        self.elevations[self.index(x, y)]
+    }
+
+    fn set(&mut self, x: i32, y: i32, elevation: f32) {
+        let index = self.index(x, y);
+        self.elevations[index] = elevation;
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        let stride = self.stride() as i32;
+        debug_assert!((-1..stride - 1).contains(&x) && (-1..stride - 1).contains(&y));
+        ((y + 1) * stride + x + 1) as usize
+    }
+
+    /// Copies the edge of the `neighbor` tile into the border of this tile. The neighbour is
+    /// located at the offset `dx` and `dy` in tiles, which range from -1 to 1.
+    pub fn backfill_border(&mut self, neighbor: &DemData, dx: i32, dy: i32) {
+        if neighbor.dim != self.dim || (dx == 0 && dy == 0) {
+            return;
+        }
+
+        let dim = self.dim as i32;
+        let range = |d: i32| match d {
+            -1 => -1..0,
+            0 => 0..dim,
+            _ => dim..dim + 1,
+        };
+
+        for y in range(dy) {
+            for x in range(dx) {
+                self.set(x, y, neighbor.get(x - dx * dim, y - dy * dim));
+            }
+        }
+        self.revision += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    const TERRARIUM: DemUnpackFactors = DemUnpackFactors {
+        red: 256.0,
+        green: 1.0,
+        blue: 1.0 / 256.0,
+        base_shift: 32768.0,
+    };
+
+    fn terrarium(elevations: [[u8; 2]; 2]) -> DemData {
+        let image = RgbaImage::from_fn(2, 2, |x, y| {
+            Rgba([128, elevations[y as usize][x as usize], 0, 255])
+        });
+        DemData::decode(&image, &TERRARIUM).unwrap()
+    }
+
+    #[test]
+    fn test_decode() {
+        let dem = terrarium([[1, 2], [3, 4]]);
+        assert_eq!(dem.dim(), 2);
+        assert_eq!(dem.get(1, 0), 2.0);
+        // The border repeats the edge of the tile
+        assert_eq!(dem.get(-1, -1), 1.0);
+        assert_eq!(dem.get(2, 0), 2.0);
+        assert_eq!(dem.get(1, 2), 4.0);
+
+        assert_eq!(DemData::from_image(&dem.to_image()), Some(dem));
+        assert!(DemData::decode(&RgbaImage::new(2, 1), &TERRARIUM).is_none());
+    }
+
+    #[test]
+    fn test_backfill_border() {
+        let mut dem = terrarium([[1, 2], [3, 4]]);
+        let right = terrarium([[5, 6], [7, 8]]);
+        let below_left = terrarium([[9, 10], [11, 12]]);
+
+        dem.backfill_border(&right, 1, 0);
+        assert_eq!(dem.revision(), 1);
+        assert_eq!(dem.get(2, 0), 5.0);
+        assert_eq!(dem.get(2, 1), 7.0);
+        // The corners are only backfilled from the diagonal neighbours
+        assert_eq!(dem.get(2, -1), 2.0);
+
+        dem.backfill_border(&below_left, -1, 1);
+        assert_eq!(dem.get(-1, 2), 10.0);
+        assert_eq!(dem.get(-1, 1), 3.0);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, rc::Rc};
+use std::{collections::HashMap, marker::PhantomData, rc::Rc};
 
 use image::RgbaImage;
 
@@ -17,6 +17,7 @@ use crate::{
     tcs::{system::SystemContainer, tiles::TileComponent, world::World},
 };
 
+mod dem;
 mod populate_world_system;
 mod process_raster;
 mod queue_system;
@@ -27,12 +28,18 @@ mod resource_system;
 mod transferables;
 mod upload_system;
 
+pub use dem::DemData;
 pub use transferables::{
     DefaultRasterTransferables, LayerRaster, LayerRasterMissing, RasterTransferables,
 };
 
 use crate::render::graph::RenderGraph;
 
+/// The id under which the elevations of the terrain are requested, because the terrain is not a
+/// style layer. The elevations are stored for the source of the terrain, like those of any
+/// raster-dem layer.
+pub const TERRAIN_DEM_LAYER_ID: &str = "maplibre:terrain";
+
 pub struct RasterPlugin<T>(PhantomData<T>);
 
 impl<T: RasterTransferables> Default for RasterPlugin<T> {
@@ -74,13 +81,13 @@ impl<E: Environment, T: RasterTransferables> Plugin<E> for RasterPlugin<T> {
 
 pub struct AvailableRasterLayerData {
     pub coords: WorldTileCoords,
-    pub source_layer: String,
+    pub style_layer_id: String,
     pub image: RgbaImage,
 }
 
 pub struct MissingRasterLayerData {
     pub coords: WorldTileCoords,
-    pub source_layer: String,
+    pub style_layer_id: String,
 }
 
 pub enum RasterLayerData {
@@ -94,3 +101,11 @@ pub struct RasterLayersDataComponent {
 }
 
 impl TileComponent for RasterLayersDataComponent {}
+
+/// The elevations of the tile, keyed by the id of the raster-dem source.
+#[derive(Default)]
+pub struct DemDataComponent {
+    pub sources: HashMap<String, DemData>,
+}
+
+impl TileComponent for DemDataComponent {}
@@ -2,14 +2,20 @@ use std::{borrow::Cow, marker::PhantomData, rc::Rc};
 
 use crate::{
     context::MapContext,
+    coords::WorldTileCoords,
     environment::Environment,
     io::apc::{AsyncProcedureCall, Message},
     kernel::Kernel,
     raster::{
         transferables::{LayerRaster, LayerRasterMissing, RasterTransferables},
-        RasterLayerData, RasterLayersDataComponent,
+        DemData, DemDataComponent, RasterLayerData, RasterLayersDataComponent,
+        TERRAIN_DEM_LAYER_ID,
+    },
+    style::{source::Source, Style},
+    tcs::{
+        system::{System, SystemResult},
+        tiles::Tiles,
     },
-    tcs::system::{System, SystemResult},
 };
 
 pub struct PopulateWorldSystem<E: Environment, T> {
@@ -31,7 +37,7 @@ impl<E: Environment, T: RasterTransferables> System for PopulateWorldSystem<E, T
         "populate_world_system".into()
     }
 
-    fn run(&mut self, MapContext { world, .. }: &mut MapContext) -> SystemResult {
+    fn run(&mut self, MapContext { world, style, .. }: &mut MapContext) -> SystemResult {
         for message in self.kernel.apc().receive(|message| {
             message.has_tag(T::LayerRaster::message_tag())
                 || message.has_tag(T::LayerRasterMissing::message_tag())
@@ -39,17 +45,26 @@ impl<E: Environment, T: RasterTransferables> System for PopulateWorldSystem<E, T
             let message: Message = message;
             if message.has_tag(T::LayerRaster::message_tag()) {
                 let message = message.into_transferable::<T::LayerRaster>();
+                let layer = message.to_layer();
+
+                // The images of raster-dem sources carry the decoded elevations
+                if let Some(source) = dem_source(style, &layer.style_layer_id) {
+                    match DemData::from_image(&layer.image) {
+                        Some(dem) => insert_dem(&mut world.tiles, layer.coords, source, dem),
+                        None => log::error!("invalid elevations of tile {}", layer.coords),
+                    }
+                    continue;
+                }
+
                 let Some(component) = world
                     .tiles
-                    .query_mut::<&mut RasterLayersDataComponent>(message.coords())
+                    .query_mut::<&mut RasterLayersDataComponent>(layer.coords)
                 else {
                     continue;
                 };
 
-                component
-                    .layers
-                    .push(RasterLayerData::Available(message.to_layer()));
-            } else if message.has_tag(T::LayerRaster::message_tag()) {
+                component.layers.push(RasterLayerData::Available(layer));
+            } else if message.has_tag(T::LayerRasterMissing::message_tag()) {
                 let message = message.into_transferable::<T::LayerRasterMissing>();
                 let Some(component) = world
                     .tiles
@@ -67,3 +82,103 @@ impl<E: Environment, T: RasterTransferables> System for PopulateWorldSystem<E, T
         Ok(())
     }
 }
+
+/// Returns the raster-dem source of a layer, or of the terrain if its elevations were requested
+/// under [`TERRAIN_DEM_LAYER_ID`].
+fn dem_source<'a>(style: &'a Style, style_layer_id: &str) -> Option<&'a str> {
+    let source = if style_layer_id == TERRAIN_DEM_LAYER_ID {
+        style.terrain.as_ref().map(|terrain| &terrain.source)
+    } else {
+        style
+            .layers
+            .iter()
+            .find(|style_layer| style_layer.id == style_layer_id)
+            .and_then(|style_layer| style_layer.source.as_ref())
+    };
+    source
+        .filter(|source| {
+            matches!(
+                style.sources.get(source.as_str()),
+                Some(Source::RasterDem(_))
+            )
+        })
+        .map(String::as_str)
+}
+
+/// Stores the elevations of a tile and backfills the borders of the tile and its neighbours
+/// with each other. All layers of a source share the elevations, so they are stored once.
+fn insert_dem(tiles: &mut Tiles, coords: WorldTileCoords, source: &str, mut dem: DemData) {
+    if tiles
+        .query::<&DemDataComponent>(coords)
+        .is_none_or(|component| component.sources.contains_key(source))
+    {
+        return;
+    }
+
+    let neighbors = neighbors(coords);
+    for (neighbor_coords, dx, dy) in &neighbors {
+        let neighbor = if *neighbor_coords == coords {
+            Some(dem.clone())
+        } else {
+            tiles
+                .query::<&DemDataComponent>(*neighbor_coords)
+                .and_then(|component| component.sources.get(source).cloned())
+        };
+        if let Some(neighbor) = neighbor {
+            dem.backfill_border(&neighbor, *dx, *dy);
+        }
+    }
+
+    for (neighbor_coords, dx, dy) in &neighbors {
+        if let Some(neighbor) = tiles
+            .query_mut::<&mut DemDataComponent>(*neighbor_coords)
+            .and_then(|component| component.sources.get_mut(source))
+        {
+            neighbor.backfill_border(&dem, -dx, -dy);
+        }
+    }
+
+    if let Some(component) = tiles.query_mut::<&mut DemDataComponent>(coords) {
+        component.sources.insert(source.to_string(), dem);
+    }
+}
+
+/// Returns the coordinates of the eight neighbours of a tile and their offset. The neighbours
+/// wrap around the antimeridian.
+fn neighbors(coords: WorldTileCoords) -> Vec<(WorldTileCoords, i32, i32)> {
+    let tiles = 1i32 << u8::from(coords.z);
+    let mut neighbors = Vec::with_capacity(8);
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let y = coords.y + dy;
+            if (dx == 0 && dy == 0) || y < 0 || y >= tiles {
+                continue;
+            }
+            let x = (coords.x + dx).rem_euclid(tiles);
+            neighbors.push((WorldTileCoords::from((x, y, coords.z)), dx, dy));
+        }
+    }
+    neighbors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dem_source;
+    use crate::{raster::TERRAIN_DEM_LAYER_ID, style::Style};
+
+    #[test]
+    fn test_dem_source_of_terrain() {
+        let style: Style = serde_json::from_value(serde_json::json!({
+            "version": 8,
+            "sources": {
+                "dem": {"type": "raster-dem", "tiles": ["https://example.com/{z}/{x}/{y}.png"]}
+            },
+            "layers": [{"id": "background", "type": "background"}],
+            "terrain": {"source": "dem"}
+        }))
+        .unwrap();
+
+        assert_eq!(dem_source(&style, TERRAIN_DEM_LAYER_ID), Some("dem"));
+        assert_eq!(dem_source(&style, "background"), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{collections::HashSet, marker::PhantomData};
 
 use image::RgbaImage;
 use thiserror::Error;
@@ -6,7 +6,11 @@ use thiserror::Error;
 use crate::{
     coords::WorldTileCoords,
     io::apc::Context,
-    raster::transferables::{LayerRaster, RasterTransferables},
+    raster::{
+        dem::DemData,
+        transferables::{LayerRaster, RasterTransferables},
+    },
+    style::source::DemUnpackFactors,
 };
 
 #[derive(Error, Debug)]
@@ -14,25 +18,71 @@ pub enum ProcessRasterError {
     /// Error during processing of the pipeline
     #[error("processing data in pipeline failed")]
     Processing(Box<dyn std::error::Error>),
+    #[error("decoding the raster tile failed")]
+    Decoding(#[from] image::ImageError),
+    #[error("the tile of a raster-dem source is not square")]
+    InvalidDem,
+}
+
+/// The fetched image data of a raster tile.
+pub enum RasterTileData {
+    /// The image of the tile itself.
+    Tile(Vec<u8>),
+    /// The images of the four children of the tile, ordered like
+    /// [`WorldTileCoords::get_children`]. This is used for sources with a `tileSize` of 256,
+    /// whose tiles cover only a quarter of a rendered tile.
+    Children([Vec<u8>; 4]),
 }
 
 pub struct RasterTileRequest {
     pub coords: WorldTileCoords,
+    /// The ids of the style layers which display the tile.
+    pub layers: HashSet<String>,
+    /// Set if the tile belongs to a raster-dem source. The elevations are decoded and
+    /// transferred instead of the image, see [`DemData::to_image`].
+    pub dem: Option<DemUnpackFactors>,
 }
 
 pub fn process_raster_tile<T: RasterTransferables, C: Context>(
-    data: &[u8],
+    data: &RasterTileData,
     tile_request: RasterTileRequest,
     context: &mut ProcessRasterContext<T, C>,
 ) -> Result<(), ProcessRasterError> {
     let coords = &tile_request.coords;
-    let img = image::load_from_memory(data).unwrap();
-    let rgba = img.to_rgba8();
+    let rgba = match data {
+        RasterTileData::Tile(data) => image::load_from_memory(data)?.to_rgba8(),
+        RasterTileData::Children(children) => stitch_children(children)?,
+    };
+    let rgba = match &tile_request.dem {
+        Some(factors) => DemData::decode(&rgba, factors)
+            .ok_or(ProcessRasterError::InvalidDem)?
+            .to_image(),
+        None => rgba,
+    };
 
-    context.layer_raster_finished(coords, "raster".to_string(), rgba)?;
+    for layer_id in tile_request.layers {
+        context.layer_raster_finished(coords, layer_id, rgba.clone())?;
+    }
 
     Ok(())
 }
+
+/// Composes the images of four child tiles to the image of their parent.
+fn stitch_children(children: &[Vec<u8>; 4]) -> Result<RgbaImage, ProcessRasterError> {
+    let children = children
+        .iter()
+        .map(|data| Ok(image::load_from_memory(data)?.to_rgba8()))
+        .collect::<Result<Vec<_>, ProcessRasterError>>()?;
+
+    let (width, height) = children[0].dimensions();
+    let mut parent = RgbaImage::new(width * 2, height * 2);
+    let offsets = [(0, 0), (width, 0), (width, height), (0, height)];
+    for (child, (x, y)) in children.iter().zip(offsets) {
+        image::imageops::replace(&mut parent, child, i64::from(x), i64::from(y));
+    }
+    Ok(parent)
+}
+
 pub struct ProcessRasterContext<T: RasterTransferables, C: Context> {
     context: C,
     phantom_t: PhantomData<T>,
@@ -62,25 +112,56 @@ impl<T: RasterTransferables, C: Context> ProcessRasterContext<T, C> {
 
 #[cfg(test)]
 mod tests {
-    use super::process_raster_tile;
+    use std::io::Cursor;
+
+    use image::{ImageFormat, Rgba, RgbaImage};
+
+    use super::{stitch_children, RasterTileData};
     use crate::{
         coords::ZoomLevel,
         io::apc::tests::DummyContext,
         raster::{
-            process_raster::{ProcessRasterContext, RasterTileRequest},
+            process_raster::{process_raster_tile, ProcessRasterContext, RasterTileRequest},
             DefaultRasterTransferables,
         },
     };
 
-    #[test] // TODO: Add proper tile byte array
-    #[ignore]
-    fn test() {
-        let _output = process_raster_tile(
-            &[0],
+    fn encode_png(color: [u8; 4]) -> Vec<u8> {
+        let mut data = Cursor::new(Vec::new());
+        RgbaImage::from_pixel(2, 2, Rgba(color))
+            .write_to(&mut data, ImageFormat::Png)
+            .unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn test_stitch_children() {
+        let parent = stitch_children(&[
+            encode_png([255, 0, 0, 255]),
+            encode_png([0, 255, 0, 255]),
+            encode_png([0, 0, 255, 255]),
+            encode_png([0, 0, 0, 255]),
+        ])
+        .unwrap();
+
+        assert_eq!(parent.dimensions(), (4, 4));
+        assert_eq!(parent.get_pixel(1, 1), &Rgba([255, 0, 0, 255]));
+        assert_eq!(parent.get_pixel(3, 0), &Rgba([0, 255, 0, 255]));
+        assert_eq!(parent.get_pixel(3, 3), &Rgba([0, 0, 255, 255]));
+        assert_eq!(parent.get_pixel(0, 2), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_invalid_tile() {
+        assert!(process_raster_tile(
+            &RasterTileData::Tile(vec![0]),
             RasterTileRequest {
                 coords: (0, 0, ZoomLevel::default()).into(),
+                layers: ["satellite".to_string()].into(),
+                dem: None,
             },
             &mut ProcessRasterContext::<DefaultRasterTransferables, _>::new(DummyContext),
-        );
+        )
+        .is_err());
     }
 }
@@ -2,7 +2,7 @@
 
 use crate::{
     context::MapContext,
-    raster::render_commands::DrawRasterTiles,
+    raster::{render_commands::DrawRasterTiles, resource::RasterResources},
     render::{
         eventually::{Eventually, Eventually::Initialized},
         render_commands::DrawMasks,
@@ -15,14 +15,30 @@ use crate::{
     },
 };
 
-pub fn queue_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
-    let Some((Initialized(tile_view_pattern),)) = world
-        .resources
-        .query::<(&Eventually<WgpuTileViewPattern>,)>()
+pub fn queue_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some((Initialized(tile_view_pattern), Initialized(raster_resources))) =
+        world.resources.query::<(
+            &Eventually<WgpuTileViewPattern>,
+            &Eventually<RasterResources>,
+        )>()
     else {
         return Err(SystemError::Dependencies);
     };
 
+    let zoom = view_state.zoom().level();
+    let raster_layers = style
+        .layers
+        .iter()
+        .filter(|layer| layer.type_ == "raster" && layer.is_visible_at(zoom))
+        .collect::<Vec<_>>();
+
     let mut items = Vec::new();
 
     for view_tile in tile_view_pattern.iter() {
@@ -31,24 +47,33 @@ pub fn queue_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
 
         // draw tile normal or the source e.g. parent or children
         view_tile.render(|source_shape| {
-            // FIXME if raster_resources.has_tile(source_shape.coords(), world) {
-            items.push((
-                LayerItem {
-                    draw_function: Box::new(DrawState::<LayerItem, DrawRasterTiles>::new()),
-                    index: 0,
-                    is_line: false,
-                    style_layer: "raster".to_string(),
-                    tile: Tile {
-                        coords: source_shape.coords(),
+            let source_coords = source_shape.coords();
+            for style_layer in &raster_layers {
+                if raster_resources
+                    .get_bound_texture(&source_coords, &style_layer.id)
+                    .is_none()
+                {
+                    continue;
+                }
+
+                items.push((
+                    LayerItem {
+                        draw_function: Box::new(DrawState::<LayerItem, DrawRasterTiles>::new()),
+                        index: style_layer.index,
+                        is_line: false,
+                        style_layer: style_layer.id.clone(),
+                        tile: Tile {
+                            coords: source_coords,
+                        },
+                        source_shape: source_shape.clone(),
+                    },
+                    // FIXME tsc: Tile masks are currently drawn twice by each plugin
+                    TileMaskItem {
+                        draw_function: Box::new(DrawState::<TileMaskItem, DrawMasks>::new()),
+                        source_shape: source_shape.clone(),
                     },
-                    source_shape: source_shape.clone(),
-                },
-                // FIXME tsc: Tile masks are currently drawn twice by each plugin
-                TileMaskItem {
-                    draw_function: Box::new(DrawState::<TileMaskItem, DrawMasks>::new()),
-                    source_shape: source_shape.clone(),
-                },
-            ));
+                ));
+            }
         });
     }
 
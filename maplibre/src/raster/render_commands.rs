@@ -40,7 +40,9 @@ impl<const I: usize> RenderCommand<LayerItem> for SetRasterViewBindGroup<I> {
             return RenderCommandResult::Failure;
         };
 
-        let Some(bind_group) = raster_resources.get_bound_texture(&item.tile.coords) else {
+        let Some(bind_group) =
+            raster_resources.get_bound_texture(&item.tile.coords, &item.style_layer)
+        else {
             return RenderCommandResult::Failure;
         };
 
@@ -1,22 +1,34 @@
 //! Requests tiles which are currently in view
 
-use std::{borrow::Cow, collections::HashSet, marker::PhantomData, rc::Rc};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    rc::Rc,
+};
 
 use crate::{
     context::MapContext,
+    coords::WorldTileCoords,
     environment::{Environment, OffscreenKernel},
     io::{
         apc::{AsyncProcedureCall, AsyncProcedureFuture, Context, Input, ProcedureError},
-        source_type::{RasterSource, SourceType},
+        source_client::{HttpClient, SourceClient, SourceFetchError},
+        source_type::{SourceType, TemplateSource},
     },
     kernel::Kernel,
     raster::{
-        process_raster::{process_raster_tile, ProcessRasterContext, RasterTileRequest},
+        process_raster::{
+            process_raster_tile, ProcessRasterContext, RasterTileData, RasterTileRequest,
+        },
         transferables::{LayerRasterMissing, RasterTransferables},
-        RasterLayersDataComponent,
+        DemDataComponent, RasterLayersDataComponent, TERRAIN_DEM_LAYER_ID,
     },
     render::{tile_view_pattern::DEFAULT_TILE_SIZE, view_state::ViewStatePadding},
-    style::layer::LayerPaint,
+    style::{
+        source::{RasterSource, Source},
+        Style,
+    },
     tcs::system::{System, SystemResult},
 };
 
@@ -75,7 +87,8 @@ impl<E: Environment, T: RasterTransferables> System for RequestSystem<E, T> {
                         .tiles
                         .spawn_mut(coords)
                         .expect("unable to spawn a raster tile")
-                        .insert(RasterLayersDataComponent::default());
+                        .insert(RasterLayersDataComponent::default())
+                        .insert(DemDataComponent::default());
 
                     tracing::event!(tracing::Level::ERROR, %coords, "tile request started: {coords}");
                     log::info!("tile request started: {coords}");
@@ -86,6 +99,7 @@ impl<E: Environment, T: RasterTransferables> System for RequestSystem<E, T> {
                             Input::TileRequest {
                                 coords,
                                 style: style.clone(), // TODO: Avoid cloning whole style
+                                source_backends: self.kernel.source_client().backends().clone(),
                             },
                             fetch_raster_apc::<
                                 E::OffscreenKernelEnvironment,
@@ -111,45 +125,61 @@ pub fn fetch_raster_apc<K: OffscreenKernel, T: RasterTransferables, C: Context +
     kernel: K,
 ) -> AsyncProcedureFuture {
     Box::pin(async move {
-        let Input::TileRequest { coords, style } = input else {
+        let Input::TileRequest {
+            coords,
+            style,
+            source_backends,
+        } = input
+        else {
             return Err(ProcedureError::IncompatibleInput);
         };
 
-        let raster_layers: HashSet<String> = style
-            .layers
-            .iter()
-            .filter_map(|layer| {
-                if matches!(layer.paint, Some(LayerPaint::Raster(_))) {
-                    layer.source_layer.clone()
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let requested_layers = requested_layers(&style, coords);
 
-        let client = kernel.source_client();
+        let mut client = kernel.source_client();
+        client.register_backends(&source_backends);
 
-        if !raster_layers.is_empty() {
-            let context = context.clone();
-            let source = SourceType::Raster(RasterSource::default());
+        // Every source is fetched once per tile, for all the layers which use it
+        for (source_id, layers) in requested_layers {
+            let (source, dem) = match style.sources.get(source_id) {
+                Some(Source::Raster(source)) => (source, None),
+                Some(Source::RasterDem(source)) => (&source.raster, Some(source.unpack_factors())),
+                Some(_) => continue,
+                None => {
+                    log::warn!("raster source {source_id} is not defined in the style");
+                    send_layers_missing::<T, C>(&context, coords, &layers)?;
+                    continue;
+                }
+            };
 
-            match client.fetch(&coords, &source).await {
-                Ok(data) => {
-                    let data = data.into_boxed_slice();
+            if !source.has_tile(&coords) {
+                send_layers_missing::<T, C>(&context, coords, &layers)?;
+                continue;
+            }
 
-                    let mut process_context = ProcessRasterContext::<T, C>::new(context);
+            let Some(template_source) = TemplateSource::from_raster_source(source) else {
+                log::warn!("raster source {source_id} does not define any tiles");
+                send_layers_missing::<T, C>(&context, coords, &layers)?;
+                continue;
+            };
 
-                    process_raster_tile(&data, RasterTileRequest { coords }, &mut process_context)
-                        .map_err(|e| ProcedureError::Execution(Box::new(e)))?;
+            match fetch_raster_tile(&client, source, template_source, coords).await {
+                Ok(data) => {
+                    let mut process_context = ProcessRasterContext::<T, C>::new(context.clone());
+                    process_raster_tile(
+                        &data,
+                        RasterTileRequest {
+                            coords,
+                            layers,
+                            dem,
+                        },
+                        &mut process_context,
+                    )
+                    .map_err(|e| ProcedureError::Execution(Box::new(e)))?;
                 }
                 Err(e) => {
                     log::error!("{e:?}");
-
-                    context
-                        .send_back(<T as RasterTransferables>::LayerRasterMissing::build_from(
-                            coords,
-                        ))
-                        .map_err(ProcedureError::Send)?;
+                    send_layers_missing::<T, C>(&context, coords, &layers)?;
                 }
             }
         }
@@ -157,3 +187,131 @@ pub fn fetch_raster_apc<K: OffscreenKernel, T: RasterTransferables, C: Context +
         Ok(())
     })
 }
+
+/// Returns the ids of the layers which display the tile at `coords`, keyed by their raster or
+/// raster-dem source. The elevations of the terrain are requested even if no layer uses its
+/// source.
+fn requested_layers(style: &Style, coords: WorldTileCoords) -> HashMap<&str, HashSet<String>> {
+    let mut requested_layers: HashMap<&str, HashSet<String>> = HashMap::new();
+    for layer in &style.layers {
+        let Some(source) = &layer.source else {
+            continue;
+        };
+        // Layers of raster-dem sources, like hillshade layers, consume the elevations
+        let is_dem = matches!(style.sources.get(source), Some(Source::RasterDem(_)));
+        if (layer.type_ != "raster" && !is_dem) || !layer.is_in_zoom_range_of_tile(coords.z) {
+            continue;
+        }
+        requested_layers
+            .entry(source.as_str())
+            .or_default()
+            .insert(layer.id.clone());
+    }
+
+    if let Some(terrain) = &style.terrain {
+        if matches!(
+            style.sources.get(&terrain.source),
+            Some(Source::RasterDem(_))
+        ) {
+            requested_layers
+                .entry(terrain.source.as_str())
+                .or_insert_with(|| HashSet::from([TERRAIN_DEM_LAYER_ID.to_string()]));
+        } else {
+            log::warn!(
+                "the source {} of the terrain is no raster-dem source",
+                terrain.source
+            );
+        }
+    }
+    requested_layers
+}
+
+/// Fetches the image data which covers the tile at `coords`. The tiles of sources with a
+/// `tileSize` of 256 only cover a quarter of a rendered tile, so the four children of the tile
+/// are fetched instead, as long as the source provides them.
+async fn fetch_raster_tile<HC: HttpClient>(
+    client: &SourceClient<HC>,
+    source: &RasterSource,
+    template_source: TemplateSource,
+    coords: WorldTileCoords,
+) -> Result<RasterTileData, SourceFetchError> {
+    let source_type = SourceType::Template(template_source);
+    let children = coords.get_children();
+
+    if source.tile_size() > 256 || !children.iter().all(|child| source.has_tile(child)) {
+        return Ok(RasterTileData::Tile(
+            client.fetch(&coords, &source_type).await?,
+        ));
+    }
+
+    let mut data = Vec::with_capacity(children.len());
+    for child in &children {
+        data.push(client.fetch(child, &source_type).await?);
+    }
+    Ok(RasterTileData::Children(
+        data.try_into().expect("a tile has four children"),
+    ))
+}
+
+fn send_layers_missing<T: RasterTransferables, C: Context>(
+    context: &C,
+    coords: WorldTileCoords,
+    layers: &HashSet<String>,
+) -> Result<(), ProcedureError> {
+    for layer in layers {
+        context
+            .send_back(<T as RasterTransferables>::LayerRasterMissing::build_from(
+                coords,
+                layer.clone(),
+            ))
+            .map_err(ProcedureError::Send)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::requested_layers;
+    use crate::{coords::WorldTileCoords, raster::TERRAIN_DEM_LAYER_ID, style::Style};
+
+    fn style(layers: serde_json::Value) -> Style {
+        serde_json::from_value(serde_json::json!({
+            "version": 8,
+            "sources": {
+                "dem": {"type": "raster-dem", "tiles": ["https://example.com/{z}/{x}/{y}.png"]}
+            },
+            "layers": layers,
+            "terrain": {"source": "dem"}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_terrain_without_dem_layer() {
+        let style = style(serde_json::json!([
+            {"id": "background", "type": "background"}
+        ]));
+        let layers = requested_layers(&style, WorldTileCoords::from((0, 0, 0.into())));
+
+        assert_eq!(
+            layers.get("dem"),
+            Some(&HashSet::from([TERRAIN_DEM_LAYER_ID.to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_terrain_with_dem_layer() {
+        let style = style(serde_json::json!([
+            {"id": "hillshade", "type": "hillshade", "source": "dem"}
+        ]));
+        let layers = requested_layers(&style, WorldTileCoords::from((0, 0, 0.into())));
+
+        // The elevations of the hillshade layer are shared with the terrain
+        assert_eq!(
+            layers.get("dem"),
+            Some(&HashSet::from(["hillshade".to_string()]))
+        );
+    }
+}
@@ -15,7 +15,8 @@ pub struct RasterResources {
     sampler: wgpu::Sampler,
     msaa: Msaa,
     pipeline: wgpu::RenderPipeline,
-    bound_textures: HashMap<WorldTileCoords, wgpu::BindGroup>,
+    /// The bind groups of the textures of each tile, keyed by the id of the style layer.
+    bound_textures: HashMap<WorldTileCoords, HashMap<String, wgpu::BindGroup>>,
 }
 
 impl RasterResources {
@@ -49,19 +50,25 @@ impl RasterResources {
         Texture::new(label, device, format, width, height, self.msaa, usage)
     }
 
-    pub fn get_bound_texture(&self, coords: &WorldTileCoords) -> Option<&wgpu::BindGroup> {
-        self.bound_textures.get(coords)
+    pub fn get_bound_texture(
+        &self,
+        coords: &WorldTileCoords,
+        style_layer_id: &str,
+    ) -> Option<&wgpu::BindGroup> {
+        self.bound_textures.get(coords)?.get(style_layer_id)
     }
 
-    /// Creates a bind group for each fetched raster tile and store it inside a hashmap.
+    /// Creates a bind group for each fetched raster tile of a style layer and store it inside a
+    /// hashmap.
     pub fn bind_texture(
         &mut self,
         device: &wgpu::Device,
         coords: &WorldTileCoords,
+        style_layer_id: &str,
         texture: Texture,
     ) {
-        self.bound_textures.insert(
-            *coords,
+        self.bound_textures.entry(*coords).or_default().insert(
+            style_layer_id.to_string(),
             device.create_bind_group(&wgpu::BindGroupDescriptor {
                 layout: &self.pipeline.get_bind_group_layout(0),
                 entries: &[
@@ -33,7 +33,7 @@ pub trait LayerRaster: IntoMessage + Debug + Send {
 pub trait LayerRasterMissing: IntoMessage + Debug + Send {
     fn message_tag() -> &'static dyn MessageTag;
 
-    fn build_from(coords: WorldTileCoords) -> Self;
+    fn build_from(coords: WorldTileCoords, layer_name: String) -> Self;
 
     fn coords(&self) -> WorldTileCoords;
 
@@ -78,7 +78,7 @@ impl LayerRaster for DefaultLayerRaster {
     fn to_layer(self) -> AvailableRasterLayerData {
         AvailableRasterLayerData {
             coords: self.coords,
-            source_layer: "raster".to_string(),
+            style_layer_id: self.layer_name,
             image: self.image,
         }
     }
@@ -86,6 +86,7 @@ impl LayerRaster for DefaultLayerRaster {
 
 pub struct DefaultLayerRasterMissing {
     pub coords: WorldTileCoords,
+    pub layer_name: String,
 }
 
 impl Debug for DefaultLayerRasterMissing {
@@ -105,8 +106,8 @@ impl LayerRasterMissing for DefaultLayerRasterMissing {
         &RasterMessageTag::LayerRasterMissing
     }
 
-    fn build_from(coords: WorldTileCoords) -> Self {
-        Self { coords }
+    fn build_from(coords: WorldTileCoords, layer_name: String) -> Self {
+        Self { coords, layer_name }
     }
 
     fn coords(&self) -> WorldTileCoords {
@@ -116,7 +117,7 @@ impl LayerRasterMissing for DefaultLayerRasterMissing {
     fn to_layer(self) -> MissingRasterLayerData {
         MissingRasterLayerData {
             coords: self.coords,
-            source_layer: "raster".to_string(),
+            style_layer_id: self.layer_name,
         }
     }
 }
@@ -63,16 +63,17 @@ fn upload_raster_layer(
     view_region: &ViewRegion,
 ) {
     for coords in view_region.iter() {
-        if raster_resources.get_bound_texture(&coords).is_some() {
-            continue;
-        }
-
         let Some(raster_layers) = tiles.query::<&RasterLayersDataComponent>(coords) else {
             continue;
         };
 
         for style_layer in &style.layers {
-            let style_source_layer = style_layer.source_layer.as_ref().unwrap(); // FIXME: Remove unwrap
+            if raster_resources
+                .get_bound_texture(&coords, &style_layer.id)
+                .is_some()
+            {
+                continue;
+            }
 
             let Some(AvailableRasterLayerData { coords, image, .. }) = raster_layers
                 .layers
@@ -81,7 +82,7 @@ fn upload_raster_layer(
                     RasterLayerData::Available(data) => Some(data),
                     RasterLayerData::Missing(_) => None,
                 })
-                .find(|layer| style_source_layer.as_str() == layer.source_layer)
+                .find(|layer| style_layer.id == layer.style_layer_id)
             else {
                 continue;
             };
@@ -113,7 +114,7 @@ fn upload_raster_layer(
                 texture.size,
             );
 
-            raster_resources.bind_texture(device, coords, texture);
+            raster_resources.bind_texture(device, coords, &style_layer.id, texture);
         }
     }
 }
@@ -0,0 +1,212 @@
+//! Packs the images of the sprite which are used as patterns or icons into a texture.
+//!
+//! Adopted from
+//! [ImageAtlas](https://github.com/maplibre/maplibre-gl-js/blob/main/src/render/image_atlas.ts).
+
+use std::collections::HashMap;
+
+use guillotiere::{size2, AtlasAllocator};
+
+use crate::io::sprite::{SpriteImage, SpriteSheet};
+
+/// Width and height of the atlas.
+pub const IMAGE_ATLAS_SIZE: u32 = 1024;
+/// Bytes per texel of the RGBA atlas.
+const TEXEL_BYTES: usize = 4;
+/// Images are surrounded by a border of one texel. The border of patterns repeats the opposite
+/// edge of the image, such that patterns wrap seamlessly with linear filtering. The border of icons
+/// is transparent.
+pub const PADDING: u32 = 1;
+
+/// The location of an image within the [`ImageAtlas`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImagePosition {
+    /// Texture coordinates of the top left corner of the image, without padding.
+    pub tl: [f32; 2],
+    /// Texture coordinates of the bottom right corner of the image, without padding.
+    pub br: [f32; 2],
+    /// Size of the image in pixels on the screen.
+    pub display_size: [f32; 2],
+    /// Whether the image is a signed distance field, which is colored when drawn.
+    pub sdf: bool,
+}
+
+impl ImagePosition {
+    /// Returns the texture coordinates of the top left and the bottom right corner.
+    pub fn rect(&self) -> [f32; 4] {
+        [self.tl[0], self.tl[1], self.br[0], self.br[1]]
+    }
+}
+
+/// Stores the images of the [`SpriteSheet`] of the style, which are added to the atlas when they
+/// are used for the first time.
+pub struct ImageAtlas {
+    sprite: SpriteSheet,
+    allocator: AtlasAllocator,
+    positions: HashMap<String, ImagePosition>,
+    icon_positions: HashMap<String, ImagePosition>,
+    data: Vec<u8>,
+    dirty: bool,
+}
+
+impl Default for ImageAtlas {
+    fn default() -> Self {
+        Self {
+            sprite: SpriteSheet::default(),
+            allocator: AtlasAllocator::new(size2(IMAGE_ATLAS_SIZE as i32, IMAGE_ATLAS_SIZE as i32)),
+            positions: HashMap::new(),
+            icon_positions: HashMap::new(),
+            data: vec![0; (IMAGE_ATLAS_SIZE * IMAGE_ATLAS_SIZE) as usize * TEXEL_BYTES],
+            dirty: true,
+        }
+    }
+}
+
+impl ImageAtlas {
+    /// Replaces the sprite and removes all images from the atlas.
+    pub fn set_sprite(&mut self, sprite: SpriteSheet) {
+        *self = Self {
+            sprite,
+            ..Self::default()
+        };
+    }
+
+    /// Returns the position of the image `id` of the sprite. The image is added to the atlas if
+    /// it is not part of it yet. Returns `None` if the sprite has no such image or the atlas is
+    /// full.
+    pub fn get_pattern(&mut self, id: &str) -> Option<ImagePosition> {
+        if let Some(position) = self.positions.get(id) {
+            return Some(*position);
+        }
+        let position = self.add_image(id, true)?;
+        self.positions.insert(id.to_string(), position);
+        Some(position)
+    }
+
+    /// Returns the position of the image `id` of the sprite, which is drawn as an icon. Unlike
+    /// [`Self::get_pattern`], the image is surrounded by a transparent border.
+    pub fn get_icon(&mut self, id: &str) -> Option<ImagePosition> {
+        if let Some(position) = self.icon_positions.get(id) {
+            return Some(*position);
+        }
+        let position = self.add_image(id, false)?;
+        self.icon_positions.insert(id.to_string(), position);
+        Some(position)
+    }
+
+    /// Copies the image `id` to a free space of the atlas. The border of the image repeats its
+    /// opposite edge if `wrap` is set.
+    fn add_image(&mut self, id: &str, wrap: bool) -> Option<ImagePosition> {
+        let Some(image) = self.sprite.get(id) else {
+            log::warn!("the image {id} is not part of the sprite");
+            return None;
+        };
+
+        let padded_width = image.width + 2 * PADDING;
+        let padded_height = image.height + 2 * PADDING;
+        let Some(allocation) = self
+            .allocator
+            .allocate(size2(padded_width as i32, padded_height as i32))
+        else {
+            log::warn!("image atlas is out of space");
+            return None;
+        };
+        let x = allocation.rectangle.min.x as u32;
+        let y = allocation.rectangle.min.y as u32;
+        copy_padded(&mut self.data, image, x, y, wrap);
+        self.dirty = true;
+
+        let size = IMAGE_ATLAS_SIZE as f32;
+        Some(ImagePosition {
+            tl: [(x + PADDING) as f32 / size, (y + PADDING) as f32 / size],
+            br: [
+                (x + PADDING + image.width) as f32 / size,
+                (y + PADDING + image.height) as f32 / size,
+            ],
+            display_size: image.display_size(),
+            sdf: image.sdf,
+        })
+    }
+
+    /// Returns the texels of the atlas if they changed since the last call.
+    pub fn take_changes(&mut self) -> Option<&[u8]> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        Some(&self.data)
+    }
+}
+
+/// Copies the image to the atlas, such that the padding starts at `x` and `y`. The padding is
+/// transparent unless `wrap` is set.
+fn copy_padded(data: &mut [u8], image: &SpriteImage, x: u32, y: u32, wrap: bool) {
+    let (width, height) = (image.width, image.height);
+    for padded_y in 0..height + 2 * PADDING {
+        let source_y = (padded_y + height - PADDING) % height;
+        let border_y = padded_y < PADDING || padded_y >= height + PADDING;
+        for padded_x in 0..width + 2 * PADDING {
+            let source_x = (padded_x + width - PADDING) % width;
+            let border = border_y || padded_x < PADDING || padded_x >= width + PADDING;
+            let target = ((y + padded_y) * IMAGE_ATLAS_SIZE + x + padded_x) as usize * TEXEL_BYTES;
+            if border && !wrap {
+                data[target..target + TEXEL_BYTES].fill(0);
+                continue;
+            }
+            let source = (source_y * width + source_x) as usize * TEXEL_BYTES;
+            data[target..target + TEXEL_BYTES]
+                .copy_from_slice(&image.data[source..source + TEXEL_BYTES]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_patterns() {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[255, 0, 0, 255, 0, 0, 255, 255])
+            .unwrap();
+        let index = br#"{"stripes": {"x": 0, "y": 0, "width": 2, "height": 1}}"#;
+
+        let mut atlas = ImageAtlas::default();
+        atlas.set_sprite(SpriteSheet::parse(index, &data).unwrap());
+        assert_eq!(atlas.get_pattern("missing"), None);
+
+        let position = atlas.get_pattern("stripes").unwrap();
+        assert_eq!(position.display_size, [2.0, 1.0]);
+        assert_eq!(
+            position.br[0] - position.tl[0],
+            2.0 / IMAGE_ATLAS_SIZE as f32
+        );
+        assert_eq!(atlas.get_pattern("stripes"), Some(position));
+
+        // The padding left of the red texel repeats the blue texel
+        let x = (position.tl[0] * IMAGE_ATLAS_SIZE as f32) as usize;
+        let y = (position.tl[1] * IMAGE_ATLAS_SIZE as f32) as usize;
+        let data = atlas.take_changes().unwrap();
+        let texel = |x: usize| {
+            let start = (y * IMAGE_ATLAS_SIZE as usize + x) * TEXEL_BYTES;
+            &data[start..start + TEXEL_BYTES]
+        };
+        assert_eq!(texel(x - 1), [0, 0, 255, 255]);
+        assert_eq!(texel(x), [255, 0, 0, 255]);
+        assert_eq!(texel(x + 2), [255, 0, 0, 255]);
+
+        // Icons are stored separately with a transparent border
+        let icon = atlas.get_icon("stripes").unwrap();
+        assert_ne!(icon, position);
+        let x = (icon.tl[0] * IMAGE_ATLAS_SIZE as f32) as usize;
+        let y = (icon.tl[1] * IMAGE_ATLAS_SIZE as f32) as usize;
+        let data = atlas.take_changes().unwrap();
+        let start = (y * IMAGE_ATLAS_SIZE as usize + x - 1) * TEXEL_BYTES;
+        assert_eq!(data[start..start + TEXEL_BYTES], [0, 0, 0, 0]);
+    }
+}
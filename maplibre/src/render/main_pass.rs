@@ -90,7 +90,8 @@ impl Node for MainPassNode {
                     depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                         view: &depth_texture.view,
                         depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(0.0),
+                            // The far plane, such that 3D layers which test the depth are drawn
+                            load: wgpu::LoadOp::Clear(1.0),
                             store: StoreOp::Store,
                         }),
                         stencil_ops: Some(wgpu::Operations {
@@ -28,8 +28,9 @@ use crate::{
         error::RenderError,
         eventually::Eventually,
         graph::{EmptyNode, RenderGraph},
+        image_atlas::ImageAtlas,
         main_pass::{MainPassDriverNode, MainPassNode},
-        resource::{Head, Surface, Texture, TextureView},
+        resource::{Head, ImageAtlasTexture, Surface, Texture, TextureView},
         settings::{RendererSettings, WgpuSettings},
         systems::{
             cleanup_system::cleanup_system, resource_system::ResourceSystem,
@@ -60,6 +61,7 @@ pub mod builder;
 pub mod camera;
 pub mod error;
 pub mod eventually;
+pub mod image_atlas;
 pub mod render_commands;
 pub mod render_phase;
 pub mod settings;
@@ -613,6 +615,9 @@ impl<E: Environment> Plugin<E> for RenderPlugin {
         resources.init::<ViewTileSources>();
         // masks
         resources.insert(Eventually::<MaskPipeline>::Uninitialized);
+        // images of the sprite
+        resources.init::<ImageAtlas>();
+        resources.insert(Eventually::<ImageAtlasTexture>::Uninitialized);
 
         schedule.add_stage(RenderStageLabel::Extract, SystemStage::default());
         schedule.add_stage(
@@ -41,6 +41,16 @@ impl<I: PhaseItem> RenderPhase<I> {
         self.items.clear();
     }
 
+    /// Removes the [`PhaseItems`](PhaseItem) for which `predicate` returns true and returns
+    /// them in their current order.
+    pub fn take_where(&mut self, mut predicate: impl FnMut(&I) -> bool) -> Vec<I> {
+        let (taken, kept) = std::mem::take(&mut self.items)
+            .into_iter()
+            .partition(|item| predicate(item));
+        self.items = kept;
+        taken
+    }
+
     pub fn size(&self) -> usize {
         self.items.len()
     }
@@ -0,0 +1,88 @@
+use crate::render::{image_atlas::IMAGE_ATLAS_SIZE, resource::TilePipeline};
+
+/// Bytes per texel of [`wgpu::TextureFormat::Rgba8Unorm`].
+const TEXEL_BYTES: u32 = 4;
+
+/// The texture of the [`ImageAtlas`](crate::render::image_atlas::ImageAtlas) and its bind group.
+/// The bind group can be used with every pipeline which binds textures like raster pipelines.
+pub struct ImageAtlasTexture {
+    texture: wgpu::Texture,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl ImageAtlasTexture {
+    pub fn from_device(device: &wgpu::Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Image atlas texture"),
+            size: Self::size(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        // Patterns are wrapped by the shaders within the padded images
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Image atlas sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Image atlas bind group layout"),
+            entries: &TilePipeline::texture_bind_group_layout_entries(),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Image atlas bind group"),
+        });
+
+        Self {
+            texture,
+            bind_group,
+        }
+    }
+
+    /// Uploads the texels of the whole atlas.
+    pub fn upload(&self, queue: &wgpu::Queue, data: &[u8]) {
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(IMAGE_ATLAS_SIZE * TEXEL_BYTES),
+                rows_per_image: Some(IMAGE_ATLAS_SIZE),
+            },
+            Self::size(),
+        );
+    }
+
+    fn size() -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: IMAGE_ATLAS_SIZE,
+            height: IMAGE_ATLAS_SIZE,
+            depth_or_array_layers: 1,
+        }
+    }
+}
@@ -17,8 +17,15 @@ pub struct TilePipeline {
     debug_stencil: bool,
     wireframe: bool,
     msaa: bool,
-    raster: bool,
+    /// Count of bind groups with a texture and a sampler
+    texture_bind_groups: usize,
+    /// Appends a bind group with a uniform buffer after the texture bind groups
+    uniform_bind_group: bool,
     glyph_rendering: bool,
+    /// Compares the depth of fragments with the depth buffer
+    depth_compare: wgpu::CompareFunction,
+    /// Writes the depth of fragments which pass the depth test
+    depth_write_enabled: bool,
     settings: RendererSettings,
 
     vertex_state: VertexState,
@@ -46,13 +53,65 @@ impl TilePipeline {
             debug_stencil,
             wireframe,
             msaa: multisampling,
-            raster,
+            texture_bind_groups: raster as usize,
+            uniform_bind_group: false,
             glyph_rendering,
+            depth_compare: wgpu::CompareFunction::Always,
+            depth_write_enabled: false,
             settings,
             vertex_state,
             fragment_state,
         }
     }
+
+    /// Sets the count of bind groups with a texture and a sampler, which is 1 for raster
+    /// pipelines.
+    pub fn with_texture_bind_groups(mut self, count: usize) -> Self {
+        self.texture_bind_groups = count;
+        self
+    }
+
+    /// Appends a bind group with a uniform buffer, which holds the properties of a layer that
+    /// do not fit into vertex attributes.
+    pub fn with_uniform_bind_group(mut self) -> Self {
+        self.uniform_bind_group = true;
+        self
+    }
+
+    /// Enables the depth test, which 3D layers need to occlude each other. Other layers are
+    /// drawn in order of the style and ignore the depth buffer.
+    pub fn with_depth_test(
+        mut self,
+        depth_compare: wgpu::CompareFunction,
+        depth_write_enabled: bool,
+    ) -> Self {
+        self.depth_compare = depth_compare;
+        self.depth_write_enabled = depth_write_enabled;
+        self
+    }
+
+    /// Returns the entries of a bind group with a texture and a sampler. Bind groups with these
+    /// entries can be used with all pipelines which bind textures.
+    pub fn texture_bind_group_layout_entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+        vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ]
+    }
 }
 
 impl RenderPipeline for TilePipeline {
@@ -77,49 +136,51 @@ impl RenderPipeline for TilePipeline {
             }
         };
 
-        RenderPipelineDescriptor {
-            label: Some(self.name),
-            layout: if self.raster {
-                Some(vec![vec![
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        },
-                        count: None,
+        let mut layout = if self.texture_bind_groups > 0 {
+            Some(vec![
+                Self::texture_bind_group_layout_entries();
+                self.texture_bind_groups
+            ])
+        } else if self.glyph_rendering {
+            Some(vec![vec![
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
                     },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ]])
-            } else if self.glyph_rendering {
-                Some(vec![vec![
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            multisampled: false,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ]])
+        } else {
+            None
+        };
+        if self.uniform_bind_group {
+            layout
+                .get_or_insert_with(Vec::new)
+                .push(vec![wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
                     },
-                ]])
-            } else {
-                None
-            },
+                    count: None,
+                }]);
+        }
+
+        RenderPipelineDescriptor {
+            label: Some(self.name),
+            layout,
             vertex: self.vertex_state,
             fragment: self.fragment_state,
             primitive: wgpu::PrimitiveState {
@@ -142,10 +203,10 @@ impl RenderPipeline for TilePipeline {
             } else {
                 Some(wgpu::DepthStencilState {
                     format: self.settings.depth_texture_format,
-                    // Depth writes disabled: layers use painter's algorithm (draw order),
-                    // matching MapLibre GL behavior. Stencil handles tile masking.
-                    depth_write_enabled: false,
-                    depth_compare: wgpu::CompareFunction::Always,
+                    // Depth writes are disabled by default: layers use painter's algorithm
+                    // (draw order), matching MapLibre GL behavior. Stencil handles tile masking.
+                    depth_write_enabled: self.depth_write_enabled,
+                    depth_compare: self.depth_compare,
                     stencil: wgpu::StencilState {
                         front: stencil_state,
                         back: stencil_state,
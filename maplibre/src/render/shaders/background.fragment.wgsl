@@ -0,0 +1,27 @@
+struct FragmentInput {
+    @location(0) v_color: vec4<f32>,
+    @location(1) @interpolate(flat) v_pattern: vec4<f32>,
+    @location(2) @interpolate(flat) v_pattern_placement: vec4<f32>,
+    @location(3) @interpolate(flat) v_pattern_scale: f32,
+    @builtin(position) position: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var t_image_atlas: texture_2d<f32>;
+@group(0) @binding(1)
+var s_image_atlas: sampler;
+
+struct Output {
+    @location(0) out_color: vec4<f32>,
+};
+
+@fragment
+fn main(in: FragmentInput) -> Output {
+    // Repeat the image pattern across the screen, anchored at the world origin
+    let size = max(in.v_pattern_placement.xy, vec2<f32>(1e-6));
+    let pattern_pos = fract((in.position.xy / in.v_pattern_scale + in.v_pattern_placement.zw) / size);
+    let pattern_tex = mix(in.v_pattern.xy, in.v_pattern.zw, pattern_pos);
+    let pattern_color = textureSample(t_image_atlas, s_image_atlas, pattern_tex);
+
+    return Output(select(in.v_color, pattern_color, in.v_pattern.z > in.v_pattern.x));
+}
@@ -1,16 +1,8 @@
-struct ShaderCamera {
-    view_proj: mat4x4<f32>,
-    view_position: vec4<f32>,
-};
-
-struct ShaderGlobals {
-    camera: ShaderCamera,
-};
-
-@group(0) @binding(0) var<uniform> globals: ShaderGlobals;
-
 struct VertexOutput {
     @location(0) color: vec4<f32>,
+    @location(1) @interpolate(flat) pattern: vec4<f32>,
+    @location(2) @interpolate(flat) pattern_placement: vec4<f32>,
+    @location(3) @interpolate(flat) pattern_scale: f32,
     @builtin(position) position: vec4<f32>,
 };
 
@@ -19,6 +11,9 @@ fn main(
     @builtin(vertex_index) vertex_idx: u32,
     @location(0) color: vec4<f32>,
     @location(1) z_index: f32, // Passed from per-layer metadata
+    @location(2) pattern: vec4<f32>,
+    @location(3) pattern_placement: vec4<f32>,
+    @location(4) pattern_scale: f32,
 ) -> VertexOutput {
     // Generate a fullscreen quad using standard 6-vertex triangle list layout
     var positions = array<vec2<f32>, 6>(
@@ -41,6 +36,9 @@ fn main(
     // We use a small epsilon near 0.0 (the far plane) because wgpu `Greater` won't pass 0.0 > 0.0 
     out.position = vec4<f32>(pos, 1.0e-5, 1.0);
     out.color = color;
+    out.pattern = pattern;
+    out.pattern_placement = pattern_placement;
+    out.pattern_scale = pattern_scale;
 
     return out;
 }
@@ -0,0 +1,34 @@
+struct FragmentInput {
+    @location(0) v_extrude: vec2<f32>,
+    @location(1) v_color: vec4<f32>,
+    @location(2) v_stroke_color: vec4<f32>,
+    @location(3) v_data: vec3<f32>,
+};
+
+struct Output {
+    @location(0) out_color: vec4<f32>,
+};
+
+// Like smoothstep, but also defined if edge0 > edge1
+fn interpolate(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = clamp((x - edge0) / (edge1 - edge0), 0.0, 1.0);
+    return t * t * (3.0 - 2.0 * t);
+}
+
+@fragment
+fn main(in: FragmentInput) -> Output {
+    let radius = in.v_data.x;
+    let stroke_width = in.v_data.y;
+    let antialiased_blur = in.v_data.z;
+
+    let extrude_length = length(in.v_extrude);
+
+    let opacity_t = interpolate(0.0, antialiased_blur, extrude_length - 1.0);
+    var color_t = 0.0;
+    if (stroke_width >= 0.01) {
+        color_t = interpolate(antialiased_blur, 0.0, extrude_length - radius / (radius + stroke_width));
+    }
+
+    // The colors are premultiplied with their opacity
+    return Output(opacity_t * mix(in.v_color, in.v_stroke_color, color_t));
+}
@@ -0,0 +1,94 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v_extrude: vec2<f32>,
+    @location(1) v_color: vec4<f32>,
+    @location(2) v_stroke_color: vec4<f32>,
+    @location(3) v_data: vec3<f32>,
+};
+
+// Must match TILE_SIZE and EXTENT in coords.rs
+const TILE_SIZE: f32 = 512.0;
+const EXTENT: f32 = 4096.0;
+
+@vertex
+fn main(
+    @location(0) position: vec2<f32>,
+    @location(1) extrude: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) stroke_color: vec4<f32>,
+    @location(8) circle: vec3<f32>,
+    @location(4) translate1: vec4<f32>,
+    @location(5) translate2: vec4<f32>,
+    @location(6) translate3: vec4<f32>,
+    @location(7) translate4: vec4<f32>,
+    @location(9) zoom_factor: f32,
+    @location(10) z_index: f32,
+    @location(11) viewport_width: f32,
+    @location(12) viewport_height: f32,
+    @location(13) translate: vec2<f32>,
+    @location(14) camera_to_center_distance: f32,
+    @location(15) flags: vec3<u32>,
+) -> VertexOutput {
+    let radius = circle.x;
+    let blur = circle.y;
+    let stroke_width = circle.z;
+
+    let translate_viewport = flags.x != 0u;
+    let scale_with_map = flags.y != 0u;
+    let pitch_with_map = flags.z != 0u;
+
+    let transform = mat4x4<f32>(translate1, translate2, translate3, translate4);
+
+    // Size of a pixel in tile units and in clip space. The y axis of the clip space points up.
+    let pixels_to_tile_units = EXTENT / (TILE_SIZE * zoom_factor);
+    let pixels_to_clip = vec2<f32>(2.0 / viewport_width, -2.0 / viewport_height);
+
+    var center = position;
+    if (!translate_viewport) {
+        center += translate * pixels_to_tile_units;
+    }
+
+    let size = radius + stroke_width;
+
+    var final_position: vec4<f32>;
+    if (pitch_with_map) {
+        var corner = center;
+        if (scale_with_map) {
+            corner += extrude * size * pixels_to_tile_units;
+        } else {
+            // Compensate the perspective, such that the circle keeps its size on the screen
+            let projected_center = transform * vec4<f32>(center, 0.0, 1.0);
+            corner += extrude * size * pixels_to_tile_units
+                * (projected_center.w / camera_to_center_distance);
+        }
+        final_position = transform * vec4<f32>(corner, 0.0, 1.0);
+    } else {
+        final_position = transform * vec4<f32>(center, 0.0, 1.0);
+        if (scale_with_map) {
+            // The perspective divide shrinks circles which are further away than the center
+            final_position.x += extrude.x * size * pixels_to_clip.x * camera_to_center_distance;
+            final_position.y += extrude.y * size * pixels_to_clip.y * camera_to_center_distance;
+        } else {
+            final_position.x += extrude.x * size * pixels_to_clip.x * final_position.w;
+            final_position.y += extrude.y * size * pixels_to_clip.y * final_position.w;
+        }
+    }
+
+    if (translate_viewport) {
+        final_position.x += translate.x * pixels_to_clip.x * final_position.w;
+        final_position.y += translate.y * pixels_to_clip.y * final_position.w;
+    }
+
+    final_position.z = z_index;
+
+    // The blur is relative to the radius. At least one pixel is blurred for antialiasing.
+    let antialiased_blur = -max(1.0 / size, blur);
+
+    return VertexOutput(
+        final_position,
+        extrude,
+        color,
+        stroke_color,
+        vec3<f32>(radius, stroke_width, antialiased_blur),
+    );
+}
@@ -0,0 +1,51 @@
+struct VertexOutput {
+    @location(0) tex_coords: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+struct LayerUniform {
+    // Elevations in meters of the first and the last texel of the color ramp
+    min_elevation: f32,
+    max_elevation: f32,
+    opacity: f32,
+    padding: f32,
+};
+
+// Elevations in meters with a border of one sample around the tile
+@group(0) @binding(0)
+var t_dem: texture_2d<f32>;
+
+// The premultiplied colors of the elevations between the first and the last stop
+@group(1) @binding(0)
+var t_color_ramp: texture_2d<f32>;
+@group(1) @binding(1)
+var s_color_ramp: sampler;
+
+@group(2) @binding(0)
+var<uniform> color_relief: LayerUniform;
+
+fn elevation(position: vec2<i32>) -> f32 {
+    return textureLoad(t_dem, position, 0).r;
+}
+
+@fragment
+fn main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The samples are located at the centers of the pixels, which are offset by the border
+    let dim = f32(textureDimensions(t_dem).x - 2u);
+    let position = in.tex_coords * dim + 0.5;
+    let base = floor(position);
+    let f = position - base;
+    let p = vec2<i32>(base);
+
+    // The elevations can not be filtered, so they are interpolated bilinearly
+    let top = mix(elevation(p), elevation(p + vec2<i32>(1, 0)), f.x);
+    let bottom = mix(elevation(p + vec2<i32>(0, 1)), elevation(p + vec2<i32>(1, 1)), f.x);
+    let e = mix(top, bottom, f.y);
+
+    let range = color_relief.max_elevation - color_relief.min_elevation;
+    let t = clamp((e - color_relief.min_elevation) / range, 0.0, 1.0);
+    // Look up the centers of the first and the last texel at the ends of the range
+    let size = f32(textureDimensions(t_color_ramp).x);
+    let u = (t * (size - 1.0) + 0.5) / size;
+    return textureSample(t_color_ramp, s_color_ramp, vec2<f32>(u, 0.5)) * color_relief.opacity;
+}
@@ -1,14 +1,32 @@
+struct FragmentInput {
+    @location(0) v_color: vec4<f32>,
+    @location(1) @interpolate(flat) v_pattern: vec4<f32>,
+    @location(2) v_pattern_pos: vec2<f32>,
+    @location(3) @interpolate(flat) v_opacity: f32,
+};
+
+@group(0) @binding(0)
+var t_image_atlas: texture_2d<f32>;
+@group(0) @binding(1)
+var s_image_atlas: sampler;
+
 struct Output {
     @location(0) out_color: vec4<f32>,
 };
 
 @fragment
-fn main(
-    @location(0) v_color: vec4<f32>
-) -> Output {
-    // Basic fill shader fragment implementation.
-    // Receives the per-vertex color/opacity and outputs it.
-    // In native, opacity might be uniform or per-vertex.
-    // For now we assume v_color has alpha pre-multiplied or applied.
-    return Output(v_color);
+fn main(in: FragmentInput) -> Output {
+    // Repeat the pattern within its rectangle in the image atlas
+    let tex = mix(in.v_pattern.xy, in.v_pattern.zw, fract(in.v_pattern_pos));
+    let pattern_color = textureSample(t_image_atlas, s_image_atlas, tex);
+    let has_pattern = in.v_pattern.z > in.v_pattern.x;
+
+    // Output non-premultiplied alpha, the color is blended with (SrcAlpha, OneMinusSrcAlpha).
+    // The opacity is already multiplied into the alpha of the color by the vertex shader.
+    let color = select(
+        in.v_color,
+        vec4<f32>(pattern_color.rgb, pattern_color.a * in.v_opacity),
+        has_pattern
+    );
+    return Output(color);
 }
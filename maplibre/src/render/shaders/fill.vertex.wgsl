@@ -12,12 +12,20 @@ struct ShaderGlobals {
 struct VertexOutput {
     @location(0)  v_color: vec4<f32>,
     @builtin(position) position: vec4<f32>,
+    @location(1) @interpolate(flat) v_pattern: vec4<f32>,
+    @location(2) v_pattern_pos: vec2<f32>,
+    @location(3) @interpolate(flat) v_opacity: f32,
 };
 
+// Must match TILE_SIZE and EXTENT in coords.rs
+const TILE_SIZE: f32 = 512.0;
+const EXTENT: f32 = 4096.0;
+
 @vertex
 fn main(
     @location(0) position: vec2<f32>,
     @location(1) normal: vec2<f32>,
+    @location(3) opacity: f32,
     @location(4) translate1: vec4<f32>,
     @location(5) translate2: vec4<f32>,
     @location(6) translate3: vec4<f32>,
@@ -25,18 +33,53 @@ fn main(
     @location(8) color: vec4<f32>,
     @location(9) zoom_factor: f32,
     @location(10) z_index: f32,
+    @location(11) viewport_width: f32,
+    @location(12) viewport_height: f32,
+    @location(13) pattern_placement: vec4<f32>,
+    @location(14) pattern: vec4<f32>,
+    @location(15) translate: vec3<f32>,
     @builtin(instance_index) instance_idx: u32 // instance_index is used when we have multiple instances of the same "object"
 ) -> VertexOutput {
-    let z = -z_index;
-    let width = 3.0 * zoom_factor;
+    let translate_viewport = translate.z != 0.0;
+    let transform = mat4x4<f32>(translate1, translate2, translate3, translate4);
+
+    // Size of a pixel in tile units
+    let pixels_to_tile_units = EXTENT / (TILE_SIZE * zoom_factor);
 
-    // The following code moves all "invisible" vertices to (0, 0, 0)
-    //if (color.w == 0.0) {
-    //   return VertexOutput(color, vec4<f32>(0.0, 0.0, 0.0, 1.0));
-    //}
+    var tile_position = position;
+    if (!translate_viewport) {
+        tile_position += translate.xy * pixels_to_tile_units;
+    }
 
-    var final_position = mat4x4<f32>(translate1, translate2, translate3, translate4) * vec4<f32>(position + normal * width, z, 1.0);
+    var final_position = transform * vec4<f32>(tile_position, 0.0, 1.0);
+
+    // Only the vertices of outlines have normals. They are moved by half a pixel to each side,
+    // such that the outline is one pixel wide.
+    let viewport = vec2<f32>(viewport_width, viewport_height);
+    var pixel_offset = vec2<f32>(0.0);
+    let normal_length = length(normal);
+    if (normal_length > 0.0) {
+        let normal_clip = transform * vec4<f32>(normal, 0.0, 0.0);
+        pixel_offset = normalize(normal_clip.xy * viewport) * normal_length * 0.5;
+    }
+    if (translate_viewport) {
+        // The y axis of the clip space points up
+        pixel_offset += vec2<f32>(translate.x, -translate.y);
+    }
+    final_position.x += pixel_offset.x * (2.0 / viewport_width) * final_position.w;
+    final_position.y += pixel_offset.y * (2.0 / viewport_height) * final_position.w;
     final_position.z = z_index;
 
-    return VertexOutput(color, final_position);
+    // The pattern is anchored to the map and has its display size at the zoom level of the tile
+    let pattern_size = max(pattern_placement.xy, vec2<f32>(1e-6));
+    let tile_offset = pattern_placement.zw;
+    let pattern_pos = (position * TILE_SIZE / EXTENT + tile_offset) / pattern_size;
+
+    return VertexOutput(
+        vec4<f32>(color.rgb, color.a * opacity),
+        final_position,
+        pattern,
+        pattern_pos,
+        opacity
+    );
 }
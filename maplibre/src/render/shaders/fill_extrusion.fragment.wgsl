@@ -0,0 +1,12 @@
+struct FragmentInput {
+    @location(0) v_color: vec4<f32>,
+};
+
+struct Output {
+    @location(0) out_color: vec4<f32>,
+};
+
+@fragment
+fn main(in: FragmentInput) -> Output {
+    return Output(in.v_color);
+}
@@ -0,0 +1,94 @@
+struct VertexOutput {
+    @location(0) v_color: vec4<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+// Must match TILE_SIZE and EXTENT in coords.rs
+const TILE_SIZE: f32 = 512.0;
+const EXTENT: f32 = 4096.0;
+
+@vertex
+fn main(
+    @location(0) position: vec2<f32>,
+    @location(1) normal: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) top_base_height: vec3<f32>,
+    @location(4) translate1: vec4<f32>,
+    @location(5) translate2: vec4<f32>,
+    @location(6) translate3: vec4<f32>,
+    @location(7) translate4: vec4<f32>,
+    @location(8) translate: vec2<f32>,
+    @location(9) zoom_factor: f32,
+    @location(10) style: vec3<f32>,
+    @location(11) viewport_width: f32,
+    @location(12) viewport_height: f32,
+    @location(13) light: vec4<f32>,
+    @location(14) pixels_per_meter: f32,
+    @location(15) light_color: vec3<f32>,
+) -> VertexOutput {
+    let transform = mat4x4<f32>(translate1, translate2, translate3, translate4);
+    let translate_viewport = style.x != 0.0;
+    let opacity = style.y;
+    let vertical_gradient = style.z;
+    let light_direction = light.xyz;
+    let light_intensity = light.w;
+
+    let top = top_base_height.x;
+    let base = max(top_base_height.y, 0.0);
+    let height = max(top_base_height.z, 0.0);
+
+    // Size of a pixel in tile units
+    let pixels_to_tile_units = EXTENT / (TILE_SIZE * zoom_factor);
+
+    var tile_position = position;
+    if (!translate_viewport) {
+        tile_position += translate * pixels_to_tile_units;
+    }
+
+    // The z axis of the world is measured in pixels, like the x and y axes
+    let z = select(base, height, top > 0.0) * pixels_per_meter;
+    var final_position = transform * vec4<f32>(tile_position, z, 1.0);
+    if (translate_viewport) {
+        // The y axis of the clip space points up
+        final_position.x += translate.x * (2.0 / viewport_width) * final_position.w;
+        final_position.y -= translate.y * (2.0 / viewport_height) * final_position.w;
+    }
+
+    // Roofs face upwards and walls face outwards
+    let is_wall = any(normal != vec2<f32>(0.0));
+    let surface_normal = select(vec3<f32>(0.0, 0.0, 1.0), vec3<f32>(normal, 0.0), is_wall);
+
+    // Relative luminance of the color
+    let colorvalue = color.r * 0.2126 + color.g * 0.7152 + color.b * 0.0722;
+
+    // A slight ambient light, such that no extrusion is totally black
+    let ambient_color = color.rgb + vec3<f32>(0.03);
+
+    // Surfaces facing the light are brighter. The range of the shading is narrower for lower
+    // intensities of the light and brighter colors.
+    var directional = clamp(dot(surface_normal, light_direction), 0.0, 1.0);
+    directional = mix(
+        1.0 - light_intensity,
+        max(1.0 - colorvalue + light_intensity, 1.0),
+        directional
+    );
+
+    // Walls are shaded darker towards the ground
+    if (is_wall) {
+        let gradient = clamp(
+            (top + base) * pow(height / 150.0, 0.5),
+            mix(0.7, 0.98, 1.0 - light_intensity),
+            1.0
+        );
+        directional *= (1.0 - vertical_gradient) + vertical_gradient * gradient;
+    }
+
+    let shaded = clamp(
+        ambient_color * directional * light_color,
+        mix(vec3<f32>(0.0), vec3<f32>(0.3), vec3<f32>(1.0) - light_color),
+        vec3<f32>(1.0)
+    );
+
+    // The color is premultiplied with the opacity
+    return VertexOutput(vec4<f32>(shaded, 1.0) * opacity, final_position);
+}
@@ -0,0 +1,22 @@
+struct FragmentInput {
+    @location(0) v_extrude: vec2<f32>,
+    @location(1) v_weight: f32,
+    @location(2) v_intensity: f32,
+};
+
+struct Output {
+    @location(0) out_color: vec4<f32>,
+};
+
+// 1 / sqrt(2 * PI)
+const GAUSS_COEF: f32 = 0.3989422804014327;
+
+@fragment
+fn main(in: FragmentInput) -> Output {
+    // Gaussian kernel whose standard deviation is a third of the radius
+    let d = -0.5 * 3.0 * 3.0 * dot(in.v_extrude, in.v_extrude);
+    let density = in.v_weight * in.v_intensity * GAUSS_COEF * exp(d);
+
+    // The densities of all kernels are summed up in the red channel
+    return Output(vec4<f32>(density, 1.0, 1.0, 1.0));
+}
@@ -0,0 +1,47 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v_extrude: vec2<f32>,
+    @location(1) v_weight: f32,
+    @location(2) v_intensity: f32,
+};
+
+// Must match TILE_SIZE and EXTENT in coords.rs
+const TILE_SIZE: f32 = 512.0;
+const EXTENT: f32 = 4096.0;
+
+// Densities below this value are invisible in the color ramp, so the kernel is cut off there
+const ZERO: f32 = 1.0 / 255.0 / 16.0;
+// 1 / sqrt(2 * PI)
+const GAUSS_COEF: f32 = 0.3989422804014327;
+
+@vertex
+fn main(
+    @location(0) position: vec2<f32>,
+    @location(1) extrude: vec2<f32>,
+    @location(8) kernel: vec2<f32>,
+    @location(4) translate1: vec4<f32>,
+    @location(5) translate2: vec4<f32>,
+    @location(6) translate3: vec4<f32>,
+    @location(7) translate4: vec4<f32>,
+    @location(9) zoom_factor: f32,
+    @location(10) intensity: f32,
+) -> VertexOutput {
+    let weight = kernel.x;
+    let radius = kernel.y;
+
+    let transform = mat4x4<f32>(translate1, translate2, translate3, translate4);
+    let pixels_to_tile_units = EXTENT / (TILE_SIZE * zoom_factor);
+
+    // The radius covers three standard deviations of the kernel. The quad is scaled such that
+    // it encloses all fragments with a density above ZERO.
+    let scale = sqrt(-2.0 * log(ZERO / weight / intensity / GAUSS_COEF)) / 3.0;
+    let scaled_extrude = scale * extrude;
+    let corner = position + scaled_extrude * radius * pixels_to_tile_units;
+
+    return VertexOutput(
+        transform * vec4<f32>(corner, 0.0, 1.0),
+        scaled_extrude,
+        weight,
+        intensity,
+    );
+}
@@ -0,0 +1,28 @@
+struct FragmentInput {
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) opacity: f32,
+};
+
+@group(0) @binding(0)
+var t_density: texture_2d<f32>;
+@group(0) @binding(1)
+var s_density: sampler;
+
+@group(1) @binding(0)
+var t_color_ramp: texture_2d<f32>;
+@group(1) @binding(1)
+var s_color_ramp: sampler;
+
+struct Output {
+    @location(0) out_color: vec4<f32>,
+};
+
+@fragment
+fn main(in: FragmentInput) -> Output {
+    let density = textureSample(t_density, s_density, in.tex_coords).r;
+    // The ramp is clamped to its edges, so densities above 1 have the color of 1
+    let color = textureSample(t_color_ramp, s_color_ramp, vec2<f32>(density, 0.5));
+
+    // The colors of the ramp are premultiplied with their opacity
+    return Output(color * in.opacity);
+}
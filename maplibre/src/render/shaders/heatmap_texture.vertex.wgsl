@@ -0,0 +1,27 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) opacity: f32,
+};
+
+@vertex
+fn main(
+    @builtin(vertex_index) vertex_idx: u32,
+    @location(0) opacity: f32,
+) -> VertexOutput {
+    // Fullscreen quad which samples the density texture
+    var positions = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0)
+    );
+    let pos = positions[vertex_idx % 6u];
+
+    // The y axis of the texture points down
+    let tex_coords = vec2<f32>(pos.x * 0.5 + 0.5, 0.5 - pos.y * 0.5);
+
+    return VertexOutput(vec4<f32>(pos, 1.0e-5, 1.0), tex_coords, opacity);
+}
@@ -0,0 +1,133 @@
+struct VertexOutput {
+    @location(0) tex_coords: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+// The methods in the order of `HillshadeMethod`
+const STANDARD: u32 = 0u;
+const BASIC: u32 = 1u;
+const COMBINED: u32 = 2u;
+const IGOR: u32 = 3u;
+const MULTIDIRECTIONAL: u32 = 4u;
+
+const PI: f32 = 3.141592653589793;
+
+struct LayerUniform {
+    // The colors are premultiplied
+    accent: vec4<f32>,
+    shadows: array<vec4<f32>, 4>,
+    highlights: array<vec4<f32>, 4>,
+    // Directions and altitudes of the light sources in radians
+    azimuths: vec4<f32>,
+    altitudes: vec4<f32>,
+    method: u32,
+    light_count: u32,
+    exaggeration: f32,
+    padding: f32,
+};
+
+// The slopes of the tile, see hillshade_prepare.fragment.wgsl
+@group(0) @binding(0)
+var t_deriv: texture_2d<f32>;
+@group(0) @binding(1)
+var s_deriv: sampler;
+
+@group(1) @binding(0)
+var<uniform> hillshade: LayerUniform;
+
+fn aspect(deriv: vec2<f32>) -> f32 {
+    if deriv.x != 0.0 {
+        return atan2(deriv.y, -deriv.x);
+    }
+    return PI / 2.0 * select(-1.0, 1.0, deriv.y > 0.0);
+}
+
+// Cosine of the angle between the light and the normal of the surface
+fn illumination(deriv: vec2<f32>, azimuth: f32, altitude: f32) -> f32 {
+    let cos_alt = cos(altitude);
+    return (sin(altitude) - (deriv.y * cos(azimuth) * cos_alt - deriv.x * sin(azimuth) * cos_alt))
+        / sqrt(1.0 + dot(deriv, deriv));
+}
+
+// Shadows below an illumination of 0.5 and highlights above
+fn shade(deriv: vec2<f32>, azimuth: f32, altitude: f32, shadow: vec4<f32>, highlight: vec4<f32>) -> vec4<f32> {
+    let light = clamp(illumination(deriv, azimuth, altitude), 0.0, 1.0);
+    if light > 0.5 {
+        return highlight * (2.0 * light - 1.0);
+    }
+    return shadow * (1.0 - 2.0 * light);
+}
+
+fn standard_hillshade(deriv: vec2<f32>) -> vec4<f32> {
+    // Like maplibre-gl, the slope is multiplied by an arbitrary z-factor of 1.25
+    let slope = atan(1.25 * length(deriv));
+    let intensity = hillshade.exaggeration;
+    let azimuth = hillshade.azimuths.x + PI;
+
+    // Higher intensities scale the slope exponentially, so that the shading is more opaque
+    let base = 1.875 - intensity * 1.75;
+    let max_value = 0.5 * PI;
+    var scaled_slope = slope;
+    if intensity != 0.5 {
+        scaled_slope = ((pow(base, slope) - 1.0) / (pow(base, max_value) - 1.0)) * max_value;
+    }
+
+    // The accent eases in with the slope while the shade eases out. Intensities below 0.5 make
+    // both more transparent.
+    let opacity = clamp(intensity * 2.0, 0.0, 1.0);
+    let accent = hillshade.accent * (1.0 - cos(scaled_slope)) * opacity;
+    let facing = abs((((aspect(deriv) + azimuth) / PI + 0.5) % 2.0 + 2.0) % 2.0 - 1.0);
+    let shade_color = mix(hillshade.shadows[0], hillshade.highlights[0], facing) * sin(scaled_slope) * opacity;
+    return accent * (1.0 - shade_color.a) + shade_color;
+}
+
+fn basic_hillshade(deriv: vec2<f32>) -> vec4<f32> {
+    return shade(deriv, hillshade.azimuths.x + PI, hillshade.altitudes.x, hillshade.shadows[0], hillshade.highlights[0]);
+}
+
+fn combined_hillshade(deriv: vec2<f32>) -> vec4<f32> {
+    let angle = clamp(acos(illumination(deriv, hillshade.azimuths.x + PI, hillshade.altitudes.x)), 0.0, PI / 2.0);
+    let steepness = atan(length(deriv)) * 4.0 / PI / PI;
+    return hillshade.shadows[0] * angle * steepness + hillshade.highlights[0] * (PI / 2.0 - angle) * steepness;
+}
+
+fn igor_hillshade(deriv: vec2<f32>) -> vec4<f32> {
+    let azimuth = hillshade.azimuths.x + PI;
+    let slope_strength = atan(length(deriv)) * 2.0 / PI;
+    let aspect_strength = 1.0 - abs((((aspect(deriv) + azimuth) / PI + 0.5) % 2.0 + 2.0) % 2.0 - 1.0);
+    return hillshade.shadows[0] * slope_strength * aspect_strength
+        + hillshade.highlights[0] * slope_strength * (1.0 - aspect_strength);
+}
+
+fn multidirectional_hillshade(deriv: vec2<f32>) -> vec4<f32> {
+    var color = vec4<f32>(0.0);
+    let count = max(hillshade.light_count, 1u);
+    for (var i = 0u; i < count; i++) {
+        color += shade(deriv, hillshade.azimuths[i] + PI, hillshade.altitudes[i], hillshade.shadows[i], hillshade.highlights[i]);
+    }
+    return color / f32(count);
+}
+
+@fragment
+fn main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let deriv = textureSample(t_deriv, s_deriv, in.tex_coords).rg;
+    if hillshade.method == STANDARD {
+        return standard_hillshade(deriv);
+    }
+
+    let exaggerated = deriv * hillshade.exaggeration * 2.0;
+    switch hillshade.method {
+        case BASIC: {
+            return basic_hillshade(exaggerated);
+        }
+        case COMBINED: {
+            return combined_hillshade(exaggerated);
+        }
+        case IGOR: {
+            return igor_hillshade(exaggerated);
+        }
+        case MULTIDIRECTIONAL, default: {
+            return multidirectional_hillshade(exaggerated);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+struct VertexOutput {
+    @location(0) tex_coords: vec2<f32>,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+var<private> EXTENT: f32 = 4096.0;
+
+@vertex
+fn main(
+    @location(4) translate1: vec4<f32>,
+    @location(5) translate2: vec4<f32>,
+    @location(6) translate3: vec4<f32>,
+    @location(7) translate4: vec4<f32>,
+
+    @builtin(vertex_index) vertex_idx: u32,
+) -> VertexOutput {
+    var VERTICES: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let tex_coords = VERTICES[vertex_idx];
+
+    let position = mat4x4<f32>(translate1, translate2, translate3, translate4) * vec4<f32>(tex_coords * EXTENT, 0.0, 1.0);
+    return VertexOutput(tex_coords, position);
+}
@@ -0,0 +1,47 @@
+struct TileUniform {
+    // Latitudes of the northern and southern edge of the tile in degrees
+    latitudes: vec2<f32>,
+    zoom: f32,
+    padding: f32,
+};
+
+// Elevations in meters with a border of one sample around the tile
+@group(0) @binding(0)
+var t_dem: texture_2d<f32>;
+@group(0) @binding(1)
+var<uniform> tile: TileUniform;
+
+fn elevation(position: vec2<i32>) -> f32 {
+    return textureLoad(t_dem, position, 0).r;
+}
+
+@fragment
+fn main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let dim = f32(textureDimensions(t_dem).x - 2u);
+    let center = vec2<i32>(position.xy) + vec2<i32>(1, 1);
+
+    let a = elevation(center + vec2<i32>(-1, -1));
+    let b = elevation(center + vec2<i32>(0, -1));
+    let c = elevation(center + vec2<i32>(1, -1));
+    let d = elevation(center + vec2<i32>(-1, 0));
+    let f = elevation(center + vec2<i32>(1, 0));
+    let g = elevation(center + vec2<i32>(-1, 1));
+    let h = elevation(center + vec2<i32>(0, 1));
+    let i = elevation(center + vec2<i32>(1, 1));
+
+    // Like maplibre-gl, the slopes are exaggerated at low zoom levels
+    let zoom = tile.zoom;
+    let exaggeration_factor = select(select(0.3, 0.35, zoom < 4.5), 0.4, zoom < 2.0);
+    let exaggeration = select(0.0, (zoom - 15.0) * exaggeration_factor, zoom < 15.0);
+
+    // The Sobel operator sums up 8 times the difference between neighbouring samples, which are
+    // 2^25.2562 / (dim * 2^zoom) meters apart at the equator
+    let deriv = vec2<f32>(
+        (c + f + f + i) - (a + d + d + g),
+        (g + h + h + i) - (a + b + b + c)
+    ) * dim / pow(2.0, exaggeration + (28.2562 - zoom));
+
+    // Distances shrink towards the poles in the mercator projection
+    let latitude = mix(tile.latitudes.x, tile.latitudes.y, position.y / dim);
+    return vec4<f32>(deriv / cos(radians(latitude)), 0.0, 1.0);
+}
@@ -0,0 +1,10 @@
+@vertex
+fn main(@builtin(vertex_index) vertex_idx: u32) -> @builtin(position) vec4<f32> {
+    // Triangle which covers the whole derivative texture
+    var positions = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 3.0, -1.0),
+        vec2<f32>(-1.0,  3.0)
+    );
+    return vec4<f32>(positions[vertex_idx % 3u], 0.0, 1.0);
+}
@@ -0,0 +1,38 @@
+struct FragmentInput {
+    @location(0) v_tex: vec2<f32>,
+    @location(1) v_color: vec4<f32>,
+    @location(2) @interpolate(flat) v_style: vec3<f32>,
+};
+
+@group(0) @binding(0)
+var t_image_atlas: texture_2d<f32>;
+@group(0) @binding(1)
+var s_image_atlas: sampler;
+
+struct Output {
+    @location(0) out_color: vec4<f32>,
+};
+
+// Distance of the edge of the shape within a signed distance field
+const SDF_EDGE: f32 = 0.75;
+const EDGE_GAMMA: f32 = 0.105;
+
+@fragment
+fn main(in: FragmentInput) -> Output {
+    let sdf = in.v_style.x;
+    let size = in.v_style.y;
+    let opacity = in.v_style.z;
+
+    let texel = textureSample(t_image_atlas, s_image_atlas, in.v_tex);
+
+    // The alpha channel of SDF icons stores the distance to the edge of the shape, which is
+    // colored by icon-color
+    let gamma = EDGE_GAMMA / max(size, 1e-6);
+    let alpha = smoothstep(SDF_EDGE - gamma, SDF_EDGE + gamma, texel.a);
+    let sdf_color = in.v_color * alpha;
+
+    // The texels of other icons are not premultiplied
+    let image_color = vec4<f32>(texel.rgb * texel.a, texel.a);
+
+    return Output(select(image_color, sdf_color, sdf > 0.5) * opacity);
+}
@@ -0,0 +1,39 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v_tex: vec2<f32>,
+    @location(1) v_color: vec4<f32>,
+    @location(2) @interpolate(flat) v_style: vec3<f32>,
+};
+
+@vertex
+fn main(
+    @location(0) anchor: vec2<f32>,
+    @location(1) offset: vec2<f32>,
+    @location(2) tex_coords: vec2<f32>,
+    @location(3) color: vec4<f32>,
+    @location(8) icon: vec2<f32>,
+    @location(4) translate1: vec4<f32>,
+    @location(5) translate2: vec4<f32>,
+    @location(6) translate3: vec4<f32>,
+    @location(7) translate4: vec4<f32>,
+    @location(11) viewport_width: f32,
+    @location(12) viewport_height: f32,
+    @location(10) z_index: f32,
+    @location(13) opacity: f32,
+) -> VertexOutput {
+    let transform = mat4x4<f32>(translate1, translate2, translate3, translate4);
+
+    // Size of a pixel in clip space. The y axis of the clip space points up.
+    let pixels_to_clip = vec2<f32>(2.0 / viewport_width, -2.0 / viewport_height);
+
+    // Icons face the viewport, such that the offset of the corner is added in clip space
+    var final_position = transform * vec4<f32>(anchor, 0.0, 1.0);
+    final_position.x += offset.x * pixels_to_clip.x * final_position.w;
+    final_position.y += offset.y * pixels_to_clip.y * final_position.w;
+    final_position.z = z_index;
+
+    let sdf = icon.x;
+    let size = icon.y;
+
+    return VertexOutput(final_position, tex_coords, color, vec3<f32>(sdf, size, opacity));
+}
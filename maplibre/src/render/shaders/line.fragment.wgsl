@@ -3,8 +3,26 @@ struct FragmentInput {
     @location(1) v_normal: vec2<f32>,
     @location(2) v_width2: vec2<f32>,
     @location(3) v_gamma_scale: f32,
+    @location(4) v_tex: vec2<f32>,
+    @location(5) @interpolate(flat) v_dash: vec2<f32>,
+    @location(6) @interpolate(flat) v_style: vec2<f32>,
+    @location(7) v_gradient: vec2<f32>,
+    @location(8) @interpolate(flat) v_pattern: vec4<f32>,
+    @location(9) v_pattern_pos: vec2<f32>,
 };
 
+// Must match LINE_ATLAS_WIDTH in line_atlas.rs
+const LINE_ATLAS_WIDTH: f32 = 256.0;
+
+@group(0) @binding(0)
+var t_line_atlas: texture_2d<f32>;
+@group(0) @binding(1)
+var s_line_atlas: sampler;
+@group(1) @binding(0)
+var t_image_atlas: texture_2d<f32>;
+@group(1) @binding(1)
+var s_image_atlas: sampler;
+
 struct Output {
     @location(0) out_color: vec4<f32>,
 };
@@ -14,15 +32,37 @@ fn main(in: FragmentInput) -> Output {
     // Calculate the distance of the pixel from the line in pixels
     let dist = length(in.v_normal) * in.v_width2.x;
 
-    let pixel_ratio = 1.0; 
-    let blur = 0.0;
-    
+    let pixel_ratio = 1.0;
+    let blur = in.v_style.x;
+    let opacity = in.v_style.y;
+
     // Calculate the antialiasing fade factor
     let blur2 = (blur + (1.0 / pixel_ratio)) * in.v_gamma_scale;
     let denom = max(blur2, 1e-6);
-    let alpha = clamp(min(dist - (in.v_width2.y - blur2), in.v_width2.x - dist) / denom, 0.0, 1.0);
+    var alpha = clamp(min(dist - (in.v_width2.y - blur2), in.v_width2.x - dist) / denom, 0.0, 1.0);
+
+    // Sample the signed distance to the closest edge of a dash
+    let sdf_dist = textureSample(t_line_atlas, s_line_atlas, in.v_tex).r;
+    let dash_length = in.v_dash.x;
+    let sdf_gamma = in.v_dash.y;
+    if (dash_length > 0.0) {
+        alpha *= smoothstep(0.5 - sdf_gamma, 0.5 + sdf_gamma, sdf_dist);
+    }
+
+    // Sample the color of the gradient at the center of the texel of the progress along the line
+    let progress = in.v_gradient.x;
+    let gradient_y = in.v_gradient.y;
+    let gradient_x = (progress * (LINE_ATLAS_WIDTH - 1.0) + 0.5) / LINE_ATLAS_WIDTH;
+    let gradient_color = textureSample(t_line_atlas, s_line_atlas, vec2<f32>(gradient_x, gradient_y));
+    var color = select(in.v_color, gradient_color, gradient_y > 0.0);
+
+    // Repeat the image pattern along the line within its rectangle in the image atlas
+    let pattern_pos = vec2<f32>(fract(in.v_pattern_pos.x), clamp(in.v_pattern_pos.y, 0.0, 1.0));
+    let pattern_tex = mix(in.v_pattern.xy, in.v_pattern.zw, pattern_pos);
+    let pattern_color = textureSample(t_image_atlas, s_image_atlas, pattern_tex);
+    color = select(color, pattern_color, in.v_pattern.z > in.v_pattern.x);
 
     // Output non-premultiplied alpha: the blend state (SrcAlpha, OneMinusSrcAlpha)
     // handles the premultiplication. Using v_color * alpha here would double-apply alpha.
-    return Output(vec4<f32>(in.v_color.rgb, in.v_color.a * alpha));
+    return Output(vec4<f32>(color.rgb, color.a * alpha * opacity));
 }
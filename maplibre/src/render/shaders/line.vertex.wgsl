@@ -15,12 +15,37 @@ struct VertexOutput {
     @location(1) v_normal: vec2<f32>,
     @location(2) v_width2: vec2<f32>,
     @location(3) v_gamma_scale: f32,
+    @location(4) v_tex: vec2<f32>,
+    @location(5) @interpolate(flat) v_dash: vec2<f32>,
+    @location(6) @interpolate(flat) v_style: vec2<f32>,
+    @location(7) v_gradient: vec2<f32>,
+    @location(8) @interpolate(flat) v_pattern: vec4<f32>,
+    @location(9) v_pattern_pos: vec2<f32>,
 };
 
+// Must match TILE_SIZE and EXTENT in coords.rs
+const TILE_SIZE: f32 = 512.0;
+const EXTENT: f32 = 4096.0;
+// Must match LINE_ATLAS_WIDTH in line_atlas.rs
+const LINE_ATLAS_WIDTH: f32 = 256.0;
+
+// Converts a direction in tile units to a direction in pixels with the same length.
+fn to_pixel_direction(transform: mat4x4<f32>, direction: vec2<f32>, viewport: vec2<f32>) -> vec2<f32> {
+    let direction_length = length(direction);
+    let direction_clip = transform * vec4<f32>(direction, 0.0, 0.0);
+    return select(
+        vec2<f32>(0.0),
+        normalize(direction_clip.xy * viewport) * direction_length,
+        direction_length > 0.0
+    );
+}
+
 @vertex
 fn main(
     @location(0) position: vec2<f32>,
     @location(1) normal: vec2<f32>,
+    @location(2) line_data: vec3<f32>,
+    @location(3) line_style: vec4<f32>,
     @location(4) translate1: vec4<f32>,
     @location(5) translate2: vec4<f32>,
     @location(6) translate3: vec4<f32>,
@@ -30,11 +55,20 @@ fn main(
     @location(10) z_index: f32,
     @location(11) viewport_width: f32,
     @location(12) viewport_height: f32,
-    @location(13) line_width: f32,
+    @location(13) line_atlas: vec4<f32>,
+    @location(14) pattern: vec4<f32>,
+    @location(15) translate: vec3<f32>,
 ) -> VertexOutput {
-    let line_width_px = line_width;
-    let blur = 0.0;
-    let gapwidth = 0.0;
+    let line_distance = line_data.x;
+    let line_side = line_data.y;
+    let line_progress = line_data.z;
+
+    let line_width_px = line_atlas.x;
+    let opacity = line_style.x;
+    let blur = line_style.y;
+    let gapwidth = line_style.z * 0.5;
+    let offset = line_style.w;
+    let translate_viewport = translate.z != 0.0;
 
     let halfwidth = line_width_px * 0.5;
     let pixel_ratio = 1.0;
@@ -46,12 +80,27 @@ fn main(
 
     let transform = mat4x4<f32>(translate1, translate2, translate3, translate4);
 
+    // Size of a pixel in tile units
+    let pixels_to_tile_units = EXTENT / (TILE_SIZE * zoom_factor);
+
+    var tile_position = position;
+    if (!translate_viewport) {
+        tile_position += translate.xy * pixels_to_tile_units;
+    }
+
     // Transform center position to clip space
-    var center = transform * vec4<f32>(position, 0.0, 1.0);
+    var center = transform * vec4<f32>(tile_position, 0.0, 1.0);
 
-    // Transform the normal direction to clip space (w=0 for direction vectors)
-    let normal_clip = transform * vec4<f32>(normal, 0.0, 0.0);
-    let dir = normalize(normal_clip.xy);
+    // Transform the normal direction to pixels, such that the direction is not distorted by
+    // non-square viewports. The length of the normal is kept, because it is larger than 1 for
+    // miter joins and square caps.
+    let viewport = vec2<f32>(viewport_width, viewport_height);
+    let normal_length = length(normal);
+    let dir = to_pixel_direction(transform, normal, viewport);
+    // The fragment shader measures the distance to the line by the unit normal
+    let unit_normal = select(vec2<f32>(0.0), normal / normal_length, normal_length > 0.0);
+    // The offset moves both sides of the line to its right side
+    let offset_dir = to_pixel_direction(transform, normal * line_side, viewport);
 
     // Apply pixel-width offset in clip space.
     // NDC spans 2 units across the viewport, so 1 pixel = 2/viewport_px in NDC.
@@ -59,14 +108,44 @@ fn main(
     // Use per-axis conversion to handle non-square viewports correctly.
     let px_to_clip_x = (2.0 / viewport_width) * center.w;
     let px_to_clip_y = (2.0 / viewport_height) * center.w;
-    let clip_offset = vec2<f32>(dir.x * outset * px_to_clip_x, dir.y * outset * px_to_clip_y);
+    var pixel_offset = dir * outset + offset_dir * offset;
+    if (translate_viewport) {
+        // The y axis of the clip space points up
+        pixel_offset += vec2<f32>(translate.x, -translate.y);
+    }
+    let clip_offset = vec2<f32>(pixel_offset.x * px_to_clip_x, pixel_offset.y * px_to_clip_y);
     center = vec4<f32>(center.x + clip_offset.x, center.y + clip_offset.y, z_index, center.w);
 
+    // The dash pattern repeats every `dash_length` line widths. The edges of the dashes are
+    // smoothed over about a pixel.
+    let dash_y = line_atlas.y;
+    let dash_length = line_atlas.z;
+    let gradient_y = line_atlas.w;
+    let tile_units_to_pixels = TILE_SIZE / (EXTENT * zoom_factor);
+    let pattern_width = max(dash_length * line_width_px, 1e-6);
+    let tex = vec2<f32>(line_distance * tile_units_to_pixels / pattern_width, dash_y);
+    let sdf_gamma = LINE_ATLAS_WIDTH / (pattern_width * 256.0 * pixel_ratio) / 2.0;
+
+    // The image pattern is scaled to the width of the line and repeats along the line
+    let pattern_texels = pattern.zw - pattern.xy;
+    let pattern_aspect = pattern_texels.x / max(pattern_texels.y, 1e-6);
+    let image_width = max(pattern_aspect * line_width_px, 1e-6);
+    let pattern_pos = vec2<f32>(
+        line_distance * tile_units_to_pixels / image_width,
+        (1.0 - line_side) * 0.5
+    );
+
     return VertexOutput(
         center,
         color,
-        normal,
+        unit_normal,
         vec2<f32>(outset, inset),
-        1.0
+        1.0,
+        tex,
+        vec2<f32>(dash_length, sdf_gamma),
+        vec2<f32>(blur, opacity),
+        vec2<f32>(line_progress, gradient_y),
+        pattern,
+        pattern_pos
     );
 }
@@ -154,6 +154,20 @@ impl Shader for FillShader {
                             format: wgpu::VertexFormat::Float32,
                             shader_location: 9,
                         },
+                        // viewport_width
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 11,
+                        },
+                        // viewport_height
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + 2 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 12,
+                        },
                     ],
                 },
                 // layer metadata
@@ -167,6 +181,12 @@ impl Shader for FillShader {
                             format: wgpu::VertexFormat::Float32,
                             shader_location: 10,
                         },
+                        // translate and translate_viewport
+                        wgpu::VertexAttribute {
+                            offset: 9 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32x3,
+                            shader_location: 15,
+                        },
                     ],
                 },
                 // features
@@ -180,6 +200,26 @@ impl Shader for FillShader {
                             format: wgpu::VertexFormat::Float32x4,
                             shader_location: 8,
                         },
+                        // opacity
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 3,
+                        },
+                        // pattern
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x4.size()
+                                + wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 14,
+                        },
+                        // pattern_placement
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x4.size()
+                                + wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 13,
+                        },
                     ],
                 },
             ],
@@ -192,7 +232,18 @@ impl Shader for FillShader {
             entry_point: "main",
             targets: vec![Some(wgpu::ColorTargetState {
                 format: self.format,
-                blend: None,
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }
@@ -226,6 +277,12 @@ impl Shader for LineShader {
                             format: wgpu::VertexFormat::Float32x2,
                             shader_location: 1,
                         },
+                        // line_distance, line_side and line_progress
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x3,
+                            shader_location: 2,
+                        },
                     ],
                 },
                 // tile metadata
@@ -287,12 +344,24 @@ impl Shader for LineShader {
                             format: wgpu::VertexFormat::Float32,
                             shader_location: 10,
                         },
-                        // line_width
+                        // line_width, dash_y, dash_length and gradient_y
                         wgpu::VertexAttribute {
                             offset: wgpu::VertexFormat::Float32.size(),
-                            format: wgpu::VertexFormat::Float32,
+                            format: wgpu::VertexFormat::Float32x4,
                             shader_location: 13,
                         },
+                        // opacity, line_blur, line_gap_width and line_offset
+                        wgpu::VertexAttribute {
+                            offset: 5 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 3,
+                        },
+                        // translate and translate_viewport
+                        wgpu::VertexAttribute {
+                            offset: 9 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32x3,
+                            shader_location: 15,
+                        },
                     ],
                 },
                 // features
@@ -306,6 +375,13 @@ impl Shader for LineShader {
                             format: wgpu::VertexFormat::Float32x4,
                             shader_location: 8,
                         },
+                        // pattern
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x4.size()
+                                + wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 14,
+                        },
                     ],
                 },
             ],
@@ -336,6 +412,679 @@ impl Shader for LineShader {
     }
 }
 
+pub struct CircleShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for CircleShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("circle.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![
+                // vertex data
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderCircleVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: vec![
+                        // position
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 0,
+                        },
+                        // extrude
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 1,
+                        },
+                        // color
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 2,
+                        },
+                        // stroke_color
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x2.size()
+                                + wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 3,
+                        },
+                        // radius, blur and stroke_width
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x2.size()
+                                + 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x3,
+                            shader_location: 8,
+                        },
+                    ],
+                },
+                // tile metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderTileMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 4,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 1 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 5,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 6,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 7,
+                        },
+                        // zoom_factor
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 9,
+                        },
+                        // viewport_width
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 11,
+                        },
+                        // viewport_height
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + 2 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 12,
+                        },
+                        // camera_to_center_distance
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + 3 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 14,
+                        },
+                    ],
+                },
+                // layer metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderCircleLayerMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // z_index
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 10,
+                        },
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 13,
+                        },
+                        // translate_viewport, scale_with_map and pitch_with_map
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32.size()
+                                + wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Uint32x3,
+                            shader_location: 15,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("circle.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                // The fragment shader outputs premultiplied colors
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }
+    }
+}
+
+pub struct FillExtrusionShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for FillExtrusionShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("fill_extrusion.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![
+                // vertex data
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderFillExtrusionVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: vec![
+                        // position
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 0,
+                        },
+                        // normal
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 1,
+                        },
+                        // color
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 2,
+                        },
+                        // top, base and height
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x2.size()
+                                + wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x3,
+                            shader_location: 3,
+                        },
+                    ],
+                },
+                // tile metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderTileMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 4,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 1 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 5,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 6,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 7,
+                        },
+                        // zoom_factor
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 9,
+                        },
+                        // viewport_width
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 11,
+                        },
+                        // viewport_height
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + 2 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 12,
+                        },
+                        // pixels_per_meter
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + 4 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 14,
+                        },
+                    ],
+                },
+                // layer metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderFillExtrusionLayerMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 8,
+                        },
+                        // translate_viewport, opacity and vertical_gradient
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x3,
+                            shader_location: 10,
+                        },
+                        // light_direction and light_intensity
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x2.size()
+                                + wgpu::VertexFormat::Float32x3.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 13,
+                        },
+                        // light_color
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x2.size()
+                                + wgpu::VertexFormat::Float32x3.size()
+                                + wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x3,
+                            shader_location: 15,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("fill_extrusion.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                // The vertex shader outputs premultiplied colors
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }
+    }
+}
+
+/// Accumulates the kernels of the points of a heatmap layer in the red channel of an offscreen
+/// texture.
+pub struct HeatmapShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for HeatmapShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("heatmap.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![
+                // vertex data
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderHeatmapVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: vec![
+                        // position
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 0,
+                        },
+                        // extrude
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 1,
+                        },
+                        // weight and radius
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 8,
+                        },
+                    ],
+                },
+                // tile metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderTileMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 4,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 1 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 5,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 6,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 7,
+                        },
+                        // zoom_factor
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 9,
+                        },
+                    ],
+                },
+                // layer metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderHeatmapLayerMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // intensity
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 10,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("heatmap.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                // The densities of overlapping kernels are summed up
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }
+    }
+}
+
+/// Colors the density texture of a heatmap layer with its color ramp.
+pub struct HeatmapTextureShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for HeatmapTextureShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("heatmap_texture.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![VertexBufferLayout {
+                array_stride: std::mem::size_of::<ShaderHeatmapLayerMetadata>() as u64,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: vec![
+                    // opacity
+                    wgpu::VertexAttribute {
+                        offset: wgpu::VertexFormat::Float32.size(),
+                        format: wgpu::VertexFormat::Float32,
+                        shader_location: 0,
+                    },
+                ],
+            }],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("heatmap_texture.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                // The color ramp is premultiplied
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }
+    }
+}
+
+/// Calculates the slopes of a tile from its elevations.
+pub struct HillshadePrepareShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for HillshadePrepareShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("hillshade_prepare.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("hillshade_prepare.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }
+    }
+}
+
+/// Shades the slopes of a tile, which are calculated by the [`HillshadePrepareShader`].
+pub struct HillshadeShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for HillshadeShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("hillshade.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![
+                // tile metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderTileMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 4,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 1 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 5,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 6,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 7,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("hillshade.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                // The colors of the layer are premultiplied
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }
+    }
+}
+
+/// Colors a tile by its elevations, which are looked up in the color ramp of the layer.
+pub struct ColorReliefShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for ColorReliefShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("color_relief.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![
+                // tile metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderTileMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 4,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 1 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 5,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 6,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 7,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("color_relief.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                // The color ramp is premultiplied
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }
+    }
+}
+
+pub struct TerrainShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for TerrainShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("terrain.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![
+                // vertex data
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderTerrainVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: vec![
+                        // position
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 0,
+                        },
+                        // elevation
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 1,
+                        },
+                    ],
+                },
+                // tile metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderTileMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 4,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 1 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 5,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 6,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 7,
+                        },
+                        // pixels_per_meter
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + 4 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 14,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("terrain.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                // The layers were blended into the drape textures, so their colors are
+                // premultiplied
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
 pub struct ShaderCamera {
@@ -380,11 +1129,39 @@ impl ShaderGlobals {
 pub struct ShaderVertex {
     pub position: Vec2f32,
     pub normal: Vec2f32,
+    /// Distance from the start of the line in tile units. Zero for vertices of fills.
+    pub line_distance: f32,
+    /// 1 for vertices on the right side of the line and -1 for vertices on the left side, such
+    /// that `normal * line_side` points to the right. Zero for vertices of fills.
+    pub line_side: f32,
+    /// Progress along the line between 0 and 1. Only calculated for sources with line metrics.
+    pub line_progress: f32,
 }
 
 impl ShaderVertex {
     pub fn new(position: Vec2f32, normal: Vec2f32) -> Self {
-        Self { position, normal }
+        Self {
+            position,
+            normal,
+            line_distance: 0.0,
+            line_side: 0.0,
+            line_progress: 0.0,
+        }
+    }
+
+    pub fn with_line_distance(mut self, line_distance: f32) -> Self {
+        self.line_distance = line_distance;
+        self
+    }
+
+    pub fn with_line_side(mut self, line_side: f32) -> Self {
+        self.line_side = line_side;
+        self
+    }
+
+    pub fn with_line_progress(mut self, line_progress: f32) -> Self {
+        self.line_progress = line_progress;
+        self
     }
 }
 
@@ -398,19 +1175,186 @@ impl Default for ShaderVertex {
 #[derive(Debug, Copy, Clone, Pod, Zeroable)]
 pub struct FillShaderFeatureMetadata {
     pub color: Vec4f32,
+    /// Opacity of fills, which is multiplied with the alpha of the color. Ignored by lines.
+    pub opacity: f32,
+    /// Texture coordinates of the top left and the bottom right corner of the pattern in the
+    /// image atlas. Zero if the feature has no pattern.
+    pub pattern: Vec4f32,
+    /// Size of the pattern in pixels, followed by the offset of the origin of the tile within
+    /// the pattern. Ignored by lines, whose patterns are scaled to the line width.
+    pub pattern_placement: Vec4f32,
 }
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Pod, Zeroable, Default)]
 pub struct SDFShaderFeatureMetadata {
+    /// Opacity of the symbol, which hides symbols colliding with other symbols.
     pub opacity: f32,
+    /// Color of the glyphs, premultiplied with its alpha. Ignored by icons.
+    pub color: Vec4f32,
+    /// Color of the halo around the glyphs, premultiplied with its alpha. Ignored by icons.
+    pub halo_color: Vec4f32,
+    /// Width of the halo in pixels. Ignored by icons.
+    pub halo_width: f32,
+    /// Blur of the halo in pixels. Ignored by icons.
+    pub halo_blur: f32,
+}
+
+impl SDFShaderFeatureMetadata {
+    /// Metadata of a visible symbol with black glyphs and no halo.
+    pub fn visible() -> Self {
+        Self {
+            opacity: 1.0,
+            color: [0.0, 0.0, 0.0, 1.0],
+            ..Default::default()
+        }
+    }
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Pod, Zeroable)]
+#[derive(Copy, Clone, Pod, Zeroable, Default)]
 pub struct ShaderLayerMetadata {
     pub z_index: f32,
     pub line_width: f32,
+    /// Vertical texture coordinate of the dash pattern in the line atlas.
+    pub dash_y: f32,
+    /// Length of the dash pattern in units of the line width. Zero for solid lines.
+    pub dash_length: f32,
+    /// Vertical texture coordinate of the gradient in the line atlas. Zero for lines without a
+    /// gradient.
+    pub gradient_y: f32,
+    pub opacity: f32,
+    pub line_blur: f32,
+    pub line_gap_width: f32,
+    pub line_offset: f32,
+    pub translate: Vec2f32,
+    /// 1 if `translate` is relative to the viewport instead of the map, otherwise 0.
+    pub translate_viewport: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderCircleVertex {
+    pub position: Vec2f32,
+    /// The corner of the quad of the circle, each component is either -1 or 1.
+    pub extrude: Vec2f32,
+    /// Fill color, premultiplied with its opacity.
+    pub color: Vec4f32,
+    /// Stroke color, premultiplied with its opacity.
+    pub stroke_color: Vec4f32,
+    pub radius: f32,
+    pub blur: f32,
+    pub stroke_width: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ShaderCircleLayerMetadata {
+    pub z_index: f32,
+    pub translate: Vec2f32,
+    /// Non-zero if `translate` is relative to the viewport instead of the map.
+    pub translate_viewport: u32,
+    /// Non-zero if circles are scaled with the map when the map is pitched.
+    pub scale_with_map: u32,
+    /// Non-zero if circles lie on the plane of the map.
+    pub pitch_with_map: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderFillExtrusionVertex {
+    pub position: Vec2f32,
+    /// The outward normal of a wall, zero for vertices of the roof.
+    pub normal: Vec2f32,
+    pub color: Vec4f32,
+    /// 1 if the vertex is at the top of the extrusion, 0 if it is at the bottom.
+    pub top: f32,
+    /// Height of the bottom of the extrusion in meters.
+    pub base: f32,
+    /// Height of the top of the extrusion in meters.
+    pub height: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ShaderFillExtrusionLayerMetadata {
+    pub translate: Vec2f32,
+    /// 1 if `translate` is relative to the viewport instead of the map, otherwise 0.
+    pub translate_viewport: f32,
+    pub opacity: f32,
+    /// 1 if the walls are shaded darker towards the ground, otherwise 0.
+    pub vertical_gradient: f32,
+    /// The direction towards the light in the coordinates of the map.
+    pub light_direction: [f32; 3],
+    pub light_intensity: f32,
+    pub light_color: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderHeatmapVertex {
+    pub position: Vec2f32,
+    /// The corner of the quad of the kernel, each component is either -1 or 1.
+    pub extrude: Vec2f32,
+    pub weight: f32,
+    /// Radius of the kernel in pixels.
+    pub radius: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ShaderHeatmapLayerMetadata {
+    pub intensity: f32,
+    pub opacity: f32,
+}
+
+/// The uniform of the [`HillshadePrepareShader`] for a tile.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ShaderHillshadeTileMetadata {
+    /// Latitudes of the northern and southern edge of the tile in degrees.
+    pub latitudes: Vec2f32,
+    pub zoom: f32,
+    pub _padding: f32,
+}
+
+/// The uniform of the [`HillshadeShader`] for a layer.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ShaderHillshadeLayerMetadata {
+    pub accent: Vec4f32,
+    pub shadows: [Vec4f32; 4],
+    pub highlights: [Vec4f32; 4],
+    /// Directions of the light sources in radians.
+    pub azimuths: Vec4f32,
+    /// Altitudes of the light sources in radians.
+    pub altitudes: Vec4f32,
+    pub method: u32,
+    pub light_count: u32,
+    pub exaggeration: f32,
+    pub _padding: f32,
+}
+
+/// The uniform of the [`ColorReliefShader`] for a layer.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ShaderColorReliefLayerMetadata {
+    /// Elevations in meters of the first and the last texel of the color ramp. The first texel
+    /// lies below the first stop of the `color-relief-color`.
+    pub min_elevation: f32,
+    pub max_elevation: f32,
+    pub opacity: f32,
+    pub _padding: f32,
+}
+
+/// A vertex of the grid of a tile, which is displaced by the terrain.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderTerrainVertex {
+    /// The position within the tile in tile units.
+    pub position: Vec2f32,
+    /// The exaggerated elevation in meters.
+    pub elevation: f32,
 }
 
 #[repr(C)]
@@ -420,6 +1364,10 @@ pub struct ShaderTileMetadata {
     pub zoom_factor: f32,
     pub viewport_width: f32,
     pub viewport_height: f32,
+    /// Distance between the camera and the center of the map in pixels.
+    pub camera_to_center_distance: f32,
+    /// Size of a meter in pixels at the center of the map.
+    pub pixels_per_meter: f32,
 }
 
 impl ShaderTileMetadata {
@@ -429,6 +1377,8 @@ impl ShaderTileMetadata {
             zoom_factor,
             viewport_width: 512.0,
             viewport_height: 512.0,
+            camera_to_center_distance: 0.0,
+            pixels_per_meter: 0.0,
         }
     }
 }
@@ -653,6 +1603,20 @@ impl Shader for SymbolShader {
                             format: wgpu::VertexFormat::Float32,
                             shader_location: 9,
                         },
+                        // viewport_width
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 11,
+                        },
+                        // viewport_height
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + 2 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 12,
+                        },
                     ],
                 },
                 // layer metadata
@@ -675,18 +1639,38 @@ impl Shader for SymbolShader {
                     ],
                 },
                 // features
-                //VertexBufferLayout {
-                //    array_stride: std::mem::size_of::<SDFShaderFeatureMetadata>() as u64,
-                //    step_mode: wgpu::VertexStepMode::Vertex,
-                //    attributes: vec![
-                //        // opacity
-                //        wgpu::VertexAttribute {
-                //            offset: 0,
-                //            format: wgpu::VertexFormat::Float32,
-                //            shader_location: 12,
-                //        },
-                //    ],
-                //},
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<SDFShaderFeatureMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: vec![
+                        // opacity
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 3,
+                        },
+                        // color
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 14,
+                        },
+                        // halo_color
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32.size()
+                                + wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 15,
+                        },
+                        // halo_width and halo_blur
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32.size()
+                                + 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 8,
+                        },
+                    ],
+                },
             ],
         }
     }
@@ -698,18 +1682,158 @@ impl Shader for SymbolShader {
             targets: vec![Some(wgpu::ColorTargetState {
                 format: self.format,
                 write_mask: wgpu::ColorWrites::ALL,
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::SrcAlpha,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                    alpha: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::One,
-                        dst_factor: wgpu::BlendFactor::Zero,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                }),
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            })],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderIconVertex {
+    /// The anchor of the symbol in tile coordinates.
+    pub anchor: Vec2f32,
+    /// The offset of the corner of the icon from the anchor in pixels.
+    pub offset: Vec2f32,
+    /// Texture coordinates of the corner in the image atlas.
+    pub tex_coords: Vec2f32,
+    /// Color of icons whose image is a signed distance field, premultiplied with its opacity.
+    pub color: Vec4f32,
+    /// 1 if the image is a signed distance field, 0 otherwise.
+    pub sdf: f32,
+    /// The size of the icon relative to its image, which scales the antialiasing of SDF icons.
+    pub size: f32,
+}
+
+pub struct IconShader {
+    pub format: wgpu::TextureFormat,
+}
+
+impl Shader for IconShader {
+    fn describe_vertex(&self) -> VertexState {
+        VertexState {
+            source: include_str!("icon.vertex.wgsl"),
+            entry_point: "main",
+            buffers: vec![
+                // vertex data
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderIconVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: vec![
+                        // anchor
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 0,
+                        },
+                        // offset
+                        wgpu::VertexAttribute {
+                            offset: wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 1,
+                        },
+                        // tex_coords
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 2,
+                        },
+                        // color
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x2.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 3,
+                        },
+                        // sdf and size
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x2.size()
+                                + wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 8,
+                        },
+                    ],
+                },
+                // tile metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderTileMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // translate
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 4,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 1 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 5,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 2 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 6,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 3 * wgpu::VertexFormat::Float32x4.size(),
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 7,
+                        },
+                        // viewport_width
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 11,
+                        },
+                        // viewport_height
+                        wgpu::VertexAttribute {
+                            offset: 4 * wgpu::VertexFormat::Float32x4.size()
+                                + 2 * wgpu::VertexFormat::Float32.size(),
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 12,
+                        },
+                    ],
+                },
+                // layer metadata
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<ShaderLayerMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: vec![
+                        // z_index
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 10,
+                        },
+                    ],
+                },
+                // features
+                VertexBufferLayout {
+                    array_stride: std::mem::size_of::<SDFShaderFeatureMetadata>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: vec![
+                        // opacity
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32,
+                            shader_location: 13,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    fn describe_fragment(&self) -> FragmentState {
+        FragmentState {
+            source: include_str!("icon.fragment.wgsl"),
+            entry_point: "main",
+            targets: vec![Some(wgpu::ColorTargetState {
+                format: self.format,
+                // The fragment shader outputs premultiplied colors
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
             })],
         }
     }
@@ -720,6 +1844,14 @@ impl Shader for SymbolShader {
 pub struct BackgroundLayerMetadata {
     pub color: [f32; 4],
     pub z_index: f32,
+    /// Texture coordinates of the top left and bottom right corner of the pattern in the image
+    /// atlas. All zero if the layer has no pattern.
+    pub pattern: [f32; 4],
+    /// Display width and height of the pattern, followed by the offset of the top left corner of
+    /// the viewport within the pattern.
+    pub pattern_placement: [f32; 4],
+    /// Scale of the pattern relative to its display size at the current fractional zoom.
+    pub pattern_scale: f32,
 }
 
 pub struct BackgroundShader {
@@ -745,6 +1877,21 @@ impl Shader for BackgroundShader {
                         format: wgpu::VertexFormat::Float32,
                         shader_location: 1,
                     },
+                    wgpu::VertexAttribute {
+                        offset: 20,
+                        format: wgpu::VertexFormat::Float32x4,
+                        shader_location: 2,
+                    },
+                    wgpu::VertexAttribute {
+                        offset: 36,
+                        format: wgpu::VertexFormat::Float32x4,
+                        shader_location: 3,
+                    },
+                    wgpu::VertexAttribute {
+                        offset: 52,
+                        format: wgpu::VertexFormat::Float32,
+                        shader_location: 4,
+                    },
                 ],
             }],
         }
@@ -752,7 +1899,7 @@ impl Shader for BackgroundShader {
 
     fn describe_fragment(&self) -> FragmentState {
         FragmentState {
-            source: include_str!("basic.fragment.wgsl"),
+            source: include_str!("background.fragment.wgsl"),
             entry_point: "main",
             targets: vec![Some(wgpu::ColorTargetState {
                 format: self.format,
@@ -1,6 +1,9 @@
 struct VertexOutput {
     @location(1) v_data0: vec2<f32>,
     @location(2) v_data1: vec3<f32>,
+    @location(3) v_color: vec4<f32>,
+    @location(4) v_halo_color: vec4<f32>,
+    @location(5) v_halo: vec2<f32>,
     @builtin(position) position: vec4<f32>,
 };
 
@@ -18,53 +21,43 @@ var s_glyphs: sampler;
 // https://www.khronos.org/opengl/wiki/Sampler_(GLSL)#Non-uniform_flow_control
 @fragment
 fn main(in: VertexOutput) -> Output {
-let SDF_PX:f32 =  8.0;
+    let SDF_PX: f32 = 8.0;
 
-    let   fill_color: vec4<f32> = vec4<f32>(0.2, 0.2, 0.2, 1.0);  // highp
-    let  halo_color: vec4<f32>= vec4<f32>(1.0, 1.0, 1.0, 1.0);   // highp
-    let   opacity: f32 = 1.0; // lowp
-    let   halo_width: f32 = 1.0; // lowp
-    let   halo_blur: f32 = 1.0; // lowp
+    let fill_color: vec4<f32> = in.v_color; // highp, premultiplied
+    let halo_color: vec4<f32> = in.v_halo_color; // highp, premultiplied
+    let halo_width: f32 = in.v_halo.x; // lowp
+    let halo_blur: f32 = in.v_halo.y; // lowp
 
-let  u_is_halo: bool = false;
-let  u_gamma_scale: f32 = 1.0; // highp
-let  u_device_pixel_ratio: f32 = 1.0; // lowp
-let  u_is_text: bool = true;
+    let u_gamma_scale: f32 = 1.0; // highp
+    let u_device_pixel_ratio: f32 = 1.0; // lowp
+    let u_is_text: bool = true;
 
-let tex: vec2<f32> = in.v_data0.xy;
-
-
- let EDGE_GAMMA: f32 = 0.105 / u_device_pixel_ratio;
+    let tex: vec2<f32> = in.v_data0.xy;
 
+    let EDGE_GAMMA: f32 = 0.105 / u_device_pixel_ratio;
 
     let gamma_scale: f32 = in.v_data1.x;
     let size: f32 = in.v_data1.y;
-    let fade_opacity: f32 = in.v_data1[2];
-
-    let font_scale: f32 = select(size,  size / 24.0, u_is_text);
-
-    var color: vec4<f32> = fill_color; // lowp
-    var  gamma: f32 = EDGE_GAMMA / (font_scale * u_gamma_scale); // highp
-     var buff = (256.0 - 64.0) / 256.0; // lowp
-    if (u_is_halo) {
-        color = halo_color;
-        gamma = (halo_blur * 1.19 / SDF_PX + EDGE_GAMMA) / (font_scale * u_gamma_scale);
-        buff = (6.0 - halo_width / font_scale) / SDF_PX;
-    }
-
-     let dist: f32 =  textureSample(t_glyphs, s_glyphs, tex).r; // lowp
-     let gamma_scaled: f32 = gamma * gamma_scale; // highp
-     let alpha: f32 = smoothstep(buff - gamma_scaled, buff + gamma_scaled, dist); // highp
-
-    let fragColor = color * (alpha * opacity * fade_opacity);
-
+    // Product of the text-opacity and the opacity which hides colliding labels
+    let opacity: f32 = in.v_data1[2];
 
+    let font_scale: f32 = select(size, size / 24.0, u_is_text);
 
-    return Output(fragColor);
+    let dist: f32 = textureSample(t_glyphs, s_glyphs, tex).r; // lowp
 
+    // Instead of drawing the halo in a separate pass, the glyph is composited over its halo.
+    let gamma: f32 = EDGE_GAMMA / (font_scale * u_gamma_scale) * gamma_scale; // highp
+    let buff: f32 = (256.0 - 64.0) / 256.0; // lowp
+    let alpha: f32 = smoothstep(buff - gamma, buff + gamma, dist); // highp
 
+    let halo_gamma: f32 = (halo_blur * 1.19 / SDF_PX + EDGE_GAMMA) / (font_scale * u_gamma_scale) * gamma_scale; // highp
+    let halo_buff: f32 = (6.0 - halo_width / font_scale) / SDF_PX; // lowp
+    let halo_alpha: f32 = select(0.0, smoothstep(halo_buff - halo_gamma, halo_buff + halo_gamma, dist), halo_width > 0.0); // highp
 
+    let fill = fill_color * alpha;
+    let halo = halo_color * halo_alpha;
 
+    return Output((fill + halo * (1.0 - fill.a)) * opacity);
 }
 
 /*
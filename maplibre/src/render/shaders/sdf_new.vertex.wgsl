@@ -2,6 +2,9 @@
 struct VertexOutput {
     @location(1) v_data0: vec2<f32>,
     @location(2) v_data1: vec3<f32>,
+    @location(3) v_color: vec4<f32>,
+    @location(4) v_halo_color: vec4<f32>,
+    @location(5) v_halo: vec2<f32>,
     @builtin(position) position: vec4<f32>,
 };
 
@@ -10,17 +13,19 @@ fn main(
     @location(0) a_pos_offset: vec4<i32>,
     @location(1) a_data: vec4<u32>,
     @location(2) a_pixeloffset: vec4<i32>,
-
-
-
+    @location(3) opacity: f32,
     @location(4) translate1: vec4<f32>,
     @location(5) translate2: vec4<f32>,
     @location(6) translate3: vec4<f32>,
     @location(7) translate4: vec4<f32>,
+    @location(8) halo: vec2<f32>,
     @location(9) zoom_factor: f32,
     @location(10) z_index: f32,
+    @location(11) viewport_width: f32,
+    @location(12) viewport_height: f32,
     @location(13) text_size: f32,
-    //@location(12) opacity: f32,
+    @location(14) color: vec4<f32>,
+    @location(15) halo_color: vec4<f32>,
     @builtin(instance_index) instance_idx: u32 // instance_index i0 used when we have multiple instances of the same "object"
 ) -> VertexOutput {
 
@@ -51,7 +56,7 @@ let u_pitch_with_map: bool = true;
 let  u_pitch: f32= 0.0; // highp
 let u_rotate_symbol: bool = false;
 let   u_aspect_ratio: f32 = 0.0; // highp
-let   u_camera_to_center_distance: f32 = 0.0; // highp
+let   u_camera_to_center_distance: f32 = 1.0; // highp
 let u_fade_change: f32 = 0.0;
 let  u_texsize: vec2<f32>= vec2<f32>(3178.0, 30.0);
 
@@ -86,11 +91,8 @@ let a_projected_pos: vec3<f32> =  vec3<f32>(vec2<f32>(a_pos_offset.xy), 0.0);
     // If the label isn't pitched with the map, we do layout in viewport space,
     // which makes labels in the distance larger relative to the features around
     // them. We counteract part of that effect by dividing by the perspective ratio.
-    let distance_ratio: f32 = select(
-        u_camera_to_center_distance / camera_to_anchor_distance,
-        camera_to_anchor_distance / u_camera_to_center_distance,
-        u_pitch_with_map
-    ); // highp
+    // TODO: The perspective ratio is not applied, because labels are not pitched with the map yet
+    let distance_ratio: f32 = 1.0; // highp
 
      let perspective_ratio: f32 = clamp(
         0.5 + 0.5 * distance_ratio,
@@ -118,12 +120,19 @@ let a_projected_pos: vec3<f32> =  vec3<f32>(vec2<f32>(a_pos_offset.xy), 0.0);
      let angle_cos: f32  = cos(segment_angle + symbol_rotation); // highp
     let rotation_matrix: mat2x2<f32> = mat2x2<f32>(angle_cos, -1.0 * angle_sin, angle_sin, angle_cos);
 
-    let projected_pos: vec4<f32> = u_label_plane_matrix * vec4(a_projected_pos.xy, 0.0, 1.0);
-    let gl_Position = u_coord_matrix * vec4(projected_pos.xy / projected_pos.w + rotation_matrix * (vec2<f32>(a_offset) / 32.0 * font_scale + vec2<f32>(a_pxoffset)), 0.0, 1.0);
-    let gamma_scale: f32 = gl_Position.w;
+    // The label faces the viewport, such that the offsets of the glyphs are added in pixels
+    let pixel_offset: vec2<f32> = rotation_matrix * (vec2<f32>(a_offset) / 32.0 * font_scale + vec2<f32>(a_pxoffset));
+    let pixels_to_clip = vec2<f32>(2.0 / viewport_width, -2.0 / viewport_height);
+    var final_position = projectedPoint;
+    final_position.x += pixel_offset.x * pixels_to_clip.x * final_position.w;
+    final_position.y += pixel_offset.y * pixels_to_clip.y * final_position.w;
+    final_position.z = z_index;
+    // The labels face the viewport, such that the edges of the glyphs are not scaled by the perspective
+    let gamma_scale: f32 = 1.0;
 
     // TODO let fade_opacity: vec4<f32> = unpack_opacity(a_fade_opacity);
-    let fade_opacity: vec4<f32> = vec4<f32>(1.0,1.0,1.0,1.0);
+    // The opacity of the feature is the text-opacity, or zero for labels which collide with other symbols
+    let fade_opacity: vec4<f32> = vec4<f32>(opacity, 1.0, 1.0, 1.0);
     let fade_change: f32  = select(-u_fade_change, u_fade_change, fade_opacity[1] > 0.5);
     let interpolated_fade_opacity: f32  = max(0.0, min(1.0, fade_opacity[0] + fade_change));
 
@@ -132,8 +141,5 @@ let a_projected_pos: vec3<f32> =  vec3<f32>(vec2<f32>(a_pos_offset.xy), 0.0);
     let v_data1 = vec3<f32>(gamma_scale, size, interpolated_fade_opacity);
 
 
-    //var final_position = mat4x4<f32>(translate1, translate2, translate3, translate4) * vec4<f32>(a_projected_pos + vec3<f32>(vec2<f32>(a_offset) / 32.0 * font_scale + vec2<f32>(a_pxoffset), 0.0), 1.0);
-    var final_position =  mat4x4<f32>(translate1, translate2, translate3, translate4)*gl_Position;
-    final_position.z = 10.0;
-    return VertexOutput(v_data0, v_data1, final_position);
+    return VertexOutput(v_data0, v_data1, color, halo_color, halo, final_position);
 }
\ No newline at end of file
@@ -0,0 +1,15 @@
+struct VertexOutput {
+    @location(0) tex_coords: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var t_drape: texture_2d<f32>;
+@group(0) @binding(1)
+var s_drape: sampler;
+
+@fragment
+fn main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The layers were blended into the transparent drape texture, so its colors are premultiplied
+    return textureSample(t_drape, s_drape, in.tex_coords);
+}
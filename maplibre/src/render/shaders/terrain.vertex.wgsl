@@ -0,0 +1,27 @@
+struct VertexOutput {
+    @location(0) tex_coords: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+// Must match EXTENT in coords.rs
+const EXTENT: f32 = 4096.0;
+
+@vertex
+fn main(
+    @location(0) position: vec2<f32>,
+    @location(1) elevation: f32,
+    @location(4) translate1: vec4<f32>,
+    @location(5) translate2: vec4<f32>,
+    @location(6) translate3: vec4<f32>,
+    @location(7) translate4: vec4<f32>,
+    @location(14) pixels_per_meter: f32,
+) -> VertexOutput {
+    let transform = mat4x4<f32>(translate1, translate2, translate3, translate4);
+
+    // The z axis of the world is measured in pixels, like the x and y axes
+    let z = elevation * pixels_per_meter;
+    let final_position = transform * vec4<f32>(position, z, 1.0);
+
+    // The drape texture covers the whole tile
+    return VertexOutput(position / EXTENT, final_position);
+}
@@ -6,7 +6,9 @@ use crate::{
     context::MapContext,
     render::{
         eventually::Eventually,
-        resource::{BackingBufferDescriptor, RenderPipeline, Texture, TilePipeline},
+        resource::{
+            BackingBufferDescriptor, ImageAtlasTexture, RenderPipeline, Texture, TilePipeline,
+        },
         settings::Msaa,
         shaders,
         shaders::{Shader, ShaderTileMetadata},
@@ -38,10 +40,13 @@ impl System for ResourceSystem {
             ..
         }: &mut MapContext,
     ) -> SystemResult {
-        let Some((tile_view_pattern, mask_pipeline)) = world.resources.query_mut::<(
-            &mut Eventually<WgpuTileViewPattern>,
-            &mut Eventually<MaskPipeline>,
-        )>() else {
+        let Some((tile_view_pattern, mask_pipeline, image_atlas_texture)) =
+            world.resources.query_mut::<(
+                &mut Eventually<WgpuTileViewPattern>,
+                &mut Eventually<MaskPipeline>,
+                &mut Eventually<ImageAtlasTexture>,
+            )>()
+        else {
             return Err(SystemError::Dependencies);
         };
 
@@ -135,6 +140,8 @@ impl System for ResourceSystem {
             MaskPipeline(pipeline)
         });
 
+        image_atlas_texture.initialize(|| ImageAtlasTexture::from_device(device));
+
         Ok(())
     }
 }
@@ -30,6 +30,8 @@ pub fn upload_system(
         &view_proj,
         view_state.width() as f32,
         view_state.height() as f32,
+        view_state.camera_to_center_distance() as f32,
+        view_state.pixels_per_meter() as f32,
     );
 
     Ok(())
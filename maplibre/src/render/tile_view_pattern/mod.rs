@@ -44,6 +44,8 @@ pub enum SourceShapes {
 pub struct ViewTile {
     target: WorldTileCoords,
     source: SourceShapes,
+    /// The shape of the `target` itself, which is only uploaded if the pattern is draped.
+    target_shape: TileShape,
 }
 
 impl ViewTile {
@@ -51,6 +53,12 @@ impl ViewTile {
         self.target
     }
 
+    /// Returns the shape of the `target` tile. It has a buffer range only if the pattern is
+    /// draped, see [`TileViewPattern::set_draped`].
+    pub fn target_shape(&self) -> &TileShape {
+        &self.target_shape
+    }
+
     pub fn render<F>(&self, mut callback: F)
     where
         F: FnMut(&TileShape),
@@ -79,6 +87,9 @@ pub struct TileShape {
     transform: Matrix4<f64>,
 
     buffer_range: Option<Range<wgpu::BufferAddress>>,
+    /// The range of the metadata which draws the shape into the texture of its target tile,
+    /// see [`TileViewPattern::set_draped`].
+    drape_buffer_range: Option<Range<wgpu::BufferAddress>>,
 }
 
 impl TileShape {
@@ -88,18 +99,37 @@ impl TileShape {
             zoom_factor: zoom.scale_to_tile(&coords),
             transform: coords.transform_for_zoom(zoom),
             buffer_range: None,
+            drape_buffer_range: None,
         }
     }
 
-    fn set_buffer_range(&mut self, index: u64) {
+    fn buffer_range_at(index: u64) -> Range<wgpu::BufferAddress> {
         const STRIDE: u64 = size_of::<ShaderTileMetadata>() as u64;
-        self.buffer_range = Some(index * STRIDE..(index + 1) * STRIDE);
+        index * STRIDE..(index + 1) * STRIDE
+    }
+
+    fn set_buffer_range(&mut self, index: u64) {
+        self.buffer_range = Some(Self::buffer_range_at(index));
+    }
+
+    fn set_drape_buffer_range(&mut self, index: Option<u64>) {
+        self.drape_buffer_range = index.map(Self::buffer_range_at);
     }
 
     pub fn buffer_range(&self) -> Option<Range<wgpu::BufferAddress>> {
         self.buffer_range.clone()
     }
 
+    /// Returns this shape as it is drawn into the texture of its target tile. Draw commands
+    /// which use the returned shape render into that texture instead of the view.
+    pub fn draped(&self) -> Option<TileShape> {
+        Some(TileShape {
+            buffer_range: Some(self.drape_buffer_range.clone()?),
+            drape_buffer_range: None,
+            ..self.clone()
+        })
+    }
+
     pub fn coords(&self) -> WorldTileCoords {
         self.coords
     }
@@ -1,7 +1,9 @@
 use std::{collections::HashSet, marker::PhantomData};
 
+use cgmath::{Matrix4, SquareMatrix};
+
 use crate::{
-    coords::{ViewRegion, Zoom},
+    coords::{ViewRegion, Zoom, EXTENT, TILE_SIZE},
     render::{
         camera::ViewProjection,
         resource::{BackingBufferDescriptor, Queue},
@@ -32,10 +34,16 @@ impl<B> BackingBuffer<B> {
     }
 }
 
+/// The `w` of the clip coordinates within the textures of draped tiles. Some layers write their
+/// index into the `z` of the clip coordinates, so `w` needs to exceed the count of layers.
+const DRAPE_CLIP_W: f64 = 4096.0;
+
 /// The tile mask pattern assigns each tile a value which can be used for stencil testing.
 pub struct TileViewPattern<Q, B> {
     view_tiles: Vec<ViewTile>,
     view_tiles_buffer: BackingBuffer<B>,
+    /// Whether tiles are drawn into textures, which are draped over the terrain.
+    draped: bool,
     phantom_q: PhantomData<Q>,
 }
 
@@ -47,6 +55,7 @@ impl<Q: Queue<B>, B> TileViewPattern<Q, B> {
                 view_tiles_buffer.buffer,
                 view_tiles_buffer.inner_size,
             ),
+            draped: false,
             phantom_q: Default::default(),
         }
     }
@@ -106,6 +115,7 @@ impl<Q: Queue<B>, B> TileViewPattern<Q, B> {
             view_tiles.push(ViewTile {
                 target: coords,
                 source: source_shapes,
+                target_shape: TileShape::new(coords, zoom),
             });
         }
 
@@ -117,6 +127,17 @@ impl<Q: Queue<B>, B> TileViewPattern<Q, B> {
         self.view_tiles.append(&mut view_tiles)
     }
 
+    /// Sets whether the source shapes are also uploaded as they are drawn into a texture of
+    /// their target tile, which is available through [`TileShape::draped`]. The target tiles
+    /// are uploaded as well in that case, see [`ViewTile::target_shape`].
+    pub fn set_draped(&mut self, draped: bool) {
+        self.draped = draped;
+    }
+
+    pub fn is_draped(&self) -> bool {
+        self.draped
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &ViewTile> + '_ {
         self.view_tiles.iter()
     }
@@ -132,39 +153,90 @@ impl<Q: Queue<B>, B> TileViewPattern<Q, B> {
         view_proj: &ViewProjection,
         viewport_width: f32,
         viewport_height: f32,
+        camera_to_center_distance: f32,
+        pixels_per_meter: f32,
     ) {
         let mut buffer = Vec::with_capacity(self.view_tiles.len());
 
-        let mut add_to_buffer = |shape: &mut TileShape| {
-            shape.set_buffer_range(buffer.len() as u64);
+        let mut add_to_buffer = |transform: Matrix4<f64>,
+                                 zoom_factor: f64,
+                                 viewport_width: f32,
+                                 viewport_height: f32| {
             // TODO: Name `ShaderTileMetadata` is unfortunate here, because for raster rendering it actually is a layer
-            let transform = view_proj
-                .to_model_view_projection(shape.transform)
-                .downcast()
-                .into(); // TODO: move this calculation to update() fn above
             buffer.push(ShaderTileMetadata {
                 // We are casting here from 64bit to 32bit, because 32bit is more performant and is
                 // better supported.
-                transform,
-                zoom_factor: shape.zoom_factor as f32,
+                transform: transform
+                    .cast::<f32>()
+                    .expect("unable to cast transform")
+                    .into(),
+                zoom_factor: zoom_factor as f32,
                 viewport_width,
                 viewport_height,
+                camera_to_center_distance,
+                pixels_per_meter,
             });
+            buffer.len() as u64 - 1
         };
 
+        let draped = self.draped;
         for view_tile in &mut self.view_tiles {
+            let target_transform = view_tile.target_shape.transform;
+            // Maps the target tile to the whole texture, in which the y axis points down
+            let to_texture = Matrix4::from_translation(cgmath::Vector3::new(-1.0, 1.0, 0.0))
+                * Matrix4::from_nonuniform_scale(2.0 / EXTENT, -2.0 / EXTENT, 0.0)
+                * target_transform
+                    .invert()
+                    .expect("tile transforms are invertible")
+                * DRAPE_CLIP_W;
+            // The size of the target tile in the view in pixels
+            let target_size = (TILE_SIZE / view_tile.target_shape.zoom_factor) as f32;
+
+            let mut add_shape = |shape: &mut TileShape| {
+                let index = add_to_buffer(
+                    view_proj.to_model_view_projection(shape.transform).get(), // TODO: move this calculation to update() fn above
+                    shape.zoom_factor,
+                    viewport_width,
+                    viewport_height,
+                );
+                shape.set_buffer_range(index);
+
+                let drape_index = draped.then(|| {
+                    add_to_buffer(
+                        to_texture * shape.transform,
+                        shape.zoom_factor,
+                        target_size,
+                        target_size,
+                    )
+                });
+                shape.set_drape_buffer_range(drape_index);
+            };
+
             match &mut view_tile.source {
                 SourceShapes::Parent(source_shape) => {
-                    add_to_buffer(source_shape);
+                    add_shape(source_shape);
                 }
                 SourceShapes::Children(source_shapes) => {
                     for source_shape in source_shapes {
-                        add_to_buffer(source_shape);
+                        add_shape(source_shape);
                     }
                 }
-                SourceShapes::SourceEqTarget(source_shape) => add_to_buffer(source_shape),
+                SourceShapes::SourceEqTarget(source_shape) => add_shape(source_shape),
                 SourceShapes::None => {}
             }
+
+            if draped {
+                let target_shape = &mut view_tile.target_shape;
+                let index = add_to_buffer(
+                    view_proj
+                        .to_model_view_projection(target_shape.transform)
+                        .get(),
+                    target_shape.zoom_factor,
+                    viewport_width,
+                    viewport_height,
+                );
+                target_shape.set_buffer_range(index);
+            }
         }
 
         let raw_buffer = bytemuck::cast_slice(buffer.as_slice());
@@ -42,6 +42,9 @@ impl Node for TranslucentPassNode {
         let Initialized(multisampling_texture) = &state.multisampling_texture else {
             return Ok(());
         };
+        let Initialized(depth_texture) = &state.depth_texture else {
+            return Ok(());
+        };
 
         let color_attachment = if let Some(texture) = multisampling_texture {
             wgpu::RenderPassColorAttachment {
@@ -69,7 +72,19 @@ impl Node for TranslucentPassNode {
                 .begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: Some("translucent_pass"),
                     color_attachments: &[Some(color_attachment)],
-                    depth_stencil_attachment: None,
+                    // The depth of the main pass is kept, such that 3D layers are occluded by
+                    // each other
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &depth_texture.view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: StoreOp::Store,
+                        }),
+                        stencil_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: StoreOp::Store,
+                        }),
+                    }),
                     timestamp_writes: None,
                     occlusion_query_set: None,
                 });
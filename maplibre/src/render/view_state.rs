@@ -1,12 +1,13 @@
 use std::{
     f64,
     ops::{Deref, DerefMut},
+    rc::Rc,
 };
 
 use cgmath::{prelude::*, *};
 
 use crate::{
-    coords::{ViewRegion, WorldCoords, Zoom, ZoomLevel},
+    coords::{ViewRegion, WorldCoords, Zoom, ZoomLevel, TILE_SIZE},
     render::camera::{
         Camera, EdgeInsets, InvertedViewProjection, Perspective, ViewProjection, FLIP_Y,
         OPENGL_TO_WGPU_MATRIX,
@@ -20,6 +21,17 @@ use crate::{
 
 const VIEW_REGION_PADDING: i32 = 1;
 const MAX_N_TILES: usize = 512;
+/// The number of samples along a ray, which are tested for an intersection with the terrain.
+const ELEVATION_RAY_STEPS: usize = 64;
+/// The number of bisections, which refine the intersection of a ray with the terrain.
+const ELEVATION_RAY_REFINEMENTS: usize = 16;
+
+/// Provides the elevation of the ground, which is lifted above the `z=0` plane by terrain.
+pub trait Elevation {
+    /// Returns the elevation in meters at the world coordinates for the `zoom`, or `None` if
+    /// the elevation is unknown there.
+    fn elevation_at(&self, coords: WorldCoords, zoom: Zoom) -> Option<f64>;
+}
 
 pub enum ViewStatePadding {
     // This is helpful for loading a set of tiles.
@@ -37,6 +49,7 @@ pub struct ViewState {
     width: f64,
     height: f64,
     edge_insets: EdgeInsets,
+    elevation: Option<Rc<dyn Elevation>>,
 }
 
 impl ViewState {
@@ -63,6 +76,7 @@ impl ViewState {
                 left: 0.0,
                 right: 0.0,
             },
+            elevation: None,
         }
     }
     pub fn set_edge_insets(&mut self, edge_insets: EdgeInsets) {
@@ -73,6 +87,31 @@ impl ViewState {
         &self.edge_insets
     }
 
+    /// Sets the elevation of the ground, which is taken into account by
+    /// [`Self::window_to_world_at_ground`].
+    pub fn set_elevation(&mut self, elevation: Option<Rc<dyn Elevation>>) {
+        self.elevation = elevation;
+    }
+
+    /// Returns the height of the ground in the units of the world, which are pixels at the
+    /// current zoom. The ground is at `z=0` if there is no elevation.
+    pub fn ground_height_at(&self, x: f64, y: f64) -> f64 {
+        self.elevation
+            .as_ref()
+            .and_then(|elevation| elevation.elevation_at(WorldCoords::at_ground(x, y), self.zoom()))
+            .map_or(0.0, |meters| meters * self.pixels_per_meter())
+    }
+
+    /// Returns the position of the camera in world coordinates.
+    pub fn camera_position(&self) -> Point3<f64> {
+        let camera_matrix = self.camera.calc_matrix(self.camera_to_center_distance());
+        let position = camera_matrix
+            .invert()
+            .expect("camera matrix must be invertible")
+            * Vector4::new(0.0, 0.0, 0.0, 1.0);
+        Point3::new(position.x, position.y, position.z)
+    }
+
     pub fn resize(&mut self, size: LogicalSize) {
         self.width = size.width() as f64;
         self.height = size.height() as f64;
@@ -197,6 +236,16 @@ impl ViewState {
         *self.zoom
     }
 
+    /// Returns the size of a meter in pixels at the latitude of the center of the map, which is
+    /// used to scale heights like the ones of `fill-extrusion` layers.
+    pub fn pixels_per_meter(&self) -> f64 {
+        let zoom = self.zoom();
+        let center = self.camera.position();
+        let lat_lon = WorldCoords::at_ground(center.x, center.y).into_lat_lon(zoom);
+        let world_size = TILE_SIZE * 2.0_f64.powf(zoom.level() as f64);
+        lat_lon.mercator_z_from_altitude(1.0) * world_size
+    }
+
     pub fn did_zoom_change(&self) -> bool {
         self.zoom.did_change(0.05)
     }
@@ -350,7 +399,9 @@ impl ViewState {
         )
     }
 
-    /// Gets the world coordinates for the specified `window` coordinates on the `z=0` plane.
+    /// Gets the world coordinates for the specified `window` coordinates on the ground. The
+    /// ground is the `z=0` plane, which is lifted by the elevation if there is one, see
+    /// [`Self::set_elevation`].
     pub fn window_to_world_at_ground(
         &self,
         window: &Vector2<f64>,
@@ -365,7 +416,10 @@ impl ViewState {
 
         // for z = 0 in world coordinates
         // Idea comes from: https://dondi.lmu.build/share/cg/unproject-explained.pdf
-        let u = -near_world.z / (far_world.z - near_world.z);
+        let mut u = -near_world.z / (far_world.z - near_world.z);
+        if self.elevation.is_some() && u > 0.0 {
+            u = self.intersect_ground(near_world, far_world, u);
+        }
         if !bound || (0.0..=1.01).contains(&u) {
             let result = near_world + u * (far_world - near_world);
             Some(Vector2::new(result.x, result.y))
@@ -374,6 +428,41 @@ impl ViewState {
         }
     }
 
+    /// Returns the first point at which the ray from `near_world` to `far_world` hits the
+    /// elevated ground, as a factor of the ray. The ray is sampled until it reaches the `z=0`
+    /// plane at `plane_u`, which is returned if the ground is not hit before.
+    fn intersect_ground(
+        &self,
+        near_world: Vector3<f64>,
+        far_world: Vector3<f64>,
+        plane_u: f64,
+    ) -> f64 {
+        let is_below_ground = |u: f64| {
+            let point = near_world + u * (far_world - near_world);
+            point.z <= self.ground_height_at(point.x, point.y)
+        };
+
+        let mut above = 0.0;
+        for step in 1..=ELEVATION_RAY_STEPS {
+            let below = plane_u * step as f64 / ELEVATION_RAY_STEPS as f64;
+            if is_below_ground(below) {
+                // Bisect between the last sample above and the first sample below the ground
+                let mut below = below;
+                for _ in 0..ELEVATION_RAY_REFINEMENTS {
+                    let middle = (above + below) / 2.0;
+                    if is_below_ground(middle) {
+                        below = middle;
+                    } else {
+                        above = middle;
+                    }
+                }
+                return below;
+            }
+            above = below;
+        }
+        plane_u
+    }
+
     /// Calculates an [`Aabb2`] bounding box which contains at least the visible area on the `z=0`
     /// plane. One can think of it as being the bounding box of the geometry which forms the
     /// intersection between the viewing frustum and the `z=0` plane.
@@ -472,14 +561,65 @@ impl ViewState {
 
 #[cfg(test)]
 mod tests {
-    use cgmath::{Deg, Matrix4, Vector2, Vector4};
+    use std::rc::Rc;
+
+    use cgmath::{Deg, InnerSpace, Matrix4, Vector2, Vector4};
 
     use crate::{
         coords::{WorldCoords, Zoom},
-        render::view_state::ViewState,
+        render::view_state::{Elevation, ViewState},
         window::PhysicalSize,
     };
 
+    /// A plateau east of `x=-100`.
+    struct Plateau(f64);
+
+    impl Elevation for Plateau {
+        fn elevation_at(&self, coords: WorldCoords, _zoom: Zoom) -> Option<f64> {
+            Some(if coords.x > -100.0 { self.0 } else { 0.0 })
+        }
+    }
+
+    #[test]
+    fn window_to_world_at_elevated_ground() {
+        let mut state = ViewState::new(
+            PhysicalSize::new(800, 600).unwrap(),
+            WorldCoords::at_ground(0.0, 0.0),
+            Zoom::new(10.0),
+            Deg(30.0),
+            Deg(60.0),
+        );
+        let center = Vector2::new(400.0, 300.0);
+        let left = Vector2::new(200.0, 300.0);
+
+        let projection = state.view_projection().invert();
+        let flat_center = state
+            .window_to_world_at_ground(&center, &projection, true)
+            .unwrap();
+        let flat_left = state
+            .window_to_world_at_ground(&left, &projection, true)
+            .unwrap();
+
+        // The plateau is 100 pixels high
+        let meters = 100.0 / state.pixels_per_meter();
+        state.set_elevation(Some(Rc::new(Plateau(meters))));
+        assert!((state.ground_height_at(0.0, 0.0) - 100.0).abs() < 1e-6);
+
+        let projection = state.view_projection().invert();
+        let center_on_plateau = state
+            .window_to_world_at_ground(&center, &projection, true)
+            .unwrap();
+        let left_on_plateau = state
+            .window_to_world_at_ground(&left, &projection, true)
+            .unwrap();
+
+        // The ray hits the plateau before it reaches the plane, so closer to the camera which
+        // is south of the center
+        assert!(center_on_plateau.y > flat_center.y + 10.0);
+        // The ground west of the plateau is not elevated
+        assert!((left_on_plateau - flat_left).magnitude() < 1e-6);
+    }
+
     #[test]
     fn conform_transformation() {
         let fov = Deg(60.0);
@@ -1,18 +1,20 @@
 use std::borrow::Cow;
 
-use cgmath::{Matrix3, Vector3};
-
 use crate::{
     context::MapContext,
-    coords::{EXTENT, TILE_SIZE},
-    euclid::Point2D,
+    euclid::{Box2D, Point2D},
     legacy::{
         buckets::symbol_bucket::PlacedSymbol,
-        collision_feature::{CollisionBox, CollisionFeature},
+        collision_feature::CollisionFeature,
         collision_index::CollisionIndex,
-        geometry::feature_index::{IndexedSubfeature, RefIndexedSubfeature},
+        geometry::{
+            anchor::Anchor,
+            feature_index::{IndexedSubfeature, RefIndexedSubfeature},
+        },
         geometry_tile_data::GeometryCoordinates,
-        MapMode,
+        style_types::SymbolPlacementType,
+        util::constants::ONE_EM,
+        MapMode, TileSpace,
     },
     render::{
         eventually::{Eventually, Eventually::Initialized},
@@ -20,10 +22,18 @@ use crate::{
         tile_view_pattern::WgpuTileViewPattern,
         Renderer,
     },
-    sdf::{SymbolBufferPool, SymbolLayersDataComponent},
+    sdf::{
+        upload_system::{text_feature_metadata, text_size},
+        IconResources, SymbolBufferPool, SymbolLayersDataComponent,
+    },
+    style::layer::LayerPaint,
     tcs::system::{System, SystemError, SystemResult},
 };
 
+/// Padding around texts and icons in pixels, which is the default of `text-padding` and
+/// `icon-padding`.
+const SYMBOL_PADDING: f64 = 2.0;
+
 pub struct CollisionSystem {}
 
 impl Default for CollisionSystem {
@@ -47,16 +57,21 @@ impl System for CollisionSystem {
         &mut self,
         MapContext {
             world,
+            style,
             view_state,
-            renderer: Renderer { device, queue, .. },
+            renderer: Renderer { queue, .. },
             ..
         }: &mut MapContext,
     ) -> SystemResult {
-        let Some((Initialized(tile_view_pattern), Initialized(symbol_buffer_pool))) =
-            world.resources.query_mut::<(
-                &mut Eventually<WgpuTileViewPattern>,
-                &mut Eventually<SymbolBufferPool>,
-            )>()
+        let Some((
+            Initialized(tile_view_pattern),
+            Initialized(symbol_buffer_pool),
+            Initialized(icon_resources),
+        )) = world.resources.query_mut::<(
+            &mut Eventually<WgpuTileViewPattern>,
+            &mut Eventually<SymbolBufferPool>,
+            &mut Eventually<IconResources>,
+        )>()
         else {
             return Err(SystemError::Dependencies);
         };
@@ -66,171 +81,188 @@ impl System for CollisionSystem {
             // return Ok(());
         }
 
+        let zoom = view_state.zoom().level();
         let mut collision_index = CollisionIndex::new(view_state, MapMode::Continuous);
 
         for view_tile in tile_view_pattern.iter() {
             let coords = view_tile.coords();
-            if let Some(component) = world.tiles.query::<&SymbolLayersDataComponent>(coords) {
-                for layer in &component.layers {
-                    let metadata_count = if layer.features.is_empty() {
-                        layer.new_buffer.buffer.vertices.len()
-                    } else {
-                        layer
-                            .features
-                            .last()
-                            .map(|feature| feature.indices.end)
-                            .unwrap_or_default()
-                    };
-                    let mut feature_metadata =
-                        vec![SDFShaderFeatureMetadata { opacity: 1.0 }; metadata_count];
-
-                    for feature in &layer.features {
-                        // calculate where tile is
-
-                        let transform = coords.transform_for_zoom(view_state.zoom());
-
-                        let pos_matrix = view_state
-                            .view_projection()
-                            .to_model_view_projection(transform);
-
-                        let zoom_factor = view_state.zoom().scale_to_tile(&coords);
-
-                        let font_scale = 6.0;
-                        let scaling = Matrix3::from_cols(
-                            Vector3::new(zoom_factor * font_scale, 0.0, 0.0),
-                            Vector3::new(0.0, zoom_factor * font_scale, 0.0),
-                            Vector3::new(0.0, 0.0, 1.0),
-                        );
+            let Some(component) = world.tiles.query::<&SymbolLayersDataComponent>(coords) else {
+                continue;
+            };
 
-                        let vec3 = Vector3::new(
-                            feature.bbox.max.x as f64,
-                            feature.bbox.max.y as f64,
-                            0.0f64,
-                        );
-                        let text_anchor = Vector3::new(
-                            feature.text_anchor.x as f64,
-                            feature.text_anchor.y as f64,
-                            0.0f64,
-                        );
+            let transform = coords.transform_for_zoom(view_state.zoom());
+            let pos_matrix = view_state
+                .view_projection()
+                .to_model_view_projection(transform);
+            let scale = view_state.zoom().scale_to_zoom_level(coords.z);
 
-                        let shader = pos_matrix.get()
-                            * (scaling * (vec3 - text_anchor) + text_anchor).extend(1.0);
-                        let window = view_state.clip_to_window(&shader);
+            for layer in &component.layers {
+                let Some(LayerPaint::Symbol(paint)) = style
+                    .layers
+                    .iter()
+                    .find(|style_layer| style_layer.id == layer.style_layer_id)
+                    .and_then(|style_layer| style_layer.paint.as_ref())
+                else {
+                    continue;
+                };
+                let text_size = text_size(paint, zoom) as f64;
+                let icon_buffers = icon_resources.get_buffers(&coords, &layer.style_layer_id);
 
-                        //println!("{:?}", window);
+                let mut text_metadata = vec![
+                    SDFShaderFeatureMetadata::visible();
+                    layer.new_buffer.buffer.vertices.len()
+                ];
+                let mut icon_metadata = icon_buffers
+                    .map(|buffers| vec![SDFShaderFeatureMetadata::visible(); buffers.num_vertices]);
 
-                        let anchor_point =
-                            Point2D::new(feature.bbox.min.x as f64, feature.bbox.min.y as f64); // TODO
+                for (feature_index, feature) in layer.features.iter().enumerate() {
+                    let anchor = feature.text_anchor.cast::<f64>();
+                    let placed_icon = icon_buffers
+                        .and_then(|buffers| buffers.icons.get(feature_index))
+                        .and_then(Option::as_ref);
 
-                        let boxes = vec![CollisionBox {
-                            anchor: anchor_point,
-                            x1: 0.0 * (EXTENT / TILE_SIZE),
-                            y1: 0. * (EXTENT / TILE_SIZE),
-                            x2: (feature.bbox.max.x - feature.bbox.min.x) as f64, //* (EXTENT / TILE_SIZE),
-                            y2: (feature.bbox.max.y - feature.bbox.min.y) as f64, // * (EXTENT / TILE_SIZE),
-                            signed_distance_from_anchor: 0.0,
-                        }]; // TODO
+                    // The text and the icon of a symbol are only drawn if neither of them collides
+                    let mut collision_features = Vec::new();
+                    if !feature.indices.is_empty() {
+                        let font_scale = text_size / ONE_EM;
+                        let bbox = feature.bbox.cast::<f64>().scale(font_scale, font_scale);
+                        collision_features.push(collision_feature(anchor, &bbox, 0.0));
+                    }
+                    if let Some(icon) = placed_icon {
+                        collision_features.push(collision_feature(anchor, &icon.bbox, icon.rotate));
+                    }
 
+                    let mut placed = true;
+                    let mut placements = Vec::with_capacity(collision_features.len());
+                    for collision_feature in collision_features {
                         let mut projected_boxes = vec![];
-                        let collision_feature = CollisionFeature {
-                            boxes,
-                            indexed_feature: IndexedSubfeature {
-                                ref_: RefIndexedSubfeature {
-                                    index: 0,
-                                    sort_index: 0,
-                                    source_layer_name: "".to_string(),
-                                    bucket_leader_id: "".to_string(),
-                                    bucket_instance_id: 0,
-                                    collision_group_id: 0,
-                                },
-                                source_layer_name_copy: "".to_string(),
-                                bucket_leader_idcopy: "".to_string(),
-                            },
-                            along_line: false, // false if point, else true
-                        };
-                        let (placed_text, is_offscreen) = collision_index.place_feature(
+                        let (placed_feature, _is_offscreen) = collision_index.place_feature(
                             &collision_feature,
                             Point2D::zero(), // shift
                             &pos_matrix,
                             &pos_matrix.get(), // TODO
-                            //TILE_SIZE / EXTENT,
                             1.0,
-                            &PlacedSymbol {
-                                anchor_point,
-                                segment: 0,
-                                lower_size: 0.0,
-                                upper_size: 0.0,
-                                line_offset: [0., 0.],
-                                writing_modes: Default::default(),
-                                line: GeometryCoordinates(vec![anchor_point.cast()]), // TODO can be linestring or just a single point
-                                tile_distances: vec![],                               // TODO
-                                glyph_offsets: vec![0., 0.],                          // TODO
-                                hidden: false,
-                                vertex_start_index: 0,
-                                cross_tile_id: 0,
-                                placed_orientation: None,
-                                angle: 0.0,
-
-                                placed_icon_index: None,
-                            },
-                            view_state.zoom().scale_to_zoom_level(coords.z),
-                            6.0,
+                            &placed_symbol(anchor),
+                            scale,
+                            text_size,
                             false,
                             false,
                             false,
                             None,                               // avoidEdges
-                            Some(|f: &IndexedSubfeature| true), // collisionGroupPredicate
+                            Some(|_: &IndexedSubfeature| true), // collisionGroupPredicate
                             &mut projected_boxes,               // output
                         );
-                        if feature.str.starts_with("Ette") {
-                            //println!("{}", feature.str);
-                            //println!("{:?}", &collision_feature.boxes);
-                            //println!("proj {:?}", &projected_boxes.get(0));
-                        }
+                        placed &= placed_feature;
+                        placements.push((collision_feature, projected_boxes));
+                    }
 
-                        if placed_text {
+                    if placed {
+                        for (collision_feature, projected_boxes) in placements {
                             collision_index.insert_feature(
                                 collision_feature,
                                 &projected_boxes,
                                 false,
-                                55,
-                                66,
+                                0,
+                                0,
                             );
-
-                            for index in feature.indices.clone() {
-                                let index = layer.new_buffer.buffer.indices[index] as usize;
-                                feature_metadata[index].opacity = 1.0;
-                            }
-                        } else {
-                            for index in feature.indices.clone() {
-                                let index = layer.new_buffer.buffer.indices[index] as usize;
-                                feature_metadata[index].opacity = 0.0;
-                            }
-
-                            //feature_metadata.extend(iter::repeat(SDFShaderFeatureMetadata { opacity: 0.0 }).take(feature.indices.len()))
                         }
                     }
 
-                    if let Some(layer_at_coords) = symbol_buffer_pool.index().get_layers(coords) {
-                        for entry in layer_at_coords {
-                            debug_assert_eq!(entry.coords, coords);
-
-                            let source_layer = entry.style_layer.source_layer.as_ref().unwrap();
+                    let opacity = if placed { 1.0 } else { 0.0 };
+                    let mut metadata = text_feature_metadata(paint, feature, zoom);
+                    metadata.opacity *= opacity;
+                    for index in feature.indices.clone() {
+                        let index = layer.new_buffer.buffer.indices[index] as usize;
+                        text_metadata[index] = metadata;
+                    }
+                    if let (Some(icon), Some(icon_metadata)) = (placed_icon, &mut icon_metadata) {
+                        for vertex in icon.vertices.clone() {
+                            icon_metadata[vertex].opacity = opacity;
+                        }
+                    }
+                }
 
-                            if source_layer != &layer.source_layer {
-                                continue;
-                            }
+                if let Some(layer_at_coords) = symbol_buffer_pool.index().get_layers(coords) {
+                    for entry in layer_at_coords {
+                        debug_assert_eq!(entry.coords, coords);
 
-                            symbol_buffer_pool.update_feature_metadata(
-                                queue,
-                                entry,
-                                &feature_metadata,
-                            );
+                        if entry.style_layer.id != layer.style_layer_id {
+                            continue;
                         }
+
+                        symbol_buffer_pool.update_feature_metadata(queue, entry, &text_metadata);
                     }
                 }
+
+                if let (Some(buffers), Some(icon_metadata)) = (icon_buffers, &icon_metadata) {
+                    queue.write_buffer(
+                        &buffers.feature_metadata,
+                        0,
+                        bytemuck::cast_slice(icon_metadata),
+                    );
+                }
             }
         }
         Ok(())
     }
 }
+
+/// Creates the collision feature of a text or an icon at `anchor`, whose bounds relative to the
+/// anchor are given in pixels.
+fn collision_feature(
+    anchor: Point2D<f64, TileSpace>,
+    bbox: &Box2D<f64, TileSpace>,
+    rotate: f64,
+) -> CollisionFeature {
+    CollisionFeature::new(
+        &GeometryCoordinates(vec![anchor.cast()]),
+        &Anchor {
+            point: anchor,
+            angle: 0.0,
+            segment: None,
+        },
+        bbox.min.y,
+        bbox.max.y,
+        bbox.min.x,
+        bbox.max.x,
+        None,
+        1.0,
+        SYMBOL_PADDING,
+        SymbolPlacementType::Point,
+        IndexedSubfeature {
+            ref_: RefIndexedSubfeature {
+                index: 0,
+                sort_index: 0,
+                source_layer_name: "".to_string(),
+                bucket_leader_id: "".to_string(),
+                bucket_instance_id: 0,
+                collision_group_id: 0,
+            },
+            source_layer_name_copy: "".to_string(),
+            bucket_leader_idcopy: "".to_string(),
+        },
+        1.0,
+        rotate,
+    )
+}
+
+fn placed_symbol(anchor_point: Point2D<f64, TileSpace>) -> PlacedSymbol {
+    PlacedSymbol {
+        anchor_point,
+        segment: 0,
+        lower_size: 0.0,
+        upper_size: 0.0,
+        line_offset: [0., 0.],
+        writing_modes: Default::default(),
+        line: GeometryCoordinates(vec![anchor_point.cast()]),
+        tile_distances: vec![],
+        glyph_offsets: vec![0., 0.],
+        hidden: false,
+        vertex_start_index: 0,
+        cross_tile_id: 0,
+        placed_orientation: None,
+        angle: 0.0,
+
+        placed_icon_index: None,
+    }
+}
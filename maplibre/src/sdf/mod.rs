@@ -22,6 +22,7 @@ use crate::{
     },
     schedule::Schedule,
     sdf::resource::GlyphTexture,
+    style::{expression::FeatureProperties, layer::SymbolAnchor},
     tcs::{system::SystemContainer, tiles::TileComponent, world::World},
     vector::{
         resource::BufferPool,
@@ -42,6 +43,8 @@ pub mod tessellation;
 pub mod tessellation_new;
 pub mod text;
 
+pub use resource::{IconBuffers, IconResources, PlacedIcon};
+
 struct SymbolPipeline(wgpu::RenderPipeline);
 
 impl Deref for SymbolPipeline {
@@ -82,6 +85,7 @@ impl<E: Environment, T: VectorTransferables> Plugin<E> for SdfPlugin<T> {
         resources.insert(Eventually::<SymbolPipeline>::Uninitialized);
         resources.insert(Eventually::<SymbolBufferPool>::Uninitialized);
         resources.insert(Eventually::<GlyphTexture>::Uninitialized);
+        resources.insert(Eventually::<IconResources>::Uninitialized);
         resources.insert(Eventually::<(wgpu::Texture, wgpu::Sampler)>::Uninitialized);
 
         schedule.add_system_to_stage(
@@ -102,11 +106,30 @@ impl<E: Environment, T: VectorTransferables> Plugin<E> for SdfPlugin<T> {
     }
 }
 
+/// A symbol of a [`SymbolLayerData`], which consists of a text, an icon or both.
 pub struct Feature {
+    /// Bounds of the shaped text relative to the anchor, in pixels at a text size of 24 pixels.
     pub bbox: Box2D<f32, TileSpace>,
+    /// Range of the indices of the glyphs of the text. Empty if the symbol has no text.
     pub indices: Range<usize>,
     pub text_anchor: Point2D<f32, TileSpace>,
     pub str: String,
+    pub icon: Option<SymbolIcon>,
+    /// Properties of the feature, which are needed to evaluate the data-driven paint properties.
+    pub properties: FeatureProperties,
+}
+
+/// The layout of the icon of a symbol, which is evaluated at the zoom level of the tile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolIcon {
+    /// Id of the image within the sprite.
+    pub image: String,
+    pub size: f32,
+    /// Clockwise rotation in degrees.
+    pub rotate: f32,
+    pub anchor: SymbolAnchor,
+    /// Offset from the anchor in pixels at a size of 1.
+    pub offset: [f32; 2],
 }
 
 pub struct SymbolLayerData {
@@ -7,24 +7,67 @@ use crate::{
         render_phase::{DrawState, RenderPhase, TranslucentItem},
         tile_view_pattern::WgpuTileViewPattern,
     },
-    sdf::{render_commands::DrawSymbols, SymbolBufferPool},
+    sdf::{
+        render_commands::{DrawIcons, DrawSymbols},
+        resource::IconResources,
+        SymbolBufferPool,
+    },
     tcs::{
         system::{SystemError, SystemResult},
         tiles::Tile,
     },
 };
 
-pub fn queue_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
-    let Some((Initialized(tile_view_pattern), translucent_phase, Initialized(symbol_buffer_pool))) =
-        world.resources.query_mut::<(
-            &mut Eventually<WgpuTileViewPattern>,
-            &mut RenderPhase<TranslucentItem>,
-            &mut Eventually<SymbolBufferPool>,
-        )>()
+pub fn queue_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some((
+        Initialized(tile_view_pattern),
+        translucent_phase,
+        Initialized(symbol_buffer_pool),
+        Initialized(icon_resources),
+    )) = world.resources.query_mut::<(
+        &mut Eventually<WgpuTileViewPattern>,
+        &mut RenderPhase<TranslucentItem>,
+        &mut Eventually<SymbolBufferPool>,
+        &mut Eventually<IconResources>,
+    )>()
     else {
         return Err(SystemError::Dependencies);
     };
 
+    let visible_layers = style.visible_layer_ids(view_state.zoom().level());
+
+    // Icons are queued first, such that the texts of a layer are drawn on top of its icons
+    for view_tile in tile_view_pattern.iter() {
+        view_tile.render(|source_shape| {
+            let coords = source_shape.coords();
+
+            for style_layer in &style.layers {
+                if !visible_layers.contains(style_layer.id.as_str())
+                    || icon_resources
+                        .get_buffers(&coords, &style_layer.id)
+                        .is_none()
+                {
+                    continue;
+                }
+
+                translucent_phase.add(TranslucentItem {
+                    draw_function: Box::new(DrawState::<TranslucentItem, DrawIcons>::new()),
+                    index: style_layer.index,
+                    style_layer: style_layer.id.clone(),
+                    tile: Tile { coords },
+                    source_shape: source_shape.clone(),
+                });
+            }
+        });
+    }
+
     for view_tile in tile_view_pattern.iter() {
         let coords = &view_tile.coords();
         tracing::trace!("Drawing tile at {coords}");
@@ -35,6 +78,10 @@ pub fn queue_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
                 symbol_buffer_pool.index().get_layers(source_shape.coords())
             {
                 for layer_entry in layer_entries {
+                    if !visible_layers.contains(layer_entry.style_layer.id.as_str()) {
+                        continue;
+                    }
+
                     // Draw tile
                     translucent_phase.add(TranslucentItem {
                         draw_function: Box::new(DrawState::<TranslucentItem, DrawSymbols>::new()),
@@ -2,11 +2,14 @@ use crate::{
     render::{
         eventually::{Eventually, Eventually::Initialized},
         render_phase::{PhaseItem, RenderCommand, RenderCommandResult, TranslucentItem},
-        resource::TrackedRenderPass,
+        resource::{ImageAtlasTexture, TrackedRenderPass},
         tile_view_pattern::WgpuTileViewPattern,
         INDEX_FORMAT,
     },
-    sdf::{resource::GlyphTexture, SymbolBufferPool, SymbolPipeline},
+    sdf::{
+        resource::{GlyphTexture, IconResources},
+        SymbolBufferPool, SymbolPipeline,
+    },
     tcs::world::World,
 };
 
@@ -102,12 +105,12 @@ impl RenderCommand<TranslucentItem> for DrawSymbol {
                 .metadata()
                 .slice(entry.layer_metadata_buffer_range()),
         );
-        //pass.set_vertex_buffer(
-        //    3,
-        //    symbol_buffer_pool
-        //        .feature_metadata()
-        //        .slice(entry.feature_metadata_buffer_range()),
-        //);
+        pass.set_vertex_buffer(
+            3,
+            symbol_buffer_pool
+                .feature_metadata()
+                .slice(entry.feature_metadata_buffer_range()),
+        );
 
         pass.draw_indexed(entry.indices_range(), 0, 0..1);
         RenderCommandResult::Success
@@ -115,3 +118,67 @@ impl RenderCommand<TranslucentItem> for DrawSymbol {
 }
 
 pub type DrawSymbols = (SetSymbolPipeline, DrawSymbol);
+
+pub struct SetIconPipeline;
+impl<P: PhaseItem> RenderCommand<P> for SetIconPipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &P,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some((Initialized(icon_resources), Initialized(ImageAtlasTexture { bind_group, .. }))) =
+            world
+                .resources
+                .query::<(&Eventually<IconResources>, &Eventually<ImageAtlasTexture>)>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.set_render_pipeline(icon_resources.pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawIcon;
+impl RenderCommand<TranslucentItem> for DrawIcon {
+    fn render<'w>(
+        world: &'w World,
+        item: &TranslucentItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some((Initialized(icon_resources), Initialized(tile_view_pattern))) = world
+            .resources
+            .query::<(&Eventually<IconResources>, &Eventually<WgpuTileViewPattern>)>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let Some(buffers) = icon_resources.get_buffers(&item.tile.coords, &item.style_layer) else {
+            return RenderCommandResult::Failure;
+        };
+
+        let source_shape = &item.source_shape;
+
+        // Uses stencil value of requested tile and the shape of the requested tile
+        let reference = source_shape.coords().stencil_reference_value_3d() as u32;
+        pass.set_stencil_reference(reference);
+
+        pass.set_index_buffer(buffers.indices.slice(..), INDEX_FORMAT);
+        pass.set_vertex_buffer(0, buffers.vertices.slice(..));
+        let tile_view_pattern_buffer = source_shape
+            .buffer_range()
+            .expect("tile_view_pattern needs to be uploaded first"); // FIXME tcs
+        pass.set_vertex_buffer(
+            1,
+            tile_view_pattern.buffer().slice(tile_view_pattern_buffer),
+        );
+        pass.set_vertex_buffer(2, buffers.layer_metadata.slice(..));
+        pass.set_vertex_buffer(3, buffers.feature_metadata.slice(..));
+        pass.draw_indexed(0..buffers.num_indices, 0, 0..1);
+
+        RenderCommandResult::Success
+    }
+}
+
+pub type DrawIcons = (SetIconPipeline, DrawIcon);
@@ -0,0 +1,75 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{coords::WorldTileCoords, euclid::Box2D, legacy::TileSpace};
+
+/// The icon of a [`Feature`](crate::sdf::Feature) as it was laid out for drawing.
+pub struct PlacedIcon {
+    /// Range of the vertices of the icon within the vertex buffer.
+    pub vertices: Range<usize>,
+    /// Bounds of the unrotated icon relative to the anchor in pixels.
+    pub bbox: Box2D<f64, TileSpace>,
+    /// Clockwise rotation of the icon in degrees.
+    pub rotate: f64,
+}
+
+/// The GPU buffers of the icons of a symbol style layer within a tile.
+pub struct IconBuffers {
+    pub vertices: wgpu::Buffer,
+    pub indices: wgpu::Buffer,
+    pub layer_metadata: wgpu::Buffer,
+    /// The opacity of each vertex, which hides icons that collide with other symbols.
+    pub feature_metadata: wgpu::Buffer,
+    pub num_indices: u32,
+    pub num_vertices: usize,
+    /// The icon of each feature of the layer. `None` for features without an icon.
+    pub icons: Vec<Option<PlacedIcon>>,
+    /// The zoom level at which the paint properties were evaluated. `None` if none of them
+    /// depends on the zoom level.
+    pub evaluated_zoom: Option<f32>,
+}
+
+/// Holds the resources necessary for drawing icons such as the
+/// * pipeline
+/// * buffers of each tile
+pub struct IconResources {
+    pipeline: wgpu::RenderPipeline,
+    /// The buffers of each tile, keyed by the id of the style layer.
+    buffers: HashMap<WorldTileCoords, HashMap<String, IconBuffers>>,
+}
+
+impl IconResources {
+    pub fn new(pipeline: wgpu::RenderPipeline) -> Self {
+        Self {
+            pipeline,
+            buffers: Default::default(),
+        }
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn get_buffers(
+        &self,
+        coords: &WorldTileCoords,
+        style_layer_id: &str,
+    ) -> Option<&IconBuffers> {
+        self.buffers.get(coords)?.get(style_layer_id)
+    }
+
+    pub fn insert_buffers(
+        &mut self,
+        coords: WorldTileCoords,
+        style_layer_id: &str,
+        buffers: IconBuffers,
+    ) {
+        self.buffers
+            .entry(coords)
+            .or_default()
+            .insert(style_layer_id.to_string(), buffers);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+    }
+}
@@ -1,2 +1,4 @@
 pub use glyph_texture::*;
+pub use icon::*;
 mod glyph_texture;
+mod icon;
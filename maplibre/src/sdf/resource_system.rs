@@ -10,7 +10,11 @@ use crate::{
         shaders::Shader,
         RenderResources, Renderer,
     },
-    sdf::{resource::GlyphTexture, text::GlyphSet, SymbolBufferPool, SymbolPipeline},
+    sdf::{
+        resource::{GlyphTexture, IconResources},
+        text::GlyphSet,
+        SymbolBufferPool, SymbolPipeline,
+    },
     tcs::system::{SystemError, SystemResult},
     vector::resource::BufferPool,
 };
@@ -34,11 +38,13 @@ pub fn resource_system(
         symbol_pipeline,
         glyph_texture_sampler,
         glyph_texture_bind_group,
+        icon_resources,
     )) = world.resources.query_mut::<(
         &mut Eventually<SymbolBufferPool>,
         &mut Eventually<SymbolPipeline>,
         &mut Eventually<(wgpu::Texture, wgpu::Sampler)>,
         &mut Eventually<GlyphTexture>,
+        &mut Eventually<IconResources>,
     )>()
     else {
         return Err(SystemError::Dependencies);
@@ -56,7 +62,7 @@ pub fn resource_system(
             *settings,
             tile_shader.describe_vertex(),
             tile_shader.describe_fragment(),
-            false,
+            true, // The translucent pass has a depth stencil attachment
             false,
             true, // TODO ignore tile mask
             false,
@@ -109,5 +115,30 @@ pub fn resource_system(
 
         SymbolPipeline(pipeline)
     });
+
+    icon_resources.initialize(|| {
+        let shader = shaders::IconShader {
+            format: surface.surface_format(),
+        };
+
+        IconResources::new(
+            TilePipeline::new(
+                "icon_pipeline".into(),
+                *settings,
+                shader.describe_vertex(),
+                shader.describe_fragment(),
+                true,
+                false,
+                true, // Like texts, icons are not clipped by the tile mask
+                false,
+                surface.is_multisampling_supported(settings.msaa),
+                false,
+                false,
+            )
+            .with_texture_bind_groups(1)
+            .describe_render_pipeline()
+            .initialize(device),
+        )
+    });
     Ok(())
 }
@@ -268,6 +268,8 @@ impl<I: std::ops::Add + From<lyon::tessellation::VertexId> + MaxIndex> FeaturePr
                 indices: start..end,
                 text_anchor: origin.cast(),
                 str: text,
+                icon: None,
+                properties: Default::default(),
             });
 
             self.current_origin = None;
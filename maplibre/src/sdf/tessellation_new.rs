@@ -15,11 +15,12 @@ use widestring::U16String;
 use crate::{
     euclid::{Rect, Size2D},
     legacy::{
-        bidi::{apply_arabic_shaping, Char16},
+        bidi::{apply_arabic_shaping, BiDi, Char16},
         buckets::symbol_bucket::SymbolBucketBuffer,
         font_stack::FontStackHasher,
         geometry_tile_data::{GeometryCoordinates, SymbolGeometryTileLayer},
         glyph::{Glyph, GlyphDependencies, GlyphMap, GlyphMetrics, Glyphs},
+        glyph::{Shaping, WritingModeType},
         glyph_atlas::{GlyphPosition, GlyphPositionMap, GlyphPositions},
         image::ImageMap,
         image_atlas::ImagePositions,
@@ -28,39 +29,68 @@ use crate::{
             symbol_feature::{SymbolGeometryTileFeature, VectorGeometryTileFeature},
             symbol_layout::{FeatureIndex, LayerProperties, SymbolLayer, SymbolLayout},
         },
-        style_types::SymbolLayoutProperties_Unevaluated,
+        shaping::get_shaping,
+        style_types::{
+            DataDrivenLayoutProperty, LayoutProperty, SymbolLayoutProperties_Unevaluated,
+            TextAnchor, TextJustify, TextLetterSpacing, TextLineHeight, TextMaxWidth, TextOffset,
+            TextSize,
+        },
         tagged_string::TaggedString,
+        util::constants::ONE_EM,
         CanonicalTileID, MapMode, OverscaledTileID, TileSpace,
     },
     render::shaders::ShaderSymbolVertexNew,
-    sdf::{tessellation::IndexDataType, text::GlyphSet, Feature},
+    sdf::{tessellation::IndexDataType, text::GlyphSet, Feature, SymbolIcon},
+    style::{
+        expression::{EvaluationContext, FeatureProperties, FromValue, Value},
+        layer::{StyleProperty, SymbolPaint},
+    },
 };
 
 type GeoResult<T> = geozero::error::Result<T>;
 
+/// A symbol which is collected while processing the features of a layer.
+struct CollectedSymbol {
+    text: Option<String>,
+    icon: Option<SymbolIcon>,
+    point: (f64, f64),
+    properties: FeatureProperties,
+}
+
 /// Build tessellations with vectors.
 pub struct TextTessellatorNew {
     geo_writer: GeoWriter,
 
     // configuration
-    text_field: String,
+    text_field: Option<StyleProperty<String>>,
+    paint: SymbolPaint,
+    zoom: f64,
 
     // output
     pub quad_buffer: VertexBuffers<ShaderSymbolVertexNew, IndexDataType>,
     pub features: Vec<Feature>,
 
     // collected feature data from tile processing
-    collected_features: Vec<(String, f64, f64)>,
+    collected_symbols: Vec<CollectedSymbol>,
 
     // iteration variables
     current_index: usize,
-    current_text: Option<String>,
+    current_properties: FeatureProperties,
     current_origin: Option<Box2D<f32, TileSpace>>,
     current_point: Option<(f64, f64)>,
 }
 
 impl TextTessellatorNew {
     pub fn finish(&mut self) {
+        // Bare GeoJSON geometries are not wrapped in a feature
+        if self.current_point.is_some() {
+            let _ = self.feature_end(0);
+        }
+
+        if self.collected_symbols.is_empty() {
+            return;
+        }
+
         let data = include_bytes!("../../../data/0-255.pbf");
         let glyphs = GlyphSet::try_from(data.as_slice()).unwrap();
 
@@ -69,63 +99,6 @@ impl TextTessellatorNew {
             "Arial Unicode MS Regular".to_string(),
         ];
 
-        let layer_name = "layer".to_string();
-
-        let mut glyph_dependencies = GlyphDependencies::new();
-
-        let tile_id = OverscaledTileID {
-            canonical: CanonicalTileID { x: 0, y: 0, z: 0 },
-            overscaled_z: 0,
-        };
-        let mut parameters = BucketParameters {
-            tile_id: tile_id,
-            mode: MapMode::Continuous,
-            pixel_ratio: 1.0,
-            layer_type: LayerTypeInfo,
-        };
-
-        // Build SymbolGeometryTileFeatures from the tile data collected during processing.
-        // Pre-populate formatted_text so symbol_layout uses actual names instead of defaults.
-        let features: Vec<SymbolGeometryTileFeature> = self
-            .collected_features
-            .iter()
-            .map(|(text, x, y)| {
-                let geometry = vec![GeometryCoordinates(vec![Point2D::new(
-                    *x as i16, *y as i16,
-                )])];
-                let mut feature =
-                    SymbolGeometryTileFeature::new(Box::new(VectorGeometryTileFeature {
-                        geometry,
-                    }));
-                let mut tagged_string = TaggedString::default();
-                tagged_string.add_text_section(
-                    &apply_arabic_shaping(&U16String::from(text.as_str())),
-                    1.0,
-                    font_stack.clone(),
-                    None,
-                );
-                feature.formatted_text = Some(tagged_string);
-                feature
-            })
-            .collect();
-
-        if features.is_empty() {
-            return;
-        }
-
-        let layer_data = SymbolGeometryTileLayer {
-            name: layer_name.clone(),
-            features,
-        };
-        let layer_properties = vec![LayerProperties {
-            id: layer_name.clone(),
-            layer: SymbolLayer {
-                layout: SymbolLayoutProperties_Unevaluated,
-            },
-        }];
-
-        let image_positions = ImagePositions::new();
-
         let glyph_map =
             GlyphPositionMap::from_iter(glyphs.glyphs.iter().map(|(unicode_point, glyph)| {
                 (
@@ -175,6 +148,88 @@ impl TextTessellatorNew {
             })),
         )]);
 
+        // Each symbol is laid out on its own, such that the range of the indices of its glyphs is
+        // known for the collision detection.
+        for symbol in std::mem::take(&mut self.collected_symbols) {
+            let (x, y) = symbol.point;
+            let mut indices = 0..0;
+            let mut bbox = Box2D::zero();
+
+            if let Some(text) = &symbol.text {
+                let mut tagged_string = TaggedString::default();
+                tagged_string.add_text_section(
+                    &apply_arabic_shaping(&U16String::from(text.as_str())),
+                    1.0,
+                    font_stack.clone(),
+                    None,
+                );
+
+                let start = self.quad_buffer.indices.len();
+                self.layout_text(&tagged_string, x, y, &glyphs, &glyph_positions);
+                indices = start..self.quad_buffer.indices.len();
+
+                let shaping = shape_text(&tagged_string, &glyphs, &glyph_positions);
+                bbox = Box2D::new(
+                    Point2D::new(shaping.left as f32, shaping.top as f32),
+                    Point2D::new(shaping.right as f32, shaping.bottom as f32),
+                );
+            }
+
+            self.features.push(Feature {
+                bbox,
+                indices,
+                text_anchor: Point2D::new(x as f32, y as f32),
+                str: symbol.text.unwrap_or_default(),
+                icon: symbol.icon,
+                properties: symbol.properties,
+            });
+        }
+    }
+
+    /// Lays out the glyphs of a point label at `x` and `y` and appends their quads to the
+    /// [`Self::quad_buffer`].
+    fn layout_text(
+        &mut self,
+        tagged_string: &TaggedString,
+        x: f64,
+        y: f64,
+        glyphs: &GlyphMap,
+        glyph_positions: &GlyphPositions,
+    ) {
+        let layer_name = "layer".to_string();
+
+        let mut glyph_dependencies = GlyphDependencies::new();
+
+        let tile_id = OverscaledTileID {
+            canonical: CanonicalTileID { x: 0, y: 0, z: 0 },
+            overscaled_z: 0,
+        };
+        let parameters = BucketParameters {
+            tile_id,
+            mode: MapMode::Continuous,
+            pixel_ratio: 1.0,
+            layer_type: LayerTypeInfo,
+        };
+
+        // Pre-populate formatted_text so symbol_layout uses actual names instead of defaults.
+        let geometry = vec![GeometryCoordinates(vec![Point2D::new(x as i16, y as i16)])];
+        let mut feature =
+            SymbolGeometryTileFeature::new(Box::new(VectorGeometryTileFeature { geometry }));
+        feature.formatted_text = Some(tagged_string.clone());
+
+        let layer_data = SymbolGeometryTileLayer {
+            name: layer_name.clone(),
+            features: vec![feature],
+        };
+        let layer_properties = vec![LayerProperties {
+            id: layer_name.clone(),
+            layer: SymbolLayer {
+                layout: SymbolLayoutProperties_Unevaluated,
+            },
+        }];
+
+        let image_positions = ImagePositions::new();
+
         let mut layout = SymbolLayout::new(
             &parameters,
             &layer_properties,
@@ -189,12 +244,7 @@ impl TextTessellatorNew {
         .unwrap();
 
         let empty_image_map = ImageMap::new();
-        layout.prepare_symbols(
-            &glyphs,
-            &glyph_positions,
-            &empty_image_map,
-            &image_positions,
-        );
+        layout.prepare_symbols(glyphs, glyph_positions, &empty_image_map, &image_positions);
 
         let mut output = HashMap::new();
         layout.create_bucket(
@@ -206,48 +256,96 @@ impl TextTessellatorNew {
             &tile_id.canonical,
         );
 
-        let new_buffer = output.remove(&layer_name).unwrap();
+        let Some(new_buffer) = output.remove(&layer_name) else {
+            return;
+        };
 
-        let mut buffer = VertexBuffers::new();
-        let text_buffer = new_buffer.bucket.text;
         let SymbolBucketBuffer {
             shared_vertices,
             triangles,
             ..
-        } = text_buffer;
-        buffer.vertices = shared_vertices
-            .iter()
-            .map(|v| ShaderSymbolVertexNew::new(v))
-            .collect();
-        buffer.indices = triangles.indices.iter().map(|i| *i as u32).collect();
-
-        self.quad_buffer = buffer;
+        } = new_buffer.bucket.text;
+        let first_vertex = self.quad_buffer.vertices.len() as IndexDataType;
+        self.quad_buffer
+            .vertices
+            .extend(shared_vertices.iter().map(ShaderSymbolVertexNew::new));
+        self.quad_buffer.indices.extend(
+            triangles
+                .indices
+                .iter()
+                .map(|i| first_vertex + *i as IndexDataType),
+        );
     }
 }
 
-impl TextTessellatorNew {
-    pub fn new(text_field: String) -> Self {
-        Self {
-            text_field,
-            ..Default::default()
-        }
-    }
+/// Shapes a point label with the default layout properties, like the [`SymbolLayout`] does.
+fn shape_text(
+    tagged_string: &TaggedString,
+    glyphs: &GlyphMap,
+    glyph_positions: &GlyphPositions,
+) -> Shaping {
+    let text_offset = TextOffset::default_value();
+    get_shaping(
+        tagged_string,
+        TextMaxWidth::default_value() * ONE_EM,
+        TextLineHeight::default_value() * ONE_EM,
+        TextAnchor::default_value(),
+        TextJustify::default_value(),
+        TextLetterSpacing::default_value() * ONE_EM,
+        &[text_offset[0] * ONE_EM, text_offset[1] * ONE_EM],
+        WritingModeType::Horizontal,
+        &BiDi,
+        glyphs,
+        glyph_positions,
+        &ImagePositions::new(),
+        TextSize::default_value(),
+        TextSize::default_value(),
+        false,
+    )
 }
 
-impl Default for TextTessellatorNew {
-    fn default() -> Self {
+impl TextTessellatorNew {
+    /// Creates a tessellator which evaluates the layout properties of `paint` at the zoom level
+    /// of the tile.
+    pub fn new(paint: &SymbolPaint, zoom: f64) -> Self {
         Self {
             geo_writer: Default::default(),
-            text_field: "name".to_string(),
+            text_field: paint.text_field_or_default(),
+            paint: paint.clone(),
+            zoom,
             quad_buffer: VertexBuffers::new(),
             features: vec![],
-            collected_features: vec![],
+            collected_symbols: vec![],
             current_index: 0,
-            current_text: None,
+            current_properties: Default::default(),
             current_origin: None,
             current_point: None,
         }
     }
+
+    /// Evaluates the icon of the current feature. Returns `None` if the feature has no icon.
+    fn evaluate_icon(&self, context: &EvaluationContext) -> Option<SymbolIcon> {
+        let image = self
+            .paint
+            .icon_image
+            .as_ref()?
+            .evaluate(context)
+            .filter(|image| !image.is_empty())?;
+        Some(SymbolIcon {
+            image,
+            size: evaluate(&self.paint.icon_size, context).unwrap_or(1.0),
+            rotate: evaluate(&self.paint.icon_rotate, context).unwrap_or(0.0),
+            anchor: evaluate(&self.paint.icon_anchor, context).unwrap_or_default(),
+            offset: evaluate(&self.paint.icon_offset, context).unwrap_or([0.0, 0.0]),
+        })
+    }
+}
+
+fn evaluate<T: FromValue + Clone>(
+    property: &Option<StyleProperty<T>>,
+    context: &EvaluationContext,
+) -> Option<T> {
+    property.as_ref()?.evaluate(context)
 }
 
 impl GeomProcessor for TextTessellatorNew {
@@ -297,15 +395,9 @@ impl PropertyProcessor for TextTessellatorNew {
         name: &str,
         value: &ColumnValue,
     ) -> geozero::error::Result<bool> {
-        if name == self.text_field {
-            match value {
-                ColumnValue::String(str) => {
-                    self.current_text = Some(str.to_string());
-                }
-                _ => {}
-            }
-        }
-        Ok(true)
+        self.current_properties
+            .insert(name.to_string(), Value::from(value));
+        Ok(false)
     }
 }
 
@@ -313,12 +405,28 @@ impl FeatureProcessor for TextTessellatorNew {
     fn feature_end(&mut self, _idx: u64) -> geozero::error::Result<()> {
         let geometry = self.geo_writer.take_geometry();
 
-        // Collect features that have both a name and a point geometry
-        if let (Some(text), Some((x, y))) = (self.current_text.take(), self.current_point.take()) {
-            self.collected_features.push((text, x, y));
-        } else {
-            self.current_text = None;
-            self.current_point = None;
+        let context = EvaluationContext::default()
+            .with_zoom(self.zoom)
+            .with_properties(&self.current_properties);
+        let text = self
+            .text_field
+            .as_ref()
+            .and_then(|text_field| text_field.evaluate(&context))
+            .filter(|text| !text.is_empty());
+        let icon = self.evaluate_icon(&context);
+        let properties = std::mem::take(&mut self.current_properties);
+
+        // Collect features that have a text or an icon and a point geometry
+        match self.current_point.take() {
+            Some(point) if text.is_some() || icon.is_some() => {
+                self.collected_symbols.push(CollectedSymbol {
+                    text,
+                    icon,
+                    point,
+                    properties,
+                });
+            }
+            _ => {}
         }
 
         match geometry {
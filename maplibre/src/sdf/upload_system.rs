@@ -1,19 +1,39 @@
 //! Uploads data to the GPU which is needed for rendering.
 
-use std::iter;
+use csscolorparser::Color;
+use wgpu::util::DeviceExt;
 
 use crate::{
     context::MapContext,
     coords::ViewRegion,
+    euclid::{Box2D, Point2D, Rect, Size2D},
+    legacy::{
+        glyph::Shaping,
+        image_atlas::ImagePosition as LegacyImagePosition,
+        layout::symbol_instance::SymbolContent,
+        quads::get_icon_quads,
+        shaping::PositionedIcon,
+        style_types::{IconTextFitType, SymbolAnchorType},
+        util::constants::ONE_EM,
+    },
     render::{
         eventually::{Eventually, Eventually::Initialized},
-        shaders::{SDFShaderFeatureMetadata, ShaderLayerMetadata},
+        image_atlas::{ImageAtlas, ImagePosition, IMAGE_ATLAS_SIZE, PADDING},
+        resource::ImageAtlasTexture,
+        shaders::{SDFShaderFeatureMetadata, ShaderIconVertex, ShaderLayerMetadata, Vec4f32},
         tile_view_pattern::DEFAULT_TILE_SIZE,
         view_state::ViewStatePadding,
         Renderer,
     },
-    sdf::{SymbolBufferPool, SymbolLayerData, SymbolLayersDataComponent},
-    style::{layer::LayerPaint, Style},
+    sdf::{
+        tessellation::IndexDataType, Feature, IconBuffers, IconResources, PlacedIcon,
+        SymbolBufferPool, SymbolLayerData, SymbolLayersDataComponent,
+    },
+    style::{
+        expression::EvaluationContext,
+        layer::{IconTextFit, LayerPaint, StyleLayer, StyleProperty, SymbolAnchor, SymbolPaint},
+        Style,
+    },
     tcs::{
         system::{SystemError, SystemResult},
         tiles::Tiles,
@@ -25,24 +45,43 @@ pub fn upload_system(
         world,
         style,
         view_state,
-        renderer: Renderer { queue, .. },
+        renderer: Renderer { device, queue, .. },
         ..
     }: &mut MapContext,
 ) -> SystemResult {
-    let Some(Initialized(symbol_buffer_pool)) = world
-        .resources
-        .query_mut::<&mut Eventually<SymbolBufferPool>>()
+    let zoom = view_state.zoom().level();
+
+    let zoom_changed = {
+        let evaluated_zoom = world.resources.get_or_init_mut::<EvaluatedZoom>();
+        let changed = evaluated_zoom.0 != Some(zoom);
+        evaluated_zoom.0 = Some(zoom);
+        changed
+    };
+
+    let Some((
+        Initialized(symbol_buffer_pool),
+        Initialized(icon_resources),
+        Initialized(image_atlas_texture),
+        image_atlas,
+    )) = world.resources.query_mut::<(
+        &mut Eventually<SymbolBufferPool>,
+        &mut Eventually<IconResources>,
+        &mut Eventually<ImageAtlasTexture>,
+        &mut ImageAtlas,
+    )>()
     else {
         return Err(SystemError::Dependencies);
     };
 
+    if zoom_changed {
+        update_zoom_dependent_metadata(symbol_buffer_pool, queue, style, zoom);
+    }
+
     let view_region = view_state.create_view_region(
         view_state.zoom().zoom_level(DEFAULT_TILE_SIZE),
         ViewStatePadding::Loose,
     );
 
-    let zoom = view_state.zoom().level();
-
     if let Some(view_region) = &view_region {
         upload_symbol_layer(
             symbol_buffer_pool,
@@ -52,6 +91,20 @@ pub fn upload_system(
             view_region,
             zoom,
         );
+        upload_icons(
+            icon_resources,
+            image_atlas,
+            device,
+            &world.tiles,
+            style,
+            view_region,
+            zoom,
+        );
+    }
+
+    // Icons are added to the atlas while laying them out
+    if let Some(data) = image_atlas.take_changes() {
+        image_atlas_texture.upload(queue, data);
     }
 
     Ok(())
@@ -79,73 +132,439 @@ fn upload_symbol_layer(
         let available_layers = vector_layers
             .layers
             .iter()
-            .filter(|data| !loaded_layers.contains(data.source_layer.as_str()))
+            .filter(|data| !loaded_layers.contains(data.style_layer_id.as_str()))
             .collect::<Vec<_>>();
 
         for style_layer in &style.layers {
-            let layer_id = &style_layer.id;
-            let source_layer = match style_layer.source_layer.as_ref() {
-                Some(layer) => layer,
-                None => {
-                    log::trace!("style layer {layer_id} does not have a source layer");
-                    continue;
-                }
-            };
-
             let Some(SymbolLayerData {
                 coords,
-                features,
                 //buffer,
                 new_buffer: buffer,
+                features,
                 ..
             }) = available_layers
                 .iter()
-                .find(|layer| source_layer.as_str() == layer.source_layer)
+                .find(|layer| style_layer.id == layer.style_layer_id)
             else {
                 continue;
             };
 
-            // Per-vertex opacity metadata. Default to 1.0 (visible) so text renders
-            // even when collision detection features are not yet populated.
-            let metadata_count = if features.is_empty() {
-                buffer.buffer.vertices.len()
-            } else {
-                features
-                    .last()
-                    .map(|feature| feature.indices.end)
-                    .unwrap_or_default()
-            };
-            let feature_metadata = iter::repeat(SDFShaderFeatureMetadata { opacity: 1.0 })
-                .take(metadata_count)
-                .collect::<Vec<_>>();
+            // Per-vertex paint metadata. Labels are visible so that text renders even when
+            // collision detection did not run yet.
+            let mut feature_metadata =
+                vec![SDFShaderFeatureMetadata::visible(); buffer.buffer.vertices.len()];
+            if let Some(LayerPaint::Symbol(paint)) = &style_layer.paint {
+                for feature in features {
+                    let metadata = text_feature_metadata(paint, feature, zoom);
+                    for index in feature.indices.clone() {
+                        feature_metadata[buffer.buffer.indices[index] as usize] = metadata;
+                    }
+                }
+            }
 
             // FIXME avoid uploading empty indices
             if buffer.buffer.indices.is_empty() {
                 continue;
             }
 
-            // Extract text-size from style (default 16.0 per MapLibre GL JS spec)
-            let text_size = match &style_layer.paint {
-                Some(LayerPaint::Symbol(paint)) => paint
-                    .text_size
-                    .as_ref()
-                    .map(|s| s.evaluate_at_zoom(zoom))
-                    .unwrap_or(16.0),
-                _ => 16.0,
-            };
-
             log::debug!("Allocating geometry at {coords}");
             symbol_buffer_pool.allocate_layer_geometry(
                 queue,
                 *coords,
                 style_layer.clone(),
                 buffer,
-                ShaderLayerMetadata {
-                    z_index: style_layer.index as f32,
-                    line_width: text_size, // repurposed as text_size for SDF pipeline
-                },
+                layer_metadata(style_layer, zoom),
                 &feature_metadata,
             );
         }
     }
 }
+
+/// The zoom level at which the metadata of the uploaded layers was evaluated.
+#[derive(Default)]
+struct EvaluatedZoom(Option<f32>);
+
+/// Re-evaluates the zoom-dependent text sizes of all uploaded layers.
+fn update_zoom_dependent_metadata(
+    symbol_buffer_pool: &SymbolBufferPool,
+    queue: &wgpu::Queue,
+    style: &Style,
+    zoom: f32,
+) {
+    for entry in symbol_buffer_pool.index().iter().flatten() {
+        let Some(style_layer) = style
+            .layers
+            .iter()
+            .find(|layer| layer.id == entry.style_layer.id)
+        else {
+            continue;
+        };
+
+        if let Some(LayerPaint::Symbol(SymbolPaint {
+            text_size: Some(text_size),
+            ..
+        })) = &style_layer.paint
+        {
+            if !text_size.is_zoom_constant() {
+                symbol_buffer_pool.update_layer_metadata(
+                    queue,
+                    entry,
+                    layer_metadata(style_layer, zoom),
+                );
+            }
+        }
+    }
+}
+
+/// Evaluates the `text-size` of the layer at `zoom`. The default is 16 like in MapLibre GL JS.
+pub(crate) fn text_size(paint: &SymbolPaint, zoom: f32) -> f32 {
+    paint
+        .text_size
+        .as_ref()
+        .and_then(|s| s.evaluate_at_zoom(zoom))
+        .unwrap_or(16.0)
+}
+
+/// Evaluates the paint properties of the text of a feature. The opacity is the `text-opacity`.
+pub(crate) fn text_feature_metadata(
+    paint: &SymbolPaint,
+    feature: &Feature,
+    zoom: f32,
+) -> SDFShaderFeatureMetadata {
+    let context = EvaluationContext::default()
+        .with_zoom(zoom as f64)
+        .with_properties(&feature.properties);
+    let evaluate = |property: &Option<StyleProperty<f32>>, default: f32| {
+        property
+            .as_ref()
+            .and_then(|property| property.evaluate(&context))
+            .unwrap_or(default)
+    };
+
+    SDFShaderFeatureMetadata {
+        opacity: evaluate(&paint.text_opacity, 1.0),
+        color: evaluate_color(&paint.text_color, &context, Color::new(0.0, 0.0, 0.0, 1.0)),
+        halo_color: evaluate_color(
+            &paint.text_halo_color,
+            &context,
+            Color::new(0.0, 0.0, 0.0, 0.0),
+        ),
+        halo_width: evaluate(&paint.text_halo_width, 0.0),
+        halo_blur: evaluate(&paint.text_halo_blur, 0.0),
+    }
+}
+
+/// Evaluates a color and premultiplies it with its alpha.
+fn evaluate_color(
+    property: &Option<StyleProperty<Color>>,
+    context: &EvaluationContext,
+    default: Color,
+) -> Vec4f32 {
+    let color = property
+        .as_ref()
+        .and_then(|property| property.evaluate(context))
+        .unwrap_or(default);
+    let alpha = color.a as f32;
+    [
+        color.r as f32 * alpha,
+        color.g as f32 * alpha,
+        color.b as f32 * alpha,
+        alpha,
+    ]
+}
+
+fn layer_metadata(style_layer: &StyleLayer, zoom: f32) -> ShaderLayerMetadata {
+    let text_size = match &style_layer.paint {
+        Some(LayerPaint::Symbol(paint)) => text_size(paint, zoom),
+        _ => 16.0,
+    };
+
+    ShaderLayerMetadata {
+        z_index: style_layer.index as f32,
+        line_width: text_size, // repurposed as text_size for SDF pipeline
+        ..Default::default()
+    }
+}
+
+/// Lays out the icons of all symbol layers which are in view and uploads them. Layers are laid out
+/// again if their paint properties depend on the zoom level and it changed.
+fn upload_icons(
+    icon_resources: &mut IconResources,
+    image_atlas: &mut ImageAtlas,
+    device: &wgpu::Device,
+    tiles: &Tiles,
+    style: &Style,
+    view_region: &ViewRegion,
+    zoom: f32,
+) {
+    for coords in view_region.iter() {
+        let Some(symbol_layers) = tiles.query::<&SymbolLayersDataComponent>(coords) else {
+            continue;
+        };
+
+        for style_layer in &style.layers {
+            let Some(LayerPaint::Symbol(paint)) = &style_layer.paint else {
+                continue;
+            };
+            if paint.icon_image.is_none() {
+                continue;
+            }
+
+            if icon_resources
+                .get_buffers(&coords, &style_layer.id)
+                .is_some_and(|buffers| buffers.evaluated_zoom.is_none_or(|z| z == zoom))
+            {
+                continue;
+            }
+
+            let Some(layer) = symbol_layers
+                .layers
+                .iter()
+                .find(|layer| layer.style_layer_id == style_layer.id)
+            else {
+                continue;
+            };
+
+            let (vertices, indices, icons) =
+                icon_vertices(paint, &layer.features, image_atlas, zoom);
+            if indices.is_empty() {
+                continue;
+            }
+
+            let feature_metadata = vec![SDFShaderFeatureMetadata::visible(); vertices.len()];
+            let is_zoom_constant = paint
+                .icon_color
+                .as_ref()
+                .is_none_or(StyleProperty::is_zoom_constant);
+
+            log::debug!("Allocating icons at {coords}");
+            icon_resources.insert_buffers(
+                coords,
+                &style_layer.id,
+                IconBuffers {
+                    vertices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Icon Vertex Buffer"),
+                        contents: bytemuck::cast_slice(&vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                    indices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Icon Index Buffer"),
+                        contents: bytemuck::cast_slice(&indices),
+                        usage: wgpu::BufferUsages::INDEX,
+                    }),
+                    layer_metadata: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Icon Layer Metadata Buffer"),
+                        contents: bytemuck::cast_slice(&[layer_metadata(style_layer, zoom)]),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                    feature_metadata: device.create_buffer_init(
+                        &wgpu::util::BufferInitDescriptor {
+                            label: Some("Icon Feature Metadata Buffer"),
+                            contents: bytemuck::cast_slice(&feature_metadata),
+                            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                        },
+                    ),
+                    num_indices: indices.len() as u32,
+                    num_vertices: vertices.len(),
+                    icons,
+                    evaluated_zoom: (!is_zoom_constant).then_some(zoom),
+                },
+            );
+        }
+    }
+}
+
+/// Expands the icon of every feature into quads with the image of the sprite. The paint
+/// properties are evaluated for each feature at `zoom`.
+fn icon_vertices(
+    paint: &SymbolPaint,
+    features: &[Feature],
+    image_atlas: &mut ImageAtlas,
+    zoom: f32,
+) -> (
+    Vec<ShaderIconVertex>,
+    Vec<IndexDataType>,
+    Vec<Option<PlacedIcon>>,
+) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut icons = Vec::with_capacity(features.len());
+
+    let text_fit = paint.icon_text_fit.unwrap_or_default();
+    let text_fit_padding = paint
+        .icon_text_fit_padding
+        .unwrap_or_default()
+        .map(|padding| padding as f64);
+    let font_scale = text_size(paint, zoom) as f64 / ONE_EM;
+
+    for feature in features {
+        let Some((icon, position)) = feature
+            .icon
+            .as_ref()
+            .and_then(|icon| Some((icon, image_atlas.get_icon(&icon.image)?)))
+        else {
+            icons.push(None);
+            continue;
+        };
+
+        let context = EvaluationContext::default()
+            .with_zoom(zoom as f64)
+            .with_properties(&feature.properties);
+        let color = if position.sdf {
+            evaluate_color(&paint.icon_color, &context, Color::new(0.0, 0.0, 0.0, 1.0))
+        } else {
+            [0.0; 4]
+        };
+
+        let offset = icon.offset.map(|offset| offset as f64);
+        let mut shaped_icon = PositionedIcon::shape_icon(
+            legacy_image_position(&position),
+            &offset,
+            symbol_anchor_type(icon.anchor),
+        );
+
+        // Icons are only fitted to symbols with a text
+        let has_icon_text_fit = text_fit != IconTextFit::None && !feature.indices.is_empty();
+        if has_icon_text_fit {
+            let shaped_text = Shaping {
+                top: feature.bbox.min.y as f64,
+                bottom: feature.bbox.max.y as f64,
+                left: feature.bbox.min.x as f64,
+                right: feature.bbox.max.x as f64,
+                ..Default::default()
+            };
+            shaped_icon.fit_icon_to_text(
+                &shaped_text,
+                icon_text_fit_type(text_fit),
+                &text_fit_padding,
+                &offset,
+                font_scale,
+            );
+        }
+
+        let content = if position.sdf {
+            SymbolContent::IconSDF
+        } else {
+            SymbolContent::IconRGBA
+        };
+        let quads = get_icon_quads(&shaped_icon, icon.rotate as f64, content, has_icon_text_fit);
+
+        let first_vertex = vertices.len();
+        let anchor = [feature.text_anchor.x, feature.text_anchor.y];
+        let size = icon.size as f64;
+        for quad in quads {
+            let first = vertices.len() as IndexDataType;
+            indices.extend([0, 1, 2, 0, 2, 3].map(|offset| first + offset));
+
+            let tex = quad.tex.to_box2d().cast::<f32>() / IMAGE_ATLAS_SIZE as f32;
+            let (px_tl, px_br) = (quad.pixel_offset_tl, quad.pixel_offset_br);
+            let corners = [
+                (quad.tl, [px_tl.x, px_tl.y], [tex.min.x, tex.min.y]),
+                (quad.tr, [px_br.x, px_tl.y], [tex.max.x, tex.min.y]),
+                (quad.br, [px_br.x, px_br.y], [tex.max.x, tex.max.y]),
+                (quad.bl, [px_tl.x, px_br.y], [tex.min.x, tex.max.y]),
+            ];
+            vertices.extend(
+                corners.map(|(corner, pixel_offset, tex_coords)| ShaderIconVertex {
+                    anchor,
+                    offset: [
+                        (corner.x * size + pixel_offset[0]) as f32,
+                        (corner.y * size + pixel_offset[1]) as f32,
+                    ],
+                    tex_coords,
+                    color,
+                    sdf: position.sdf as u8 as f32,
+                    size: icon.size,
+                }),
+            );
+        }
+
+        icons.push(Some(PlacedIcon {
+            vertices: first_vertex..vertices.len(),
+            bbox: Box2D::new(
+                Point2D::new(shaped_icon.left * size, shaped_icon.top * size),
+                Point2D::new(shaped_icon.right * size, shaped_icon.bottom * size),
+            ),
+            rotate: icon.rotate as f64,
+        }));
+    }
+
+    (vertices, indices, icons)
+}
+
+/// Converts the position of an image within the [`ImageAtlas`] into the type of the legacy layout.
+fn legacy_image_position(position: &ImagePosition) -> LegacyImagePosition {
+    let size = IMAGE_ATLAS_SIZE as f32;
+    let tl = position
+        .tl
+        .map(|tl| (tl * size).round() as u16 - PADDING as u16);
+    let br = position
+        .br
+        .map(|br| (br * size).round() as u16 + PADDING as u16);
+    LegacyImagePosition {
+        pixel_ratio: (br[0] - tl[0] - 2 * PADDING as u16) as f64 / position.display_size[0] as f64,
+        padded_rect: Rect::new(
+            Point2D::new(tl[0], tl[1]),
+            Size2D::new(br[0] - tl[0], br[1] - tl[1]),
+        ),
+        version: 0,
+        stretch_x: Vec::new(),
+        stretch_y: Vec::new(),
+        content: None,
+    }
+}
+
+fn symbol_anchor_type(anchor: SymbolAnchor) -> SymbolAnchorType {
+    match anchor {
+        SymbolAnchor::Center => SymbolAnchorType::Center,
+        SymbolAnchor::Left => SymbolAnchorType::Left,
+        SymbolAnchor::Right => SymbolAnchorType::Right,
+        SymbolAnchor::Top => SymbolAnchorType::Top,
+        SymbolAnchor::Bottom => SymbolAnchorType::Bottom,
+        SymbolAnchor::TopLeft => SymbolAnchorType::TopLeft,
+        SymbolAnchor::TopRight => SymbolAnchorType::TopRight,
+        SymbolAnchor::BottomLeft => SymbolAnchorType::BottomLeft,
+        SymbolAnchor::BottomRight => SymbolAnchorType::BottomRight,
+    }
+}
+
+fn icon_text_fit_type(text_fit: IconTextFit) -> IconTextFitType {
+    match text_fit {
+        IconTextFit::None => IconTextFitType::None,
+        IconTextFit::Width => IconTextFitType::Width,
+        IconTextFit::Height => IconTextFitType::Height,
+        IconTextFit::Both => IconTextFitType::Both,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::expression::Value;
+
+    #[test]
+    fn test_text_feature_metadata() {
+        let paint: SymbolPaint = serde_json::from_value(serde_json::json!({
+            "text-color": ["get", "color"],
+            "text-opacity": 0.5,
+            "text-halo-color": "rgba(255, 255, 255, 0.5)",
+            "text-halo-width": ["interpolate", ["linear"], ["zoom"], 0, 1, 10, 3],
+        }))
+        .unwrap();
+        let feature = Feature {
+            bbox: Box2D::zero(),
+            indices: 0..0,
+            text_anchor: Point2D::zero(),
+            str: "Label".to_string(),
+            icon: None,
+            properties: [("color".to_string(), Value::from("red"))].into(),
+        };
+
+        let metadata = text_feature_metadata(&paint, &feature, 5.0);
+        assert_eq!(metadata.opacity, 0.5);
+        assert_eq!(metadata.color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(metadata.halo_color, [0.5, 0.5, 0.5, 0.5]);
+        assert_eq!(metadata.halo_width, 2.0);
+        assert_eq!(metadata.halo_blur, 0.0);
+    }
+}
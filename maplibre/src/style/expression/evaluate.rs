@@ -0,0 +1,808 @@
+//! Evaluation of parsed expressions.
+
+use std::{cmp::Ordering, f64::consts};
+
+use csscolorparser::Color;
+
+use crate::style::expression::{
+    parse::{Builtin, ColorSpace, ComparisonOp, Expr, Interpolation, MatchLabel},
+    value::{format_number, Type, Value},
+    EvaluationContext, EvaluationError,
+};
+
+type EvaluationResult = Result<Value, EvaluationError>;
+
+fn error<T>(message: impl Into<String>) -> Result<T, EvaluationError> {
+    Err(EvaluationError(message.into()))
+}
+
+impl Expr {
+    pub(crate) fn evaluate(&self, context: &EvaluationContext) -> EvaluationResult {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Get { key, object } => {
+                let key = key.evaluate(context)?;
+                let key = key.as_str().unwrap_or_default();
+                match object {
+                    Some(object) => match object.evaluate(context)? {
+                        Value::Object(map) => Ok(map.get(key).cloned().unwrap_or(Value::Null)),
+                        _ => Ok(Value::Null),
+                    },
+                    None => Ok(context.property(key).cloned().unwrap_or(Value::Null)),
+                }
+            }
+            Expr::Has { key, object } => {
+                let key = key.evaluate(context)?;
+                let key = key.as_str().unwrap_or_default();
+                match object {
+                    Some(object) => match object.evaluate(context)? {
+                        Value::Object(map) => Ok(Value::Boolean(map.contains_key(key))),
+                        _ => Ok(Value::Boolean(false)),
+                    },
+                    None => Ok(Value::Boolean(context.property(key).is_some())),
+                }
+            }
+            Expr::Properties => Ok(Value::Object(
+                context
+                    .properties
+                    .map(|properties| {
+                        properties
+                            .iter()
+                            .map(|(key, value)| (key.clone(), value.clone()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            )),
+            Expr::GeometryType => Ok(context
+                .geometry_type
+                .map(|geometry_type| Value::String(geometry_type.as_str().to_string()))
+                .unwrap_or(Value::Null)),
+            Expr::Id => Ok(context.id.clone().unwrap_or(Value::Null)),
+            Expr::Zoom => match context.zoom {
+                Some(zoom) => Ok(Value::Number(zoom)),
+                None => error("The \"zoom\" expression requires a zoom level"),
+            },
+            Expr::LineProgress => match context.line_progress {
+                Some(progress) => Ok(Value::Number(progress)),
+                None => error("The \"line-progress\" expression requires line metrics"),
+            },
+            Expr::HeatmapDensity => match context.heatmap_density {
+                Some(density) => Ok(Value::Number(density)),
+                None => error("The \"heatmap-density\" expression requires a heatmap"),
+            },
+            Expr::Elevation => match context.elevation {
+                Some(elevation) => Ok(Value::Number(elevation)),
+                None => error("The \"elevation\" expression requires a raster-dem source"),
+            },
+            Expr::FeatureState => Ok(Value::Null),
+            Expr::At { index, array } => {
+                let index = index.evaluate(context)?.as_f64().unwrap_or(f64::NAN);
+                let Value::Array(items) = array.evaluate(context)? else {
+                    return error("Expected an array");
+                };
+                if index < 0.0 {
+                    return error(format!("Array index out of bounds: {index} < 0."));
+                }
+                if index >= items.len() as f64 {
+                    return error(format!(
+                        "Array index out of bounds: {index} > {}.",
+                        items.len() as i64 - 1
+                    ));
+                }
+                if index.fract() != 0.0 {
+                    return error(format!(
+                        "Array index must be an integer, but found {index} instead."
+                    ));
+                }
+                Ok(items[index as usize].clone())
+            }
+            Expr::In { needle, haystack } => {
+                let needle = needle.evaluate(context)?;
+                let haystack = haystack.evaluate(context)?;
+                check_searchable(&needle, &haystack)?;
+                Ok(Value::Boolean(match haystack {
+                    Value::String(haystack) => haystack.contains(&needle.to_string()),
+                    Value::Array(items) => items.contains(&needle),
+                    _ => false,
+                }))
+            }
+            Expr::IndexOf {
+                needle,
+                haystack,
+                from_index,
+            } => {
+                let needle = needle.evaluate(context)?;
+                let haystack = haystack.evaluate(context)?;
+                check_searchable(&needle, &haystack)?;
+                let from_index = match from_index {
+                    Some(from_index) => from_index.evaluate(context)?.as_f64().unwrap_or(0.0),
+                    None => 0.0,
+                }
+                .max(0.0) as usize;
+                let position = match haystack {
+                    Value::String(haystack) => {
+                        let needle = needle.to_string();
+                        let chars = haystack.chars().collect::<Vec<_>>();
+                        let needle = needle.chars().collect::<Vec<_>>();
+                        (from_index..=chars.len().saturating_sub(needle.len()))
+                            .find(|&i| chars[i..].starts_with(&needle))
+                    }
+                    Value::Array(items) => items
+                        .iter()
+                        .skip(from_index)
+                        .position(|item| *item == needle)
+                        .map(|i| i + from_index),
+                    _ => None,
+                };
+                Ok(Value::Number(position.map(|i| i as f64).unwrap_or(-1.0)))
+            }
+            Expr::Slice { input, begin, end } => {
+                let input = input.evaluate(context)?;
+                let begin = begin.evaluate(context)?.as_f64().unwrap_or(0.0);
+                let end = match end {
+                    Some(end) => end.evaluate(context)?.as_f64(),
+                    None => None,
+                };
+                match input {
+                    Value::String(s) => {
+                        let chars = s.chars().collect::<Vec<_>>();
+                        let range = slice_range(chars.len(), begin, end);
+                        Ok(Value::String(chars[range].iter().collect()))
+                    }
+                    Value::Array(items) => {
+                        let range = slice_range(items.len(), begin, end);
+                        Ok(Value::Array(items[range].to_vec()))
+                    }
+                    other => error(format!(
+                        "Expected first argument to be of type array or string, but found {} instead.",
+                        other.type_of()
+                    )),
+                }
+            }
+            Expr::Length(input) => match input.evaluate(context)? {
+                Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+                Value::Array(items) => Ok(Value::Number(items.len() as f64)),
+                other => error(format!(
+                    "Expected value to be of type string or array, but found {} instead.",
+                    other.type_of()
+                )),
+            },
+            Expr::Case {
+                branches,
+                otherwise,
+            } => {
+                for (condition, output) in branches {
+                    if condition.evaluate(context)?.as_bool() == Some(true) {
+                        return output.evaluate(context);
+                    }
+                }
+                otherwise.evaluate(context)
+            }
+            Expr::Match {
+                input,
+                cases,
+                outputs,
+                otherwise,
+            } => {
+                let label = match input.evaluate(context)? {
+                    Value::Number(n) if n.fract() == 0.0 => Some(MatchLabel::Number(n as i64)),
+                    Value::String(s) => Some(MatchLabel::String(s)),
+                    _ => None,
+                };
+                match label.and_then(|label| cases.get(&label)) {
+                    Some(index) => outputs[*index].evaluate(context),
+                    None => otherwise.evaluate(context),
+                }
+            }
+            Expr::Coalesce(args) => {
+                let mut result = Value::Null;
+                for arg in args {
+                    result = arg.evaluate(context)?;
+                    if !result.is_null() {
+                        break;
+                    }
+                }
+                Ok(result)
+            }
+            Expr::Step { input, stops } => {
+                let input = input.evaluate(context)?.as_f64().unwrap_or(f64::NAN);
+                let index = find_stop_less_than_or_equal_to(stops, input);
+                stops[index].1.evaluate(context)
+            }
+            Expr::Interpolate {
+                interpolation,
+                space,
+                input,
+                stops,
+            } => {
+                let input = input.evaluate(context)?.as_f64().unwrap_or(f64::NAN);
+                let (first, last) = (&stops[0], &stops[stops.len() - 1]);
+                if stops.len() == 1 || input <= first.0 {
+                    return first.1.evaluate(context);
+                }
+                if input >= last.0 {
+                    return last.1.evaluate(context);
+                }
+                let index = find_stop_less_than_or_equal_to(stops, input);
+                let (lower, upper) = (&stops[index], &stops[index + 1]);
+                let t = interpolation_factor(*interpolation, input, lower.0, upper.0);
+                let lower = lower.1.evaluate(context)?;
+                let upper = upper.1.evaluate(context)?;
+                interpolate_values(&lower, &upper, t, *space)
+            }
+            Expr::Assertion { ty, args } => {
+                for (i, arg) in args.iter().enumerate() {
+                    let value = arg.evaluate(context)?;
+                    let actual = value.type_of();
+                    if actual.is_subtype_of(ty) {
+                        return Ok(value);
+                    }
+                    if i == args.len() - 1 {
+                        return error(format!(
+                            "Expected value to be of type {ty}, but found {actual} instead."
+                        ));
+                    }
+                }
+                unreachable!("assertions have at least one argument")
+            }
+            Expr::Coercion { ty, args } => coerce(ty, args, context),
+            Expr::Comparison { op, lhs, rhs } => {
+                let lhs = lhs.evaluate(context)?;
+                let rhs = rhs.evaluate(context)?;
+                compare(*op, &lhs, &rhs).map(Value::Boolean)
+            }
+            Expr::All(args) => {
+                for arg in args {
+                    if arg.evaluate(context)?.as_bool() != Some(true) {
+                        return Ok(Value::Boolean(false));
+                    }
+                }
+                Ok(Value::Boolean(true))
+            }
+            Expr::Any(args) => {
+                for arg in args {
+                    if arg.evaluate(context)?.as_bool() == Some(true) {
+                        return Ok(Value::Boolean(true));
+                    }
+                }
+                Ok(Value::Boolean(false))
+            }
+            Expr::Call { op, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(context))
+                    .collect::<Result<Vec<_>, _>>()?;
+                call(*op, &args)
+            }
+            Expr::NumberFormat {
+                input,
+                min_fraction_digits,
+                max_fraction_digits,
+            } => {
+                let input = input.evaluate(context)?.as_f64().unwrap_or(f64::NAN);
+                let digits = |expr: &Option<Box<Expr>>| -> Result<Option<usize>, EvaluationError> {
+                    Ok(match expr {
+                        Some(expr) => expr
+                            .evaluate(context)?
+                            .as_f64()
+                            .map(|n| n.max(0.0) as usize),
+                        None => None,
+                    })
+                };
+                let min = digits(min_fraction_digits)?.unwrap_or(0);
+                let max = digits(max_fraction_digits)?.unwrap_or(min.max(3)).max(min);
+                let formatted = format!("{input:.max$}");
+                let formatted = match formatted.split_once('.') {
+                    Some((integer, fraction)) => {
+                        let trimmed = fraction.trim_end_matches('0');
+                        let fraction = &fraction[..trimmed.len().max(min)];
+                        if fraction.is_empty() {
+                            integer.to_string()
+                        } else {
+                            format!("{integer}.{fraction}")
+                        }
+                    }
+                    None => formatted,
+                };
+                Ok(Value::String(formatted))
+            }
+            Expr::Format(sections) => {
+                let mut text = String::new();
+                for section in sections {
+                    text.push_str(&section.evaluate(context)?.to_string());
+                }
+                Ok(Value::String(text))
+            }
+        }
+    }
+}
+
+fn check_searchable(needle: &Value, haystack: &Value) -> Result<(), EvaluationError> {
+    if !matches!(
+        needle,
+        Value::Boolean(_) | Value::String(_) | Value::Number(_) | Value::Null
+    ) {
+        return error(format!(
+            "Expected first argument to be of type boolean, string, number or null, but found {} instead.",
+            needle.type_of()
+        ));
+    }
+    if !matches!(haystack, Value::String(_) | Value::Array(_)) {
+        return error(format!(
+            "Expected second argument to be of type array or string, but found {} instead.",
+            haystack.type_of()
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves JavaScript-like `slice` indices which may be negative.
+fn slice_range(len: usize, begin: f64, end: Option<f64>) -> std::ops::Range<usize> {
+    let resolve = |index: f64| {
+        let index = index.trunc();
+        if index < 0.0 {
+            (len as f64 + index).max(0.0) as usize
+        } else {
+            (index as usize).min(len)
+        }
+    };
+    let begin = resolve(begin);
+    let end = end.map(resolve).unwrap_or(len);
+    begin..end.max(begin)
+}
+
+fn find_stop_less_than_or_equal_to(stops: &[(f64, Expr)], input: f64) -> usize {
+    stops
+        .iter()
+        .rposition(|(label, _)| *label <= input)
+        .unwrap_or(0)
+}
+
+pub(crate) fn interpolation_factor(
+    interpolation: Interpolation,
+    input: f64,
+    lower: f64,
+    upper: f64,
+) -> f64 {
+    match interpolation {
+        Interpolation::Linear => exponential_interpolation(input, 1.0, lower, upper),
+        Interpolation::Exponential(base) => exponential_interpolation(input, base, lower, upper),
+        Interpolation::CubicBezier([x1, y1, x2, y2]) => {
+            let t = exponential_interpolation(input, 1.0, lower, upper);
+            UnitBezier::new(x1, y1, x2, y2).solve(t, 1e-6)
+        }
+    }
+}
+
+fn exponential_interpolation(input: f64, base: f64, lower: f64, upper: f64) -> f64 {
+    let difference = upper - lower;
+    let progress = input - lower;
+    if difference == 0.0 {
+        0.0
+    } else if base == 1.0 {
+        progress / difference
+    } else {
+        (base.powf(progress) - 1.0) / (base.powf(difference) - 1.0)
+    }
+}
+
+fn interpolate_values(lower: &Value, upper: &Value, t: f64, space: ColorSpace) -> EvaluationResult {
+    let number = |a: f64, b: f64| a + t * (b - a);
+    match (lower, upper) {
+        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(number(*a, *b))),
+        (Value::Color(a), Value::Color(b)) => Ok(Value::Color(interpolate_color(a, b, t, space))),
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| match (a, b) {
+                (Value::Number(a), Value::Number(b)) => Ok(Value::Number(number(*a, *b))),
+                _ => error("Only arrays of numbers can be interpolated"),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        _ => error(format!(
+            "Can not interpolate between {} and {}",
+            lower.type_of(),
+            upper.type_of()
+        )),
+    }
+}
+
+fn interpolate_color(a: &Color, b: &Color, t: f64, space: ColorSpace) -> Color {
+    let number = |a: f64, b: f64| a + t * (b - a);
+    match space {
+        ColorSpace::Rgb => {
+            // Interpolate premultiplied colors to avoid dark fringes towards transparent stops.
+            let alpha = number(a.a, b.a);
+            let premultiplied = |ca: f64, cb: f64| number(ca * a.a, cb * b.a);
+            let (r, g, bl) = (
+                premultiplied(a.r, b.r),
+                premultiplied(a.g, b.g),
+                premultiplied(a.b, b.b),
+            );
+            if alpha == 0.0 {
+                Color::new(0.0, 0.0, 0.0, 0.0)
+            } else {
+                Color::new(r / alpha, g / alpha, bl / alpha, alpha)
+            }
+        }
+        ColorSpace::Lab => {
+            let (l1, a1, b1) = color_spaces::rgb_to_lab(a);
+            let (l2, a2, b2) = color_spaces::rgb_to_lab(b);
+            color_spaces::lab_to_rgb(
+                number(l1, l2),
+                number(a1, a2),
+                number(b1, b2),
+                number(a.a, b.a),
+            )
+        }
+        ColorSpace::Hcl => {
+            let (h1, c1, l1) = color_spaces::rgb_to_hcl(a);
+            let (h2, c2, l2) = color_spaces::rgb_to_hcl(b);
+            let hue = match (h1.is_nan(), h2.is_nan()) {
+                (true, true) => f64::NAN,
+                (true, false) => h2,
+                (false, true) => h1,
+                (false, false) => color_spaces::interpolate_hue(h1, h2, t),
+            };
+            color_spaces::hcl_to_rgb(hue, number(c1, c2), number(l1, l2), number(a.a, b.a))
+        }
+    }
+}
+
+fn coerce(ty: &Type, args: &[Expr], context: &EvaluationContext) -> EvaluationResult {
+    match ty {
+        Type::Boolean => Ok(Value::Boolean(args[0].evaluate(context)?.is_truthy())),
+        Type::String => Ok(Value::String(args[0].evaluate(context)?.to_string())),
+        Type::Color => {
+            let mut last = Value::Null;
+            for arg in args {
+                last = arg.evaluate(context)?;
+                if let Some(color) = to_color(&last)? {
+                    return Ok(Value::Color(color));
+                }
+            }
+            error(format!(
+                "Could not parse color from value '{}'",
+                serde_json::Value::from(&last)
+            ))
+        }
+        Type::Number => {
+            let mut last = Value::Null;
+            for arg in args {
+                last = arg.evaluate(context)?;
+                let number = match &last {
+                    Value::Null => Some(0.0),
+                    Value::Boolean(b) => Some(if *b { 1.0 } else { 0.0 }),
+                    Value::Number(n) => Some(*n),
+                    Value::String(s) if s.trim().is_empty() => Some(0.0),
+                    Value::String(s) => s.trim().parse::<f64>().ok(),
+                    _ => None,
+                };
+                if let Some(number) = number {
+                    return Ok(Value::Number(number));
+                }
+            }
+            error(format!(
+                "Could not convert {} to number.",
+                serde_json::Value::from(&last)
+            ))
+        }
+        _ => error(format!("Can not coerce to {ty}")),
+    }
+}
+
+fn to_color(value: &Value) -> Result<Option<Color>, EvaluationError> {
+    Ok(match value {
+        Value::Color(color) => Some(color.clone()),
+        Value::String(s) => s.parse::<Color>().ok(),
+        Value::Array(items) if items.len() == 3 || items.len() == 4 => {
+            let components = items.iter().map(Value::as_f64).collect::<Option<Vec<_>>>();
+            match components {
+                Some(components) => Some(rgba(&components)?),
+                None => None,
+            }
+        }
+        _ => None,
+    })
+}
+
+fn rgba(components: &[f64]) -> Result<Color, EvaluationError> {
+    let [r, g, b] = [components[0], components[1], components[2]];
+    let a = components.get(3).copied().unwrap_or(1.0);
+    let formatted = || {
+        components
+            .iter()
+            .map(|c| format_number(*c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if ![r, g, b].iter().all(|c| (0.0..=255.0).contains(c)) {
+        return error(format!(
+            "Invalid rgba value [{}]: 'r', 'g', and 'b' must be between 0 and 255.",
+            formatted()
+        ));
+    }
+    if !(0.0..=1.0).contains(&a) {
+        return error(format!(
+            "Invalid rgba value [{}]: 'a' must be between 0 and 1.",
+            formatted()
+        ));
+    }
+    Ok(Color::new(r / 255.0, g / 255.0, b / 255.0, a))
+}
+
+fn compare(op: ComparisonOp, lhs: &Value, rhs: &Value) -> Result<bool, EvaluationError> {
+    let ordering = match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ if matches!(op, ComparisonOp::Equal | ComparisonOp::NotEqual) => None,
+        _ => {
+            return error(format!(
+                "Expected arguments for \"{}\" to be (string, string) or (number, number), but found ({}, {}) instead.",
+                op.name(),
+                lhs.type_of(),
+                rhs.type_of()
+            ))
+        }
+    };
+    Ok(match op {
+        ComparisonOp::Equal => lhs == rhs,
+        ComparisonOp::NotEqual => lhs != rhs,
+        ComparisonOp::Less => ordering == Some(Ordering::Less),
+        ComparisonOp::LessOrEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        ComparisonOp::Greater => ordering == Some(Ordering::Greater),
+        ComparisonOp::GreaterOrEqual => {
+            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+        }
+    })
+}
+
+fn call(op: Builtin, args: &[Value]) -> EvaluationResult {
+    let numbers = || args.iter().map(|arg| arg.as_f64().unwrap_or(f64::NAN));
+    let unary = |f: fn(f64) -> f64| Ok(Value::Number(f(numbers().next().unwrap_or(f64::NAN))));
+    let n = |i: usize| args[i].as_f64().unwrap_or(f64::NAN);
+
+    match op {
+        Builtin::Not => Ok(Value::Boolean(args[0].as_bool() != Some(true))),
+        Builtin::Add => Ok(Value::Number(numbers().sum())),
+        Builtin::Multiply => Ok(Value::Number(numbers().product())),
+        Builtin::Subtract if args.len() == 1 => Ok(Value::Number(-n(0))),
+        Builtin::Subtract => Ok(Value::Number(n(0) - n(1))),
+        Builtin::Divide => Ok(Value::Number(n(0) / n(1))),
+        Builtin::Remainder => Ok(Value::Number(n(0) % n(1))),
+        Builtin::Power => Ok(Value::Number(n(0).powf(n(1)))),
+        Builtin::Sqrt => unary(f64::sqrt),
+        Builtin::Log10 => unary(f64::log10),
+        Builtin::Ln => unary(f64::ln),
+        Builtin::Log2 => unary(f64::log2),
+        Builtin::Sin => unary(f64::sin),
+        Builtin::Cos => unary(f64::cos),
+        Builtin::Tan => unary(f64::tan),
+        Builtin::Asin => unary(f64::asin),
+        Builtin::Acos => unary(f64::acos),
+        Builtin::Atan => unary(f64::atan),
+        Builtin::Abs => unary(f64::abs),
+        Builtin::Ceil => unary(f64::ceil),
+        Builtin::Floor => unary(f64::floor),
+        Builtin::Round => unary(f64::round),
+        Builtin::Min => Ok(Value::Number(numbers().fold(f64::INFINITY, f64::min))),
+        Builtin::Max => Ok(Value::Number(numbers().fold(f64::NEG_INFINITY, f64::max))),
+        Builtin::E => Ok(Value::Number(consts::E)),
+        Builtin::Pi => Ok(Value::Number(consts::PI)),
+        Builtin::Ln2 => Ok(Value::Number(consts::LN_2)),
+        Builtin::Concat => Ok(Value::String(
+            args.iter().map(|arg| arg.to_string()).collect(),
+        )),
+        Builtin::Upcase => Ok(Value::String(args[0].to_string().to_uppercase())),
+        Builtin::Downcase => Ok(Value::String(args[0].to_string().to_lowercase())),
+        Builtin::Image => Ok(args[0].clone()),
+        Builtin::Rgb | Builtin::Rgba => {
+            let components = numbers().collect::<Vec<_>>();
+            rgba(&components).map(Value::Color)
+        }
+        Builtin::ToRgba => match &args[0] {
+            Value::Color(color) => Ok(Value::Array(vec![
+                Value::Number(color.r * 255.0),
+                Value::Number(color.g * 255.0),
+                Value::Number(color.b * 255.0),
+                Value::Number(color.a),
+            ])),
+            other => error(format!("Expected a color, but found {}", other.type_of())),
+        },
+        Builtin::TypeOf => Ok(Value::String(args[0].type_of().to_string())),
+    }
+}
+
+/// Solver for cubic bezier timing functions, ported from WebKit's `UnitBezier`.
+struct UnitBezier {
+    cx: f64,
+    bx: f64,
+    ax: f64,
+    cy: f64,
+    by: f64,
+    ay: f64,
+}
+
+impl UnitBezier {
+    fn new(p1x: f64, p1y: f64, p2x: f64, p2y: f64) -> Self {
+        let cx = 3.0 * p1x;
+        let bx = 3.0 * (p2x - p1x) - cx;
+        let cy = 3.0 * p1y;
+        let by = 3.0 * (p2y - p1y) - cy;
+        Self {
+            cx,
+            bx,
+            ax: 1.0 - cx - bx,
+            cy,
+            by,
+            ay: 1.0 - cy - by,
+        }
+    }
+
+    fn sample_curve_x(&self, t: f64) -> f64 {
+        ((self.ax * t + self.bx) * t + self.cx) * t
+    }
+
+    fn sample_curve_y(&self, t: f64) -> f64 {
+        ((self.ay * t + self.by) * t + self.cy) * t
+    }
+
+    fn sample_curve_derivative_x(&self, t: f64) -> f64 {
+        (3.0 * self.ax * t + 2.0 * self.bx) * t + self.cx
+    }
+
+    fn solve_curve_x(&self, x: f64, epsilon: f64) -> f64 {
+        // Newton's method first, it converges quickly for most curves.
+        let mut t = x;
+        for _ in 0..8 {
+            let x2 = self.sample_curve_x(t) - x;
+            if x2.abs() < epsilon {
+                return t;
+            }
+            let d2 = self.sample_curve_derivative_x(t);
+            if d2.abs() < 1e-6 {
+                break;
+            }
+            t -= x2 / d2;
+        }
+
+        // Fall back to bisection for reliability.
+        let (mut t0, mut t1) = (0.0, 1.0);
+        t = x;
+        if t < t0 {
+            return t0;
+        }
+        if t > t1 {
+            return t1;
+        }
+        while t0 < t1 {
+            let x2 = self.sample_curve_x(t);
+            if (x2 - x).abs() < epsilon {
+                return t;
+            }
+            if x > x2 {
+                t0 = t;
+            } else {
+                t1 = t;
+            }
+            t = (t1 - t0) * 0.5 + t0;
+        }
+        t
+    }
+
+    fn solve(&self, x: f64, epsilon: f64) -> f64 {
+        self.sample_curve_y(self.solve_curve_x(x, epsilon))
+    }
+}
+
+/// Conversions between sRGB and the CIELAB/HCL color spaces (D50 white point), matching the
+/// style specification's reference implementation.
+mod color_spaces {
+    use csscolorparser::Color;
+
+    const XN: f64 = 0.96422;
+    const YN: f64 = 1.0;
+    const ZN: f64 = 0.82521;
+    const T0: f64 = 4.0 / 29.0;
+    const T1: f64 = 6.0 / 29.0;
+    const T2: f64 = 3.0 * T1 * T1;
+    const T3: f64 = T1 * T1 * T1;
+
+    fn xyz_to_lab(t: f64) -> f64 {
+        if t > T3 {
+            t.cbrt()
+        } else {
+            t / T2 + T0
+        }
+    }
+
+    fn lab_to_xyz(t: f64) -> f64 {
+        if t > T1 {
+            t * t * t
+        } else {
+            T2 * (t - T0)
+        }
+    }
+
+    fn xyz_to_rgb(x: f64) -> f64 {
+        let x = if x <= 0.0031308 {
+            12.92 * x
+        } else {
+            1.055 * x.powf(1.0 / 2.4) - 0.055
+        };
+        x.clamp(0.0, 1.0)
+    }
+
+    fn rgb_to_xyz(x: f64) -> f64 {
+        if x <= 0.04045 {
+            x / 12.92
+        } else {
+            ((x + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    pub fn rgb_to_lab(color: &Color) -> (f64, f64, f64) {
+        let r = rgb_to_xyz(color.r);
+        let g = rgb_to_xyz(color.g);
+        let b = rgb_to_xyz(color.b);
+        let y = xyz_to_lab((0.2225045 * r + 0.7168786 * g + 0.0606169 * b) / YN);
+        let (x, z) = if r == g && g == b {
+            (y, y)
+        } else {
+            (
+                xyz_to_lab((0.4360747 * r + 0.3850649 * g + 0.1430804 * b) / XN),
+                xyz_to_lab((0.0139322 * r + 0.0971045 * g + 0.7141733 * b) / ZN),
+            )
+        };
+        (116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z))
+    }
+
+    pub fn lab_to_rgb(l: f64, a: f64, b: f64, alpha: f64) -> Color {
+        let y = (l + 16.0) / 116.0;
+        let x = if a.is_nan() { y } else { y + a / 500.0 };
+        let z = if b.is_nan() { y } else { y - b / 200.0 };
+        let y = YN * lab_to_xyz(y);
+        let x = XN * lab_to_xyz(x);
+        let z = ZN * lab_to_xyz(z);
+        Color::new(
+            xyz_to_rgb(3.1338561 * x - 1.6168667 * y - 0.4906146 * z),
+            xyz_to_rgb(-0.9787684 * x + 1.9161415 * y + 0.0334540 * z),
+            xyz_to_rgb(0.0719453 * x - 0.2289914 * y + 1.4052427 * z),
+            alpha,
+        )
+    }
+
+    pub fn rgb_to_hcl(color: &Color) -> (f64, f64, f64) {
+        let (l, a, b) = rgb_to_lab(color);
+        let c = (a * a + b * b).sqrt();
+        let h = if c.abs() < 1e-10 {
+            f64::NAN
+        } else {
+            let h = b.atan2(a).to_degrees();
+            if h < 0.0 {
+                h + 360.0
+            } else {
+                h
+            }
+        };
+        (h, c, l)
+    }
+
+    pub fn hcl_to_rgb(h: f64, c: f64, l: f64, alpha: f64) -> Color {
+        let h = if h.is_nan() { 0.0 } else { h.to_radians() };
+        lab_to_rgb(l, h.cos() * c, h.sin() * c, alpha)
+    }
+
+    pub fn interpolate_hue(a: f64, b: f64, t: f64) -> f64 {
+        let d = b - a;
+        let d = if b > a && d > 180.0 {
+            d - 360.0
+        } else if b < a && a - b > 180.0 {
+            d + 360.0
+        } else {
+            d
+        };
+        a + t * d
+    }
+}
@@ -0,0 +1,291 @@
+//! Conversion of legacy style functions (`{"stops": [...]}`) into expressions.
+//!
+//! Ported from `convert_function.ts` of the style specification. The conversion happens on the
+//! JSON level, the result is parsed like any other expression.
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::style::expression::value::Type;
+
+fn convert_literal(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Array(_) | JsonValue::Object(_) | JsonValue::Null => json!(["literal", value]),
+        _ => value.clone(),
+    }
+}
+
+/// Converts a string with `{token}` placeholders into a `concat` expression. Returns `None` if
+/// the string does not contain any tokens.
+pub(crate) fn convert_token_string(s: &str) -> Option<JsonValue> {
+    let mut parts = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        if start > 0 {
+            parts.push(json!(rest[..start]));
+        }
+        parts.push(json!([
+            "to-string",
+            ["get", &rest[start + 1..start + length]]
+        ]));
+        rest = &rest[start + length + 1..];
+    }
+
+    if parts.is_empty() {
+        return None;
+    }
+    if !rest.is_empty() {
+        parts.push(json!(rest));
+    }
+    Some(if parts.len() == 1 {
+        parts.remove(0)
+    } else {
+        let mut concat = vec![json!("concat")];
+        concat.extend(parts);
+        JsonValue::Array(concat)
+    })
+}
+
+fn convert_output(value: &JsonValue, tokens: bool) -> JsonValue {
+    match value
+        .as_str()
+        .filter(|_| tokens)
+        .and_then(convert_token_string)
+    {
+        Some(expression) => expression,
+        None => convert_literal(value),
+    }
+}
+
+fn interpolate_operator(parameters: &JsonValue) -> &'static str {
+    match parameters.get("colorSpace").and_then(JsonValue::as_str) {
+        Some("hcl") => "interpolate-hcl",
+        Some("lab") => "interpolate-lab",
+        _ => "interpolate",
+    }
+}
+
+fn function_type<'a>(parameters: &'a JsonValue, expected: &Type) -> &'a str {
+    parameters
+        .get("type")
+        .and_then(JsonValue::as_str)
+        .unwrap_or(if expected.is_interpolatable() {
+            "exponential"
+        } else {
+            "interval"
+        })
+}
+
+fn interpolation(parameters: &JsonValue) -> JsonValue {
+    match parameters.get("base").and_then(JsonValue::as_f64) {
+        Some(base) if base != 1.0 => json!(["exponential", base]),
+        _ => json!(["linear"]),
+    }
+}
+
+fn append_stop_pair(
+    curve: &mut Vec<JsonValue>,
+    input: JsonValue,
+    output: JsonValue,
+    is_step: bool,
+) {
+    // Duplicate stops were not validated for functions, but they are for expressions.
+    if curve.len() > 3 && curve[curve.len() - 2] == input {
+        return;
+    }
+    // Step curves don't get the first input value, as it is redundant.
+    if !(is_step && curve.len() == 2) {
+        curve.push(input);
+    }
+    curve.push(output);
+}
+
+/// Steps with a single output are not valid expressions, so the output is repeated.
+fn fixup_degenerate_step_curve(curve: &mut Vec<JsonValue>) {
+    if curve.len() == 3 && curve[0] == "step" {
+        let output = curve[2].clone();
+        curve.push(json!(0));
+        curve.push(output);
+    }
+}
+
+fn with_default(parameters: &JsonValue, get: &JsonValue, expression: JsonValue) -> JsonValue {
+    match parameters.get("default") {
+        None => expression,
+        Some(default) => json!([
+            "case",
+            ["==", ["typeof", get], "number"],
+            expression,
+            convert_literal(default)
+        ]),
+    }
+}
+
+/// Converts a legacy function object into an expression. If `tokens` is set, string outputs may
+/// contain `{token}` placeholders, as in `text-field`.
+pub(crate) fn convert_function(
+    parameters: &JsonValue,
+    expected: &Type,
+    tokens: bool,
+) -> Result<JsonValue, String> {
+    let Some(stops) = parameters.get("stops").and_then(JsonValue::as_array) else {
+        return convert_identity_function(parameters, expected);
+    };
+    if stops.is_empty() {
+        return Err("function must have at least one stop".to_string());
+    }
+
+    let stops = stops
+        .iter()
+        .map(|stop| match stop.as_array().map(Vec::as_slice) {
+            Some([input, output]) => Ok((input.clone(), convert_output(output, tokens))),
+            _ => Err(format!("function stop must be a pair, found {stop}")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let zoom_and_feature_dependent = stops[0].0.is_object();
+    let feature_dependent = zoom_and_feature_dependent || parameters.get("property").is_some();
+
+    if zoom_and_feature_dependent {
+        convert_zoom_and_property_function(parameters, expected, &stops)
+    } else if feature_dependent {
+        convert_property_function(parameters, expected, &stops)
+    } else {
+        convert_zoom_function(parameters, expected, &stops)
+    }
+}
+
+fn convert_identity_function(parameters: &JsonValue, expected: &Type) -> Result<JsonValue, String> {
+    let Some(property) = parameters.get("property") else {
+        return Err("identity function requires a property".to_string());
+    };
+    let get = json!(["get", property]);
+    let coercion = match expected {
+        Type::Color => "to-color",
+        Type::Number => "number",
+        Type::Boolean => "boolean",
+        Type::String => "string",
+        Type::Array(..) => "array",
+        _ => return Ok(get),
+    };
+    Ok(match parameters.get("default") {
+        None if *expected == Type::String => json!(["string", get]),
+        None => get,
+        Some(default) => json!([coercion, get, convert_literal(default)]),
+    })
+}
+
+fn convert_zoom_function(
+    parameters: &JsonValue,
+    expected: &Type,
+    stops: &[(JsonValue, JsonValue)],
+) -> Result<JsonValue, String> {
+    let (mut curve, is_step) = match function_type(parameters, expected) {
+        "interval" => (vec![json!("step"), json!(["zoom"])], true),
+        "exponential" => (
+            vec![
+                json!(interpolate_operator(parameters)),
+                interpolation(parameters),
+                json!(["zoom"]),
+            ],
+            false,
+        ),
+        other => return Err(format!("unknown zoom function type \"{other}\"")),
+    };
+    for (input, output) in stops {
+        append_stop_pair(&mut curve, input.clone(), output.clone(), is_step);
+    }
+    fixup_degenerate_step_curve(&mut curve);
+    Ok(JsonValue::Array(curve))
+}
+
+fn convert_property_function(
+    parameters: &JsonValue,
+    expected: &Type,
+    stops: &[(JsonValue, JsonValue)],
+) -> Result<JsonValue, String> {
+    let get = json!([
+        "get",
+        parameters.get("property").cloned().unwrap_or_default()
+    ]);
+    let default = parameters
+        .get("default")
+        .map(convert_literal)
+        .unwrap_or(json!(["literal", null]));
+
+    match function_type(parameters, expected) {
+        "categorical" if stops[0].0.is_boolean() => {
+            let mut curve = vec![json!("case")];
+            for (input, output) in stops {
+                curve.push(json!(["==", get, input]));
+                curve.push(output.clone());
+            }
+            curve.push(default);
+            Ok(JsonValue::Array(curve))
+        }
+        "categorical" => {
+            let mut curve = vec![json!("match"), get];
+            for (input, output) in stops {
+                append_stop_pair(&mut curve, input.clone(), output.clone(), false);
+            }
+            curve.push(default);
+            Ok(JsonValue::Array(curve))
+        }
+        "interval" => {
+            let mut curve = vec![json!("step"), json!(["number", get])];
+            for (input, output) in stops {
+                append_stop_pair(&mut curve, input.clone(), output.clone(), true);
+            }
+            fixup_degenerate_step_curve(&mut curve);
+            Ok(with_default(parameters, &get, JsonValue::Array(curve)))
+        }
+        "exponential" => {
+            let mut curve = vec![
+                json!(interpolate_operator(parameters)),
+                interpolation(parameters),
+                json!(["number", get]),
+            ];
+            for (input, output) in stops {
+                append_stop_pair(&mut curve, input.clone(), output.clone(), false);
+            }
+            Ok(with_default(parameters, &get, JsonValue::Array(curve)))
+        }
+        other => Err(format!("unknown property function type \"{other}\"")),
+    }
+}
+
+fn convert_zoom_and_property_function(
+    parameters: &JsonValue,
+    expected: &Type,
+    stops: &[(JsonValue, JsonValue)],
+) -> Result<JsonValue, String> {
+    // Group the stops by zoom level, every group becomes a property function.
+    let mut zoom_groups: Vec<(JsonValue, Vec<(JsonValue, JsonValue)>)> = Vec::new();
+    for (input, output) in stops {
+        let zoom = input.get("zoom").cloned().unwrap_or_default();
+        let value = input.get("value").cloned().unwrap_or_default();
+        match zoom_groups.iter_mut().find(|(z, _)| *z == zoom) {
+            Some((_, group)) => group.push((value, output.clone())),
+            None => zoom_groups.push((zoom, vec![(value, output.clone())])),
+        }
+    }
+
+    let is_step = !expected.is_interpolatable();
+    let mut curve = if is_step {
+        vec![json!("step"), json!(["zoom"])]
+    } else {
+        vec![
+            json!(interpolate_operator(parameters)),
+            json!(["linear"]),
+            json!(["zoom"]),
+        ]
+    };
+    for (zoom, group) in zoom_groups {
+        let output = convert_property_function(parameters, expected, &group)?;
+        append_stop_pair(&mut curve, zoom, output, is_step);
+    }
+    fixup_degenerate_step_curve(&mut curve);
+    Ok(JsonValue::Array(curve))
+}
@@ -0,0 +1,687 @@
+//! Implementation of the [expressions](https://maplibre.org/maplibre-style-spec/expressions/)
+//! of the style specification.
+//!
+//! Expressions are parsed once into a typed syntax tree and can then be evaluated many times
+//! against the properties, geometry type and zoom level of features.
+
+use std::{collections::HashMap, fmt};
+
+use csscolorparser::Color;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::style::expression::parse::{Expr, ParsingContext};
+pub use crate::style::expression::value::{Type, Value};
+
+mod evaluate;
+mod legacy;
+mod parse;
+mod value;
+
+/// Error which occurs if an expression is malformed or does not type check.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{key}: {message}")]
+pub struct ParseError {
+    /// Position of the faulty sub-expression, e.g. `[2][1]`.
+    pub key: String,
+    pub message: String,
+}
+
+/// Error which occurs if an expression can not be evaluated for a specific feature, e.g. because
+/// a property has an unexpected type.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{0}")]
+pub struct EvaluationError(pub String);
+
+/// The geometry type of a feature as returned by the `geometry-type` expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryType {
+    Point,
+    LineString,
+    Polygon,
+}
+
+impl GeometryType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GeometryType::Point => "Point",
+            GeometryType::LineString => "LineString",
+            GeometryType::Polygon => "Polygon",
+        }
+    }
+}
+
+/// Properties of a feature which can be accessed by expressions.
+pub type FeatureProperties = HashMap<String, Value>;
+
+/// Inputs which are available while evaluating an expression.
+#[derive(Default, Clone)]
+pub struct EvaluationContext<'a> {
+    pub zoom: Option<f64>,
+    /// Progress along the line, which is set while evaluating `line-gradient`.
+    pub line_progress: Option<f64>,
+    /// Density of the heatmap, which is set while evaluating `heatmap-color`.
+    pub heatmap_density: Option<f64>,
+    /// Elevation in meters, which is set while evaluating `color-relief-color`.
+    pub elevation: Option<f64>,
+    pub properties: Option<&'a FeatureProperties>,
+    pub geometry_type: Option<GeometryType>,
+    pub id: Option<Value>,
+}
+
+impl<'a> EvaluationContext<'a> {
+    pub fn with_zoom(mut self, zoom: f64) -> Self {
+        self.zoom = Some(zoom);
+        self
+    }
+
+    pub fn with_line_progress(mut self, line_progress: f64) -> Self {
+        self.line_progress = Some(line_progress);
+        self
+    }
+
+    pub fn with_heatmap_density(mut self, heatmap_density: f64) -> Self {
+        self.heatmap_density = Some(heatmap_density);
+        self
+    }
+
+    pub fn with_elevation(mut self, elevation: f64) -> Self {
+        self.elevation = Some(elevation);
+        self
+    }
+
+    pub fn with_properties(mut self, properties: &'a FeatureProperties) -> Self {
+        self.properties = Some(properties);
+        self
+    }
+
+    pub fn with_geometry_type(mut self, geometry_type: GeometryType) -> Self {
+        self.geometry_type = Some(geometry_type);
+        self
+    }
+
+    pub fn with_id(mut self, id: Value) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    fn property(&self, key: &str) -> Option<&Value> {
+        self.properties.and_then(|properties| properties.get(key))
+    }
+}
+
+/// A parsed and type checked expression.
+#[derive(Clone)]
+pub struct Expression {
+    json: serde_json::Value,
+    expr: Expr,
+    ty: Type,
+}
+
+impl Expression {
+    /// Parses an expression or a legacy function. If `expected` is set, the result of the
+    /// expression is asserted or coerced to that type.
+    pub fn parse(json: &serde_json::Value, expected: Option<&Type>) -> Result<Self, ParseError> {
+        Self::parse_legacy(json, expected, false)
+    }
+
+    /// Like [`Expression::parse`], but strings and the outputs of legacy functions may contain
+    /// `{token}` placeholders which are replaced by feature properties, as in `text-field`.
+    pub fn parse_with_tokens(
+        json: &serde_json::Value,
+        expected: Option<&Type>,
+    ) -> Result<Self, ParseError> {
+        Self::parse_legacy(json, expected, true)
+    }
+
+    fn parse_legacy(
+        json: &serde_json::Value,
+        expected: Option<&Type>,
+        tokens: bool,
+    ) -> Result<Self, ParseError> {
+        let converted;
+        let source = match json {
+            serde_json::Value::Object(_) => {
+                converted =
+                    legacy::convert_function(json, expected.unwrap_or(&Type::Value), tokens)
+                        .map_err(|message| ParseError {
+                            key: String::new(),
+                            message,
+                        })?;
+                &converted
+            }
+            serde_json::Value::String(s) if tokens => {
+                converted = legacy::convert_token_string(s).unwrap_or_else(|| json.clone());
+                &converted
+            }
+            _ => json,
+        };
+
+        let (expr, ty) = ParsingContext::default().parse(source, expected)?;
+        Ok(Self {
+            json: json.clone(),
+            expr,
+            ty,
+        })
+    }
+
+    pub fn evaluate(&self, context: &EvaluationContext) -> Result<Value, EvaluationError> {
+        self.expr.evaluate(context)
+    }
+
+    /// The type of values which are returned by this expression.
+    pub fn output_type(&self) -> &Type {
+        &self.ty
+    }
+
+    /// The JSON representation this expression was parsed from.
+    pub fn json(&self) -> &serde_json::Value {
+        &self.json
+    }
+
+    /// Returns true if the expression does not depend on feature properties, id or geometry.
+    pub fn is_feature_constant(&self) -> bool {
+        !self.expr.any(&Expr::is_feature_dependent)
+    }
+
+    /// Returns true if the expression does not depend on the zoom level.
+    pub fn is_zoom_constant(&self) -> bool {
+        !self.expr.any(&Expr::is_zoom_dependent)
+    }
+
+    /// Returns the inputs of the first and the last stop if this expression is an `interpolate`
+    /// or a `step`. The first stop of a `step` is ignored, as it covers all lower inputs.
+    pub fn stop_range(&self) -> Option<(f64, f64)> {
+        let mut expr = &self.expr;
+        while let Expr::Assertion { args, .. } | Expr::Coercion { args, .. } = expr {
+            expr = args.first()?;
+        }
+        let stops = match expr {
+            Expr::Interpolate { stops, .. } => stops.as_slice(),
+            Expr::Step { stops, .. } => stops.get(1..)?,
+            _ => return None,
+        };
+        Some((stops.first()?.0, stops.last()?.0))
+    }
+}
+
+impl fmt::Debug for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Expression({})", self.json)
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.json.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = serde_json::Value::deserialize(deserializer)?;
+        Expression::parse(&json, None).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Types which can be the result of evaluating a style property.
+pub trait FromValue: Sized {
+    /// The type expressions for this property are checked against.
+    fn expected_type() -> Type;
+
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+impl FromValue for f32 {
+    fn expected_type() -> Type {
+        Type::Number
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        value.as_f64().map(|n| n as f32)
+    }
+}
+
+impl FromValue for f64 {
+    fn expected_type() -> Type {
+        Type::Number
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        value.as_f64()
+    }
+}
+
+impl FromValue for bool {
+    fn expected_type() -> Type {
+        Type::Boolean
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+impl FromValue for String {
+    fn expected_type() -> Type {
+        Type::String
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Color {
+    fn expected_type() -> Type {
+        Type::Color
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Color(color) => Some(color),
+            _ => None,
+        }
+    }
+}
+
+impl<const N: usize> FromValue for [f32; N] {
+    fn expected_type() -> Type {
+        Type::array_of_length(Type::Number, N)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Array(items) => items
+                .iter()
+                .map(|item| item.as_f64().map(|n| n as f32))
+                .collect::<Option<Vec<_>>>()?
+                .try_into()
+                .ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Vec<f32> {
+    fn expected_type() -> Type {
+        Type::array(Type::Number)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Array(items) => items
+                .iter()
+                .map(|item| item.as_f64().map(|n| n as f32))
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn evaluate(json: serde_json::Value, context: &EvaluationContext) -> Value {
+        Expression::parse(&json, None)
+            .unwrap()
+            .evaluate(context)
+            .unwrap()
+    }
+
+    fn properties(json: serde_json::Value) -> FeatureProperties {
+        json.as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::from_json(value)))
+            .collect()
+    }
+
+    #[test]
+    fn test_math_and_constant_folding() {
+        let expression =
+            Expression::parse(&json!(["+", 1, ["*", 2, 3], ["^", 2, 2]]), None).unwrap();
+        assert!(matches!(expression.expr, Expr::Literal(Value::Number(n)) if n == 11.0));
+        assert_eq!(
+            evaluate(json!(["round", ["/", 7, 2]]), &Default::default()),
+            Value::Number(4.0)
+        );
+        assert_eq!(
+            evaluate(json!(["%", 7, 3]), &Default::default()),
+            Value::Number(1.0)
+        );
+    }
+
+    #[test]
+    fn test_case_coalesce_and_let() {
+        let props = properties(json!({"class": "primary", "rank": 3}));
+        let context = EvaluationContext::default().with_properties(&props);
+
+        assert_eq!(
+            evaluate(
+                json!([
+                    "case",
+                    ["==", ["get", "class"], "motorway"],
+                    1,
+                    [">", ["get", "rank"], 2],
+                    2,
+                    3
+                ]),
+                &context
+            ),
+            Value::Number(2.0)
+        );
+        assert_eq!(
+            evaluate(
+                json!(["coalesce", ["get", "missing"], ["get", "class"]]),
+                &context
+            ),
+            Value::from("primary")
+        );
+        assert_eq!(
+            evaluate(
+                json!([
+                    "let",
+                    "double",
+                    ["*", ["get", "rank"], 2],
+                    ["+", ["var", "double"], 1]
+                ]),
+                &context
+            ),
+            Value::Number(7.0)
+        );
+    }
+
+    #[test]
+    fn test_match_with_numeric_and_string_labels() {
+        let props = properties(json!({"admin_level": 4, "class": "river"}));
+        let context = EvaluationContext::default().with_properties(&props);
+
+        assert_eq!(
+            evaluate(
+                json!(["match", ["get", "admin_level"], [2, 3], "a", 4, "b", "c"]),
+                &context
+            ),
+            Value::from("b")
+        );
+        assert_eq!(
+            evaluate(
+                json!(["match", ["get", "class"], "lake", "a", "c"]),
+                &context
+            ),
+            Value::from("c")
+        );
+    }
+
+    #[test]
+    fn test_interpolate_and_step_on_zoom() {
+        let interpolate = json!(["interpolate", ["linear"], ["zoom"], 10, 1, 20, 11]);
+        let context = EvaluationContext::default().with_zoom(15.0);
+        assert_eq!(evaluate(interpolate.clone(), &context), Value::Number(6.0));
+        assert_eq!(
+            evaluate(interpolate, &EvaluationContext::default().with_zoom(5.0)),
+            Value::Number(1.0)
+        );
+
+        let exponential = json!(["interpolate", ["exponential", 2], ["zoom"], 0, 0, 2, 3]);
+        assert_eq!(
+            evaluate(exponential, &EvaluationContext::default().with_zoom(1.0)),
+            Value::Number(1.0)
+        );
+
+        let step = json!(["step", ["zoom"], "small", 10, "medium", 15, "large"]);
+        assert_eq!(evaluate(step.clone(), &context), Value::from("large"));
+        assert_eq!(
+            evaluate(step, &EvaluationContext::default().with_zoom(12.0)),
+            Value::from("medium")
+        );
+
+        let expression = Expression::parse(
+            &json!(["interpolate", ["linear"], ["zoom"], 10, 1, 20, 11]),
+            Some(&Type::Number),
+        )
+        .unwrap();
+        assert!(!expression.is_zoom_constant());
+        assert!(expression.is_feature_constant());
+    }
+
+    #[test]
+    fn test_line_progress() {
+        let interpolate = json!(["interpolate", ["linear"], ["line-progress"], 0, 0, 1, 10]);
+        assert_eq!(
+            evaluate(
+                interpolate.clone(),
+                &EvaluationContext::default().with_line_progress(0.25)
+            ),
+            Value::Number(2.5)
+        );
+        // The progress is not known while parsing, so it is not folded into a constant
+        let expression = Expression::parse(&interpolate, None).unwrap();
+        assert!(expression.evaluate(&Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_heatmap_density() {
+        let interpolate = json!(["interpolate", ["linear"], ["heatmap-density"], 0, 0, 1, 10]);
+        assert_eq!(
+            evaluate(
+                interpolate.clone(),
+                &EvaluationContext::default().with_heatmap_density(0.5)
+            ),
+            Value::Number(5.0)
+        );
+        let expression = Expression::parse(&interpolate, None).unwrap();
+        assert!(expression.evaluate(&Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_elevation() {
+        let interpolate = json!(["interpolate", ["linear"], ["elevation"], 400, 0, 2000, 16]);
+        assert_eq!(
+            evaluate(
+                interpolate.clone(),
+                &EvaluationContext::default().with_elevation(800.0)
+            ),
+            Value::Number(4.0)
+        );
+        let expression = Expression::parse(&interpolate, None).unwrap();
+        assert!(expression.evaluate(&Default::default()).is_err());
+        assert_eq!(expression.stop_range(), Some((400.0, 2000.0)));
+
+        let step = json!(["step", ["elevation"], "red", 100, "green", 500, "blue"]);
+        let expression = Expression::parse(&step, Some(&Type::Color)).unwrap();
+        assert_eq!(expression.stop_range(), Some((100.0, 500.0)));
+        assert_eq!(
+            Expression::parse(&json!(["elevation"]), None)
+                .unwrap()
+                .stop_range(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_color_interpolation_and_coercion() {
+        let expression = Expression::parse(
+            &json!([
+                "interpolate",
+                ["linear"],
+                ["zoom"],
+                0,
+                "black",
+                10,
+                "#ffffff"
+            ]),
+            Some(&Type::Color),
+        )
+        .unwrap();
+        let Value::Color(color) = expression
+            .evaluate(&EvaluationContext::default().with_zoom(5.0))
+            .unwrap()
+        else {
+            panic!("expected a color");
+        };
+        assert_eq!(color.to_rgba8(), [128, 128, 128, 255]);
+
+        let props = properties(json!({"color": "red"}));
+        let expression = Expression::parse(&json!(["get", "color"]), Some(&Type::Color)).unwrap();
+        assert_eq!(expression.output_type(), &Type::Color);
+        assert_eq!(
+            expression
+                .evaluate(&EvaluationContext::default().with_properties(&props))
+                .unwrap(),
+            Value::Color(Color::new(1.0, 0.0, 0.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn test_string_operators() {
+        let props = properties(json!({"name": "Berlin", "population": 3.5}));
+        let context = EvaluationContext::default().with_properties(&props);
+        assert_eq!(
+            evaluate(
+                json!([
+                    "concat",
+                    ["upcase", ["get", "name"]],
+                    " ",
+                    ["get", "population"],
+                    "M"
+                ]),
+                &context
+            ),
+            Value::from("BERLIN 3.5M")
+        );
+        assert_eq!(
+            evaluate(json!(["slice", ["get", "name"], 0, 3]), &context),
+            Value::from("Ber")
+        );
+        assert_eq!(
+            evaluate(json!(["in", "rl", ["get", "name"]]), &context),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            evaluate(
+                json!(["number-format", 12.34567, {"max-fraction-digits": 2}]),
+                &context
+            ),
+            Value::from("12.35")
+        );
+    }
+
+    #[test]
+    fn test_geometry_type_and_comparisons() {
+        let props = properties(json!({"height": 12}));
+        let context = EvaluationContext::default()
+            .with_properties(&props)
+            .with_geometry_type(GeometryType::Polygon);
+        assert_eq!(
+            evaluate(
+                json!([
+                    "all",
+                    ["==", ["geometry-type"], "Polygon"],
+                    [">=", ["get", "height"], 10]
+                ]),
+                &context
+            ),
+            Value::Boolean(true)
+        );
+        // Values of different types are never equal
+        assert_eq!(
+            evaluate(json!(["==", ["get", "height"], "12"]), &context),
+            Value::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let error = Expression::parse(&json!(["+", 1, "two"]), None).unwrap_err();
+        assert_eq!(error.key, "[2]");
+        assert_eq!(error.message, "Expected number but found string instead.");
+
+        assert!(Expression::parse(&json!(["unknown-op"]), None).is_err());
+        assert!(Expression::parse(&json!(["var", "undefined"]), None).is_err());
+        assert!(Expression::parse(&json!(["step", ["zoom"], 0, 5, 1, 3, 2]), None).is_err());
+        assert!(Expression::parse(&json!("not a color"), Some(&Type::Color)).is_err());
+
+        let props = properties(json!({"width": "wide"}));
+        let expression = Expression::parse(&json!(["get", "width"]), Some(&Type::Number)).unwrap();
+        assert!(expression
+            .evaluate(&EvaluationContext::default().with_properties(&props))
+            .is_err());
+    }
+
+    #[test]
+    fn test_legacy_functions() {
+        let expression =
+            Expression::parse(&json!({"stops": [[10, 1], [20, 11]]}), Some(&Type::Number)).unwrap();
+        assert_eq!(
+            expression
+                .evaluate(&EvaluationContext::default().with_zoom(15.0))
+                .unwrap(),
+            Value::Number(6.0)
+        );
+
+        let expression = Expression::parse(
+            &json!({"property": "class", "type": "categorical", "stops": [["a", "red"], ["b", "blue"]], "default": "black"}),
+            Some(&Type::Color),
+        )
+        .unwrap();
+        let props = properties(json!({"class": "b"}));
+        assert_eq!(
+            expression
+                .evaluate(&EvaluationContext::default().with_properties(&props))
+                .unwrap(),
+            Value::Color(Color::new(0.0, 0.0, 1.0, 1.0))
+        );
+
+        let expression = Expression::parse(
+            &json!({"stops": [[0, "{ABBREV}"], [4, "{NAME}"]]}),
+            Some(&Type::String),
+        )
+        .unwrap();
+        assert_eq!(
+            expression
+                .evaluate(&EvaluationContext::default().with_zoom(5.0))
+                .unwrap(),
+            Value::from("{NAME}")
+        );
+    }
+
+    #[test]
+    fn test_token_strings() {
+        let props = properties(json!({"name": "Berlin", "ref": 7}));
+        let context = EvaluationContext::default()
+            .with_properties(&props)
+            .with_zoom(5.0);
+
+        let expression =
+            Expression::parse_with_tokens(&json!("{name} ({ref})"), Some(&Type::String)).unwrap();
+        assert_eq!(
+            expression.evaluate(&context).unwrap(),
+            Value::from("Berlin (7)")
+        );
+
+        let expression = Expression::parse_with_tokens(
+            &json!({"stops": [[0, "{ref}"], [4, "{name}"]]}),
+            Some(&Type::String),
+        )
+        .unwrap();
+        assert_eq!(
+            expression.evaluate(&context).unwrap(),
+            Value::from("Berlin")
+        );
+        assert!(!expression.is_feature_constant());
+
+        let expression =
+            Expression::parse_with_tokens(&json!("no tokens"), Some(&Type::String)).unwrap();
+        assert_eq!(
+            expression.evaluate(&context).unwrap(),
+            Value::from("no tokens")
+        );
+    }
+}
@@ -0,0 +1,1195 @@
+//! Parses JSON expressions into a typed syntax tree.
+//!
+//! The parser follows the type checking rules of the style specification: arguments are parsed
+//! with the type their operator expects, and untyped values (e.g. the result of `get`) are wrapped
+//! into assertions or coercions so that the tree is well-typed before it is ever evaluated.
+//! Sub-expressions which do not depend on the feature or zoom are evaluated once while parsing.
+
+use std::collections::HashMap;
+
+use serde_json::Value as JsonValue;
+
+use crate::style::expression::{
+    value::{Type, Value},
+    EvaluationContext, ParseError,
+};
+
+/// A typed expression tree node.
+#[derive(Debug, Clone)]
+pub(crate) enum Expr {
+    Literal(Value),
+    Get {
+        key: Box<Expr>,
+        object: Option<Box<Expr>>,
+    },
+    Has {
+        key: Box<Expr>,
+        object: Option<Box<Expr>>,
+    },
+    Properties,
+    GeometryType,
+    Id,
+    Zoom,
+    /// Progress along a line between 0 and 1, which is only available for `line-gradient`.
+    LineProgress,
+    /// Density of the kernels of a heatmap between 0 and 1, which is only available for
+    /// `heatmap-color`.
+    HeatmapDensity,
+    /// Elevation of the terrain in meters, which is only available for `color-relief-color`.
+    Elevation,
+    /// Feature state is not tracked, so `feature-state` always evaluates to null.
+    FeatureState,
+    At {
+        index: Box<Expr>,
+        array: Box<Expr>,
+    },
+    In {
+        needle: Box<Expr>,
+        haystack: Box<Expr>,
+    },
+    IndexOf {
+        needle: Box<Expr>,
+        haystack: Box<Expr>,
+        from_index: Option<Box<Expr>>,
+    },
+    Slice {
+        input: Box<Expr>,
+        begin: Box<Expr>,
+        end: Option<Box<Expr>>,
+    },
+    Length(Box<Expr>),
+    Case {
+        branches: Vec<(Expr, Expr)>,
+        otherwise: Box<Expr>,
+    },
+    Match {
+        input: Box<Expr>,
+        cases: HashMap<MatchLabel, usize>,
+        outputs: Vec<Expr>,
+        otherwise: Box<Expr>,
+    },
+    Coalesce(Vec<Expr>),
+    /// The first stop is always at negative infinity.
+    Step {
+        input: Box<Expr>,
+        stops: Vec<(f64, Expr)>,
+    },
+    Interpolate {
+        interpolation: Interpolation,
+        space: ColorSpace,
+        input: Box<Expr>,
+        stops: Vec<(f64, Expr)>,
+    },
+    Assertion {
+        ty: Type,
+        args: Vec<Expr>,
+    },
+    Coercion {
+        ty: Type,
+        args: Vec<Expr>,
+    },
+    Comparison {
+        op: ComparisonOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    All(Vec<Expr>),
+    Any(Vec<Expr>),
+    Call {
+        op: Builtin,
+        args: Vec<Expr>,
+    },
+    NumberFormat {
+        input: Box<Expr>,
+        min_fraction_digits: Option<Box<Expr>>,
+        max_fraction_digits: Option<Box<Expr>>,
+    },
+    /// Formatted text is flattened into a plain string, section options are ignored.
+    Format(Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum MatchLabel {
+    Number(i64),
+    String(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Interpolation {
+    Linear,
+    Exponential(f64),
+    CubicBezier([f64; 4]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ColorSpace {
+    Rgb,
+    Lab,
+    Hcl,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ComparisonOp {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl ComparisonOp {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "==" => ComparisonOp::Equal,
+            "!=" => ComparisonOp::NotEqual,
+            "<" => ComparisonOp::Less,
+            "<=" => ComparisonOp::LessOrEqual,
+            ">" => ComparisonOp::Greater,
+            ">=" => ComparisonOp::GreaterOrEqual,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ComparisonOp::Equal => "==",
+            ComparisonOp::NotEqual => "!=",
+            ComparisonOp::Less => "<",
+            ComparisonOp::LessOrEqual => "<=",
+            ComparisonOp::Greater => ">",
+            ComparisonOp::GreaterOrEqual => ">=",
+        }
+    }
+
+    fn is_equality(&self) -> bool {
+        matches!(self, ComparisonOp::Equal | ComparisonOp::NotEqual)
+    }
+}
+
+/// Operators with a fixed signature whose arguments all share the same type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Builtin {
+    Not,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+    Sqrt,
+    Log10,
+    Ln,
+    Log2,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Abs,
+    Ceil,
+    Floor,
+    Round,
+    Min,
+    Max,
+    E,
+    Pi,
+    Ln2,
+    Concat,
+    Upcase,
+    Downcase,
+    Rgb,
+    Rgba,
+    ToRgba,
+    TypeOf,
+    Image,
+}
+
+impl Builtin {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "!" => Builtin::Not,
+            "+" => Builtin::Add,
+            "-" => Builtin::Subtract,
+            "*" => Builtin::Multiply,
+            "/" => Builtin::Divide,
+            "%" => Builtin::Remainder,
+            "^" => Builtin::Power,
+            "sqrt" => Builtin::Sqrt,
+            "log10" => Builtin::Log10,
+            "ln" => Builtin::Ln,
+            "log2" => Builtin::Log2,
+            "sin" => Builtin::Sin,
+            "cos" => Builtin::Cos,
+            "tan" => Builtin::Tan,
+            "asin" => Builtin::Asin,
+            "acos" => Builtin::Acos,
+            "atan" => Builtin::Atan,
+            "abs" => Builtin::Abs,
+            "ceil" => Builtin::Ceil,
+            "floor" => Builtin::Floor,
+            "round" => Builtin::Round,
+            "min" => Builtin::Min,
+            "max" => Builtin::Max,
+            "e" => Builtin::E,
+            "pi" => Builtin::Pi,
+            "ln2" => Builtin::Ln2,
+            "concat" => Builtin::Concat,
+            "upcase" => Builtin::Upcase,
+            "downcase" => Builtin::Downcase,
+            "rgb" => Builtin::Rgb,
+            "rgba" => Builtin::Rgba,
+            "to-rgba" => Builtin::ToRgba,
+            "typeof" => Builtin::TypeOf,
+            "image" => Builtin::Image,
+            _ => return None,
+        })
+    }
+
+    /// Returns the parameter type, the allowed number of arguments and the output type.
+    fn signature(&self) -> (Type, std::ops::RangeInclusive<usize>, Type) {
+        match self {
+            Builtin::Not => (Type::Boolean, 1..=1, Type::Boolean),
+            Builtin::Add | Builtin::Multiply | Builtin::Min | Builtin::Max => {
+                (Type::Number, 1..=usize::MAX, Type::Number)
+            }
+            Builtin::Subtract => (Type::Number, 1..=2, Type::Number),
+            Builtin::Divide | Builtin::Remainder | Builtin::Power => {
+                (Type::Number, 2..=2, Type::Number)
+            }
+            Builtin::Sqrt
+            | Builtin::Log10
+            | Builtin::Ln
+            | Builtin::Log2
+            | Builtin::Sin
+            | Builtin::Cos
+            | Builtin::Tan
+            | Builtin::Asin
+            | Builtin::Acos
+            | Builtin::Atan
+            | Builtin::Abs
+            | Builtin::Ceil
+            | Builtin::Floor
+            | Builtin::Round => (Type::Number, 1..=1, Type::Number),
+            Builtin::E | Builtin::Pi | Builtin::Ln2 => (Type::Number, 0..=0, Type::Number),
+            Builtin::Concat => (Type::Value, 1..=usize::MAX, Type::String),
+            Builtin::Upcase | Builtin::Downcase | Builtin::Image => {
+                (Type::String, 1..=1, Type::String)
+            }
+            Builtin::Rgb => (Type::Number, 3..=3, Type::Color),
+            Builtin::Rgba => (Type::Number, 4..=4, Type::Color),
+            Builtin::ToRgba => (Type::Color, 1..=1, Type::array_of_length(Type::Number, 4)),
+            Builtin::TypeOf => (Type::Value, 1..=1, Type::String),
+        }
+    }
+}
+
+impl Expr {
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Literal(_)
+            | Expr::Properties
+            | Expr::GeometryType
+            | Expr::Id
+            | Expr::Zoom
+            | Expr::LineProgress
+            | Expr::HeatmapDensity
+            | Expr::Elevation
+            | Expr::FeatureState => vec![],
+            Expr::Get { key, object } | Expr::Has { key, object } => std::iter::once(key.as_ref())
+                .chain(object.as_deref())
+                .collect(),
+            Expr::At { index, array } => vec![index, array],
+            Expr::In { needle, haystack } => vec![needle, haystack],
+            Expr::IndexOf {
+                needle,
+                haystack,
+                from_index,
+            } => [needle.as_ref(), haystack.as_ref()]
+                .into_iter()
+                .chain(from_index.as_deref())
+                .collect(),
+            Expr::Slice { input, begin, end } => [input.as_ref(), begin.as_ref()]
+                .into_iter()
+                .chain(end.as_deref())
+                .collect(),
+            Expr::Length(input) => vec![input],
+            Expr::Case {
+                branches,
+                otherwise,
+            } => branches
+                .iter()
+                .flat_map(|(condition, output)| [condition, output])
+                .chain(std::iter::once(otherwise.as_ref()))
+                .collect(),
+            Expr::Match {
+                input,
+                outputs,
+                otherwise,
+                ..
+            } => std::iter::once(input.as_ref())
+                .chain(outputs.iter())
+                .chain(std::iter::once(otherwise.as_ref()))
+                .collect(),
+            Expr::Step { input, stops } | Expr::Interpolate { input, stops, .. } => {
+                std::iter::once(input.as_ref())
+                    .chain(stops.iter().map(|(_, output)| output))
+                    .collect()
+            }
+            Expr::Comparison { lhs, rhs, .. } => vec![lhs, rhs],
+            Expr::Coalesce(args)
+            | Expr::All(args)
+            | Expr::Any(args)
+            | Expr::Format(args)
+            | Expr::Assertion { args, .. }
+            | Expr::Coercion { args, .. }
+            | Expr::Call { args, .. } => args.iter().collect(),
+            Expr::NumberFormat {
+                input,
+                min_fraction_digits,
+                max_fraction_digits,
+            } => std::iter::once(input.as_ref())
+                .chain(min_fraction_digits.as_deref())
+                .chain(max_fraction_digits.as_deref())
+                .collect(),
+        }
+    }
+
+    /// Returns true if this node or any of its descendants satisfies the predicate.
+    pub(crate) fn any(&self, predicate: &dyn Fn(&Expr) -> bool) -> bool {
+        predicate(self)
+            || self
+                .children()
+                .into_iter()
+                .any(|child| child.any(predicate))
+    }
+
+    pub(crate) fn is_feature_dependent(&self) -> bool {
+        matches!(
+            self,
+            Expr::Get { object: None, .. }
+                | Expr::Has { object: None, .. }
+                | Expr::Properties
+                | Expr::GeometryType
+                | Expr::Id
+                | Expr::FeatureState
+        )
+    }
+
+    pub(crate) fn is_zoom_dependent(&self) -> bool {
+        matches!(self, Expr::Zoom)
+    }
+
+    fn is_constant(&self) -> bool {
+        !self.any(&|expr| {
+            expr.is_feature_dependent()
+                || expr.is_zoom_dependent()
+                || matches!(
+                    expr,
+                    Expr::LineProgress | Expr::HeatmapDensity | Expr::Elevation
+                )
+        })
+    }
+}
+
+type Typed = (Expr, Type);
+
+/// Tracks the position within the JSON document and the variables bound by enclosing `let`
+/// expressions.
+#[derive(Clone, Default)]
+pub(crate) struct ParsingContext {
+    key: String,
+    scope: Vec<(String, Typed)>,
+}
+
+impl ParsingContext {
+    fn child(&self, index: usize) -> Self {
+        Self {
+            key: format!("{}[{index}]", self.key),
+            scope: self.scope.clone(),
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            key: self.key.clone(),
+            message: message.into(),
+        }
+    }
+
+    /// Parses an expression and makes sure its result is of the `expected` type, by inserting
+    /// assertions or coercions if necessary.
+    pub(crate) fn parse(
+        &self,
+        json: &JsonValue,
+        expected: Option<&Type>,
+    ) -> Result<Typed, ParseError> {
+        self.parse_annotated(json, expected, true)
+    }
+
+    fn parse_child(
+        &self,
+        args: &[JsonValue],
+        index: usize,
+        expected: Option<&Type>,
+    ) -> Result<Typed, ParseError> {
+        self.child(index).parse(&args[index], expected)
+    }
+
+    fn parse_annotated(
+        &self,
+        json: &JsonValue,
+        expected: Option<&Type>,
+        annotate: bool,
+    ) -> Result<Typed, ParseError> {
+        let (expr, ty) = match json {
+            JsonValue::Array(items) => {
+                let Some(first) = items.first() else {
+                    return Err(self.error(
+                        "Expected an array with at least one element. If you wanted a literal array, use [\"literal\", []].",
+                    ));
+                };
+                let Some(op) = first.as_str() else {
+                    return Err(self.error(format!(
+                        "Expression name must be a string, but found {} instead. If you wanted a literal array, use [\"literal\", [...]].",
+                        Value::from_json(first).type_of()
+                    )));
+                };
+                self.parse_compound(op, items, expected)?
+            }
+            JsonValue::Object(_) => {
+                return Err(self.error("Bare objects invalid. Use [\"literal\", {...}] instead."))
+            }
+            _ => {
+                let value = Value::from_json(json);
+                let ty = value.type_of();
+                (Expr::Literal(value), ty)
+            }
+        };
+
+        let (expr, ty) = match expected {
+            Some(expected) if !ty.is_subtype_of(expected) => {
+                let needs_coercion =
+                    matches!(expected, Type::Color) && matches!(ty, Type::String | Type::Value);
+                let needs_assertion = matches!(
+                    expected,
+                    Type::String | Type::Number | Type::Boolean | Type::Object | Type::Array(..)
+                ) && ty == Type::Value;
+
+                if annotate && needs_coercion {
+                    (
+                        Expr::Coercion {
+                            ty: expected.clone(),
+                            args: vec![expr],
+                        },
+                        expected.clone(),
+                    )
+                } else if annotate && needs_assertion {
+                    (
+                        Expr::Assertion {
+                            ty: expected.clone(),
+                            args: vec![expr],
+                        },
+                        expected.clone(),
+                    )
+                } else {
+                    return Err(self.error(format!("Expected {expected} but found {ty} instead.")));
+                }
+            }
+            _ => (expr, ty),
+        };
+
+        if !matches!(expr, Expr::Literal(_)) && expr.is_constant() {
+            return match expr.evaluate(&EvaluationContext::default()) {
+                Ok(value) => Ok((Expr::Literal(value), ty)),
+                Err(e) => Err(self.error(e.to_string())),
+            };
+        }
+
+        Ok((expr, ty))
+    }
+
+    fn expect_arity(
+        &self,
+        args: &[JsonValue],
+        arity: std::ops::RangeInclusive<usize>,
+    ) -> Result<(), ParseError> {
+        let found = args.len() - 1;
+        if arity.contains(&found) {
+            return Ok(());
+        }
+        let expected = if arity.start() == arity.end() {
+            format!("{}", arity.start())
+        } else if *arity.end() == usize::MAX {
+            format!("at least {}", arity.start())
+        } else {
+            format!("{} to {}", arity.start(), arity.end())
+        };
+        Err(self.error(format!(
+            "Expected {expected} arguments, but found {found} instead."
+        )))
+    }
+
+    fn parse_compound(
+        &self,
+        op: &str,
+        args: &[JsonValue],
+        expected: Option<&Type>,
+    ) -> Result<Typed, ParseError> {
+        // Output type for branching expressions: the expected type unless it is the generic
+        // value type, in which case it is inferred from the first branch.
+        let branch_type = expected.filter(|ty| **ty != Type::Value).cloned();
+
+        Ok(match op {
+            "literal" => {
+                self.expect_arity(args, 1..=1)?;
+                let value = Value::from_json(&args[1]);
+                let ty = match (&value, expected) {
+                    (Value::Array(items), Some(expected @ Type::Array(..))) if items.is_empty() => {
+                        expected.clone()
+                    }
+                    _ => value.type_of(),
+                };
+                (Expr::Literal(value), ty)
+            }
+            "let" => {
+                if args.len() < 4 || args.len() % 2 != 0 {
+                    return Err(self.error(format!(
+                        "Expected an odd number of arguments to \"let\", but found {} instead.",
+                        args.len() - 1
+                    )));
+                }
+                let mut context = self.clone();
+                for i in (1..args.len() - 1).step_by(2) {
+                    let Some(name) = args[i].as_str() else {
+                        return Err(self.child(i).error(format!(
+                            "Expected string, but found {} instead.",
+                            Value::from_json(&args[i]).type_of()
+                        )));
+                    };
+                    let bound = context.parse_child(args, i + 1, None)?;
+                    context.scope.push((name.to_string(), bound));
+                }
+                let last = args.len() - 1;
+                context.child(last).parse(&args[last], expected)?
+            }
+            "var" => {
+                self.expect_arity(args, 1..=1)?;
+                let name = args[1].as_str().unwrap_or_default();
+                let Some((_, bound)) = self.scope.iter().rev().find(|(n, _)| n == name) else {
+                    return Err(self.child(1).error(format!(
+                        "Unknown variable \"{name}\". Make sure \"{name}\" has been bound in an enclosing \"let\" expression."
+                    )));
+                };
+                bound.clone()
+            }
+            "get" | "has" => {
+                self.expect_arity(args, 1..=2)?;
+                let (key, _) = self.parse_child(args, 1, Some(&Type::String))?;
+                let object = if args.len() == 3 {
+                    Some(Box::new(self.parse_child(args, 2, Some(&Type::Object))?.0))
+                } else {
+                    None
+                };
+                let key = Box::new(key);
+                if op == "get" {
+                    (Expr::Get { key, object }, Type::Value)
+                } else {
+                    (Expr::Has { key, object }, Type::Boolean)
+                }
+            }
+            "properties" => {
+                self.expect_arity(args, 0..=0)?;
+                (Expr::Properties, Type::Object)
+            }
+            "geometry-type" => {
+                self.expect_arity(args, 0..=0)?;
+                (Expr::GeometryType, Type::String)
+            }
+            "id" => {
+                self.expect_arity(args, 0..=0)?;
+                (Expr::Id, Type::Value)
+            }
+            "zoom" => {
+                self.expect_arity(args, 0..=0)?;
+                (Expr::Zoom, Type::Number)
+            }
+            "line-progress" => {
+                self.expect_arity(args, 0..=0)?;
+                (Expr::LineProgress, Type::Number)
+            }
+            "heatmap-density" => {
+                self.expect_arity(args, 0..=0)?;
+                (Expr::HeatmapDensity, Type::Number)
+            }
+            "elevation" => {
+                self.expect_arity(args, 0..=0)?;
+                (Expr::Elevation, Type::Number)
+            }
+            "feature-state" => {
+                self.expect_arity(args, 1..=1)?;
+                self.parse_child(args, 1, Some(&Type::String))?;
+                (Expr::FeatureState, Type::Value)
+            }
+            "at" => {
+                self.expect_arity(args, 2..=2)?;
+                let (index, _) = self.parse_child(args, 1, Some(&Type::Number))?;
+                let (array, array_type) =
+                    self.parse_child(args, 2, Some(&Type::array(Type::Value)))?;
+                let item_type = match array_type {
+                    Type::Array(item, _) => *item,
+                    _ => Type::Value,
+                };
+                (
+                    Expr::At {
+                        index: Box::new(index),
+                        array: Box::new(array),
+                    },
+                    item_type,
+                )
+            }
+            "in" => {
+                self.expect_arity(args, 2..=2)?;
+                let (needle, _) = self.parse_child(args, 1, Some(&Type::Value))?;
+                let (haystack, _) = self.parse_child(args, 2, Some(&Type::Value))?;
+                (
+                    Expr::In {
+                        needle: Box::new(needle),
+                        haystack: Box::new(haystack),
+                    },
+                    Type::Boolean,
+                )
+            }
+            "index-of" => {
+                self.expect_arity(args, 2..=3)?;
+                let (needle, _) = self.parse_child(args, 1, Some(&Type::Value))?;
+                let (haystack, _) = self.parse_child(args, 2, Some(&Type::Value))?;
+                let from_index = if args.len() == 4 {
+                    Some(Box::new(self.parse_child(args, 3, Some(&Type::Number))?.0))
+                } else {
+                    None
+                };
+                (
+                    Expr::IndexOf {
+                        needle: Box::new(needle),
+                        haystack: Box::new(haystack),
+                        from_index,
+                    },
+                    Type::Number,
+                )
+            }
+            "slice" => {
+                self.expect_arity(args, 2..=3)?;
+                let (input, input_type) = self.parse_child(args, 1, Some(&Type::Value))?;
+                let (begin, _) = self.parse_child(args, 2, Some(&Type::Number))?;
+                let end = if args.len() == 4 {
+                    Some(Box::new(self.parse_child(args, 3, Some(&Type::Number))?.0))
+                } else {
+                    None
+                };
+                let ty = match input_type {
+                    Type::String => Type::String,
+                    Type::Array(item, _) => Type::Array(item, None),
+                    _ => Type::Value,
+                };
+                (
+                    Expr::Slice {
+                        input: Box::new(input),
+                        begin: Box::new(begin),
+                        end,
+                    },
+                    ty,
+                )
+            }
+            "length" => {
+                self.expect_arity(args, 1..=1)?;
+                let (input, input_type) = self.parse_child(args, 1, Some(&Type::Value))?;
+                if !matches!(input_type, Type::String | Type::Array(..) | Type::Value) {
+                    return Err(self.child(1).error(format!(
+                        "Expected argument of type string or array, but found {input_type} instead."
+                    )));
+                }
+                (Expr::Length(Box::new(input)), Type::Number)
+            }
+            "case" => {
+                if args.len() < 4 {
+                    return Err(self.error(format!(
+                        "Expected at least 3 arguments, but found only {}.",
+                        args.len() - 1
+                    )));
+                }
+                if args.len() % 2 != 0 {
+                    return Err(self.error("Expected an odd number of arguments."));
+                }
+                let mut output_type = branch_type;
+                let mut branches = Vec::new();
+                for i in (1..args.len() - 1).step_by(2) {
+                    let (condition, _) = self.parse_child(args, i, Some(&Type::Boolean))?;
+                    let (output, ty) = self.parse_child(args, i + 1, output_type.as_ref())?;
+                    output_type.get_or_insert(ty);
+                    branches.push((condition, output));
+                }
+                let (otherwise, ty) =
+                    self.parse_child(args, args.len() - 1, output_type.as_ref())?;
+                (
+                    Expr::Case {
+                        branches,
+                        otherwise: Box::new(otherwise),
+                    },
+                    output_type.unwrap_or(ty),
+                )
+            }
+            "match" => self.parse_match(args, branch_type)?,
+            "coalesce" => {
+                self.expect_arity(args, 1..=usize::MAX)?;
+                let mut output_type = branch_type.clone();
+                let mut needs_annotation = false;
+                let mut parsed = Vec::new();
+                for (i, arg) in args.iter().enumerate().skip(1) {
+                    let (arg, ty) = self
+                        .child(i)
+                        .parse_annotated(arg, output_type.as_ref(), false)
+                        // Retry without type constraint, the result gets checked by the caller.
+                        .or_else(|_| self.child(i).parse(arg, None))?;
+                    if output_type.is_none() {
+                        output_type = Some(ty.clone());
+                    }
+                    needs_annotation |= output_type
+                        .as_ref()
+                        .is_some_and(|expected| !ty.is_subtype_of(expected));
+                    parsed.push(arg);
+                }
+                let ty = if needs_annotation {
+                    Type::Value
+                } else {
+                    output_type.unwrap_or(Type::Value)
+                };
+                (Expr::Coalesce(parsed), ty)
+            }
+            "step" => {
+                if args.len() < 5 {
+                    return Err(self.error(format!(
+                        "Expected at least 4 arguments, but found only {}.",
+                        args.len() - 1
+                    )));
+                }
+                if (args.len() - 1) % 2 != 0 {
+                    return Err(self.error("Expected an even number of arguments."));
+                }
+                let (input, _) = self.parse_child(args, 1, Some(&Type::Number))?;
+                let (first, ty) = self.parse_child(args, 2, branch_type.as_ref())?;
+                let output_type = branch_type.unwrap_or(ty);
+                let mut stops = vec![(f64::NEG_INFINITY, first)];
+                for i in (3..args.len()).step_by(2) {
+                    let label = self.parse_stop_label(args, i, "step", &stops)?;
+                    let (output, _) = self.parse_child(args, i + 1, Some(&output_type))?;
+                    stops.push((label, output));
+                }
+                (
+                    Expr::Step {
+                        input: Box::new(input),
+                        stops,
+                    },
+                    output_type,
+                )
+            }
+            "interpolate" | "interpolate-hcl" | "interpolate-lab" => {
+                self.parse_interpolate(op, args, branch_type)?
+            }
+            "all" | "any" => {
+                let mut parsed = Vec::with_capacity(args.len() - 1);
+                for i in 1..args.len() {
+                    parsed.push(self.parse_child(args, i, Some(&Type::Boolean))?.0);
+                }
+                let expr = if op == "all" {
+                    Expr::All(parsed)
+                } else {
+                    Expr::Any(parsed)
+                };
+                (expr, Type::Boolean)
+            }
+            "string" | "number" | "boolean" | "object" => {
+                self.expect_arity(args, 1..=usize::MAX)?;
+                let ty = match op {
+                    "string" => Type::String,
+                    "number" => Type::Number,
+                    "boolean" => Type::Boolean,
+                    _ => Type::Object,
+                };
+                let mut parsed = Vec::with_capacity(args.len() - 1);
+                for i in 1..args.len() {
+                    parsed.push(self.parse_child(args, i, Some(&Type::Value))?.0);
+                }
+                (
+                    Expr::Assertion {
+                        ty: ty.clone(),
+                        args: parsed,
+                    },
+                    ty,
+                )
+            }
+            "array" => {
+                self.expect_arity(args, 1..=3)?;
+                let item_type = if args.len() > 2 {
+                    match args[1].as_str() {
+                        Some("string") => Type::String,
+                        Some("number") => Type::Number,
+                        Some("boolean") => Type::Boolean,
+                        _ => {
+                            return Err(self.child(1).error(
+                                "The item type argument of \"array\" must be one of string, number, boolean",
+                            ))
+                        }
+                    }
+                } else {
+                    Type::Value
+                };
+                let length = if args.len() > 3 {
+                    match args[2].as_u64() {
+                        Some(n) => Some(n as usize),
+                        None => return Err(self.child(2).error(
+                            "The length argument to \"array\" must be a positive integer literal",
+                        )),
+                    }
+                } else {
+                    None
+                };
+                let ty = Type::Array(Box::new(item_type), length);
+                let (input, _) = self.parse_child(args, args.len() - 1, Some(&Type::Value))?;
+                (
+                    Expr::Assertion {
+                        ty: ty.clone(),
+                        args: vec![input],
+                    },
+                    ty,
+                )
+            }
+            "to-string" | "to-boolean" | "to-number" | "to-color" => {
+                let (ty, arity) = match op {
+                    "to-string" => (Type::String, 1..=1),
+                    "to-boolean" => (Type::Boolean, 1..=1),
+                    "to-number" => (Type::Number, 1..=usize::MAX),
+                    _ => (Type::Color, 1..=usize::MAX),
+                };
+                self.expect_arity(args, arity)?;
+                let mut parsed = Vec::with_capacity(args.len() - 1);
+                for i in 1..args.len() {
+                    parsed.push(self.parse_child(args, i, Some(&Type::Value))?.0);
+                }
+                (
+                    Expr::Coercion {
+                        ty: ty.clone(),
+                        args: parsed,
+                    },
+                    ty,
+                )
+            }
+            "number-format" => {
+                self.expect_arity(args, 2..=2)?;
+                let (input, _) = self.parse_child(args, 1, Some(&Type::Number))?;
+                let Some(options) = args[2].as_object() else {
+                    return Err(self
+                        .child(2)
+                        .error("Requires an options object as its second argument."));
+                };
+                let option = |name: &str| -> Result<Option<Box<Expr>>, ParseError> {
+                    options
+                        .get(name)
+                        .map(|json| {
+                            self.child(2)
+                                .parse(json, Some(&Type::Number))
+                                .map(|(expr, _)| Box::new(expr))
+                        })
+                        .transpose()
+                };
+                (
+                    Expr::NumberFormat {
+                        input: Box::new(input),
+                        min_fraction_digits: option("min-fraction-digits")?,
+                        max_fraction_digits: option("max-fraction-digits")?,
+                    },
+                    Type::String,
+                )
+            }
+            "format" => {
+                self.expect_arity(args, 1..=usize::MAX)?;
+                let mut sections = Vec::new();
+                for i in 1..args.len() {
+                    // Section options have no effect on the plain text.
+                    if args[i].is_object() {
+                        continue;
+                    }
+                    sections.push(self.parse_child(args, i, Some(&Type::Value))?.0);
+                }
+                (Expr::Format(sections), Type::String)
+            }
+            _ => {
+                if let Some(comparison) = ComparisonOp::from_name(op) {
+                    return self.parse_comparison(comparison, args);
+                }
+                let Some(builtin) = Builtin::from_name(op) else {
+                    return Err(self.child(0).error(format!(
+                        "Unknown expression \"{op}\". If you wanted a literal array, use [\"literal\", [...]]."
+                    )));
+                };
+                let (param_type, arity, output_type) = builtin.signature();
+                self.expect_arity(args, arity)?;
+                let mut parsed = Vec::with_capacity(args.len() - 1);
+                for i in 1..args.len() {
+                    parsed.push(self.parse_child(args, i, Some(&param_type))?.0);
+                }
+                (
+                    Expr::Call {
+                        op: builtin,
+                        args: parsed,
+                    },
+                    output_type,
+                )
+            }
+        })
+    }
+
+    fn parse_stop_label(
+        &self,
+        args: &[JsonValue],
+        index: usize,
+        op: &str,
+        previous: &[(f64, Expr)],
+    ) -> Result<f64, ParseError> {
+        let Some(label) = args[index].as_f64() else {
+            return Err(self.child(index).error(format!(
+                "Input/output pairs for \"{op}\" expressions must be defined using literal numeric values (not computed expressions) for the input values."
+            )));
+        };
+        if previous.last().is_some_and(|(last, _)| *last >= label) {
+            return Err(self.child(index).error(format!(
+                "Input/output pairs for \"{op}\" expressions must be arranged with input values in strictly ascending order."
+            )));
+        }
+        Ok(label)
+    }
+
+    fn parse_match(
+        &self,
+        args: &[JsonValue],
+        branch_type: Option<Type>,
+    ) -> Result<Typed, ParseError> {
+        if args.len() < 5 {
+            return Err(self.error(format!(
+                "Expected at least 4 arguments, but found only {}.",
+                args.len() - 1
+            )));
+        }
+        if args.len() % 2 != 1 {
+            return Err(self.error("Expected an even number of arguments."));
+        }
+
+        let mut input_type: Option<Type> = None;
+        let mut output_type = branch_type;
+        let mut cases = HashMap::new();
+        let mut outputs = Vec::new();
+
+        for i in (2..args.len() - 1).step_by(2) {
+            let labels = match &args[i] {
+                JsonValue::Array(labels) if labels.is_empty() => {
+                    return Err(self.child(i).error("Expected at least one branch label."))
+                }
+                JsonValue::Array(labels) => labels.iter().collect::<Vec<_>>(),
+                label => vec![label],
+            };
+
+            for label in labels {
+                let (label, ty) = match label {
+                    JsonValue::Number(n) => {
+                        let Some(n) = n.as_f64().filter(|n| n.fract() == 0.0) else {
+                            return Err(self
+                                .child(i)
+                                .error("Numeric branch labels must be integer values."));
+                        };
+                        (MatchLabel::Number(n as i64), Type::Number)
+                    }
+                    JsonValue::String(s) => (MatchLabel::String(s.clone()), Type::String),
+                    _ => {
+                        return Err(self
+                            .child(i)
+                            .error("Branch labels must be numbers or strings."))
+                    }
+                };
+                match &input_type {
+                    Some(expected) if *expected != ty => {
+                        return Err(self
+                            .child(i)
+                            .error(format!("Expected {expected} but found {ty} instead.")))
+                    }
+                    _ => input_type = Some(ty),
+                }
+                if cases.contains_key(&label) {
+                    return Err(self.child(i).error("Branch labels must be unique."));
+                }
+                cases.insert(label, outputs.len());
+            }
+
+            let (output, ty) = self.parse_child(args, i + 1, output_type.as_ref())?;
+            output_type.get_or_insert(ty);
+            outputs.push(output);
+        }
+
+        let (input, actual_input_type) = self.parse_child(args, 1, Some(&Type::Value))?;
+        if let Some(input_type) = &input_type {
+            if actual_input_type != Type::Value && actual_input_type != *input_type {
+                return Err(self.child(1).error(format!(
+                    "Expected {input_type} but found {actual_input_type} instead."
+                )));
+            }
+        }
+
+        let (otherwise, ty) = self.parse_child(args, args.len() - 1, output_type.as_ref())?;
+
+        Ok((
+            Expr::Match {
+                input: Box::new(input),
+                cases,
+                outputs,
+                otherwise: Box::new(otherwise),
+            },
+            output_type.unwrap_or(ty),
+        ))
+    }
+
+    fn parse_interpolate(
+        &self,
+        op: &str,
+        args: &[JsonValue],
+        branch_type: Option<Type>,
+    ) -> Result<Typed, ParseError> {
+        if args.len() < 5 {
+            return Err(self.error(format!(
+                "Expected at least 4 arguments, but found only {}.",
+                args.len() - 1
+            )));
+        }
+        if (args.len() - 1) % 2 != 0 {
+            return Err(self.error("Expected an even number of arguments."));
+        }
+
+        let interpolation_context = self.child(1);
+        let interpolation = match args[1].as_array().map(Vec::as_slice) {
+            Some([name]) if name == "linear" => Interpolation::Linear,
+            Some([name, base]) if name == "exponential" => match base.as_f64() {
+                Some(base) => Interpolation::Exponential(base),
+                None => {
+                    return Err(interpolation_context
+                        .child(1)
+                        .error("Exponential interpolation requires a numeric base."))
+                }
+            },
+            Some([name, controls @ ..]) if name == "cubic-bezier" => {
+                let points = controls
+                    .iter()
+                    .filter_map(JsonValue::as_f64)
+                    .collect::<Vec<_>>();
+                match points.as_slice() {
+                    [x1, y1, x2, y2]
+                        if controls.len() == 4 && (0.0..=1.0).contains(x1) && (0.0..=1.0).contains(x2) =>
+                    {
+                        Interpolation::CubicBezier([*x1, *y1, *x2, *y2])
+                    }
+                    _ => {
+                        return Err(interpolation_context.error(
+                            "Cubic bezier interpolation requires four numeric arguments with values between 0 and 1.",
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(
+                    interpolation_context.error(format!("Unknown interpolation type {}", args[1]))
+                )
+            }
+        };
+
+        let space = match op {
+            "interpolate-lab" => ColorSpace::Lab,
+            "interpolate-hcl" => ColorSpace::Hcl,
+            _ => ColorSpace::Rgb,
+        };
+
+        let mut output_type = if space != ColorSpace::Rgb {
+            Some(Type::Color)
+        } else {
+            branch_type
+        };
+
+        let (input, _) = self.parse_child(args, 2, Some(&Type::Number))?;
+
+        let mut stops: Vec<(f64, Expr)> = Vec::new();
+        for i in (3..args.len()).step_by(2) {
+            let label = self.parse_stop_label(args, i, op, &stops)?;
+            let (output, ty) = self.parse_child(args, i + 1, output_type.as_ref())?;
+            output_type.get_or_insert(ty);
+            stops.push((label, output));
+        }
+
+        let output_type = output_type.unwrap_or(Type::Value);
+        if !output_type.is_interpolatable() {
+            return Err(self.error(format!("Type {output_type} is not interpolatable.")));
+        }
+
+        Ok((
+            Expr::Interpolate {
+                interpolation,
+                space,
+                input: Box::new(input),
+                stops,
+            },
+            output_type,
+        ))
+    }
+
+    fn parse_comparison(&self, op: ComparisonOp, args: &[JsonValue]) -> Result<Typed, ParseError> {
+        if args.len() == 4 {
+            return Err(self.child(3).error("Collators are not supported."));
+        }
+        self.expect_arity(args, 2..=2)?;
+        let (lhs, lhs_type) = self.parse_child(args, 1, Some(&Type::Value))?;
+        let (rhs, rhs_type) = self.parse_child(args, 2, Some(&Type::Value))?;
+
+        let is_comparable = |ty: &Type| {
+            if op.is_equality() {
+                matches!(
+                    ty,
+                    Type::String | Type::Number | Type::Boolean | Type::Null | Type::Value
+                )
+            } else {
+                matches!(ty, Type::String | Type::Number | Type::Value)
+            }
+        };
+        if !is_comparable(&lhs_type) {
+            return Err(self.child(1).error(format!(
+                "\"{}\" comparisons are not supported for type '{lhs_type}'.",
+                op.name()
+            )));
+        }
+        if !is_comparable(&rhs_type) {
+            return Err(self.child(2).error(format!(
+                "\"{}\" comparisons are not supported for type '{rhs_type}'.",
+                op.name()
+            )));
+        }
+        if lhs_type != rhs_type && lhs_type != Type::Value && rhs_type != Type::Value {
+            return Err(self.error(format!(
+                "Cannot compare types '{lhs_type}' and '{rhs_type}'."
+            )));
+        }
+
+        Ok((
+            Expr::Comparison {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            },
+            Type::Boolean,
+        ))
+    }
+}
@@ -0,0 +1,296 @@
+//! Runtime values and static types of style expressions.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+use csscolorparser::Color;
+use geozero::{mvt::tile, ColumnValue};
+
+/// The static type of an expression as defined by the
+/// [style specification](https://maplibre.org/maplibre-style-spec/expressions/#types).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Null,
+    Number,
+    String,
+    Boolean,
+    Color,
+    Object,
+    /// Any of the other types. Values of this type need an assertion or coercion before they can
+    /// be used where a more specific type is expected.
+    Value,
+    /// An array with an item type and an optional fixed length.
+    Array(Box<Type>, Option<usize>),
+}
+
+impl Type {
+    pub fn array(item: Type) -> Self {
+        Type::Array(Box::new(item), None)
+    }
+
+    pub fn array_of_length(item: Type, length: usize) -> Self {
+        Type::Array(Box::new(item), Some(length))
+    }
+
+    /// Returns true if a value of type `self` can be used where `expected` is required without
+    /// a runtime check.
+    pub fn is_subtype_of(&self, expected: &Type) -> bool {
+        match (expected, self) {
+            (Type::Value, _) => true,
+            (Type::Array(expected_item, expected_length), Type::Array(item, length)) => {
+                (**expected_item == Type::Value || item.is_subtype_of(expected_item))
+                    && (expected_length.is_none() || expected_length == length)
+            }
+            (expected, actual) => expected == actual,
+        }
+    }
+
+    /// Interpolatable output types of `interpolate` expressions.
+    pub fn is_interpolatable(&self) -> bool {
+        match self {
+            Type::Number | Type::Color => true,
+            Type::Array(item, Some(_)) => **item == Type::Number,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Null => write!(f, "null"),
+            Type::Number => write!(f, "number"),
+            Type::String => write!(f, "string"),
+            Type::Boolean => write!(f, "boolean"),
+            Type::Color => write!(f, "color"),
+            Type::Object => write!(f, "object"),
+            Type::Value => write!(f, "value"),
+            Type::Array(item, None) if **item == Type::Value => write!(f, "array"),
+            Type::Array(item, None) => write!(f, "array<{item}>"),
+            Type::Array(item, Some(length)) => write!(f, "array<{item}, {length}>"),
+        }
+    }
+}
+
+/// A value produced by evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Color(Color),
+    Array(Vec<Value>),
+    Object(BTreeMap<String, Value>),
+}
+
+impl Value {
+    pub fn type_of(&self) -> Type {
+        match self {
+            Value::Null => Type::Null,
+            Value::Boolean(_) => Type::Boolean,
+            Value::Number(_) => Type::Number,
+            Value::String(_) => Type::String,
+            Value::Color(_) => Type::Color,
+            Value::Object(_) => Type::Object,
+            Value::Array(items) => {
+                let mut item_type: Option<Type> = None;
+                for item in items {
+                    let ty = item.type_of();
+                    match &item_type {
+                        None => item_type = Some(ty),
+                        Some(previous) if *previous == ty => {}
+                        Some(_) => {
+                            item_type = Some(Type::Value);
+                            break;
+                        }
+                    }
+                }
+                Type::array_of_length(item_type.unwrap_or(Type::Value), items.len())
+            }
+        }
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Boolean(*b),
+            serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(s) => Value::String(s.clone()),
+            serde_json::Value::Array(items) => {
+                Value::Array(items.iter().map(Value::from_json).collect())
+            }
+            serde_json::Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), Value::from_json(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Truthiness as used by the `to-boolean` expression.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Boolean(b) => *b,
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::String(s) => !s.is_empty(),
+            _ => true,
+        }
+    }
+}
+
+/// Formats numbers like JavaScript does, i.e. integers are printed without a fractional part.
+pub(crate) fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if n.fract() == 0.0 && n.abs() < 1e21 {
+        format!("{}", n as i64)
+    } else {
+        format!("{n}")
+    }
+}
+
+pub(crate) fn format_color(color: &Color) -> String {
+    let [r, g, b, _] = color.to_rgba8();
+    format!("rgba({r},{g},{b},{})", format_number(color.a))
+}
+
+impl Display for Value {
+    /// Converts a value to a string as the `to-string` expression does.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Boolean(b) => write!(f, "{b}"),
+            Value::Number(n) => write!(f, "{}", format_number(*n)),
+            Value::String(s) => write!(f, "{s}"),
+            Value::Color(color) => write!(f, "{}", format_color(color)),
+            Value::Array(_) | Value::Object(_) => write!(f, "{}", serde_json::Value::from(self)),
+        }
+    }
+}
+
+impl From<&Value> for serde_json::Value {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Null => serde_json::Value::Null,
+            Value::Boolean(b) => serde_json::Value::Bool(*b),
+            Value::Number(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::String(s) => serde_json::Value::String(s.clone()),
+            Value::Color(color) => serde_json::Value::String(format_color(color)),
+            Value::Array(items) => serde_json::Value::Array(items.iter().map(Into::into).collect()),
+            Value::Object(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<&ColumnValue<'_>> for Value {
+    fn from(value: &ColumnValue) -> Self {
+        match value {
+            ColumnValue::Byte(v) => Value::Number(*v as f64),
+            ColumnValue::UByte(v) => Value::Number(*v as f64),
+            ColumnValue::Bool(v) => Value::Boolean(*v),
+            ColumnValue::Short(v) => Value::Number(*v as f64),
+            ColumnValue::UShort(v) => Value::Number(*v as f64),
+            ColumnValue::Int(v) => Value::Number(*v as f64),
+            ColumnValue::UInt(v) => Value::Number(*v as f64),
+            ColumnValue::Long(v) => Value::Number(*v as f64),
+            ColumnValue::ULong(v) => Value::Number(*v as f64),
+            ColumnValue::Float(v) => Value::Number(*v as f64),
+            ColumnValue::Double(v) => Value::Number(*v),
+            ColumnValue::String(v) | ColumnValue::DateTime(v) => Value::String(v.to_string()),
+            ColumnValue::Json(v) => serde_json::from_str(v)
+                .map(|json| Value::from_json(&json))
+                .unwrap_or_else(|_| Value::String(v.to_string())),
+            ColumnValue::Binary(_) => Value::Null,
+        }
+    }
+}
+
+impl From<&tile::Value> for Value {
+    fn from(value: &tile::Value) -> Self {
+        if let Some(v) = &value.string_value {
+            Value::String(v.clone())
+        } else if let Some(v) = value.float_value {
+            Value::Number(v as f64)
+        } else if let Some(v) = value.double_value {
+            Value::Number(v)
+        } else if let Some(v) = value.int_value {
+            Value::Number(v as f64)
+        } else if let Some(v) = value.uint_value {
+            Value::Number(v as f64)
+        } else if let Some(v) = value.sint_value {
+            Value::Number(v as f64)
+        } else if let Some(v) = value.bool_value {
+            Value::Boolean(v)
+        } else {
+            Value::Null
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<Color> for Value {
+    fn from(value: Color) -> Self {
+        Value::Color(value)
+    }
+}
@@ -0,0 +1,265 @@
+//! Layer [filters](https://maplibre.org/maplibre-style-spec/layers/#filter) which select the
+//! features of a source layer that are rendered.
+//!
+//! Both expression filters and the deprecated legacy filter syntax are supported. Legacy filters
+//! are converted into expressions, following `convert.ts` of the style specification.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value as JsonValue};
+
+use crate::style::expression::{EvaluationContext, Expression, ParseError, Type, Value};
+
+/// A parsed layer filter.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expression: Expression,
+}
+
+impl Filter {
+    pub fn parse(json: &JsonValue) -> Result<Self, ParseError> {
+        let converted;
+        let source = if is_expression_filter(json) {
+            json
+        } else {
+            converted = convert_filter(json);
+            &converted
+        };
+
+        let expression = Expression::parse(source, Some(&Type::Boolean))?;
+        Ok(Self { expression })
+    }
+
+    /// A filter which excludes all features.
+    pub fn reject_all() -> Self {
+        Self::parse(&JsonValue::Bool(false)).expect("false is a valid filter")
+    }
+
+    /// Returns true if the feature described by `context` passes the filter. Features for which
+    /// the filter fails to evaluate are excluded.
+    pub fn evaluate(&self, context: &EvaluationContext) -> bool {
+        matches!(self.expression.evaluate(context), Ok(Value::Boolean(true)))
+    }
+
+    /// The JSON representation this filter was parsed from.
+    pub fn json(&self) -> &JsonValue {
+        self.expression.json()
+    }
+}
+
+impl Serialize for Filter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.json().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Filter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = JsonValue::deserialize(deserializer)?;
+        Filter::parse(&json).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Distinguishes expression filters from legacy filters, which share some operators.
+fn is_expression_filter(filter: &JsonValue) -> bool {
+    if filter.is_boolean() {
+        return true;
+    }
+    let Some(items) = filter.as_array().filter(|items| !items.is_empty()) else {
+        return false;
+    };
+
+    match items[0].as_str() {
+        Some("has") => items.len() >= 2 && items[1] != "$id" && items[1] != "$type",
+        Some("in") => items.len() >= 3 && (!items[1].is_string() || items[2].is_array()),
+        Some("!in") | Some("!has") | Some("none") => false,
+        Some("==") | Some("!=") | Some(">") | Some(">=") | Some("<") | Some("<=") => {
+            items.len() != 3 || items[1].is_array() || items[2].is_array()
+        }
+        Some("any") | Some("all") => items[1..]
+            .iter()
+            .all(|filter| is_expression_filter(filter) || filter.is_boolean()),
+        _ => true,
+    }
+}
+
+fn convert_filter(filter: &JsonValue) -> JsonValue {
+    let Some(items) = filter.as_array().filter(|items| !items.is_empty()) else {
+        return json!(true);
+    };
+    let op = items[0].as_str().unwrap_or_default();
+    if items.len() <= 1 {
+        return json!(op != "any");
+    }
+
+    match op {
+        "==" | "<" | ">" | "<=" | ">=" if items.len() >= 3 => {
+            convert_comparison_op(&items[1], &items[2], op)
+        }
+        "!=" if items.len() >= 3 => convert_comparison_op(&items[1], &items[2], op),
+        "any" | "all" => {
+            let mut converted = vec![json!(op)];
+            converted.extend(items[1..].iter().map(convert_filter));
+            JsonValue::Array(converted)
+        }
+        "none" => {
+            let mut converted = vec![json!("all")];
+            converted.extend(items[1..].iter().map(|f| json!(["!", convert_filter(f)])));
+            JsonValue::Array(converted)
+        }
+        "in" => convert_in_op(&items[1], &items[2..]),
+        "!in" => json!(["!", convert_in_op(&items[1], &items[2..])]),
+        "has" => convert_has_op(&items[1]),
+        "!has" => json!(["!", convert_has_op(&items[1])]),
+        _ => json!(true),
+    }
+}
+
+fn convert_comparison_op(property: &JsonValue, value: &JsonValue, op: &str) -> JsonValue {
+    let get = match property.as_str() {
+        Some("$type") => return json!([op, ["geometry-type"], value]),
+        Some("$id") => json!(["id"]),
+        _ => json!(["get", property]),
+    };
+
+    if property != "$id" && value.is_null() {
+        match op {
+            "==" => return json!(["all", ["has", property], ["==", get, null]]),
+            "!=" => return json!(["any", ["!", ["has", property]], ["!=", get, null]]),
+            _ => {}
+        }
+    }
+
+    if matches!(op, "==" | "!=") {
+        json!([op, get, value])
+    } else {
+        // Legacy filters exclude features whose property has another type than the value. With
+        // expressions this would be an evaluation error, so the type is checked upfront.
+        let type_name = match value {
+            JsonValue::String(_) => "string",
+            JsonValue::Number(_) => "number",
+            JsonValue::Bool(_) => "boolean",
+            _ => return json!(false),
+        };
+        json!([
+            "all",
+            ["==", ["typeof", get], type_name],
+            [op, [type_name, get], value]
+        ])
+    }
+}
+
+fn convert_in_op(property: &JsonValue, values: &[JsonValue]) -> JsonValue {
+    if values.is_empty() {
+        return json!(false);
+    }
+    let get = match property.as_str() {
+        Some("$type") => json!(["geometry-type"]),
+        Some("$id") => json!(["id"]),
+        _ => json!(["get", property]),
+    };
+
+    let mut converted = vec![json!("any")];
+    converted.extend(values.iter().map(|value| json!(["==", get, value])));
+    JsonValue::Array(converted)
+}
+
+fn convert_has_op(property: &JsonValue) -> JsonValue {
+    match property.as_str() {
+        Some("$type") => json!(true),
+        Some("$id") => json!(["!=", ["id"], null]),
+        _ => json!(["has", property]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::style::expression::{FeatureProperties, GeometryType};
+
+    fn properties(json: JsonValue) -> FeatureProperties {
+        json.as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::from_json(value)))
+            .collect()
+    }
+
+    fn passes(filter: JsonValue, properties: &FeatureProperties) -> bool {
+        Filter::parse(&filter).unwrap().evaluate(
+            &EvaluationContext::default()
+                .with_properties(properties)
+                .with_zoom(12.0)
+                .with_geometry_type(GeometryType::LineString)
+                .with_id(Value::Number(42.0)),
+        )
+    }
+
+    #[test]
+    fn test_legacy_filters() {
+        let props = properties(json!({"class": "primary", "admin_level": 4, "oneway": true}));
+
+        assert!(passes(json!(["==", "class", "primary"]), &props));
+        assert!(!passes(json!(["!=", "class", "primary"]), &props));
+        assert!(passes(json!(["==", "admin_level", 4]), &props));
+        // Types are not coerced, a number does not equal a string
+        assert!(!passes(json!(["==", "admin_level", "4"]), &props));
+        assert!(passes(json!(["<=", "admin_level", 4]), &props));
+        assert!(!passes(json!([">", "class", 4]), &props));
+        assert!(passes(
+            json!(["in", "class", "primary", "secondary"]),
+            &props
+        ));
+        assert!(passes(json!(["!in", "admin_level", 2, 3]), &props));
+        assert!(passes(json!(["has", "oneway"]), &props));
+        assert!(passes(json!(["!has", "name"]), &props));
+        assert!(passes(json!(["==", "$type", "LineString"]), &props));
+        assert!(passes(
+            json!(["in", "$type", "Point", "LineString"]),
+            &props
+        ));
+        assert!(passes(json!(["==", "$id", 42]), &props));
+        assert!(passes(
+            json!([
+                "all",
+                ["==", "class", "primary"],
+                ["none", ["==", "oneway", false]]
+            ]),
+            &props
+        ));
+        assert!(!passes(json!(["any"]), &props));
+        assert!(passes(json!(["all"]), &props));
+    }
+
+    #[test]
+    fn test_expression_filters() {
+        let props = properties(json!({"class": "primary", "rank": 7}));
+
+        assert!(passes(json!(["==", ["get", "class"], "primary"]), &props));
+        assert!(passes(json!([">=", ["zoom"], 10]), &props));
+        assert!(!passes(json!(["<", ["zoom"], 10]), &props));
+        assert!(passes(
+            json!(["==", ["geometry-type"], "LineString"]),
+            &props
+        ));
+        assert!(passes(
+            json!([
+                "all",
+                [">", ["get", "rank"], 5],
+                ["in", ["get", "class"], ["literal", ["primary", "trunk"]]]
+            ]),
+            &props
+        ));
+        assert!(passes(json!(["has", "rank"]), &props));
+        assert!(passes(json!(true), &props));
+        // Filters which fail to evaluate exclude the feature
+        assert!(!passes(json!(["<", ["get", "class"], 5]), &props));
+    }
+
+    #[test]
+    fn test_invalid_filter() {
+        assert!(Filter::parse(&json!(["get", "class"])).is_ok());
+        assert!(Filter::parse(&json!(["+", 1, 2])).is_err());
+    }
+}
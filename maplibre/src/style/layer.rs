@@ -9,212 +9,417 @@ use cint::{Alpha, EncodedSrgb};
 use csscolorparser::Color;
 use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::{
+    coords::ZoomLevel,
+    style::{
+        expression::{EvaluationContext, Expression, FromValue, ParseError, Type, Value},
+        filter::Filter,
+    },
+};
+
+/// A paint or layout property which is either a constant or an [`Expression`].
+#[derive(Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum StyleProperty<T> {
     Constant(T),
-    Expression(serde_json::Value),
+    Expression(Expression),
 }
 
-impl<T: std::str::FromStr + Clone> StyleProperty<T> {
-    pub fn evaluate(&self, feature_properties: &HashMap<String, String>) -> Option<T> {
+impl<T: FromValue + Clone> StyleProperty<T> {
+    /// Evaluates the property. Returns `None` if the expression fails to evaluate, in which case
+    /// the default value of the property should be used.
+    pub fn evaluate(&self, context: &EvaluationContext) -> Option<T> {
         match self {
             StyleProperty::Constant(value) => Some(value.clone()),
-            StyleProperty::Expression(expr) => {
-                if let Some(arr) = expr.as_array() {
-                    if let Some(op) = arr.get(0).and_then(|v| v.as_str()) {
-                        if op == "match" && arr.len() > 3 {
-                            // Extract the getter e.g. ["get", "ADM0_A3"]
-                            if let Some(get_arr) = arr.get(1).and_then(|v| v.as_array()) {
-                                if get_arr.get(0).and_then(|v| v.as_str()) == Some("get") {
-                                    if let Some(prop_name) = get_arr.get(1).and_then(|v| v.as_str())
-                                    {
-                                        let feature_val_opt = feature_properties.get(prop_name);
-
-                                        // If property is missing, skip match pairs and return fallback
-                                        if feature_val_opt.is_none() {
-                                            if let Some(fallback) =
-                                                arr.last().and_then(|v| v.as_str())
-                                            {
-                                                return fallback.parse::<T>().ok();
-                                            }
-                                            return None;
-                                        }
-
-                                        let feature_val = feature_val_opt.unwrap();
-
-                                        // Search the match array pairs
-                                        let mut i = 2;
-                                        while i < arr.len() - 1 {
-                                            if let Some(match_keys) =
-                                                arr.get(i).and_then(|v| v.as_array())
-                                            {
-                                                // Does this feature_val exist in the match keys?
-                                                let matches = match_keys.iter().any(|k| {
-                                                    k.as_str() == Some(feature_val.as_str())
-                                                });
-                                                if matches {
-                                                    if let Some(color_str) =
-                                                        arr.get(i + 1).and_then(|v| v.as_str())
-                                                    {
-                                                        return color_str.parse::<T>().ok();
-                                                    }
-                                                }
-                                            }
-                                            i += 2;
-                                        }
-                                        // Fallback (last element)
-                                        if i == arr.len() - 1 {
-                                            if let Some(fallback) =
-                                                arr.get(i).and_then(|v| v.as_str())
-                                            {
-                                                return fallback.parse::<T>().ok();
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                None
-            }
+            StyleProperty::Expression(expression) => expression
+                .evaluate(context)
+                .map_err(|e| log::debug!("failed to evaluate {expression:?}: {e}"))
+                .ok()
+                .and_then(T::from_value),
         }
     }
 
-    pub fn deserialize_color_or_none<'de, D>(
-        deserializer: D,
-    ) -> Result<Option<StyleProperty<T>>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        // For Color types, allow either a raw color string, or an expression value.
-        let v = serde_json::Value::deserialize(deserializer).map_err(serde::de::Error::custom)?;
-        if let Some(s) = v.as_str() {
-            if let Ok(color) = s.parse::<T>() {
-                return Ok(Some(StyleProperty::Constant(color)));
-            }
+    /// Evaluates a property which does not depend on feature data at the given zoom level.
+    pub fn evaluate_at_zoom(&self, zoom: f32) -> Option<T> {
+        self.evaluate(&EvaluationContext::default().with_zoom(zoom as f64))
+    }
+}
+
+impl<T> StyleProperty<T> {
+    /// Returns true if the value does not change with the zoom level.
+    pub fn is_zoom_constant(&self) -> bool {
+        match self {
+            StyleProperty::Constant(_) => true,
+            StyleProperty::Expression(expression) => expression.is_zoom_constant(),
         }
-        // If it's a structural generic expression like match arrays
-        if v.is_array() {
-            return Ok(Some(StyleProperty::Expression(v)));
+    }
+
+    /// Returns true if the value is the same for all features.
+    pub fn is_feature_constant(&self) -> bool {
+        match self {
+            StyleProperty::Constant(_) => true,
+            StyleProperty::Expression(expression) => expression.is_feature_constant(),
         }
-        Ok(None)
     }
 }
 
-impl StyleProperty<f32> {
-    pub fn deserialize_f32_or_none<'de, D>(
+impl StyleProperty<String> {
+    /// Parses a text property like `text-field`, in which strings may contain `{token}`
+    /// placeholders for feature properties.
+    pub fn from_text(json: &serde_json::Value) -> Result<Self, ParseError> {
+        match json.as_str() {
+            Some(s) if !s.contains('{') => Ok(StyleProperty::Constant(s.to_string())),
+            _ => Expression::parse_with_tokens(json, Some(&String::expected_type()))
+                .map(StyleProperty::Expression),
+        }
+    }
+
+    pub fn deserialize_text_or_none<'de, D>(
         deserializer: D,
-    ) -> Result<Option<StyleProperty<f32>>, D::Error>
+    ) -> Result<Option<StyleProperty<String>>, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let v = serde_json::Value::deserialize(deserializer).map_err(serde::de::Error::custom)?;
-        if let Some(f) = v.as_f64() {
-            return Ok(Some(StyleProperty::Constant(f as f32)));
-        }
-        if v.is_array() {
-            return Ok(Some(StyleProperty::Expression(v)));
-        }
-        // Handle {"stops": [[zoom, value], ...]} format
-        if v.is_object() {
-            return Ok(Some(StyleProperty::Expression(v)));
-        }
-        Ok(None)
+        let v = serde_json::Value::deserialize(deserializer)?;
+        Ok(Self::from_text(&v)
+            .map_err(|e| log::warn!("ignoring invalid text property: {e}"))
+            .ok())
     }
+}
 
-    /// Evaluate a zoom-dependent f32 property at the given zoom level.
-    /// Supports constants and `{"stops": [[z0, v0], [z1, v1], ...]}`.
-    pub fn evaluate_at_zoom(&self, zoom: f32) -> f32 {
-        match self {
-            StyleProperty::Constant(v) => *v,
-            StyleProperty::Expression(expr) => {
-                let stops = expr
-                    .get("stops")
-                    .and_then(|s| s.as_array())
-                    .or_else(|| expr.as_array());
-                let Some(stops) = stops else {
-                    return 1.0;
-                };
-                // Parse stops as [(zoom, value), ...]
-                let parsed: Vec<(f32, f32)> = stops
-                    .iter()
-                    .filter_map(|stop| {
-                        let arr = stop.as_array()?;
-                        let z = arr.first()?.as_f64()? as f32;
-                        let v = arr.get(1)?.as_f64()? as f32;
-                        Some((z, v))
-                    })
-                    .collect();
-
-                if parsed.is_empty() {
-                    return 1.0;
-                }
-                if zoom <= parsed[0].0 {
-                    return parsed[0].1;
-                }
-                if zoom >= parsed[parsed.len() - 1].0 {
-                    return parsed[parsed.len() - 1].1;
-                }
-                // Linear interpolation between stops
-                for window in parsed.windows(2) {
-                    let (z0, v0) = window[0];
-                    let (z1, v1) = window[1];
-                    if zoom >= z0 && zoom <= z1 {
-                        let t = (zoom - z0) / (z1 - z0);
-                        return v0 + t * (v1 - v0);
-                    }
-                }
-                parsed[parsed.len() - 1].1
+impl<'de, T: FromValue + Deserialize<'de>> StyleProperty<T> {
+    fn from_json(json: serde_json::Value) -> Result<Self, String> {
+        if json.is_array() || json.is_object() {
+            if let Ok(value) = T::deserialize(json.clone()) {
+                return Ok(StyleProperty::Constant(value));
             }
+            return Expression::parse(&json, Some(&T::expected_type()))
+                .map(StyleProperty::Expression)
+                .map_err(|e| e.to_string());
         }
+        T::deserialize(json)
+            .map(StyleProperty::Constant)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Deserializes an optional property. Invalid values are logged and treated as missing, so
+    /// that the default value of the property is used.
+    pub fn deserialize_or_none<D>(deserializer: D) -> Result<Option<StyleProperty<T>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v = serde_json::Value::deserialize(deserializer)?;
+        Ok(Self::from_json(v)
+            .map_err(|e| log::warn!("ignoring invalid style property: {e}"))
+            .ok())
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl<'de, T: FromValue + Deserialize<'de>> Deserialize<'de> for StyleProperty<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v = serde_json::Value::deserialize(deserializer)?;
+        Self::from_json(v).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct BackgroundPaint {
     #[serde(rename = "background-color")]
-    #[serde(
-        default,
-        deserialize_with = "StyleProperty::<Color>::deserialize_color_or_none"
-    )]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub background_color: Option<StyleProperty<Color>>,
+
+    /// Name of the image of the sprite which is repeated instead of the color.
+    #[serde(rename = "background-pattern")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_pattern: Option<StyleProperty<String>>,
     // TODO a lot
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct FillPaint {
     #[serde(rename = "fill-color")]
-    #[serde(
-        default,
-        deserialize_with = "StyleProperty::<Color>::deserialize_color_or_none"
-    )]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fill_color: Option<StyleProperty<Color>>,
-    // TODO a lot
+
+    #[serde(rename = "fill-opacity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_opacity: Option<StyleProperty<f32>>,
+
+    /// Color of the outline of the fill, which defaults to the `fill-color`.
+    #[serde(rename = "fill-outline-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_outline_color: Option<StyleProperty<Color>>,
+
+    /// Whether the fill is antialiased by drawing its outline.
+    #[serde(rename = "fill-antialias")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_antialias: Option<bool>,
+
+    #[serde(rename = "fill-translate")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_translate: Option<StyleProperty<[f32; 2]>>,
+
+    #[serde(rename = "fill-translate-anchor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_translate_anchor: Option<Reference>,
+
+    /// Name of the image of the sprite which is repeated instead of the color.
+    #[serde(rename = "fill-pattern")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_pattern: Option<StyleProperty<String>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl FillPaint {
+    /// Returns true if the outline of the fill is drawn.
+    pub fn has_outline(&self) -> bool {
+        self.fill_antialias.unwrap_or(true)
+    }
+
+    /// Returns true if none of the properties which are evaluated per layer depend on the zoom
+    /// level.
+    pub fn is_layer_zoom_constant(&self) -> bool {
+        self.fill_translate
+            .as_ref()
+            .is_none_or(StyleProperty::is_zoom_constant)
+    }
+
+    /// Returns true if none of the properties which are evaluated per feature, apart from the
+    /// `fill-color`, depend on the zoom level.
+    pub fn is_feature_zoom_constant(&self) -> bool {
+        self.fill_opacity
+            .as_ref()
+            .is_none_or(StyleProperty::is_zoom_constant)
+            && self
+                .fill_outline_color
+                .as_ref()
+                .is_none_or(StyleProperty::is_zoom_constant)
+            && self
+                .fill_pattern
+                .as_ref()
+                .is_none_or(StyleProperty::is_zoom_constant)
+    }
+
+    /// Returns true if none of the properties which are evaluated per feature, apart from the
+    /// `fill-color`, depend on the feature.
+    pub fn is_feature_constant(&self) -> bool {
+        self.fill_opacity
+            .as_ref()
+            .is_none_or(StyleProperty::is_feature_constant)
+            && self
+                .fill_outline_color
+                .as_ref()
+                .is_none_or(StyleProperty::is_feature_constant)
+            && self
+                .fill_pattern
+                .as_ref()
+                .is_none_or(StyleProperty::is_feature_constant)
+    }
+}
+
+/// The shape of the ends of lines, as set by the `line-cap` layout property.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineCap {
+    #[default]
+    #[serde(rename = "butt")]
+    Butt,
+    #[serde(rename = "round")]
+    Round,
+    #[serde(rename = "square")]
+    Square,
+}
+
+/// The shape of the corners of lines, as set by the `line-join` layout property.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineJoin {
+    #[serde(rename = "bevel")]
+    Bevel,
+    #[serde(rename = "round")]
+    Round,
+    #[default]
+    #[serde(rename = "miter")]
+    Miter,
+}
+
+/// Implements [`FromValue`] for enums which are deserialized from strings.
+macro_rules! impl_from_value_for_enum {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn expected_type() -> Type {
+                    Type::String
+                }
+
+                fn from_value(value: Value) -> Option<Self> {
+                    match value {
+                        Value::String(s) => {
+                            serde_json::from_value(serde_json::Value::String(s)).ok()
+                        }
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_value_for_enum!(LineCap, LineJoin);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct LinePaint {
     #[serde(rename = "line-color")]
-    #[serde(
-        default,
-        deserialize_with = "StyleProperty::<Color>::deserialize_color_or_none"
-    )]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line_color: Option<StyleProperty<Color>>,
 
     #[serde(rename = "line-width")]
-    #[serde(
-        default,
-        deserialize_with = "StyleProperty::<f32>::deserialize_f32_or_none"
-    )]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
     pub line_width: Option<StyleProperty<f32>>,
+
+    /// Lengths of the alternating dashes and gaps in units of the line width.
+    #[serde(rename = "line-dasharray")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_dasharray: Option<StyleProperty<Vec<f32>>>,
+
+    #[serde(rename = "line-opacity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_opacity: Option<StyleProperty<f32>>,
+
+    #[serde(rename = "line-blur")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_blur: Option<StyleProperty<f32>>,
+
+    /// Width of the gap inside the line. Lines with a gap are drawn as two parallel lines.
+    #[serde(rename = "line-gap-width")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_gap_width: Option<StyleProperty<f32>>,
+
+    /// Offset in pixels perpendicular to the line. Positive values offset the line to the right.
+    #[serde(rename = "line-offset")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_offset: Option<StyleProperty<f32>>,
+
+    #[serde(rename = "line-translate")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_translate: Option<StyleProperty<[f32; 2]>>,
+
+    #[serde(rename = "line-translate-anchor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_translate_anchor: Option<Reference>,
+
+    /// Color along the line, which is defined by an expression of `line-progress`. Requires the
+    /// `lineMetrics` of GeoJSON sources.
+    #[serde(rename = "line-gradient")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_gradient: Option<StyleProperty<Color>>,
+
+    /// Name of the image of the sprite which is repeated along the line instead of the color. The
+    /// image is scaled to the width of the line.
+    #[serde(rename = "line-pattern")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_pattern: Option<StyleProperty<String>>,
+
+    // The following properties are layout properties, which are merged into the paint.
+    #[serde(rename = "line-cap")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_cap: Option<StyleProperty<LineCap>>,
+
+    #[serde(rename = "line-join")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_join: Option<StyleProperty<LineJoin>>,
+
+    /// Miter joins which are longer than this ratio of the line width are drawn as bevel joins.
+    #[serde(rename = "line-miter-limit")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_miter_limit: Option<StyleProperty<f32>>,
+    // TODO: line-round-limit, lyon can not draw round and miter joins in the same line
     // TODO a lot
 }
 
+impl LinePaint {
+    /// Merges the layout properties of line layers into the paint.
+    fn merge_layout(&mut self, layout: &serde_json::Value) {
+        merge_layout_property(&mut self.line_cap, layout, "line-cap");
+        merge_layout_property(&mut self.line_join, layout, "line-join");
+        merge_layout_property(&mut self.line_miter_limit, layout, "line-miter-limit");
+    }
+
+    /// Returns true if none of the properties which are evaluated per layer depend on the zoom
+    /// level.
+    pub fn is_layer_zoom_constant(&self) -> bool {
+        [
+            &self.line_width,
+            &self.line_opacity,
+            &self.line_blur,
+            &self.line_gap_width,
+            &self.line_offset,
+        ]
+        .into_iter()
+        .flatten()
+        .all(StyleProperty::is_zoom_constant)
+            && self
+                .line_dasharray
+                .as_ref()
+                .is_none_or(StyleProperty::is_zoom_constant)
+            && self
+                .line_translate
+                .as_ref()
+                .is_none_or(StyleProperty::is_zoom_constant)
+    }
+
+    /// Returns true if none of the properties which are evaluated per feature, apart from the
+    /// `line-color`, depend on the zoom level.
+    pub fn is_feature_zoom_constant(&self) -> bool {
+        self.line_pattern
+            .as_ref()
+            .is_none_or(StyleProperty::is_zoom_constant)
+    }
+
+    /// Returns true if none of the properties which are evaluated per feature, apart from the
+    /// `line-color`, depend on the feature.
+    pub fn is_feature_constant(&self) -> bool {
+        self.line_pattern
+            .as_ref()
+            .is_none_or(StyleProperty::is_feature_constant)
+    }
+}
+
+/// Sets `property` to the layout property `name` unless it is already set.
+fn merge_layout_property<T: FromValue + for<'de> Deserialize<'de>>(
+    property: &mut Option<StyleProperty<T>>,
+    layout: &serde_json::Value,
+    name: &str,
+) {
+    if property.is_some() {
+        return;
+    }
+    *property = layout.get(name).and_then(|value| {
+        StyleProperty::from_json(value.clone())
+            .map_err(|e| log::warn!("ignoring invalid {name}: {e}"))
+            .ok()
+    });
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum RasterResampling {
     #[serde(rename = "linear")]
@@ -267,70 +472,552 @@ impl Default for RasterPaint {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct SymbolPaint {
     #[serde(rename = "text-field")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_text_or_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub text_field: Option<String>,
+    pub text_field: Option<StyleProperty<String>>,
 
     #[serde(rename = "text-size")]
-    #[serde(
-        default,
-        deserialize_with = "StyleProperty::<f32>::deserialize_f32_or_none"
-    )]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_size: Option<StyleProperty<f32>>,
-    // TODO a lot
+
+    #[serde(rename = "text-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_color: Option<StyleProperty<Color>>,
+
+    #[serde(rename = "text-opacity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_opacity: Option<StyleProperty<f32>>,
+
+    /// Color of the halo which is drawn around the glyphs.
+    #[serde(rename = "text-halo-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_halo_color: Option<StyleProperty<Color>>,
+
+    /// Distance of the halo to the outline of the glyphs in pixels.
+    #[serde(rename = "text-halo-width")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_halo_width: Option<StyleProperty<f32>>,
+
+    /// Distance in pixels over which the halo fades out towards its outside.
+    #[serde(rename = "text-halo-blur")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_halo_blur: Option<StyleProperty<f32>>,
+
+    /// Name of the image of the sprite which is drawn as icon. May contain `{token}`
+    /// placeholders like the `text-field`.
+    #[serde(rename = "icon-image")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_text_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_image: Option<StyleProperty<String>>,
+
+    /// Scale factor of the icon relative to the size of its image.
+    #[serde(rename = "icon-size")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_size: Option<StyleProperty<f32>>,
+
+    /// Clockwise rotation of the icon in degrees.
+    #[serde(rename = "icon-rotate")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_rotate: Option<StyleProperty<f32>>,
+
+    /// Part of the icon which is placed closest to the anchor of the feature.
+    #[serde(rename = "icon-anchor")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_anchor: Option<StyleProperty<SymbolAnchor>>,
+
+    /// Offset of the icon from its anchor in pixels at an `icon-size` of 1.
+    #[serde(rename = "icon-offset")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_offset: Option<StyleProperty<[f32; 2]>>,
+
+    /// Color of icons whose image is a signed distance field.
+    #[serde(rename = "icon-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_color: Option<StyleProperty<Color>>,
+
+    /// Dimensions in which the icon is stretched to fit the text.
+    #[serde(rename = "icon-text-fit")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_text_fit: Option<IconTextFit>,
+
+    /// Padding around the text in pixels when the icon is fitted to it, in the order top, right,
+    /// bottom and left.
+    #[serde(rename = "icon-text-fit-padding")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_text_fit_padding: Option<[f32; 4]>,
 }
 
-/// Extract the property name from a text-field template string like "{NAME}" → "NAME".
-/// If no braces, returns the string as-is.
-fn extract_text_field_property(template: &str) -> String {
-    let trimmed = template.trim();
-    if trimmed.starts_with('{') && trimmed.ends_with('}') {
-        trimmed[1..trimmed.len() - 1].to_string()
-    } else {
-        trimmed.to_string()
+impl SymbolPaint {
+    /// The `text-field` of this layer. Falls back to the `name` property of features, unless the
+    /// layer draws icons.
+    pub fn text_field_or_default(&self) -> Option<StyleProperty<String>> {
+        if self.text_field.is_none() && self.icon_image.is_some() {
+            return None;
+        }
+        Some(self.text_field.clone().unwrap_or_else(|| {
+            StyleProperty::from_text(&serde_json::json!("{name}"))
+                .expect("default text-field must be valid")
+        }))
+    }
+
+    /// Merges the layout properties of symbol layers into the paint.
+    fn merge_layout(&mut self, layout: &serde_json::Value) {
+        if self.text_field.is_none() {
+            self.text_field = parse_text_field_from_layout(layout);
+        }
+        if self.text_size.is_none() {
+            self.text_size = parse_text_size_from_layout(layout);
+        }
+        if self.icon_image.is_none() {
+            self.icon_image = layout.get("icon-image").and_then(|value| {
+                StyleProperty::from_text(value)
+                    .map_err(|e| log::warn!("ignoring invalid icon-image: {e}"))
+                    .ok()
+            });
+        }
+        merge_layout_property(&mut self.icon_size, layout, "icon-size");
+        merge_layout_property(&mut self.icon_rotate, layout, "icon-rotate");
+        merge_layout_property(&mut self.icon_anchor, layout, "icon-anchor");
+        merge_layout_property(&mut self.icon_offset, layout, "icon-offset");
+        if self.icon_text_fit.is_none() {
+            self.icon_text_fit = layout
+                .get("icon-text-fit")
+                .and_then(|value| serde_json::from_value(value.clone()).ok());
+        }
+        if self.icon_text_fit_padding.is_none() {
+            self.icon_text_fit_padding = layout
+                .get("icon-text-fit-padding")
+                .and_then(|value| serde_json::from_value(value.clone()).ok());
+        }
     }
 }
 
-/// Extract a text-field property name from a layout JSON value.
-/// Handles both:
-///   - `"text-field": "{NAME}"` (constant string)
-///   - `"text-field": {"stops": [[2, "{ABBREV}"], [4, "{NAME}"]]}` (zoom-dependent)
-fn parse_text_field_from_layout(layout: &serde_json::Value) -> Option<String> {
-    let tf = layout.get("text-field")?;
-    if let Some(s) = tf.as_str() {
-        return Some(extract_text_field_property(s));
-    }
-    // Zoom-dependent: use the last stop's value (highest zoom = most detailed)
-    if let Some(stops) = tf.get("stops").and_then(|v| v.as_array()) {
-        if let Some(last_stop) = stops.last() {
-            if let Some(s) = last_stop.get(1).and_then(|v| v.as_str()) {
-                return Some(extract_text_field_property(s));
-            }
+/// The part of a symbol which is placed closest to its anchor, as set by the `icon-anchor`
+/// layout property.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymbolAnchor {
+    #[default]
+    #[serde(rename = "center")]
+    Center,
+    #[serde(rename = "left")]
+    Left,
+    #[serde(rename = "right")]
+    Right,
+    #[serde(rename = "top")]
+    Top,
+    #[serde(rename = "bottom")]
+    Bottom,
+    #[serde(rename = "top-left")]
+    TopLeft,
+    #[serde(rename = "top-right")]
+    TopRight,
+    #[serde(rename = "bottom-left")]
+    BottomLeft,
+    #[serde(rename = "bottom-right")]
+    BottomRight,
+}
+
+impl_from_value_for_enum!(SymbolAnchor);
+
+/// The dimensions in which an icon is stretched to fit the text of its symbol, as set by the
+/// `icon-text-fit` layout property.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IconTextFit {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "width")]
+    Width,
+    #[serde(rename = "height")]
+    Height,
+    #[serde(rename = "both")]
+    Both,
+}
+
+/// Whether a property is relative to the map or to the viewport.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    #[serde(rename = "map")]
+    Map,
+    #[serde(rename = "viewport")]
+    Viewport,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CirclePaint {
+    #[serde(rename = "circle-radius")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_radius: Option<StyleProperty<f32>>,
+
+    #[serde(rename = "circle-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_color: Option<StyleProperty<Color>>,
+
+    #[serde(rename = "circle-blur")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_blur: Option<StyleProperty<f32>>,
+
+    #[serde(rename = "circle-opacity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_opacity: Option<StyleProperty<f32>>,
+
+    #[serde(rename = "circle-stroke-width")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_stroke_width: Option<StyleProperty<f32>>,
+
+    #[serde(rename = "circle-stroke-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_stroke_color: Option<StyleProperty<Color>>,
+
+    #[serde(rename = "circle-stroke-opacity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_stroke_opacity: Option<StyleProperty<f32>>,
+
+    #[serde(rename = "circle-translate")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_translate: Option<StyleProperty<[f32; 2]>>,
+
+    #[serde(rename = "circle-translate-anchor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_translate_anchor: Option<Reference>,
+
+    #[serde(rename = "circle-pitch-scale")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_pitch_scale: Option<Reference>,
+
+    #[serde(rename = "circle-pitch-alignment")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_pitch_alignment: Option<Reference>,
+
+    /// Layout property which determines the order in which the circles of a tile are drawn.
+    #[serde(rename = "circle-sort-key")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circle_sort_key: Option<StyleProperty<f32>>,
+}
+
+impl CirclePaint {
+    /// Returns true if none of the paint properties depend on the properties of the features.
+    pub fn is_feature_constant(&self) -> bool {
+        [
+            &self.circle_radius,
+            &self.circle_blur,
+            &self.circle_opacity,
+            &self.circle_stroke_width,
+            &self.circle_stroke_opacity,
+        ]
+        .into_iter()
+        .flatten()
+        .all(StyleProperty::is_feature_constant)
+            && [&self.circle_color, &self.circle_stroke_color]
+                .into_iter()
+                .flatten()
+                .all(StyleProperty::is_feature_constant)
+    }
+
+    /// Returns true if none of the paint properties depend on the zoom level.
+    pub fn is_zoom_constant(&self) -> bool {
+        [
+            &self.circle_radius,
+            &self.circle_blur,
+            &self.circle_opacity,
+            &self.circle_stroke_width,
+            &self.circle_stroke_opacity,
+        ]
+        .into_iter()
+        .flatten()
+        .all(StyleProperty::is_zoom_constant)
+            && [&self.circle_color, &self.circle_stroke_color]
+                .into_iter()
+                .flatten()
+                .all(StyleProperty::is_zoom_constant)
+            && self
+                .circle_translate
+                .as_ref()
+                .is_none_or(StyleProperty::is_zoom_constant)
+    }
+
+    /// Merges the layout properties of circle layers into the paint.
+    fn merge_layout(&mut self, layout: &serde_json::Value) {
+        merge_layout_property(&mut self.circle_sort_key, layout, "circle-sort-key");
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FillExtrusionPaint {
+    #[serde(rename = "fill-extrusion-opacity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_extrusion_opacity: Option<StyleProperty<f32>>,
+
+    #[serde(rename = "fill-extrusion-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_extrusion_color: Option<StyleProperty<Color>>,
+
+    #[serde(rename = "fill-extrusion-translate")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_extrusion_translate: Option<StyleProperty<[f32; 2]>>,
+
+    #[serde(rename = "fill-extrusion-translate-anchor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_extrusion_translate_anchor: Option<Reference>,
+
+    /// Height of the top of the extrusion in meters.
+    #[serde(rename = "fill-extrusion-height")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_extrusion_height: Option<StyleProperty<f32>>,
+
+    /// Height of the bottom of the extrusion in meters.
+    #[serde(rename = "fill-extrusion-base")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_extrusion_base: Option<StyleProperty<f32>>,
+
+    /// Whether the walls are shaded darker towards the ground.
+    #[serde(rename = "fill-extrusion-vertical-gradient")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_extrusion_vertical_gradient: Option<bool>,
+}
+
+impl FillExtrusionPaint {
+    /// Returns true if none of the properties which are evaluated per feature depend on the
+    /// properties of the features.
+    pub fn is_feature_constant(&self) -> bool {
+        [&self.fill_extrusion_height, &self.fill_extrusion_base]
+            .into_iter()
+            .flatten()
+            .all(StyleProperty::is_feature_constant)
+            && self
+                .fill_extrusion_color
+                .as_ref()
+                .is_none_or(StyleProperty::is_feature_constant)
+    }
+
+    /// Returns true if none of the properties which are evaluated per feature depend on the
+    /// zoom level.
+    pub fn is_feature_zoom_constant(&self) -> bool {
+        [&self.fill_extrusion_height, &self.fill_extrusion_base]
+            .into_iter()
+            .flatten()
+            .all(StyleProperty::is_zoom_constant)
+            && self
+                .fill_extrusion_color
+                .as_ref()
+                .is_none_or(StyleProperty::is_zoom_constant)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HeatmapPaint {
+    /// Radius of the kernel of each point in pixels.
+    #[serde(rename = "heatmap-radius")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heatmap_radius: Option<StyleProperty<f32>>,
+
+    /// Contribution of each point to the density.
+    #[serde(rename = "heatmap-weight")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heatmap_weight: Option<StyleProperty<f32>>,
+
+    /// Multiplier of the weights, which is usually increased with the zoom level.
+    #[serde(rename = "heatmap-intensity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heatmap_intensity: Option<StyleProperty<f32>>,
+
+    /// Color of each pixel, which is defined by an expression of `heatmap-density`.
+    #[serde(rename = "heatmap-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heatmap_color: Option<StyleProperty<Color>>,
+
+    #[serde(rename = "heatmap-opacity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heatmap_opacity: Option<StyleProperty<f32>>,
+}
+
+impl HeatmapPaint {
+    /// Returns true if none of the properties which are evaluated per point depend on the
+    /// properties of the features.
+    pub fn is_feature_constant(&self) -> bool {
+        [&self.heatmap_radius, &self.heatmap_weight]
+            .into_iter()
+            .flatten()
+            .all(StyleProperty::is_feature_constant)
+    }
+
+    /// Returns true if none of the properties which are evaluated per point depend on the zoom
+    /// level.
+    pub fn is_feature_zoom_constant(&self) -> bool {
+        [&self.heatmap_radius, &self.heatmap_weight]
+            .into_iter()
+            .flatten()
+            .all(StyleProperty::is_zoom_constant)
+    }
+}
+
+/// A property which takes either a single value or an array of values, like the illumination
+/// of the `multidirectional` hillshade method.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
         }
     }
-    None
+}
+
+impl<T: FromValue> FromValue for OneOrMany<T> {
+    fn expected_type() -> Type {
+        // Expressions evaluate to a single value
+        T::expected_type()
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Array(items) => items
+                .into_iter()
+                .map(T::from_value)
+                .collect::<Option<Vec<_>>>()
+                .map(OneOrMany::Many),
+            value => T::from_value(value).map(OneOrMany::One),
+        }
+    }
+}
+
+/// The algorithm which shades the slopes of a hillshade layer, as set by `hillshade-method`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HillshadeMethod {
+    /// The original algorithm of maplibre-gl, which also draws the accent color.
+    #[default]
+    Standard,
+    Basic,
+    Combined,
+    Igor,
+    /// Combines the illumination from several directions.
+    Multidirectional,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HillshadePaint {
+    /// The directions of the light sources in degrees, clockwise from the top.
+    #[serde(rename = "hillshade-illumination-direction")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hillshade_illumination_direction: Option<StyleProperty<OneOrMany<f32>>>,
+
+    /// The altitudes of the light sources in degrees above the horizon.
+    #[serde(rename = "hillshade-illumination-altitude")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hillshade_illumination_altitude: Option<StyleProperty<OneOrMany<f32>>>,
+
+    /// Whether the illumination direction is relative to the map or to the viewport.
+    #[serde(rename = "hillshade-illumination-anchor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hillshade_illumination_anchor: Option<Reference>,
+
+    /// Intensity of the shading between 0 and 1.
+    #[serde(rename = "hillshade-exaggeration")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hillshade_exaggeration: Option<StyleProperty<f32>>,
+
+    /// Colors of the slopes facing away from the light sources.
+    #[serde(rename = "hillshade-shadow-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hillshade_shadow_color: Option<StyleProperty<OneOrMany<Color>>>,
+
+    /// Colors of the slopes facing the light sources.
+    #[serde(rename = "hillshade-highlight-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hillshade_highlight_color: Option<StyleProperty<OneOrMany<Color>>>,
+
+    /// Color which emphasizes steep slopes with the `standard` method.
+    #[serde(rename = "hillshade-accent-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hillshade_accent_color: Option<StyleProperty<Color>>,
+
+    #[serde(rename = "hillshade-method")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hillshade_method: Option<HillshadeMethod>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ColorReliefPaint {
+    /// Color of each elevation, which is usually an `interpolate` on `["elevation"]`.
+    #[serde(rename = "color-relief-color")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_relief_color: Option<StyleProperty<Color>>,
+
+    #[serde(rename = "color-relief-opacity")]
+    #[serde(default, deserialize_with = "StyleProperty::deserialize_or_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_relief_opacity: Option<StyleProperty<f32>>,
+}
+
+/// Extract text-field from a layout JSON value.
+/// Handles constant strings with `{token}` placeholders, legacy functions and expressions.
+fn parse_text_field_from_layout(layout: &serde_json::Value) -> Option<StyleProperty<String>> {
+    let tf = layout.get("text-field")?;
+    StyleProperty::from_text(tf)
+        .map_err(|e| log::warn!("ignoring invalid text-field: {e}"))
+        .ok()
 }
 
 /// Extract text-size from a layout JSON value.
-/// Handles constant numbers and zoom-dependent `{"stops": [[z, size], ...]}`.
+/// Handles constant numbers, legacy functions and expressions.
 fn parse_text_size_from_layout(layout: &serde_json::Value) -> Option<StyleProperty<f32>> {
     let ts = layout.get("text-size")?;
-    if let Some(f) = ts.as_f64() {
-        return Some(StyleProperty::Constant(f as f32));
-    }
-    // Object with stops or array
-    if ts.is_object() || ts.is_array() {
-        return Some(StyleProperty::Expression(ts.clone()));
-    }
-    None
+    StyleProperty::from_json(ts.clone())
+        .map_err(|e| log::warn!("ignoring invalid text-size: {e}"))
+        .ok()
 }
 
 /// The different types of paints.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type", content = "paint")]
+#[allow(clippy::large_enum_variant)]
 pub enum LayerPaint {
     #[serde(rename = "background")]
     Background(BackgroundPaint),
@@ -342,9 +1029,35 @@ pub enum LayerPaint {
     Raster(RasterPaint),
     #[serde(rename = "symbol")]
     Symbol(SymbolPaint),
+    #[serde(rename = "circle")]
+    Circle(CirclePaint),
+    #[serde(rename = "fill-extrusion")]
+    FillExtrusion(FillExtrusionPaint),
+    #[serde(rename = "heatmap")]
+    Heatmap(HeatmapPaint),
+    #[serde(rename = "hillshade")]
+    Hillshade(HillshadePaint),
+    #[serde(rename = "color-relief")]
+    ColorRelief(ColorReliefPaint),
 }
 
 impl LayerPaint {
+    /// The property which determines the color of the features of this layer.
+    pub fn color_property(&self) -> Option<&StyleProperty<Color>> {
+        match self {
+            LayerPaint::Background(paint) => paint.background_color.as_ref(),
+            LayerPaint::Line(paint) => paint.line_color.as_ref(),
+            LayerPaint::Fill(paint) => paint.fill_color.as_ref(),
+            LayerPaint::Circle(paint) => paint.circle_color.as_ref(),
+            LayerPaint::FillExtrusion(paint) => paint.fill_extrusion_color.as_ref(),
+            LayerPaint::Raster(_)
+            | LayerPaint::Symbol(_)
+            | LayerPaint::Heatmap(_)
+            | LayerPaint::Hillshade(_)
+            | LayerPaint::ColorRelief(_) => None,
+        }
+    }
+
     pub fn get_color(&self) -> Option<Alpha<EncodedSrgb<f32>>> {
         match self {
             LayerPaint::Background(paint) => paint.background_color.as_ref().and_then(|property| {
@@ -368,25 +1081,72 @@ impl LayerPaint {
                     None
                 }
             }),
+            LayerPaint::Circle(paint) => paint.circle_color.as_ref().and_then(|property| {
+                if let StyleProperty::Constant(color) = property {
+                    Some(color.clone().into())
+                } else {
+                    None
+                }
+            }),
+            LayerPaint::FillExtrusion(paint) => {
+                paint.fill_extrusion_color.as_ref().and_then(|property| {
+                    if let StyleProperty::Constant(color) = property {
+                        Some(color.clone().into())
+                    } else {
+                        None
+                    }
+                })
+            }
             LayerPaint::Raster(_) => None,
             LayerPaint::Symbol(_) => None,
+            LayerPaint::Heatmap(_) => None,
+            LayerPaint::Hillshade(_) => None,
+            LayerPaint::ColorRelief(_) => None,
         }
     }
 }
 
+/// Whether a layer is displayed, as set by its `visibility` layout property.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    #[serde(rename = "visible")]
+    Visible,
+    #[serde(rename = "none")]
+    None,
+}
+
 /// Stores all the styles for a specific layer.
 #[derive(Debug, Clone)]
 pub struct StyleLayer {
     pub index: u32,
     pub id: String,
     pub type_: String,
-    pub filter: Option<serde_json::Value>,
+    pub filter: Option<Filter>,
     pub maxzoom: Option<u8>,
     pub minzoom: Option<u8>,
     pub metadata: Option<HashMap<String, String>>,
     pub paint: Option<LayerPaint>,
     pub source: Option<String>,
     pub source_layer: Option<String>,
+    pub visibility: Visibility,
+}
+
+impl StyleLayer {
+    /// Returns true if the layer is visible and `zoom` is within its zoom range.
+    pub fn is_visible_at(&self, zoom: f32) -> bool {
+        self.visibility == Visibility::Visible
+            && self.minzoom.is_none_or(|minzoom| zoom >= minzoom as f32)
+            && self.maxzoom.is_none_or(|maxzoom| zoom < maxzoom as f32)
+    }
+
+    /// Returns true if the zoom range of the layer overlaps with the zoom levels at which a tile
+    /// of the zoom level `tile_zoom` is displayed, i.e. `tile_zoom..tile_zoom + 1`.
+    pub fn is_in_zoom_range_of_tile(&self, tile_zoom: ZoomLevel) -> bool {
+        let tile_zoom = u8::from(tile_zoom);
+        self.minzoom.is_none_or(|minzoom| tile_zoom + 1 > minzoom)
+            && self.maxzoom.is_none_or(|maxzoom| tile_zoom < maxzoom)
+    }
 }
 
 impl Serialize for StyleLayer {
@@ -418,6 +1178,9 @@ impl Serialize for StyleLayer {
         if self.source_layer.is_some() {
             count += 1;
         }
+        if self.visibility != Visibility::Visible {
+            count += 1;
+        }
         let mut map = serializer.serialize_map(Some(count))?;
         map.serialize_entry("id", &self.id)?;
         map.serialize_entry("type", &self.type_)?;
@@ -441,6 +1204,11 @@ impl Serialize for StyleLayer {
                 LayerPaint::Fill(p) => map.serialize_entry("paint", p)?,
                 LayerPaint::Raster(p) => map.serialize_entry("paint", p)?,
                 LayerPaint::Symbol(p) => map.serialize_entry("paint", p)?,
+                LayerPaint::Circle(p) => map.serialize_entry("paint", p)?,
+                LayerPaint::FillExtrusion(p) => map.serialize_entry("paint", p)?,
+                LayerPaint::Heatmap(p) => map.serialize_entry("paint", p)?,
+                LayerPaint::Hillshade(p) => map.serialize_entry("paint", p)?,
+                LayerPaint::ColorRelief(p) => map.serialize_entry("paint", p)?,
             }
         }
         if let Some(ref source) = self.source {
@@ -449,6 +1217,12 @@ impl Serialize for StyleLayer {
         if let Some(ref source_layer) = self.source_layer {
             map.serialize_entry("source-layer", source_layer)?;
         }
+        if self.visibility != Visibility::Visible {
+            map.serialize_entry(
+                "layout",
+                &serde_json::json!({ "visibility": self.visibility }),
+            )?;
+        }
         map.end()
     }
 }
@@ -481,10 +1255,15 @@ impl<'de> serde::Deserialize<'de> for StyleLayer {
                 "background" => serde_json::from_value(p.clone())
                     .map(LayerPaint::Background)
                     .ok(),
-                "line" => serde_json::from_value(p.clone())
-                    .map(LayerPaint::Line)
-                    .map_err(|e| log::error!("line paint failed {}: {:?}", def.id, e))
-                    .ok(),
+                "line" => {
+                    let mut paint: Option<LinePaint> = serde_json::from_value(p.clone())
+                        .map_err(|e| log::error!("line paint failed {}: {:?}", def.id, e))
+                        .ok();
+                    if let (Some(paint), Some(layout)) = (paint.as_mut(), def.layout.as_ref()) {
+                        paint.merge_layout(layout);
+                    }
+                    paint.map(LayerPaint::Line)
+                }
                 "fill" => serde_json::from_value(p.clone())
                     .map(LayerPaint::Fill)
                     .map_err(|e| log::error!("fill paint failed {}: {:?}", def.id, e))
@@ -496,42 +1275,100 @@ impl<'de> serde::Deserialize<'de> for StyleLayer {
                     let mut paint: Option<SymbolPaint> = serde_json::from_value(p.clone())
                         .map_err(|e| log::error!("symbol paint failed {}: {:?}", def.id, e))
                         .ok();
-                    // text-field and text-size live in layout, not paint — merge them in
+                    // text-field, text-size and the icon live in layout, not paint — merge them in
                     if let (Some(sp), Some(layout)) = (paint.as_mut(), def.layout.as_ref()) {
-                        if sp.text_field.is_none() {
-                            sp.text_field = parse_text_field_from_layout(layout);
-                        }
-                        if sp.text_size.is_none() {
-                            sp.text_size = parse_text_size_from_layout(layout);
-                        }
+                        sp.merge_layout(layout);
                     }
                     paint.map(LayerPaint::Symbol)
                 }
+                "circle" => {
+                    let mut paint: Option<CirclePaint> = serde_json::from_value(p.clone())
+                        .map_err(|e| log::error!("circle paint failed {}: {:?}", def.id, e))
+                        .ok();
+                    if let (Some(paint), Some(layout)) = (paint.as_mut(), def.layout.as_ref()) {
+                        paint.merge_layout(layout);
+                    }
+                    paint.map(LayerPaint::Circle)
+                }
+                "fill-extrusion" => serde_json::from_value(p.clone())
+                    .map(LayerPaint::FillExtrusion)
+                    .map_err(|e| log::error!("fill-extrusion paint failed {}: {:?}", def.id, e))
+                    .ok(),
+                "heatmap" => serde_json::from_value(p.clone())
+                    .map(LayerPaint::Heatmap)
+                    .map_err(|e| log::error!("heatmap paint failed {}: {:?}", def.id, e))
+                    .ok(),
+                "hillshade" => serde_json::from_value(p.clone())
+                    .map(LayerPaint::Hillshade)
+                    .map_err(|e| log::error!("hillshade paint failed {}: {:?}", def.id, e))
+                    .ok(),
+                "color-relief" => serde_json::from_value(p.clone())
+                    .map(LayerPaint::ColorRelief)
+                    .map_err(|e| log::error!("color-relief paint failed {}: {:?}", def.id, e))
+                    .ok(),
                 _ => None,
             }
         } else if def.type_ == "symbol" {
             // Symbol layers may have no paint but still have layout with text-field/text-size
-            let text_field = def.layout.as_ref().and_then(parse_text_field_from_layout);
-            let text_size = def.layout.as_ref().and_then(parse_text_size_from_layout);
-            Some(LayerPaint::Symbol(SymbolPaint {
-                text_field,
-                text_size,
-            }))
+            let mut paint = SymbolPaint::default();
+            if let Some(layout) = def.layout.as_ref() {
+                paint.merge_layout(layout);
+            }
+            Some(LayerPaint::Symbol(paint))
+        } else if def.type_ == "line" {
+            // The caps and joins of lines are layout properties, which apply without paint
+            let mut paint = LinePaint::default();
+            if let Some(layout) = def.layout.as_ref() {
+                paint.merge_layout(layout);
+            }
+            Some(LayerPaint::Line(paint))
+        } else if def.type_ == "circle" {
+            // All circle paint properties have defaults, so circle layers are drawn without paint
+            let mut paint = CirclePaint::default();
+            if let Some(layout) = def.layout.as_ref() {
+                paint.merge_layout(layout);
+            }
+            Some(LayerPaint::Circle(paint))
+        } else if def.type_ == "fill-extrusion" {
+            // Like circles, extrusions have defaults for all paint properties
+            Some(LayerPaint::FillExtrusion(FillExtrusionPaint::default()))
+        } else if def.type_ == "heatmap" {
+            Some(LayerPaint::Heatmap(HeatmapPaint::default()))
+        } else if def.type_ == "hillshade" {
+            Some(LayerPaint::Hillshade(HillshadePaint::default()))
+        } else if def.type_ == "color-relief" {
+            Some(LayerPaint::ColorRelief(ColorReliefPaint::default()))
         } else {
             None
         };
 
+        // An invalid filter hides all features of the layer, instead of showing all of them
+        let filter = def.filter.map(|filter| {
+            Filter::parse(&filter).unwrap_or_else(|e| {
+                log::error!("filter of layer {} is invalid: {e}", def.id);
+                Filter::reject_all()
+            })
+        });
+
+        let visibility = def
+            .layout
+            .as_ref()
+            .and_then(|layout| layout.get("visibility"))
+            .and_then(|visibility| serde_json::from_value(visibility.clone()).ok())
+            .unwrap_or_default();
+
         Ok(StyleLayer {
             index: 0,
             id: def.id,
             type_: def.type_,
-            filter: def.filter,
+            filter,
             maxzoom: def.maxzoom,
             minzoom: def.minzoom,
             metadata: def.metadata,
             paint,
             source: def.source,
             source_layer: def.source_layer,
+            visibility,
         })
     }
 }
@@ -562,6 +1399,7 @@ impl Default for StyleLayer {
             paint: None,
             source: None,
             source_layer: Some("does not exist".to_string()),
+            visibility: Visibility::Visible,
         }
     }
 }
@@ -569,6 +1407,7 @@ impl Default for StyleLayer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::style::expression::{FeatureProperties, Value};
 
     #[test]
     fn test_evaluate_match_missing_property_returns_fallback() {
@@ -581,12 +1420,12 @@ mod tests {
             "rgba(9, 9, 9, 1)"
         ]
         "#;
-        let expr: serde_json::Value = serde_json::from_str(json).unwrap();
-        let prop: StyleProperty<csscolorparser::Color> = StyleProperty::Expression(expr);
+        let prop: StyleProperty<csscolorparser::Color> = serde_json::from_str(json).unwrap();
 
         // Feature that does NOT have the property → should return the JSON fallback color
         let empty_props = HashMap::new();
-        let color = prop.evaluate(&empty_props).unwrap();
+        let context = EvaluationContext::default().with_properties(&empty_props);
+        let color = prop.evaluate(&context).unwrap();
         assert_eq!(color.to_rgba8(), [9, 9, 9, 255]);
     }
 
@@ -601,13 +1440,13 @@ mod tests {
             "rgba(0, 0, 0, 1)"
         ]
         "#;
-        let expr: serde_json::Value = serde_json::from_str(json).unwrap();
-        let prop: StyleProperty<csscolorparser::Color> = StyleProperty::Expression(expr);
+        let prop: StyleProperty<csscolorparser::Color> = serde_json::from_str(json).unwrap();
 
         let mut feature_properties = HashMap::new();
-        feature_properties.insert("ADM0_A3".to_string(), "ARM".to_string());
+        feature_properties.insert("ADM0_A3".to_string(), Value::from("ARM"));
 
-        let color = prop.evaluate(&feature_properties).unwrap();
+        let context = EvaluationContext::default().with_properties(&feature_properties);
+        let color = prop.evaluate(&context).unwrap();
         assert_eq!(color.to_rgba8(), [1, 2, 3, 255]);
     }
 
@@ -628,9 +1467,13 @@ mod tests {
         }"#;
         let layer: StyleLayer = serde_json::from_str(json).unwrap();
         assert_eq!(layer.type_, "symbol");
+        let mut properties = HashMap::new();
+        properties.insert("NAME".to_string(), Value::from("Armenia"));
+        let context = EvaluationContext::default().with_properties(&properties);
         match &layer.paint {
             Some(LayerPaint::Symbol(sp)) => {
-                assert_eq!(sp.text_field.as_deref(), Some("NAME"));
+                let text_field = sp.text_field.as_ref().unwrap();
+                assert_eq!(text_field.evaluate(&context).as_deref(), Some("Armenia"));
             }
             other => panic!("expected Symbol paint, got {:?}", other),
         }
@@ -649,15 +1492,80 @@ mod tests {
             "source-layer": "centroids"
         }"#;
         let layer: StyleLayer = serde_json::from_str(json).unwrap();
+        let mut properties = HashMap::new();
+        properties.insert("ABBREV".to_string(), Value::from("Arm."));
+        properties.insert("NAME".to_string(), Value::from("Armenia"));
+        let context = EvaluationContext::default().with_properties(&properties);
         match &layer.paint {
             Some(LayerPaint::Symbol(sp)) => {
-                // Should pick the last stop (highest zoom) → NAME
-                assert_eq!(sp.text_field.as_deref(), Some("NAME"));
+                let text_field = sp.text_field.as_ref().unwrap();
+                assert!(!text_field.is_zoom_constant());
+                assert_eq!(
+                    text_field
+                        .evaluate(&context.clone().with_zoom(3.0))
+                        .as_deref(),
+                    Some("Arm.")
+                );
+                assert_eq!(
+                    text_field.evaluate(&context.with_zoom(5.0)).as_deref(),
+                    Some("Armenia")
+                );
             }
             other => panic!("expected Symbol paint, got {:?}", other),
         }
     }
 
+    #[test]
+    fn test_symbol_icon_from_layout() {
+        let json = r#"{
+            "id": "poi",
+            "type": "symbol",
+            "layout": {
+                "icon-image": "{maki}-15",
+                "icon-size": 2,
+                "icon-anchor": "bottom",
+                "icon-offset": [0, -4],
+                "icon-text-fit": "both",
+                "icon-text-fit-padding": [2, 4, 2, 4]
+            },
+            "source": "maplibre",
+            "source-layer": "poi"
+        }"#;
+        let layer: StyleLayer = serde_json::from_str(json).unwrap();
+        let mut properties = HashMap::new();
+        properties.insert("maki".to_string(), Value::from("cafe"));
+        let context = EvaluationContext::default().with_properties(&properties);
+        match &layer.paint {
+            Some(LayerPaint::Symbol(sp)) => {
+                let icon_image = sp.icon_image.as_ref().unwrap();
+                assert_eq!(icon_image.evaluate(&context).as_deref(), Some("cafe-15"));
+                assert_eq!(sp.icon_size.as_ref().unwrap().evaluate(&context), Some(2.0));
+                assert_eq!(
+                    sp.icon_anchor.as_ref().unwrap().evaluate(&context),
+                    Some(SymbolAnchor::Bottom)
+                );
+                assert_eq!(
+                    sp.icon_offset.as_ref().unwrap().evaluate(&context),
+                    Some([0.0, -4.0])
+                );
+                assert_eq!(sp.icon_text_fit, Some(IconTextFit::Both));
+                assert_eq!(sp.icon_text_fit_padding, Some([2.0, 4.0, 2.0, 4.0]));
+            }
+            other => panic!("expected Symbol paint, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_text_size_zoom_dependent() {
+        let layout = serde_json::json!({
+            "text-size": ["interpolate", ["linear"], ["zoom"], 2, 10, 6, 18]
+        });
+        let text_size = parse_text_size_from_layout(&layout).unwrap();
+        assert_eq!(text_size.evaluate_at_zoom(1.0), Some(10.0));
+        assert_eq!(text_size.evaluate_at_zoom(4.0), Some(14.0));
+        assert_eq!(text_size.evaluate_at_zoom(8.0), Some(18.0));
+    }
+
     #[test]
     fn test_demotiles_symbol_layers_have_text_field() {
         let style: crate::style::Style = Default::default();
@@ -676,4 +1584,397 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_visibility_from_layout() {
+        let json = r#"{
+            "id": "water",
+            "type": "fill",
+            "paint": {},
+            "layout": {
+                "visibility": "none"
+            },
+            "source": "maplibre",
+            "source-layer": "water"
+        }"#;
+        let layer: StyleLayer = serde_json::from_str(json).unwrap();
+        assert_eq!(layer.visibility, Visibility::None);
+        assert!(!layer.is_visible_at(5.0));
+
+        let serialized = serde_json::to_value(&layer).unwrap();
+        assert_eq!(serialized["layout"]["visibility"], "none");
+        let layer: StyleLayer = serde_json::from_value(serialized).unwrap();
+        assert_eq!(layer.visibility, Visibility::None);
+
+        let layer = StyleLayer::default();
+        assert_eq!(layer.visibility, Visibility::Visible);
+        assert!(serde_json::to_value(&layer)
+            .unwrap()
+            .get("layout")
+            .is_none());
+    }
+
+    #[test]
+    fn test_invalid_filter() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "roads",
+            "type": "line",
+            "source": "maplibre",
+            "source-layer": "roads",
+            "filter": ["==", ["get", "class"]]
+        }))
+        .unwrap();
+
+        let properties = FeatureProperties::from([("class".to_string(), Value::from("road"))]);
+        let context = EvaluationContext::default().with_properties(&properties);
+        assert!(!layer.filter.unwrap().evaluate(&context));
+    }
+
+    #[test]
+    fn test_zoom_range() {
+        let layer = StyleLayer {
+            minzoom: Some(4),
+            maxzoom: Some(8),
+            ..StyleLayer::default()
+        };
+        assert!(!layer.is_visible_at(3.9));
+        assert!(layer.is_visible_at(4.0));
+        assert!(layer.is_visible_at(7.9));
+        assert!(!layer.is_visible_at(8.0));
+
+        // A tile of zoom level 3 is only displayed until zoom level 4
+        assert!(!layer.is_in_zoom_range_of_tile(ZoomLevel::new(3)));
+        assert!(layer.is_in_zoom_range_of_tile(ZoomLevel::new(4)));
+        assert!(layer.is_in_zoom_range_of_tile(ZoomLevel::new(7)));
+        assert!(!layer.is_in_zoom_range_of_tile(ZoomLevel::new(8)));
+    }
+
+    #[test]
+    fn test_circle_paint() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "circles",
+            "type": "circle",
+            "source": "points",
+            "layout": {"circle-sort-key": ["get", "rank"]},
+            "paint": {
+                "circle-radius": ["get", "size"],
+                "circle-translate": [5, 5],
+                "circle-pitch-alignment": "map"
+            }
+        }))
+        .unwrap();
+        let Some(LayerPaint::Circle(paint)) = &layer.paint else {
+            panic!("expected a circle paint");
+        };
+        assert!(!paint.is_feature_constant());
+        assert!(paint.is_zoom_constant());
+        assert!(paint.circle_sort_key.is_some());
+        assert_eq!(paint.circle_pitch_alignment, Some(Reference::Map));
+        assert!(matches!(
+            paint.circle_translate,
+            Some(StyleProperty::Constant([5.0, 5.0]))
+        ));
+
+        // Circle layers without paint use the default paint properties
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "circles",
+            "type": "circle",
+            "source": "points"
+        }))
+        .unwrap();
+        assert!(matches!(layer.paint, Some(LayerPaint::Circle(_))));
+    }
+
+    #[test]
+    fn test_fill_extrusion_paint() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "buildings",
+            "type": "fill-extrusion",
+            "source": "openmaptiles",
+            "source-layer": "building",
+            "paint": {
+                "fill-extrusion-height": ["get", "render_height"],
+                "fill-extrusion-base": 5,
+                "fill-extrusion-vertical-gradient": false
+            }
+        }))
+        .unwrap();
+        let Some(LayerPaint::FillExtrusion(paint)) = &layer.paint else {
+            panic!("expected a fill-extrusion paint");
+        };
+        assert!(!paint.is_feature_constant());
+        assert!(paint.is_feature_zoom_constant());
+        assert_eq!(paint.fill_extrusion_vertical_gradient, Some(false));
+    }
+
+    #[test]
+    fn test_heatmap_paint() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "incidents",
+            "type": "heatmap",
+            "source": "incidents",
+            "paint": {
+                "heatmap-weight": ["get", "mag"],
+                "heatmap-intensity": ["interpolate", ["linear"], ["zoom"], 0, 1, 9, 3],
+                "heatmap-color": [
+                    "interpolate", ["linear"], ["heatmap-density"],
+                    0, "rgba(0, 0, 255, 0)",
+                    1, "red"
+                ]
+            }
+        }))
+        .unwrap();
+        let Some(LayerPaint::Heatmap(paint)) = &layer.paint else {
+            panic!("expected a heatmap paint");
+        };
+        assert!(!paint.is_feature_constant());
+        assert!(paint.is_feature_zoom_constant());
+        let color = paint.heatmap_color.as_ref().unwrap().evaluate(
+            &crate::style::expression::EvaluationContext::default().with_heatmap_density(1.0),
+        );
+        assert_eq!(color, Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+
+        // Heatmaps are drawn with the default paint if the layer has none
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "incidents",
+            "type": "heatmap",
+            "source": "incidents"
+        }))
+        .unwrap();
+        assert!(matches!(layer.paint, Some(LayerPaint::Heatmap(_))));
+    }
+
+    #[test]
+    fn test_hillshade_paint() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "hillshade",
+            "type": "hillshade",
+            "source": "terrain",
+            "paint": {
+                "hillshade-method": "multidirectional",
+                "hillshade-illumination-direction": [270, 315, 0, 45],
+                "hillshade-highlight-color": ["#FF4000", "#FFFF00", "#40ff00", "#00FF80"],
+                "hillshade-shadow-color": {"stops": [[10, "#0473c0"], [12, "#04c04c"]]},
+                "hillshade-accent-color": "purple"
+            }
+        }))
+        .unwrap();
+        let Some(LayerPaint::Hillshade(paint)) = &layer.paint else {
+            panic!("expected a hillshade paint");
+        };
+        assert_eq!(
+            paint.hillshade_method,
+            Some(HillshadeMethod::Multidirectional)
+        );
+        let directions = paint.hillshade_illumination_direction.as_ref().unwrap();
+        assert_eq!(
+            directions.evaluate_at_zoom(0.0),
+            Some(OneOrMany::Many(vec![270.0, 315.0, 0.0, 45.0]))
+        );
+        let highlights = paint.hillshade_highlight_color.as_ref().unwrap();
+        assert_eq!(
+            highlights.evaluate_at_zoom(0.0).unwrap().into_vec().len(),
+            4
+        );
+        let shadows = paint.hillshade_shadow_color.as_ref().unwrap();
+        assert!(matches!(
+            shadows.evaluate_at_zoom(11.0),
+            Some(OneOrMany::One(_))
+        ));
+
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "hillshade",
+            "type": "hillshade",
+            "source": "terrain",
+            "paint": {
+                "hillshade-illumination-direction": 335,
+                "hillshade-exaggeration": ["interpolate", ["linear"], ["zoom"], 10, 0.2, 14, 0.8]
+            }
+        }))
+        .unwrap();
+        let Some(LayerPaint::Hillshade(paint)) = &layer.paint else {
+            panic!("expected a hillshade paint");
+        };
+        assert_eq!(
+            paint
+                .hillshade_illumination_direction
+                .as_ref()
+                .unwrap()
+                .evaluate_at_zoom(0.0),
+            Some(OneOrMany::One(335.0))
+        );
+        assert_eq!(
+            paint
+                .hillshade_exaggeration
+                .as_ref()
+                .unwrap()
+                .evaluate_at_zoom(12.0),
+            Some(0.5)
+        );
+    }
+
+    #[test]
+    fn test_color_relief_paint() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "color-relief",
+            "type": "color-relief",
+            "source": "terrain",
+            "paint": {
+                "color-relief-opacity": 0.5,
+                "color-relief-color": [
+                    "interpolate", ["linear"], ["elevation"], 400, "#F00", 2000, "#00F"
+                ]
+            }
+        }))
+        .unwrap();
+        let Some(LayerPaint::ColorRelief(paint)) = &layer.paint else {
+            panic!("expected a color-relief paint");
+        };
+        assert_eq!(
+            paint
+                .color_relief_opacity
+                .as_ref()
+                .unwrap()
+                .evaluate_at_zoom(0.0),
+            Some(0.5)
+        );
+        let color = paint.color_relief_color.as_ref().unwrap().evaluate(
+            &crate::style::expression::EvaluationContext::default().with_elevation(2000.0),
+        );
+        assert_eq!(color, Some(Color::new(0.0, 0.0, 1.0, 1.0)));
+
+        // Color reliefs are parsed without paint, although they are transparent by default
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "color-relief",
+            "type": "color-relief",
+            "source": "terrain"
+        }))
+        .unwrap();
+        assert!(matches!(layer.paint, Some(LayerPaint::ColorRelief(_))));
+    }
+
+    #[test]
+    fn test_line_layout() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "lines",
+            "type": "line",
+            "source": "roads",
+            "layout": {
+                "line-cap": "round",
+                "line-join": {"type": "identity", "property": "join"},
+                "line-miter-limit": 3
+            },
+            "paint": {"line-width": 8}
+        }))
+        .unwrap();
+        let Some(LayerPaint::Line(paint)) = &layer.paint else {
+            panic!("expected a line paint");
+        };
+        assert!(matches!(
+            paint.line_cap,
+            Some(StyleProperty::Constant(LineCap::Round))
+        ));
+        assert!(matches!(
+            paint.line_miter_limit,
+            Some(StyleProperty::Constant(3.0))
+        ));
+
+        let join = paint.line_join.as_ref().unwrap();
+        let properties = HashMap::from([("join".to_string(), Value::String("bevel".to_string()))]);
+        let context = EvaluationContext::default().with_properties(&properties);
+        assert_eq!(join.evaluate(&context), Some(LineJoin::Bevel));
+    }
+
+    #[test]
+    fn test_line_layout_without_paint() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "lines",
+            "type": "line",
+            "source": "roads",
+            "layout": {"line-cap": "round", "line-join": "round"}
+        }))
+        .unwrap();
+        let Some(LayerPaint::Line(paint)) = &layer.paint else {
+            panic!("expected a line paint");
+        };
+        assert!(matches!(
+            paint.line_cap,
+            Some(StyleProperty::Constant(LineCap::Round))
+        ));
+        assert!(matches!(
+            paint.line_join,
+            Some(StyleProperty::Constant(LineJoin::Round))
+        ));
+    }
+
+    #[test]
+    fn test_line_paint() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "lines",
+            "type": "line",
+            "source": "roads",
+            "paint": {
+                "line-opacity": 0.5,
+                "line-gap-width": 4,
+                "line-offset": {"stops": [[0, 0], [10, 8]]},
+                "line-translate": [2, -2],
+                "line-translate-anchor": "viewport"
+            }
+        }))
+        .unwrap();
+        let Some(LayerPaint::Line(paint)) = &layer.paint else {
+            panic!("expected a line paint");
+        };
+        assert!(matches!(
+            paint.line_opacity,
+            Some(StyleProperty::Constant(0.5))
+        ));
+        assert!(matches!(
+            paint.line_gap_width,
+            Some(StyleProperty::Constant(4.0))
+        ));
+        assert!(matches!(
+            paint.line_translate,
+            Some(StyleProperty::Constant([2.0, -2.0]))
+        ));
+        assert_eq!(paint.line_translate_anchor, Some(Reference::Viewport));
+        assert!(paint.line_blur.is_none());
+        // The offset is interpolated between the stops
+        assert!(!paint.is_layer_zoom_constant());
+        assert_eq!(
+            paint.line_offset.as_ref().unwrap().evaluate_at_zoom(5.0),
+            Some(4.0)
+        );
+    }
+
+    #[test]
+    fn test_fill_paint() {
+        let layer: StyleLayer = serde_json::from_value(serde_json::json!({
+            "id": "water",
+            "type": "fill",
+            "source": "land",
+            "paint": {
+                "fill-opacity": ["get", "opacity"],
+                "fill-outline-color": "red",
+                "fill-translate": [4, 4],
+                "fill-translate-anchor": "viewport"
+            }
+        }))
+        .unwrap();
+        let Some(LayerPaint::Fill(paint)) = &layer.paint else {
+            panic!("expected a fill paint");
+        };
+        assert!(paint.has_outline());
+        assert!(!paint.is_feature_constant());
+        assert!(paint.is_feature_zoom_constant());
+        assert!(paint.is_layer_zoom_constant());
+        assert_eq!(paint.fill_translate_anchor, Some(Reference::Viewport));
+
+        let paint = FillPaint {
+            fill_antialias: Some(false),
+            ..FillPaint::default()
+        };
+        assert!(!paint.has_outline());
+        assert!(paint.is_feature_constant());
+    }
 }
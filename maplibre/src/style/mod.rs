@@ -18,21 +18,26 @@
 // Use manual styel
 // ----------------------
 
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
 pub use cint::*;
 use csscolorparser::Color;
 use serde::{Deserialize, Serialize};
 
+pub mod expression;
+pub mod filter;
 pub mod layer;
 pub mod source;
 
 use crate::style::{
     layer::{
-        BackgroundPaint, FillPaint, LayerPaint, LinePaint, RasterPaint, StyleLayer, StyleProperty,
-        SymbolPaint,
+        BackgroundPaint, FillPaint, LayerPaint, LinePaint, RasterPaint, Reference, StyleLayer,
+        StyleProperty, SymbolPaint, Visibility,
     },
-    source::Source,
+    source::{Source, VectorSource},
 };
 
 /// Stores the style for a multi-layered map.
@@ -49,6 +54,135 @@ pub struct Style {
     pub center: Option<[f64; 2]>, // TODO: Use LatLon type here
     pub zoom: Option<f64>,
     pub pitch: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sprite: Option<Sprite>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub light: Option<Light>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terrain: Option<Terrain>,
+}
+
+/// The images of a style which are used for patterns and icons. The url of a sprite has no file
+/// extension, the sprite sheet and its index are fetched from `<url>.png` and `<url>.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Sprite {
+    Url(String),
+    Sources(Vec<SpriteSource>),
+}
+
+/// One of multiple sprites of a style.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpriteSource {
+    pub id: String,
+    pub url: String,
+}
+
+impl Sprite {
+    /// Returns the prefix of the image ids and the url of each sprite. Images of sprites other
+    /// than the one with the id `default` are referenced as `<id>:<image>`.
+    pub fn sources(&self) -> Vec<(String, &str)> {
+        match self {
+            Sprite::Url(url) => vec![(String::new(), url.as_str())],
+            Sprite::Sources(sources) => sources
+                .iter()
+                .map(|source| {
+                    let prefix = if source.id == "default" {
+                        String::new()
+                    } else {
+                        format!("{}:", source.id)
+                    };
+                    (prefix, source.url.as_str())
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The light which shades 3D layers like `fill-extrusion`. Only constant values are supported.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Light {
+    /// Whether the light rotates with the map or stays fixed relative to the viewport.
+    pub anchor: Reference,
+    /// The radial coordinate, the azimuthal angle and the polar angle of the position of the
+    /// light. The angles are in degrees, the azimuthal angle is measured clockwise from north.
+    pub position: [f64; 3],
+    pub color: Color,
+    pub intensity: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            anchor: Reference::Viewport,
+            position: [1.15, 210.0, 30.0],
+            color: Color::new(1.0, 1.0, 1.0, 1.0),
+            intensity: 0.5,
+        }
+    }
+}
+
+impl Light {
+    /// Returns the vector of the light in the coordinates of tiles, in which the y-axis points
+    /// south and the z-axis points up. Surfaces are lit by the dot product of their normal and
+    /// this vector. The `bearing` of the map in degrees rotates lights which are anchored to the
+    /// viewport.
+    ///
+    /// Adopted from
+    /// [sphericalToCartesian](https://github.com/maplibre/maplibre-gl-js/blob/main/src/util/util.ts).
+    pub fn direction(&self, bearing: f64) -> [f32; 3] {
+        let [radial, azimuthal, polar] = self.position;
+        let mut azimuthal = (azimuthal + 90.0).to_radians();
+        if self.anchor == Reference::Viewport {
+            azimuthal += bearing.to_radians();
+        }
+        let polar = polar.to_radians();
+        [
+            (radial * azimuthal.cos() * polar.sin()) as f32,
+            (radial * azimuthal.sin() * polar.sin()) as f32,
+            (radial * polar.cos()) as f32,
+        ]
+    }
+}
+
+/// Displaces the ground by the elevations of a raster-dem source.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Terrain {
+    /// The id of the raster-dem source.
+    pub source: String,
+    /// Factor by which the elevations are multiplied.
+    #[serde(default = "Terrain::default_exaggeration")]
+    pub exaggeration: f32,
+}
+
+impl Terrain {
+    fn default_exaggeration() -> f32 {
+        1.0
+    }
+}
+
+impl Style {
+    /// Changes the visibility of the layer with the given `id`. Tiles which are already loaded are
+    /// kept, so the change is visible in the next frame. Returns false if there is no such layer.
+    pub fn set_layer_visibility(&mut self, id: &str, visibility: Visibility) -> bool {
+        match self.layers.iter_mut().find(|layer| layer.id == id) {
+            Some(layer) => {
+                layer.visibility = visibility;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the ids of the layers which are visible at the given zoom level.
+    pub fn visible_layer_ids(&self, zoom: f32) -> HashSet<&str> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.is_visible_at(zoom))
+            .map(|layer| layer.id.as_str())
+            .collect()
+    }
 }
 
 /// Default style for https://openmaptiles.org/schema/
@@ -76,10 +210,26 @@ impl Default for Style {
             version: 8,
             name: Some("Default Style".to_string()),
             metadata: Default::default(),
-            sources: Default::default(),
+            sources: HashMap::from([(
+                "openmaptiles".to_string(),
+                Source::Vector(VectorSource {
+                    attribution: None,
+                    bounds: None,
+                    maxzoom: None,
+                    minzoom: None,
+                    scheme: None,
+                    url: None,
+                    tiles: Some(vec![
+                        "https://maps.tuerantuer.org/europe_germany/{z}/{x}/{y}.pbf".to_string(),
+                    ]),
+                }),
+            )]),
             center: Some([50.85045, 4.34878]),
             pitch: Some(0.0),
             zoom: Some(13.0),
+            sprite: None,
+            light: None,
+            terrain: None,
             layers: vec![
                 StyleLayer {
                     index: 0,
@@ -93,9 +243,11 @@ impl Default for Style {
                         background_color: Some(StyleProperty::Constant(
                             Color::from_str("#ffffff").unwrap(),
                         )),
+                        ..BackgroundPaint::default()
                     })),
                     source: None,
                     source_layer: None,
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 1,
@@ -109,9 +261,11 @@ impl Default for Style {
                         fill_color: Some(StyleProperty::Constant(
                             Color::from_str("#c8facc").unwrap(),
                         )),
+                        ..FillPaint::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("park".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 2,
@@ -125,9 +279,11 @@ impl Default for Style {
                         fill_color: Some(StyleProperty::Constant(
                             Color::from_str("#e0dfdf").unwrap(),
                         )),
+                        ..FillPaint::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("landuse".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 3,
@@ -141,9 +297,11 @@ impl Default for Style {
                         fill_color: Some(StyleProperty::Constant(
                             Color::from_str("#aedfa3").unwrap(),
                         )),
+                        ..FillPaint::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("landcover".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 4,
@@ -157,10 +315,11 @@ impl Default for Style {
                         line_color: Some(StyleProperty::Constant(
                             Color::from_str("#ffffff").unwrap(),
                         )),
-                        line_width: None,
+                        ..LinePaint::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("transportation".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 5,
@@ -174,9 +333,11 @@ impl Default for Style {
                         fill_color: Some(StyleProperty::Constant(
                             Color::from_str("#d9d0c9").unwrap(),
                         )),
+                        ..FillPaint::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("building".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 6,
@@ -190,9 +351,11 @@ impl Default for Style {
                         fill_color: Some(StyleProperty::Constant(
                             Color::from_str("#aad3df").unwrap(),
                         )),
+                        ..FillPaint::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("water".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 7,
@@ -206,9 +369,11 @@ impl Default for Style {
                         fill_color: Some(StyleProperty::Constant(
                             Color::from_str("#aad3df").unwrap(),
                         )),
+                        ..FillPaint::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("waterway".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 8,
@@ -222,10 +387,11 @@ impl Default for Style {
                         line_color: Some(StyleProperty::Constant(
                             Color::from_str("black").unwrap(),
                         )),
-                        line_width: None,
+                        ..LinePaint::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("boundary".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 9,
@@ -238,6 +404,7 @@ impl Default for Style {
                     paint: Some(LayerPaint::Raster(RasterPaint::default())),
                     source: None,
                     source_layer: None,
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 10,
@@ -248,11 +415,12 @@ impl Default for Style {
                     minzoom: None,
                     metadata: None,
                     paint: Some(LayerPaint::Symbol(SymbolPaint {
-                        text_field: Some("name".to_string()),
-                        text_size: None,
+                        text_field: StyleProperty::from_text(&serde_json::json!("{name}")).ok(),
+                        ..Default::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("place".to_string()),
+                    visibility: Visibility::Visible,
                 },
                 StyleLayer {
                     index: 11,
@@ -263,11 +431,12 @@ impl Default for Style {
                     minzoom: None,
                     metadata: None,
                     paint: Some(LayerPaint::Symbol(SymbolPaint {
-                        text_field: Some("name".to_string()),
-                        text_size: None,
+                        text_field: StyleProperty::from_text(&serde_json::json!("{name}")).ok(),
+                        ..Default::default()
                     })),
-                    source: None,
+                    source: Some("openmaptiles".to_string()),
                     source_layer: Some("transportation_name-disabled".to_string()),
+                    visibility: Visibility::Visible,
                 },
             ],
         }
@@ -350,4 +519,69 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_light() {
+        let style: Style = serde_json::from_value(serde_json::json!({
+            "version": 8,
+            "layers": [],
+            "light": {"intensity": 1, "anchor": "map", "position": [1, 90, 90]}
+        }))
+        .unwrap();
+        let light = style.light.unwrap();
+        assert_eq!(light.intensity, 1.0);
+        assert_eq!(light.color, Light::default().color);
+
+        // A light at the horizon, which is not rotated with the viewport
+        let [x, y, z] = light.direction(45.0);
+        assert!((x + 1.0).abs() < 1e-6 && y.abs() < 1e-6 && z.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_terrain() {
+        let style: Style = serde_json::from_value(serde_json::json!({
+            "version": 8,
+            "layers": [],
+            "terrain": {"source": "dem"}
+        }))
+        .unwrap();
+        assert_eq!(
+            style.terrain,
+            Some(Terrain {
+                source: "dem".to_string(),
+                exaggeration: 1.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_sprite_sources() {
+        let style: Style = serde_json::from_value(serde_json::json!({
+            "version": 8,
+            "sprite": "https://example.com/sprite",
+            "layers": []
+        }))
+        .unwrap();
+        assert_eq!(
+            style.sprite.unwrap().sources(),
+            vec![(String::new(), "https://example.com/sprite")]
+        );
+
+        let style: Style = serde_json::from_value(serde_json::json!({
+            "version": 8,
+            "sprite": [
+                {"id": "default", "url": "https://example.com/sprite"},
+                {"id": "shields", "url": "https://example.com/shields"}
+            ],
+            "layers": []
+        }))
+        .unwrap();
+        assert_eq!(
+            style.sprite.unwrap().sources(),
+            vec![
+                (String::new(), "https://example.com/sprite"),
+                ("shields:".to_string(), "https://example.com/shields")
+            ]
+        );
+    }
 }
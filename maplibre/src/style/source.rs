@@ -1,7 +1,11 @@
 //! Vector tile data utilities.
 
+use std::f64::consts::PI;
+
 use serde::{Deserialize, Serialize};
 
+use crate::coords::{WorldTileCoords, ZoomLevel};
+
 /// String url to a tile.
 pub type TileUrl = String;
 
@@ -9,7 +13,7 @@ pub type TileUrl = String;
 pub type TileJSONUrl = String;
 
 /// Tiles can be positioned using either the xyz coordinates or the TMS (Tile Map Service) protocol.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TileAddressingScheme {
     #[serde(rename = "xyz")]
     XYZ,
@@ -35,13 +39,16 @@ pub enum GeoJsonData {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GeoJsonSource {
     pub data: GeoJsonData,
+    /// Whether the distance along lines is calculated, which is required by `line-gradient`.
+    #[serde(rename = "lineMetrics", default)]
+    pub line_metrics: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub maxzoom: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub minzoom: Option<u8>,
 }
 
-/// Source properties for tiles or rasters.
+/// Source properties for vector tiles.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VectorSource {
     /// String which contains attribution information for the used tiles.
@@ -63,17 +70,307 @@ pub struct VectorSource {
     /// Array of URLs which can contain place holders like {x}, {y}, {z}.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tiles: Option<Vec<TileUrl>>,
-    // url: Option<TileJSONUrl>,
+    /// URL of a TileJSON document which describes the source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<TileJSONUrl>,
     // TODO volatile
 }
 
+impl VectorSource {
+    /// Returns true if the source provides the data of a tile at `coords`, i.e. the tile is not
+    /// below the minimum zoom level and intersects the bounds of the source. Tiles beyond the
+    /// maximum zoom level are overzoomed from their ancestor, see [`Self::source_tile`].
+    pub fn has_tile(&self, coords: &WorldTileCoords) -> bool {
+        covers_tile(self.bounds, self.minzoom, None, coords)
+    }
+
+    /// Returns the tile of the source which holds the data of the tile at `coords`. This is the
+    /// ancestor at the maximum zoom level for tiles beyond it, and the tile itself otherwise.
+    pub fn source_tile(&self, coords: &WorldTileCoords) -> WorldTileCoords {
+        let z = u8::from(coords.z);
+        match self.maxzoom {
+            Some(maxzoom) if z > maxzoom => {
+                let levels = z - maxzoom;
+                WorldTileCoords {
+                    x: coords.x >> levels,
+                    y: coords.y >> levels,
+                    z: ZoomLevel::new(maxzoom),
+                }
+            }
+            _ => *coords,
+        }
+    }
+}
+
+/// The size of raster tiles if the source does not specify `tileSize`.
+pub const DEFAULT_RASTER_TILE_SIZE: u16 = 512;
+
+/// Source properties for raster tiles.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RasterSource {
+    /// String which contains attribution information for the used tiles.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<String>,
+    /// The bounds in which tiles are available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<(f64, f64, f64, f64)>,
+    /// Max zoom level at which tiles are available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maxzoom: Option<u8>,
+    /// Min zoom level at which tiles are available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minzoom: Option<u8>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<TileAddressingScheme>,
+    /// The minimum visual size in pixels to display the tiles of the source at.
+    #[serde(rename = "tileSize")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tile_size: Option<u16>,
+    /// Array of URLs which can contain place holders like {x}, {y}, {z}.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tiles: Option<Vec<TileUrl>>,
+    /// URL of a TileJSON document which describes the source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<TileJSONUrl>,
+}
+
+impl RasterSource {
+    /// Returns true if the source provides a tile at `coords`, i.e. the tile is within the zoom
+    /// range and intersects the bounds of the source.
+    pub fn has_tile(&self, coords: &WorldTileCoords) -> bool {
+        covers_tile(self.bounds, self.minzoom, self.maxzoom, coords)
+    }
+
+    pub fn tile_size(&self) -> u16 {
+        self.tile_size.unwrap_or(DEFAULT_RASTER_TILE_SIZE)
+    }
+}
+
+/// The encoding of the elevations in the texels of a raster-dem source.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DemEncoding {
+    /// Terrarium format PNG tiles, see <https://aws.amazon.com/es/public-datasets/terrain/>.
+    Terrarium,
+    /// Mapbox Terrain RGB tiles, see <https://www.mapbox.com/help/access-elevation-data/#mapbox-terrain-rgb>.
+    #[default]
+    Mapbox,
+    /// Decodes the elevations with the `redFactor`, `greenFactor`, `blueFactor` and
+    /// `baseShift` of the source.
+    Custom,
+}
+
+/// Decodes the elevation in meters from the channels of a texel:
+/// `r * red + g * green + b * blue - base_shift`, with channels between 0 and 255.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DemUnpackFactors {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub base_shift: f32,
+}
+
+impl DemUnpackFactors {
+    pub fn unpack(&self, [r, g, b]: [u8; 3]) -> f32 {
+        f32::from(r) * self.red + f32::from(g) * self.green + f32::from(b) * self.blue
+            - self.base_shift
+    }
+}
+
+/// Source properties for raster tiles which encode elevations.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RasterDemSource {
+    #[serde(flatten)]
+    pub raster: RasterSource,
+    #[serde(default)]
+    pub encoding: DemEncoding,
+    #[serde(rename = "redFactor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub red_factor: Option<f32>,
+    #[serde(rename = "greenFactor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub green_factor: Option<f32>,
+    #[serde(rename = "blueFactor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blue_factor: Option<f32>,
+    #[serde(rename = "baseShift")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_shift: Option<f32>,
+}
+
+impl RasterDemSource {
+    pub fn has_tile(&self, coords: &WorldTileCoords) -> bool {
+        self.raster.has_tile(coords)
+    }
+
+    pub fn unpack_factors(&self) -> DemUnpackFactors {
+        match self.encoding {
+            DemEncoding::Terrarium => DemUnpackFactors {
+                red: 256.0,
+                green: 1.0,
+                blue: 1.0 / 256.0,
+                base_shift: 32768.0,
+            },
+            DemEncoding::Mapbox => DemUnpackFactors {
+                red: 6553.6,
+                green: 25.6,
+                blue: 0.1,
+                base_shift: 10000.0,
+            },
+            DemEncoding::Custom => DemUnpackFactors {
+                red: self.red_factor.unwrap_or(1.0),
+                green: self.green_factor.unwrap_or(1.0),
+                blue: self.blue_factor.unwrap_or(1.0),
+                base_shift: self.base_shift.unwrap_or(0.0),
+            },
+        }
+    }
+}
+
+fn covers_tile(
+    bounds: Option<(f64, f64, f64, f64)>,
+    minzoom: Option<u8>,
+    maxzoom: Option<u8>,
+    coords: &WorldTileCoords,
+) -> bool {
+    let z = u8::from(coords.z);
+    if minzoom.is_some_and(|minzoom| z < minzoom) || maxzoom.is_some_and(|maxzoom| z > maxzoom) {
+        return false;
+    }
+
+    let Some((west, south, east, north)) = bounds else {
+        return true;
+    };
+
+    let tiles = f64::from(1u32 << z);
+    let tile_x = |longitude: f64| ((longitude + 180.0) / 360.0 * tiles).floor() as i32;
+    let tile_y = |latitude: f64| {
+        let latitude = latitude.clamp(-85.051129, 85.051129) * PI / 180.0;
+        let y = (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln() / PI) / 2.0;
+        (y * tiles).floor() as i32
+    };
+
+    // Latitudes grow to the north, while tile coordinates grow to the south
+    coords.x >= tile_x(west)
+        && coords.x <= tile_x(east)
+        && coords.y >= tile_y(north)
+        && coords.y <= tile_y(south)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type")]
 pub enum Source {
     #[serde(rename = "vector")]
     Vector(VectorSource),
     #[serde(rename = "raster")]
-    Raster(VectorSource), // FIXME: Does it make sense that a raster have a VectorSource?
+    Raster(RasterSource),
+    #[serde(rename = "raster-dem")]
+    RasterDem(RasterDemSource),
     #[serde(rename = "geojson")]
     GeoJson(GeoJsonSource),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::ZoomLevel;
+
+    #[test]
+    fn test_has_tile() {
+        let source: VectorSource = serde_json::from_value(serde_json::json!({
+            "tiles": ["https://example.com/{z}/{x}/{y}.pbf"],
+            "bounds": [5.8, 47.2, 15.1, 55.1],
+            "minzoom": 2,
+            "maxzoom": 14
+        }))
+        .unwrap();
+
+        // Germany is within the tile x=8, y=5 at zoom level 4
+        assert!(source.has_tile(&WorldTileCoords::from((8, 5, ZoomLevel::new(4)))));
+        assert!(!source.has_tile(&WorldTileCoords::from((0, 5, ZoomLevel::new(4)))));
+        assert!(!source.has_tile(&WorldTileCoords::from((8, 10, ZoomLevel::new(4)))));
+        assert!(!source.has_tile(&WorldTileCoords::from((0, 0, ZoomLevel::new(1)))));
+        assert!(!source.has_tile(&WorldTileCoords::from((0, 0, ZoomLevel::new(15)))));
+    }
+
+    #[test]
+    fn test_overzoomed_tile() {
+        let source: VectorSource = serde_json::from_value(serde_json::json!({
+            "tiles": ["https://example.com/{z}/{x}/{y}.pbf"],
+            "bounds": [5.8, 47.2, 15.1, 55.1],
+            "maxzoom": 14
+        }))
+        .unwrap();
+
+        // Berlin is within the tile x=8801, y=5373 at zoom level 14
+        let coords = WorldTileCoords::from((35207, 21492, ZoomLevel::new(16)));
+        assert!(source.has_tile(&coords));
+        assert_eq!(
+            source.source_tile(&coords),
+            WorldTileCoords::from((8801, 5373, ZoomLevel::new(14)))
+        );
+        assert!(!source.has_tile(&WorldTileCoords::from((0, 0, ZoomLevel::new(15)))));
+
+        let coords = WorldTileCoords::from((8801, 5373, ZoomLevel::new(14)));
+        assert_eq!(source.source_tile(&coords), coords);
+    }
+
+    #[test]
+    fn test_raster_source() {
+        let source: Source = serde_json::from_value(serde_json::json!({
+            "type": "raster",
+            "tiles": ["https://tile.openstreetmap.org/{z}/{x}/{y}.png"],
+            "tileSize": 256,
+            "maxzoom": 19
+        }))
+        .unwrap();
+        let Source::Raster(source) = source else {
+            panic!("expected a raster source");
+        };
+        assert_eq!(source.tile_size(), 256);
+        assert!(source.has_tile(&WorldTileCoords::from((0, 0, ZoomLevel::new(19)))));
+        assert!(!source.has_tile(&WorldTileCoords::from((0, 0, ZoomLevel::new(20)))));
+
+        let source: RasterSource = serde_json::from_value(serde_json::json!({
+            "url": "https://example.com/satellite.json"
+        }))
+        .unwrap();
+        assert_eq!(source.tile_size(), DEFAULT_RASTER_TILE_SIZE);
+    }
+
+    #[test]
+    fn test_raster_dem_source() {
+        let source: Source = serde_json::from_value(serde_json::json!({
+            "type": "raster-dem",
+            "tiles": ["local://tiles/{z}-{x}-{y}.terrain.png"],
+            "tileSize": 256,
+            "maxzoom": 15,
+            "encoding": "terrarium"
+        }))
+        .unwrap();
+        let Source::RasterDem(source) = source else {
+            panic!("expected a raster-dem source");
+        };
+        assert_eq!(source.raster.tile_size(), 256);
+        assert_eq!(source.encoding, DemEncoding::Terrarium);
+        assert_eq!(source.unpack_factors().unpack([128, 0, 0]), 0.0);
+        assert_eq!(source.unpack_factors().unpack([128, 100, 128]), 100.5);
+
+        let source: RasterDemSource = serde_json::from_value(serde_json::json!({
+            "url": "https://example.com/terrain.json"
+        }))
+        .unwrap();
+        assert_eq!(source.encoding, DemEncoding::Mapbox);
+        assert!(source.unpack_factors().unpack([1, 134, 160]).abs() < 1e-3);
+
+        let source: RasterDemSource = serde_json::from_value(serde_json::json!({
+            "tiles": ["https://example.com/{z}/{x}/{y}.png"],
+            "encoding": "custom",
+            "redFactor": 2.0,
+            "baseShift": 10.0
+        }))
+        .unwrap();
+        assert_eq!(source.unpack_factors().unpack([10, 3, 4]), 17.0);
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{
+    context::MapContext,
+    tcs::system::{SystemError, SystemResult},
+    terrain::resource::TerrainDrapePhase,
+};
+
+pub fn cleanup_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
+    let Some(drape_phase) = world.resources.get_mut::<TerrainDrapePhase>() else {
+        return Err(SystemError::Dependencies);
+    };
+
+    drape_phase.clear();
+
+    Ok(())
+}
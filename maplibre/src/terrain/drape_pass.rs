@@ -0,0 +1,104 @@
+use std::ops::Deref;
+
+use wgpu::StoreOp;
+
+use crate::{
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        graph::{Node, NodeRunError, RenderContext, RenderGraphContext, SlotInfo},
+        resource::TrackedRenderPass,
+        RenderResources,
+    },
+    tcs::world::World,
+    terrain::resource::{TerrainDrapePhase, TerrainResources},
+};
+
+/// Pass which draws the draped layers of each target tile into its texture. The textures are
+/// draped over the terrain by the main pass.
+pub struct TerrainDrapeNode {}
+
+impl TerrainDrapeNode {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Node for TerrainDrapeNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![]
+    }
+
+    fn update(&mut self, _state: &mut RenderResources) {}
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        _resources: &RenderResources,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let Some((Initialized(terrain_resources), drape_phase)) = world
+            .resources
+            .query::<(&Eventually<TerrainResources>, &TerrainDrapePhase)>()
+        else {
+            return Ok(());
+        };
+
+        for drape in &drape_phase.drapes {
+            let Some(tile) = terrain_resources.get_tile(&drape.coords) else {
+                continue;
+            };
+
+            let ops = wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: StoreOp::Store,
+            };
+            let color_attachment =
+                if let Some(texture) = terrain_resources.drape_multisampling_texture() {
+                    wgpu::RenderPassColorAttachment {
+                        view: texture.view.deref(),
+                        ops,
+                        resolve_target: Some(&tile.drape_view),
+                    }
+                } else {
+                    wgpu::RenderPassColorAttachment {
+                        view: &tile.drape_view,
+                        ops,
+                        resolve_target: None,
+                    }
+                };
+
+            let render_pass =
+                render_context
+                    .command_encoder
+                    .begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("terrain_drape_pass"),
+                        color_attachments: &[Some(color_attachment)],
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: &terrain_resources.drape_depth_texture().view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: StoreOp::Store,
+                            }),
+                            stencil_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(0),
+                                store: StoreOp::Store,
+                            }),
+                        }),
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+
+            let mut tracked_pass = TrackedRenderPass::new(render_pass);
+
+            for item in &drape.masks {
+                item.draw_function.draw(&mut tracked_pass, world, item);
+            }
+            for item in &drape.items {
+                item.draw_function.draw(&mut tracked_pass, world, item);
+            }
+        }
+
+        Ok(())
+    }
+}
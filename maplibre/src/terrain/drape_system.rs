@@ -0,0 +1,142 @@
+//! Moves the layers which are draped over the terrain from the main pass into the drape pass.
+
+use std::collections::HashMap;
+
+use crate::{
+    context::MapContext,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_commands::DrawMasks,
+        render_phase::{DrawState, LayerItem, RenderPhase, TileMaskItem},
+        tile_view_pattern::WgpuTileViewPattern,
+    },
+    style::{layer::LayerPaint, Style},
+    tcs::{
+        system::{SystemError, SystemResult},
+        tiles::Tile,
+    },
+    terrain::{
+        render_commands::DrawTerrainTiles,
+        resource::{TerrainDrape, TerrainDrapePhase, TerrainResources},
+    },
+};
+
+pub fn drape_system(MapContext { world, style, .. }: &mut MapContext) -> SystemResult {
+    if style.terrain.is_none() {
+        return Ok(());
+    }
+
+    let Some((
+        Initialized(tile_view_pattern),
+        Initialized(terrain_resources),
+        layer_item_phase,
+        drape_phase,
+    )) = world.resources.query_mut::<(
+        &Eventually<WgpuTileViewPattern>,
+        &Eventually<TerrainResources>,
+        &mut RenderPhase<LayerItem>,
+        &mut TerrainDrapePhase,
+    )>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    // The drapes of the target tiles, keyed by the draped buffer ranges of their source shapes
+    let mut drapes = Vec::new();
+    let mut drape_indices = HashMap::new();
+    for view_tile in tile_view_pattern.iter() {
+        view_tile.render(|source_shape| {
+            if let Some(range) = source_shape.draped().and_then(|shape| shape.buffer_range()) {
+                drape_indices.insert(range.start, drapes.len());
+            }
+        });
+        drapes.push(TerrainDrape {
+            coords: view_tile.coords(),
+            masks: Vec::new(),
+            items: Vec::new(),
+        });
+    }
+
+    let draped_items = layer_item_phase.take_where(|item| {
+        is_draped(style, &item.style_layer)
+            && item
+                .source_shape
+                .draped()
+                .and_then(|shape| shape.buffer_range())
+                .is_some_and(|range| drape_indices.contains_key(&range.start))
+    });
+
+    let Some(terrain_index) = draped_items.iter().map(|item| item.index).min() else {
+        return Ok(());
+    };
+
+    for mut item in draped_items {
+        let draped_shape = item
+            .source_shape
+            .draped()
+            .expect("only draped shapes are taken");
+        let range = draped_shape
+            .buffer_range()
+            .expect("only draped shapes are taken");
+        let drape = &mut drapes[drape_indices[&range.start]];
+
+        // Each source shape is masked once within the texture
+        if !drape
+            .masks
+            .iter()
+            .any(|mask| mask.source_shape.buffer_range() == Some(range.clone()))
+        {
+            drape.masks.push(TileMaskItem {
+                draw_function: Box::new(DrawState::<TileMaskItem, DrawMasks>::new()),
+                source_shape: draped_shape.clone(),
+            });
+        }
+
+        item.source_shape = draped_shape;
+        drape.items.push(item);
+    }
+
+    // The terrain is drawn in place of the lowest draped layer
+    for (view_tile, drape) in tile_view_pattern.iter().zip(&mut drapes) {
+        drape.items.sort_by_key(|item| item.index);
+
+        if terrain_resources.get_tile(&drape.coords).is_none() {
+            continue;
+        }
+        layer_item_phase.add(LayerItem {
+            draw_function: Box::new(DrawState::<LayerItem, DrawTerrainTiles>::new()),
+            index: terrain_index,
+            is_line: false,
+            style_layer: "terrain".to_string(),
+            tile: Tile {
+                coords: drape.coords,
+            },
+            source_shape: view_tile.target_shape().clone(),
+        });
+    }
+    layer_item_phase.sort();
+
+    drape_phase.drapes = drapes;
+
+    Ok(())
+}
+
+/// Whether the layer is drawn into the textures of the terrain, rather than into the view.
+fn is_draped(style: &Style, style_layer_id: &str) -> bool {
+    style
+        .layers
+        .iter()
+        .find(|layer| layer.id == style_layer_id)
+        .is_some_and(|layer| {
+            matches!(
+                layer.paint,
+                Some(
+                    LayerPaint::Fill(_)
+                        | LayerPaint::Line(_)
+                        | LayerPaint::Raster(_)
+                        | LayerPaint::Hillshade(_)
+                        | LayerPaint::ColorRelief(_)
+                )
+            )
+        })
+}
@@ -0,0 +1,173 @@
+//! The elevation of the terrain, which is sampled from the tiles of its raster-dem source.
+
+use std::collections::HashMap;
+
+use crate::{
+    coords::{WorldCoords, WorldTileCoords, Zoom, ZoomLevel, TILE_SIZE},
+    raster::DemData,
+    render::view_state::Elevation,
+};
+
+/// The elevations of the raster-dem tiles of the terrain in the view.
+pub struct TerrainElevation {
+    tiles: HashMap<WorldTileCoords, DemData>,
+    /// The highest zoom level of the `tiles`.
+    max_z: u8,
+    exaggeration: f64,
+}
+
+impl TerrainElevation {
+    pub fn new(tiles: HashMap<WorldTileCoords, DemData>, exaggeration: f64) -> Self {
+        let max_z = tiles
+            .keys()
+            .map(|coords| u8::from(coords.z))
+            .max()
+            .unwrap_or_default();
+        Self {
+            tiles,
+            max_z,
+            exaggeration,
+        }
+    }
+
+    /// Whether this elevation was created from the revisions of the `dems` and the
+    /// `exaggeration`.
+    pub fn is_created_from(&self, dems: &[(WorldTileCoords, &DemData)], exaggeration: f64) -> bool {
+        self.exaggeration == exaggeration
+            && self.tiles.len() == dems.len()
+            && dems.iter().all(|(coords, dem)| {
+                self.tiles
+                    .get(coords)
+                    .is_some_and(|tile| tile.revision() == dem.revision())
+            })
+    }
+
+    /// Returns the exaggerated elevation in meters at `x` and `y` within the tile `coords`,
+    /// which range from 0 to 1. The elevation is taken from the most detailed tile which is
+    /// available there, or is `None` if there is none.
+    pub fn elevation_in_tile(&self, coords: WorldTileCoords, x: f64, y: f64) -> Option<f64> {
+        let tiles = f64::from(1u32 << u8::from(coords.z));
+        self.elevation_at_mercator(
+            (f64::from(coords.x) + x) / tiles,
+            (f64::from(coords.y) + y) / tiles,
+        )
+    }
+
+    /// Returns the exaggerated elevation in meters at `x` and `y`, which range from 0 to 1 across
+    /// the whole world.
+    fn elevation_at_mercator(&self, x: f64, y: f64) -> Option<f64> {
+        (0..=self.max_z).rev().find_map(|z| {
+            let tiles = f64::from(1u32 << z);
+            let (tile_x, tile_y) = ((x * tiles).floor(), (y * tiles).floor());
+            let dem = self.tiles.get(&WorldTileCoords {
+                x: tile_x as i32,
+                y: tile_y as i32,
+                z: ZoomLevel::from(z),
+            })?;
+            Some(sample(dem, x * tiles - tile_x, y * tiles - tile_y) * self.exaggeration)
+        })
+    }
+}
+
+impl Elevation for TerrainElevation {
+    fn elevation_at(&self, coords: WorldCoords, zoom: Zoom) -> Option<f64> {
+        let world_size = TILE_SIZE / zoom.scale_to_zoom_level(ZoomLevel::default());
+        self.elevation_at_mercator(coords.x / world_size, coords.y / world_size)
+    }
+}
+
+/// Interpolates the elevations of the `dem` bilinearly at `x` and `y`, which range from 0 to 1
+/// within the tile. The samples are located at the centers of the pixels of the tile.
+fn sample(dem: &DemData, x: f64, y: f64) -> f64 {
+    let dim = dem.dim() as i32;
+    let (x, y) = (x * dim as f64 - 0.5, y * dim as f64 - 0.5);
+    // The border of the grid allows to interpolate up to the edges of the tile
+    let (x0, y0) = (
+        (x.floor() as i32).clamp(-1, dim - 1),
+        (y.floor() as i32).clamp(-1, dim - 1),
+    );
+    let (fx, fy) = (
+        (x - x0 as f64).clamp(0.0, 1.0),
+        (y - y0 as f64).clamp(0.0, 1.0),
+    );
+
+    let top = lerp(dem.get(x0, y0), dem.get(x0 + 1, y0), fx);
+    let bottom = lerp(dem.get(x0, y0 + 1), dem.get(x0 + 1, y0 + 1), fx);
+    top + (bottom - top) * fy
+}
+
+fn lerp(a: f32, b: f32, t: f64) -> f64 {
+    a as f64 + (b as f64 - a as f64) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use image::{Rgba, RgbaImage};
+
+    use crate::{
+        coords::{WorldCoords, WorldTileCoords, Zoom},
+        raster::DemData,
+        render::view_state::Elevation,
+        style::source::DemUnpackFactors,
+        terrain::elevation::TerrainElevation,
+    };
+
+    /// A tile whose elevations are the red channel of the pixels.
+    fn dem(pixels: [[u8; 2]; 2]) -> DemData {
+        let image = RgbaImage::from_fn(2, 2, |x, y| {
+            Rgba([pixels[y as usize][x as usize], 0, 0, 255])
+        });
+        DemData::decode(
+            &image,
+            &DemUnpackFactors {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.0,
+                base_shift: 0.0,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn elevation_in_tile() {
+        let coords = WorldTileCoords::from((0, 0, 1.into()));
+        let elevation =
+            TerrainElevation::new(HashMap::from([(coords, dem([[0, 100], [100, 200]]))]), 2.0);
+
+        // The centers of the pixels
+        assert_eq!(elevation.elevation_in_tile(coords, 0.25, 0.25), Some(0.0));
+        assert_eq!(elevation.elevation_in_tile(coords, 0.75, 0.75), Some(400.0));
+        // Between all pixels
+        assert_eq!(elevation.elevation_in_tile(coords, 0.5, 0.5), Some(200.0));
+        // The border repeats the edges of the tile
+        assert_eq!(elevation.elevation_in_tile(coords, 0.99, 0.01), Some(200.0));
+
+        // Children are sampled from the parent
+        let child = WorldTileCoords::from((1, 1, 2.into()));
+        assert_eq!(elevation.elevation_in_tile(child, 0.0, 0.0), Some(200.0));
+        // There is no elevation outside of the tile
+        let neighbor = WorldTileCoords::from((1, 0, 1.into()));
+        assert_eq!(elevation.elevation_in_tile(neighbor, 0.5, 0.5), None);
+    }
+
+    #[test]
+    fn elevation_at_world_coords() {
+        let coords = WorldTileCoords::from((0, 0, 0.into()));
+        let elevation =
+            TerrainElevation::new(HashMap::from([(coords, dem([[0, 100], [100, 200]]))]), 1.0);
+
+        // The world is 1024 pixels wide at zoom 1
+        let zoom = Zoom::new(1.0);
+        assert_eq!(
+            elevation.elevation_at(WorldCoords::at_ground(512.0, 512.0), zoom),
+            Some(100.0)
+        );
+        assert_eq!(
+            elevation.elevation_at(WorldCoords::at_ground(768.0, 768.0), zoom),
+            Some(200.0)
+        );
+    }
+}
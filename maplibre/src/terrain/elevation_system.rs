@@ -0,0 +1,169 @@
+//! Samples the elevation of the terrain from the raster-dem tiles in the view.
+
+use std::rc::Rc;
+
+use cgmath::{Deg, Point2, Rad};
+
+use crate::{
+    context::MapContext,
+    coords::Zoom,
+    raster::DemDataComponent,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        tile_view_pattern::DEFAULT_TILE_SIZE,
+        view_state::{ViewState, ViewStatePadding},
+    },
+    tcs::system::{SystemError, SystemResult},
+    terrain::{elevation::TerrainElevation, resource::TerrainResources},
+};
+
+/// The least height of the camera above the terrain in pixels.
+const CAMERA_CLEARANCE: f64 = 50.0;
+
+/// The step by which the pitch is reduced until the camera is above the terrain.
+const PITCH_STEP: Deg<f64> = Deg(1.0);
+
+pub fn elevation_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some(Initialized(terrain_resources)) = world
+        .resources
+        .query_mut::<&mut Eventually<TerrainResources>>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    let Some(terrain) = &style.terrain else {
+        if terrain_resources.elevation().is_some() {
+            terrain_resources.set_elevation(None);
+            view_state.set_elevation(None);
+        }
+        return Ok(());
+    };
+
+    let Some(view_region) = view_state.create_view_region(
+        view_state.zoom().zoom_level(DEFAULT_TILE_SIZE),
+        ViewStatePadding::Loose,
+    ) else {
+        return Ok(());
+    };
+
+    let dems = view_region
+        .iter()
+        .filter_map(|coords| {
+            let dem = world
+                .tiles
+                .query::<&DemDataComponent>(coords)?
+                .sources
+                .get(&terrain.source)?;
+            Some((coords, dem))
+        })
+        .collect::<Vec<_>>();
+
+    let exaggeration = terrain.exaggeration as f64;
+    // The grids are only displaced again if the elevations changed
+    if !terrain_resources
+        .elevation()
+        .is_some_and(|elevation| elevation.is_created_from(&dems, exaggeration))
+    {
+        log::debug!("Sampling the terrain from {} tiles", dems.len());
+        let elevation = Rc::new(TerrainElevation::new(
+            dems.into_iter()
+                .map(|(coords, dem)| (coords, dem.clone()))
+                .collect(),
+            exaggeration,
+        ));
+        view_state.set_elevation(Some(elevation.clone()));
+        terrain_resources.set_elevation(Some(elevation));
+    }
+
+    keep_camera_above_ground(view_state);
+
+    Ok(())
+}
+
+/// Reduces the pitch of the camera towards zero until it is above the terrain. If the terrain is
+/// still too high, the map is zoomed out around its center until the camera clears it.
+fn keep_camera_above_ground(view_state: &mut ViewState) {
+    let step = Rad::from(PITCH_STEP).0;
+    loop {
+        let camera = view_state.camera_position();
+        let pitch = view_state.camera().get_pitch().0;
+        if camera.z >= view_state.ground_height_at(camera.x, camera.y) + CAMERA_CLEARANCE {
+            return;
+        }
+        if pitch == 0.0 {
+            break;
+        }
+
+        let reduced = (pitch.abs() - step).max(0.0).copysign(pitch);
+        view_state.camera_mut().set_pitch(Rad(reduced));
+    }
+
+    // The camera keeps its distance to the center, while heights in pixels halve with each zoom
+    // level. So the ground is scaled down just enough to lie below the camera.
+    let camera = view_state.camera_position();
+    let ground = view_state.ground_height_at(camera.x, camera.y);
+    if camera.z <= CAMERA_CLEARANCE || ground <= 0.0 {
+        return;
+    }
+    let scale = (camera.z - CAMERA_CLEARANCE) / ground;
+    let zoom = view_state.zoom();
+    view_state.update_zoom(zoom + Zoom::new(scale.log2()));
+    let center = view_state.camera().position();
+    view_state
+        .camera_mut()
+        .move_to(Point2::new(center.x * scale, center.y * scale));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use cgmath::Deg;
+
+    use super::{keep_camera_above_ground, CAMERA_CLEARANCE};
+    use crate::{
+        coords::{WorldCoords, Zoom},
+        render::view_state::{Elevation, ViewState},
+        window::PhysicalSize,
+    };
+
+    /// A plateau which covers the whole world.
+    struct Plateau(f64);
+
+    impl Elevation for Plateau {
+        fn elevation_at(&self, _coords: WorldCoords, _zoom: Zoom) -> Option<f64> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn camera_above_plateau_at_pitch_zero() {
+        let mut state = ViewState::new(
+            PhysicalSize::new(800, 600).unwrap(),
+            WorldCoords::at_ground(1000.0, 2000.0),
+            Zoom::new(10.0),
+            Deg(0.0),
+            Deg(60.0),
+        );
+        // The plateau is twice as high as the camera
+        let camera = state.camera_position();
+        let meters = 2.0 * camera.z / state.pixels_per_meter();
+        state.set_elevation(Some(Rc::new(Plateau(meters))));
+
+        keep_camera_above_ground(&mut state);
+
+        let camera = state.camera_position();
+        assert!(camera.z >= state.ground_height_at(camera.x, camera.y) + CAMERA_CLEARANCE - 1e-6);
+        assert!(state.zoom().level() < 9.0);
+        // The map is zoomed out around its center
+        let center = state.camera().position();
+        assert!((center.x * 2.0 - center.y).abs() < 1e-6);
+    }
+}
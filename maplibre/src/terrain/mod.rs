@@ -0,0 +1,102 @@
+//! Displaces the ground by the `terrain` of the style. Each target tile of the view becomes a
+//! grid which is elevated by the raster-dem source of the terrain. The `fill`, `line`, `raster`
+//! and `hillshade` layers are drawn into a texture per tile by the [`TerrainDrapeNode`], which
+//! is draped over the grid during the main pass.
+//!
+//! The elevations are fetched and decoded by the [`RasterPlugin`](crate::raster::RasterPlugin),
+//! which therefore needs to be added as well. This plugin needs to be added after the
+//! [`HillshadePlugin`](crate::hillshade::HillshadePlugin), whose slopes are draped.
+
+use std::rc::Rc;
+
+use crate::{
+    environment::Environment,
+    kernel::Kernel,
+    plugin::Plugin,
+    render::{eventually::Eventually, graph::RenderGraph, RenderStageLabel},
+    schedule::Schedule,
+    tcs::world::World,
+    terrain::{
+        cleanup_system::cleanup_system, drape_pass::TerrainDrapeNode, drape_system::drape_system,
+        elevation_system::elevation_system, resource_system::resource_system,
+        upload_system::upload_system,
+    },
+};
+
+mod cleanup_system;
+mod drape_pass;
+mod drape_system;
+mod elevation;
+mod elevation_system;
+mod render_commands;
+mod resource;
+mod resource_system;
+mod upload_system;
+
+pub use elevation::TerrainElevation;
+pub use resource::{TerrainDrape, TerrainDrapePhase, TerrainResources, TerrainTile};
+
+/// Labels for the "draw" graph
+mod draw_graph {
+    pub const NAME: &str = "draw";
+    // Labels for input nodes
+    pub mod input {}
+    // Labels for non-input nodes
+    pub mod node {
+        pub const MAIN_PASS: &str = "main_pass";
+        pub const HILLSHADE_PREPARE_PASS: &str = "hillshade_prepare_pass";
+        pub const TERRAIN_DRAPE_PASS: &str = "terrain_drape_pass";
+    }
+}
+
+#[derive(Default)]
+pub struct TerrainPlugin;
+
+impl<E: Environment> Plugin<E> for TerrainPlugin {
+    fn build(
+        &self,
+        schedule: &mut Schedule,
+        _kernel: Rc<Kernel<E>>,
+        world: &mut World,
+        graph: &mut RenderGraph,
+    ) {
+        let draw_graph = graph.get_sub_graph_mut(draw_graph::NAME).unwrap();
+        draw_graph.add_node(
+            draw_graph::node::TERRAIN_DRAPE_PASS,
+            TerrainDrapeNode::new(),
+        );
+        // The drape textures are sampled by the main pass
+        draw_graph
+            .add_node_edge(
+                draw_graph::node::TERRAIN_DRAPE_PASS,
+                draw_graph::node::MAIN_PASS,
+            )
+            .unwrap();
+        // Draped hillshades sample the slopes of their tiles
+        if draw_graph
+            .get_node_id(draw_graph::node::HILLSHADE_PREPARE_PASS)
+            .is_ok()
+        {
+            draw_graph
+                .add_node_edge(
+                    draw_graph::node::HILLSHADE_PREPARE_PASS,
+                    draw_graph::node::TERRAIN_DRAPE_PASS,
+                )
+                .unwrap();
+        }
+
+        world
+            .resources
+            .insert(Eventually::<TerrainResources>::Uninitialized);
+        world.resources.init::<TerrainDrapePhase>();
+
+        // The elevation is sampled before the tile view pattern is uploaded, which depends on
+        // the camera
+        schedule.add_system_to_stage(RenderStageLabel::Prepare, resource_system);
+        schedule.add_system_to_stage(RenderStageLabel::Prepare, elevation_system);
+        schedule.add_system_to_stage(RenderStageLabel::Queue, upload_system);
+        // The draped layers are taken out of the main pass once all layers are queued
+        schedule.add_system_to_stage(RenderStageLabel::PhaseSort, drape_system);
+        schedule.add_system_to_stage(RenderStageLabel::Cleanup, cleanup_system);
+    }
+}
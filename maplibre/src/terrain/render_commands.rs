@@ -0,0 +1,92 @@
+use crate::{
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        render_phase::{LayerItem, PhaseItem, RenderCommand, RenderCommandResult},
+        resource::TrackedRenderPass,
+        tile_view_pattern::WgpuTileViewPattern,
+    },
+    tcs::world::World,
+    terrain::resource::{TerrainResources, GRID_SIZE, INDEX_FORMAT},
+};
+
+pub struct SetTerrainPipeline;
+impl<P: PhaseItem> RenderCommand<P> for SetTerrainPipeline {
+    fn render<'w>(
+        world: &'w World,
+        _item: &P,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(terrain_resources)) =
+            world.resources.get::<Eventually<TerrainResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_render_pipeline(terrain_resources.pipeline());
+        RenderCommandResult::Success
+    }
+}
+
+/// Binds the texture into which the layers of the tile were draped.
+pub struct SetDrapeBindGroup;
+impl RenderCommand<LayerItem> for SetDrapeBindGroup {
+    fn render<'w>(
+        world: &'w World,
+        item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(Initialized(terrain_resources)) =
+            world.resources.get::<Eventually<TerrainResources>>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let Some(tile) = terrain_resources.get_tile(&item.tile.coords) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_bind_group(0, &tile.drape_bind_group, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawTerrainTile;
+impl RenderCommand<LayerItem> for DrawTerrainTile {
+    fn render<'w>(
+        world: &'w World,
+        item: &LayerItem,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some((Initialized(tile_view_pattern), Initialized(terrain_resources))) =
+            world.resources.query::<(
+                &Eventually<WgpuTileViewPattern>,
+                &Eventually<TerrainResources>,
+            )>()
+        else {
+            return RenderCommandResult::Failure;
+        };
+
+        let Some(tile) = terrain_resources.get_tile(&item.tile.coords) else {
+            return RenderCommandResult::Failure;
+        };
+
+        let tile_view_pattern_buffer = item
+            .source_shape
+            .buffer_range()
+            .expect("tile_view_pattern needs to be uploaded first"); // FIXME tcs
+
+        pass.set_index_buffer(terrain_resources.indices().slice(..), INDEX_FORMAT);
+        pass.set_vertex_buffer(0, tile.vertices.slice(..));
+        pass.set_vertex_buffer(
+            1,
+            tile_view_pattern.buffer().slice(tile_view_pattern_buffer),
+        );
+
+        const GRID_INDICES: u32 = GRID_SIZE * GRID_SIZE * 6;
+        pass.draw_indexed(0..GRID_INDICES, 0, 0..1);
+
+        RenderCommandResult::Success
+    }
+}
+
+pub type DrawTerrainTiles = (SetTerrainPipeline, SetDrapeBindGroup, DrawTerrainTile);
@@ -0,0 +1,295 @@
+use std::{collections::HashMap, rc::Rc};
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    coords::{WorldTileCoords, EXTENT},
+    render::{
+        render_phase::{LayerItem, TileMaskItem},
+        resource::Texture,
+        settings::Msaa,
+        shaders::ShaderTerrainVertex,
+    },
+    terrain::elevation::TerrainElevation,
+};
+
+/// Width and height of the textures into which the layers of a tile are drawn.
+pub const DRAPE_SIZE: u32 = 512;
+
+/// Count of the cells of the grid of a tile along each of its edges.
+pub const GRID_SIZE: u32 = 64;
+
+/// The format of the indices of the grids.
+pub const INDEX_FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
+
+/// The resources of a target tile of the view.
+pub struct TerrainTile {
+    /// The [`ShaderTerrainVertex`] of the grid, which is displaced by the elevation.
+    pub vertices: wgpu::Buffer,
+    /// The texture into which the draped layers are drawn.
+    pub drape_view: wgpu::TextureView,
+    pub drape_bind_group: wgpu::BindGroup,
+}
+
+/// The layers which are draped over a target tile.
+pub struct TerrainDrape {
+    pub coords: WorldTileCoords,
+    /// Masks of the source shapes of the tile, as they are drawn into its texture.
+    pub masks: Vec<TileMaskItem>,
+    /// Layers of the source shapes of the tile, as they are drawn into its texture.
+    pub items: Vec<LayerItem>,
+}
+
+/// The layers which are drawn into the textures of the target tiles during the next drape pass.
+#[derive(Default)]
+pub struct TerrainDrapePhase {
+    pub drapes: Vec<TerrainDrape>,
+}
+
+impl TerrainDrapePhase {
+    pub fn clear(&mut self) {
+        self.drapes.clear();
+    }
+}
+
+/// Holds the resources necessary for drawing the terrain such as the
+/// * pipeline
+/// * grids and drape textures of each target tile
+/// * render targets of the drape pass, which are shared by all tiles
+pub struct TerrainResources {
+    pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    /// The indices of the grid, which are the same for all tiles.
+    indices: wgpu::Buffer,
+    /// The texture into which the drape pass renders if multisampling is enabled. It is resolved
+    /// into the texture of the tile.
+    drape_multisampling_texture: Option<Texture>,
+    drape_depth_texture: Texture,
+    drape_format: wgpu::TextureFormat,
+    tiles: HashMap<WorldTileCoords, TerrainTile>,
+    /// The elevation the grids of the `tiles` were displaced with.
+    elevation: Option<Rc<TerrainElevation>>,
+}
+
+impl TerrainResources {
+    pub fn new(
+        device: &wgpu::Device,
+        pipeline: wgpu::RenderPipeline,
+        drape_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        msaa: Msaa,
+    ) -> Self {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Terrain drape sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let indices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Terrain grid index buffer"),
+            contents: bytemuck::cast_slice(&grid_indices()),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let drape_multisampling_texture = msaa.is_multisampling().then(|| {
+            Texture::new(
+                Some("Terrain drape multisampling texture"),
+                device,
+                drape_format,
+                DRAPE_SIZE,
+                DRAPE_SIZE,
+                msaa,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+        });
+        let drape_depth_texture = Texture::new(
+            Some("Terrain drape depth texture"),
+            device,
+            depth_format,
+            DRAPE_SIZE,
+            DRAPE_SIZE,
+            msaa,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        Self {
+            pipeline,
+            sampler,
+            indices,
+            drape_multisampling_texture,
+            drape_depth_texture,
+            drape_format,
+            tiles: Default::default(),
+            elevation: None,
+        }
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn indices(&self) -> &wgpu::Buffer {
+        &self.indices
+    }
+
+    pub fn drape_multisampling_texture(&self) -> Option<&Texture> {
+        self.drape_multisampling_texture.as_ref()
+    }
+
+    pub fn drape_depth_texture(&self) -> &Texture {
+        &self.drape_depth_texture
+    }
+
+    pub fn elevation(&self) -> Option<&Rc<TerrainElevation>> {
+        self.elevation.as_ref()
+    }
+
+    /// Replaces the elevation of the terrain. The grids of all tiles are displaced again.
+    pub fn set_elevation(&mut self, elevation: Option<Rc<TerrainElevation>>) {
+        self.elevation = elevation;
+        self.tiles.clear();
+    }
+
+    /// Removes the elevation and the tiles, such that the elevation is sampled again.
+    pub fn clear(&mut self) {
+        self.set_elevation(None);
+    }
+
+    pub fn get_tile(&self, coords: &WorldTileCoords) -> Option<&TerrainTile> {
+        self.tiles.get(coords)
+    }
+
+    /// Removes the tiles which are not a target tile of the view anymore.
+    pub fn retain_tiles(&mut self, mut predicate: impl FnMut(&WorldTileCoords) -> bool) {
+        self.tiles.retain(|coords, _| predicate(coords));
+    }
+
+    /// Displaces the grid of a tile by the current elevation and allocates its drape texture.
+    pub fn insert_tile(&mut self, device: &wgpu::Device, coords: WorldTileCoords) {
+        let vertices = grid_vertices(coords, self.elevation.as_deref());
+        let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Terrain grid vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let drape_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Terrain drape texture"),
+            size: wgpu::Extent3d {
+                width: DRAPE_SIZE,
+                height: DRAPE_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.drape_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let drape_view = drape_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let drape_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Terrain drape bind group"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&drape_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
+        self.tiles.insert(
+            coords,
+            TerrainTile {
+                vertices,
+                drape_view,
+                drape_bind_group,
+            },
+        );
+    }
+}
+
+/// Returns the vertices of the grid of a tile, row by row. The grid is flat where the elevation
+/// is unknown.
+pub fn grid_vertices(
+    coords: WorldTileCoords,
+    elevation: Option<&TerrainElevation>,
+) -> Vec<ShaderTerrainVertex> {
+    let mut vertices = Vec::with_capacity(((GRID_SIZE + 1) * (GRID_SIZE + 1)) as usize);
+    for row in 0..=GRID_SIZE {
+        for column in 0..=GRID_SIZE {
+            let (x, y) = (
+                column as f64 / GRID_SIZE as f64,
+                row as f64 / GRID_SIZE as f64,
+            );
+            let elevation = elevation
+                .and_then(|elevation| elevation.elevation_in_tile(coords, x, y))
+                .unwrap_or_default();
+            vertices.push(ShaderTerrainVertex {
+                position: [(x * EXTENT) as f32, (y * EXTENT) as f32],
+                elevation: elevation as f32,
+            });
+        }
+    }
+    vertices
+}
+
+/// Returns the indices of two triangles for each cell of the grid.
+pub fn grid_indices() -> Vec<u16> {
+    let stride = GRID_SIZE + 1;
+    let mut indices = Vec::with_capacity((GRID_SIZE * GRID_SIZE * 6) as usize);
+    for row in 0..GRID_SIZE {
+        for column in 0..GRID_SIZE {
+            let top_left = (row * stride + column) as u16;
+            let top_right = top_left + 1;
+            let bottom_left = top_left + stride as u16;
+            let bottom_right = bottom_left + 1;
+            indices.extend_from_slice(&[
+                top_left,
+                bottom_left,
+                top_right,
+                top_right,
+                bottom_left,
+                bottom_right,
+            ]);
+        }
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        coords::{WorldTileCoords, EXTENT},
+        terrain::resource::{grid_indices, grid_vertices, GRID_SIZE},
+    };
+
+    #[test]
+    fn grid() {
+        let vertices = grid_vertices(WorldTileCoords::from((0, 0, 0.into())), None);
+        let indices = grid_indices();
+
+        assert_eq!(vertices.len(), ((GRID_SIZE + 1) * (GRID_SIZE + 1)) as usize);
+        assert_eq!(indices.len(), (GRID_SIZE * GRID_SIZE * 6) as usize);
+        assert!(indices
+            .iter()
+            .all(|index| (*index as usize) < vertices.len()));
+
+        // The grid covers the whole tile
+        assert_eq!(vertices[0].position, [0.0, 0.0]);
+        assert_eq!(
+            vertices.last().unwrap().position,
+            [EXTENT as f32, EXTENT as f32]
+        );
+        assert!(vertices.iter().all(|vertex| vertex.elevation == 0.0));
+    }
+}
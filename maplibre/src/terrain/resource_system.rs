@@ -0,0 +1,79 @@
+//! Prepares GPU-owned resources by initializing them if they are uninitialized or out-of-date.
+use crate::{
+    context::MapContext,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        resource::{RenderPipeline, TilePipeline},
+        settings::Msaa,
+        shaders,
+        shaders::Shader,
+        tile_view_pattern::WgpuTileViewPattern,
+        RenderResources, Renderer,
+    },
+    tcs::system::{SystemError, SystemResult},
+    terrain::resource::TerrainResources,
+};
+
+pub fn resource_system(
+    MapContext {
+        world,
+        style,
+        renderer:
+            Renderer {
+                device,
+                resources: RenderResources { surface, .. },
+                settings,
+                ..
+            },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some((terrain_resources, Initialized(tile_view_pattern))) = world.resources.query_mut::<(
+        &mut Eventually<TerrainResources>,
+        &mut Eventually<WgpuTileViewPattern>,
+    )>() else {
+        return Err(SystemError::Dependencies);
+    };
+
+    // The source shapes are drawn into the textures of their target tiles
+    tile_view_pattern.set_draped(style.terrain.is_some());
+
+    terrain_resources.initialize(|| {
+        let multisampling = surface.is_multisampling_supported(settings.msaa);
+
+        let shader = shaders::TerrainShader {
+            format: surface.surface_format(),
+        };
+        let pipeline = TilePipeline::new(
+            "terrain_pipeline".into(),
+            *settings,
+            shader.describe_vertex(),
+            shader.describe_fragment(),
+            true,
+            false,
+            true,
+            false,
+            multisampling,
+            true,
+            false,
+        )
+        // The terrain occludes itself, but is not clipped by the masks of the tiles
+        .with_depth_test(wgpu::CompareFunction::LessEqual, true)
+        .describe_render_pipeline()
+        .initialize(device);
+
+        // The layers are drawn into the drape textures with the same pipelines as into the view
+        TerrainResources::new(
+            device,
+            pipeline,
+            surface.surface_format(),
+            settings.depth_texture_format,
+            if multisampling {
+                settings.msaa
+            } else {
+                Msaa { samples: 1 }
+            },
+        )
+    });
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Uploads data to the GPU which is needed for rendering.
+
+use std::collections::HashSet;
+
+use crate::{
+    context::MapContext,
+    render::{
+        eventually::{Eventually, Eventually::Initialized},
+        tile_view_pattern::WgpuTileViewPattern,
+        Renderer,
+    },
+    tcs::system::{SystemError, SystemResult},
+    terrain::resource::TerrainResources,
+};
+
+pub fn upload_system(
+    MapContext {
+        world,
+        style,
+        renderer: Renderer { device, .. },
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
+    let Some((Initialized(tile_view_pattern), Initialized(terrain_resources))) =
+        world.resources.query_mut::<(
+            &Eventually<WgpuTileViewPattern>,
+            &mut Eventually<TerrainResources>,
+        )>()
+    else {
+        return Err(SystemError::Dependencies);
+    };
+
+    if style.terrain.is_none() {
+        terrain_resources.retain_tiles(|_| false);
+        return Ok(());
+    }
+
+    let targets = tile_view_pattern
+        .iter()
+        .map(|view_tile| view_tile.coords())
+        .collect::<HashSet<_>>();
+    terrain_resources.retain_tiles(|coords| targets.contains(coords));
+
+    for coords in targets {
+        if terrain_resources.get_tile(&coords).is_none() {
+            log::debug!("Displacing terrain at {coords}");
+            terrain_resources.insert_tile(device, coords);
+        }
+    }
+
+    Ok(())
+}
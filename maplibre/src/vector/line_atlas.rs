@@ -0,0 +1,261 @@
+//! Rasterizes the dash patterns and the color gradients of lines into a texture.
+//!
+//! Adopted from
+//! [LineAtlas](https://github.com/maplibre/maplibre-gl-js/blob/main/src/render/line_atlas.ts).
+
+use std::collections::HashMap;
+
+use csscolorparser::Color;
+
+use crate::style::{expression::EvaluationContext, layer::StyleProperty};
+
+/// Width of the atlas. Each dash pattern and gradient is stretched over the full width.
+pub const LINE_ATLAS_WIDTH: u32 = 256;
+/// Height of the atlas, which is the maximum count of distinct dash patterns and gradients.
+pub const LINE_ATLAS_HEIGHT: u32 = 512;
+/// Bytes per texel of the RGBA atlas.
+const TEXEL_BYTES: usize = 4;
+
+/// The location of a dash pattern within the [`LineAtlas`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DashEntry {
+    /// Vertical texture coordinate of the center of the row of the pattern.
+    pub y: f32,
+    /// Length of one repetition of the pattern in units of the line width.
+    pub length: f32,
+}
+
+struct DashRange {
+    left: f32,
+    right: f32,
+    is_dash: bool,
+    zero_length: bool,
+}
+
+/// Stores one row per dash pattern and one row per line layer with a gradient.
+///
+/// The red channel of the texels of dash patterns holds the signed distance to the closest edge
+/// of a dash, offset by 128, such that the edges are at the value 0.5 after sampling. The texels
+/// of gradients hold the color at the progress along the line.
+pub struct LineAtlas {
+    data: Vec<u8>,
+    next_row: u32,
+    dashes: HashMap<Vec<u32>, DashEntry>,
+    /// Vertical texture coordinates of the gradients of the line layers.
+    gradients: HashMap<String, f32>,
+    dirty: bool,
+}
+
+impl Default for LineAtlas {
+    fn default() -> Self {
+        Self {
+            data: vec![0; (LINE_ATLAS_WIDTH * LINE_ATLAS_HEIGHT) as usize * TEXEL_BYTES],
+            next_row: 0,
+            dashes: HashMap::new(),
+            gradients: HashMap::new(),
+            dirty: true,
+        }
+    }
+}
+
+impl LineAtlas {
+    /// Returns the entry of the given dash pattern. The pattern is added to the atlas if it is
+    /// not part of it yet. Returns `None` if the atlas is full or the pattern is invalid.
+    pub fn get_dash(&mut self, dasharray: &[f32]) -> Option<DashEntry> {
+        if dasharray.is_empty()
+            || dasharray
+                .iter()
+                .any(|part| !part.is_finite() || *part < 0.0)
+        {
+            return None;
+        }
+
+        let key = dasharray.iter().map(|part| part.to_bits()).collect();
+        if let Some(entry) = self.dashes.get(&key) {
+            return Some(*entry);
+        }
+
+        let row = self.allocate_row()?;
+        let length: f32 = dasharray.iter().sum();
+        if length > 0.0 {
+            let stretch = LINE_ATLAS_WIDTH as f32 / length;
+            self.add_regular_dash(row, dash_ranges(dasharray, stretch));
+        }
+
+        let entry = DashEntry {
+            y: row_center(row),
+            length,
+        };
+        self.dashes.insert(key, entry);
+        Some(entry)
+    }
+
+    /// Returns the vertical texture coordinate of the gradient of the line layer `layer_id`. The
+    /// gradient is evaluated and added to the atlas if it is not part of it yet. Returns `None`
+    /// if the atlas is full or the gradient fails to evaluate.
+    pub fn get_gradient(&mut self, layer_id: &str, gradient: &StyleProperty<Color>) -> Option<f32> {
+        if let Some(y) = self.gradients.get(layer_id) {
+            return Some(*y);
+        }
+
+        let colors = (0..LINE_ATLAS_WIDTH)
+            .map(|x| {
+                let progress = x as f64 / (LINE_ATLAS_WIDTH - 1) as f64;
+                gradient.evaluate(&EvaluationContext::default().with_line_progress(progress))
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(colors) = colors else {
+            log::warn!("the line-gradient of layer {layer_id} can not be evaluated");
+            return None;
+        };
+
+        let row = self.allocate_row()?;
+        let offset = row as usize * LINE_ATLAS_WIDTH as usize * TEXEL_BYTES;
+        for (x, color) in colors.iter().enumerate() {
+            let texel = offset + x * TEXEL_BYTES;
+            self.data[texel..texel + TEXEL_BYTES].copy_from_slice(&color.to_rgba8());
+        }
+
+        let y = row_center(row);
+        self.gradients.insert(layer_id.to_string(), y);
+        Some(y)
+    }
+
+    /// Returns the texels of the atlas if they changed since the last call.
+    pub fn take_changes(&mut self) -> Option<&[u8]> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        Some(&self.data)
+    }
+
+    /// Reserves the next row of the atlas.
+    fn allocate_row(&mut self) -> Option<u32> {
+        if self.next_row >= LINE_ATLAS_HEIGHT {
+            log::warn!("line atlas is out of space");
+            return None;
+        }
+        let row = self.next_row;
+        self.next_row += 1;
+        self.dirty = true;
+        Some(row)
+    }
+
+    fn add_regular_dash(&mut self, row: u32, mut ranges: Vec<DashRange>) {
+        // Collapse zero-length parts and neighbouring parts of the same type
+        for i in (0..ranges.len()).rev() {
+            if ranges[i].zero_length {
+                ranges.remove(i);
+            } else if i + 1 < ranges.len() && ranges[i + 1].is_dash == ranges[i].is_dash {
+                ranges[i + 1].left = ranges[i].left;
+                ranges.remove(i);
+            }
+        }
+        if ranges.is_empty() {
+            return;
+        }
+
+        // Join the first and the last part, because the pattern repeats
+        let width = LINE_ATLAS_WIDTH as f32;
+        let last = ranges.len() - 1;
+        if ranges[0].is_dash == ranges[last].is_dash {
+            ranges[0].left = ranges[last].left - width;
+            ranges[last].right = ranges[0].right + width;
+        }
+
+        let offset = (row * LINE_ATLAS_WIDTH) as usize * TEXEL_BYTES;
+        let mut index = 0;
+        for x in 0..LINE_ATLAS_WIDTH as usize {
+            let x_f = x as f32;
+            while x_f > ranges[index].right && index + 1 < ranges.len() {
+                index += 1;
+            }
+            let range = &ranges[index];
+            let distance = (x_f - range.left).abs().min((x_f - range.right).abs());
+            let signed_distance = if range.is_dash { distance } else { -distance };
+            self.data[offset + x * TEXEL_BYTES] = (signed_distance + 128.0).clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// Returns the vertical texture coordinate of the center of `row`.
+fn row_center(row: u32) -> f32 {
+    (row as f32 + 0.5) / LINE_ATLAS_HEIGHT as f32
+}
+
+/// Converts the pattern into ranges of texels. Patterns of odd length start and end with a dash,
+/// which are joined seamlessly.
+fn dash_ranges(dasharray: &[f32], stretch: f32) -> Vec<DashRange> {
+    let odd = dasharray.len() % 2 == 1;
+    let mut ranges = Vec::with_capacity(dasharray.len());
+    let mut left = if odd {
+        -dasharray[dasharray.len() - 1] * stretch
+    } else {
+        0.0
+    };
+    let mut so_far = 0.0;
+    for (i, part) in dasharray.iter().enumerate() {
+        if i > 0 {
+            left = so_far * stretch;
+        }
+        so_far += part;
+        ranges.push(DashRange {
+            left,
+            right: so_far * stretch,
+            is_dash: i % 2 == 0,
+            zero_length: *part == 0.0,
+        });
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dash_rows() {
+        let mut atlas = LineAtlas::default();
+
+        let entry = atlas.get_dash(&[1.0, 1.0]).unwrap();
+        assert_eq!(entry.length, 2.0);
+        assert_eq!(entry.y, 0.5 / LINE_ATLAS_HEIGHT as f32);
+        // The same pattern is stored only once
+        assert_eq!(atlas.get_dash(&[1.0, 1.0]), Some(entry));
+        assert_eq!(atlas.get_dash(&[2.0, 1.0]).unwrap().y, 1.5 / 512.0);
+        assert_eq!(atlas.get_dash(&[-1.0, 1.0]), None);
+
+        let data = atlas.take_changes().unwrap();
+        // The first half of the pattern is a dash, with the edges at 128
+        assert!(data[64 * TEXEL_BYTES] > 128);
+        assert_eq!(data[128 * TEXEL_BYTES], 128);
+        assert!(data[192 * TEXEL_BYTES] < 128);
+        assert!(atlas.take_changes().is_none());
+    }
+
+    #[test]
+    fn test_gradient_rows() {
+        let mut atlas = LineAtlas::default();
+        atlas.get_dash(&[1.0, 1.0]).unwrap();
+
+        let gradient: StyleProperty<Color> = serde_json::from_value(serde_json::json!([
+            "interpolate",
+            ["linear"],
+            ["line-progress"],
+            0,
+            "blue",
+            1,
+            "red"
+        ]))
+        .unwrap();
+        let y = atlas.get_gradient("route", &gradient).unwrap();
+        assert_eq!(y, 1.5 / LINE_ATLAS_HEIGHT as f32);
+        assert_eq!(atlas.get_gradient("route", &gradient), Some(y));
+
+        let data = atlas.take_changes().unwrap();
+        let row = LINE_ATLAS_WIDTH as usize * TEXEL_BYTES;
+        assert_eq!(data[row..row + TEXEL_BYTES], [0, 0, 255, 255]);
+        assert_eq!(data[2 * row - TEXEL_BYTES..2 * row], [255, 0, 0, 255]);
+    }
+}
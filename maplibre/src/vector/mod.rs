@@ -19,18 +19,21 @@ use crate::{
         RenderStageLabel, ShaderVertex,
     },
     schedule::Schedule,
+    style::expression::FeatureProperties,
     tcs::{system::SystemContainer, tiles::TileComponent, world::World},
     vector::{
+        line_atlas::LineAtlas,
         populate_world_system::PopulateWorldSystem,
         queue_system::queue_system,
         request_system::RequestSystem,
-        resource::BufferPool,
+        resource::{BufferPool, LineAtlasTexture},
         resource_system::resource_system,
         tessellation::{IndexDataType, OverAlignedVertexBuffer},
         upload_system::upload_system,
     },
 };
 
+mod line_atlas;
 mod populate_world_system;
 mod process_vector;
 mod queue_system;
@@ -107,6 +110,8 @@ impl<E: Environment, T: VectorTransferables> Plugin<E> for VectorPlugin<T> {
         resources.insert(Eventually::<VectorBufferPool>::Uninitialized);
         resources.insert(Eventually::<VectorPipeline>::Uninitialized);
         resources.insert(Eventually::<LinePipeline>::Uninitialized);
+        resources.insert(Eventually::<LineAtlasTexture>::Uninitialized);
+        resources.insert(LineAtlas::default());
 
         resources
             .get_or_init_mut::<ViewTileSources>()
@@ -136,6 +141,9 @@ pub struct AvailableVectorLayerBucket {
     /// Holds for each feature the count of indices.
     pub feature_indices: Vec<u32>,
     pub feature_colors: Vec<[f32; 4]>,
+    /// Holds for each feature its properties if the color of the layer needs to be re-evaluated
+    /// when zooming. Empty otherwise.
+    pub feature_properties: Vec<FeatureProperties>,
 }
 
 pub struct MissingVectorLayerBucket {
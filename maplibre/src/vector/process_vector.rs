@@ -1,8 +1,4 @@
-use std::{
-    borrow::Cow,
-    collections::{HashMap, HashSet},
-    marker::PhantomData,
-};
+use std::{borrow::Cow, collections::HashSet, marker::PhantomData};
 
 use geozero::{
     mvt::{tile, Message},
@@ -11,7 +7,9 @@ use geozero::{
 use thiserror::Error;
 
 use crate::{
-    coords::WorldTileCoords,
+    circle::tessellation::CircleTessellator,
+    coords::{WorldTileCoords, EXTENT_UINT},
+    fill_extrusion::tessellation::FillExtrusionTessellator,
     io::{
         apc::{Context, SendError},
         geometry_index::{IndexProcessor, IndexedGeometry, TileIndex},
@@ -21,11 +19,15 @@ use crate::{
         ShaderVertex,
     },
     sdf::{tessellation::TextTessellator, tessellation_new::TextTessellatorNew, Feature},
-    style::layer::{LayerPaint, StyleLayer},
+    style::{
+        expression::{EvaluationContext, FeatureProperties, GeometryType, Value},
+        filter::Filter,
+        layer::{LayerPaint, StyleLayer},
+    },
     vector::{
         tessellation::{IndexDataType, OverAlignedVertexBuffer, ZeroTessellator},
         transferables::{
-            LayerIndexed, LayerMissing, LayerTessellated, SymbolLayerTessellated, TileTessellated,
+            LayerIndexed, LayerMissing, LayerTessellated, SymbolLayerTessellated,
             VectorTransferables,
         },
     },
@@ -47,13 +49,9 @@ pub struct VectorTileRequest {
     pub layers: HashSet<StyleLayer>,
 }
 
-/// Resolve the properties of an MVT feature into a HashMap of string key-value pairs,
-/// using the layer's keys/values dictionaries.
-fn resolve_feature_properties(
-    layer: &tile::Layer,
-    feature: &tile::Feature,
-) -> HashMap<String, String> {
-    let mut props = HashMap::new();
+/// Resolve the properties of an MVT feature using the layer's keys/values dictionaries.
+fn resolve_feature_properties(layer: &tile::Layer, feature: &tile::Feature) -> FeatureProperties {
+    let mut props = FeatureProperties::new();
     for pair in feature.tags.chunks(2) {
         let [key_idx, value_idx] = [pair[0], pair[1]];
         let Some(key) = layer.keys.get(key_idx as usize) else {
@@ -62,93 +60,23 @@ fn resolve_feature_properties(
         let Some(value) = layer.values.get(value_idx as usize) else {
             continue;
         };
-        let val_str = if let Some(ref v) = value.string_value {
-            v.clone()
-        } else if let Some(v) = value.float_value {
-            v.to_string()
-        } else if let Some(v) = value.double_value {
-            v.to_string()
-        } else if let Some(v) = value.int_value {
-            v.to_string()
-        } else if let Some(v) = value.uint_value {
-            v.to_string()
-        } else if let Some(v) = value.sint_value {
-            v.to_string()
-        } else if let Some(v) = value.bool_value {
-            v.to_string()
-        } else {
-            continue;
-        };
-        props.insert(key.clone(), val_str);
+        props.insert(key.clone(), Value::from(value));
     }
     props
 }
 
-/// Evaluate a MapLibre GL JS legacy filter expression against feature properties.
-/// Supports: ["all", ...], ["any", ...], ["==", key, val], ["!=", key, val],
-/// ["has", key], ["!has", key], ["in", key, v1, v2, ...], ["!in", key, v1, v2, ...]
-fn evaluate_filter(filter: &serde_json::Value, props: &HashMap<String, String>) -> bool {
-    let Some(arr) = filter.as_array() else {
-        return true; // non-array filter passes everything
-    };
-    let Some(op) = arr.first().and_then(|v| v.as_str()) else {
-        return true;
-    };
-    match op {
-        "all" => arr[1..].iter().all(|f| evaluate_filter(f, props)),
-        "any" => arr[1..].iter().any(|f| evaluate_filter(f, props)),
-        "none" => !arr[1..].iter().any(|f| evaluate_filter(f, props)),
-        "==" if arr.len() >= 3 => {
-            let key = arr[1].as_str().unwrap_or("");
-            let expected = arr[2].as_str().map(|s| s.to_string()).unwrap_or_else(|| {
-                // Handle numeric comparisons
-                arr[2].as_f64().map(|n| n.to_string()).unwrap_or_default()
-            });
-            props.get(key).map(|v| v == &expected).unwrap_or(false)
-        }
-        "!=" if arr.len() >= 3 => {
-            let key = arr[1].as_str().unwrap_or("");
-            let expected = arr[2]
-                .as_str()
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| arr[2].as_f64().map(|n| n.to_string()).unwrap_or_default());
-            props.get(key).map(|v| v != &expected).unwrap_or(true)
-        }
-        "has" if arr.len() >= 2 => {
-            let key = arr[1].as_str().unwrap_or("");
-            props.contains_key(key)
-        }
-        "!has" if arr.len() >= 2 => {
-            let key = arr[1].as_str().unwrap_or("");
-            !props.contains_key(key)
-        }
-        "in" if arr.len() >= 3 => {
-            let key = arr[1].as_str().unwrap_or("");
-            let Some(val) = props.get(key) else {
-                return false;
-            };
-            arr[2..]
-                .iter()
-                .any(|v| v.as_str().map(|s| s == val).unwrap_or(false))
-        }
-        "!in" if arr.len() >= 3 => {
-            let key = arr[1].as_str().unwrap_or("");
-            let Some(val) = props.get(key) else {
-                return true;
-            };
-            !arr[2..]
-                .iter()
-                .any(|v| v.as_str().map(|s| s == val).unwrap_or(false))
-        }
-        _ => {
-            log::warn!("unsupported filter operator: {op}");
-            true
-        }
+fn geometry_type(feature: &tile::Feature) -> Option<GeometryType> {
+    match feature.r#type {
+        Some(t) if t == tile::GeomType::Point as i32 => Some(GeometryType::Point),
+        Some(t) if t == tile::GeomType::Linestring as i32 => Some(GeometryType::LineString),
+        Some(t) if t == tile::GeomType::Polygon as i32 => Some(GeometryType::Polygon),
+        _ => None,
     }
 }
 
-/// Filter an MVT layer's features in-place according to a style filter expression.
-fn apply_filter_to_layer(layer: &mut tile::Layer, filter: &serde_json::Value) {
+/// Filter an MVT layer's features in-place according to a style filter, evaluated at the zoom
+/// level of the tile.
+fn apply_filter_to_layer(layer: &mut tile::Layer, filter: &Filter, zoom: f64) {
     // Collect which features pass the filter (can't borrow layer immutably
     // inside retain because retain borrows features mutably).
     let keep: Vec<bool> = layer
@@ -156,7 +84,12 @@ fn apply_filter_to_layer(layer: &mut tile::Layer, filter: &serde_json::Value) {
         .iter()
         .map(|feature| {
             let props = resolve_feature_properties(layer, feature);
-            evaluate_filter(filter, &props)
+            let mut context = EvaluationContext::default()
+                .with_zoom(zoom)
+                .with_properties(&props);
+            context.geometry_type = geometry_type(feature);
+            context.id = feature.id.map(|id| Value::Number(id as f64));
+            filter.evaluate(&context)
         })
         .collect();
     let mut idx = 0;
@@ -167,6 +100,94 @@ fn apply_filter_to_layer(layer: &mut tile::Layer, filter: &serde_json::Value) {
     });
 }
 
+/// The distance in tile units around overzoomed tiles in which features are kept, such that lines
+/// and symbols which reach into the tile are still drawn.
+const OVERZOOM_BUFFER: i64 = 128;
+
+/// Maps the tile data of `source_coords` onto its descendant at `coords`, which lies beyond the
+/// maximum zoom level of the source. The geometries are scaled and translated into the tile and
+/// features which do not reach into the tile are dropped.
+pub fn overzoom_tile(
+    data: &[u8],
+    source_coords: WorldTileCoords,
+    coords: WorldTileCoords,
+) -> Result<Vec<u8>, ProcessVectorError> {
+    let mut tile = geozero::mvt::Tile::decode(data)
+        .map_err(|e| ProcessVectorError::Decoding(e.to_string().into()))?;
+
+    let levels = u8::from(coords.z).saturating_sub(u8::from(source_coords.z));
+    let offset = (
+        coords.x - (source_coords.x << levels),
+        coords.y - (source_coords.y << levels),
+    );
+    for layer in &mut tile.layers {
+        let extent = i64::from(layer.extent.unwrap_or(EXTENT_UINT));
+        layer
+            .features
+            .retain_mut(|feature| overzoom_geometry(&mut feature.geometry, extent, levels, offset));
+    }
+
+    Ok(tile.encode_to_vec())
+}
+
+/// Transforms the command integers of an MVT geometry in place, see
+/// <https://github.com/mapbox/vector-tile-spec/tree/master/2.1#43-geometry-encoding>. Returns
+/// whether the transformed geometry reaches into the tile.
+fn overzoom_geometry(geometry: &mut [u32], extent: i64, levels: u8, offset: (i32, i32)) -> bool {
+    const MOVE_TO: u32 = 1;
+    const LINE_TO: u32 = 2;
+    // Keeps the deltas between transformed coordinates within the range of the parameter integers
+    const LIMIT: i64 = 1 << 29;
+
+    let decode = |parameter: u32| i64::from(((parameter >> 1) as i32) ^ -((parameter & 1) as i32));
+    let encode = |value: i64| {
+        let value = value as i32;
+        ((value << 1) ^ (value >> 31)) as u32
+    };
+    let transform = |value: i64, offset: i32| {
+        ((value << levels) - i64::from(offset) * extent).clamp(-LIMIT, LIMIT)
+    };
+
+    let mut source_cursor = (0i64, 0i64);
+    let mut cursor = (0i64, 0i64);
+    let mut min = (i64::MAX, i64::MAX);
+    let mut max = (i64::MIN, i64::MIN);
+
+    let mut i = 0;
+    while i < geometry.len() {
+        let command = geometry[i];
+        i += 1;
+        if command & 0x7 != MOVE_TO && command & 0x7 != LINE_TO {
+            continue;
+        }
+        for _ in 0..command >> 3 {
+            let [x, y] = match geometry.get(i..i + 2) {
+                Some(&[x, y]) => [x, y],
+                _ => return false,
+            };
+            source_cursor = (source_cursor.0 + decode(x), source_cursor.1 + decode(y));
+            let point = (
+                transform(source_cursor.0, offset.0),
+                transform(source_cursor.1, offset.1),
+            );
+            geometry[i] = encode(point.0 - cursor.0);
+            geometry[i + 1] = encode(point.1 - cursor.1);
+            cursor = point;
+
+            min = (min.0.min(point.0), min.1.min(point.1));
+            max = (max.0.max(point.0), max.1.max(point.1));
+            i += 2;
+        }
+    }
+
+    max.0 >= -OVERZOOM_BUFFER
+        && max.1 >= -OVERZOOM_BUFFER
+        && min.0 <= extent + OVERZOOM_BUFFER
+        && min.1 <= extent + OVERZOOM_BUFFER
+}
+
+/// Tessellates the requested layers of the tile data of a single source. The tile is not reported
+/// as finished, as it can contain the layers of further sources.
 pub fn process_vector_tile<T: VectorTransferables, C: Context>(
     data: &[u8],
     tile_request: VectorTileRequest,
@@ -192,7 +213,7 @@ pub fn process_vector_tile<T: VectorTransferables, C: Context>(
 
                 // Apply style filter to exclude non-matching features
                 if let Some(filter) = &style_layer.filter {
-                    apply_filter_to_layer(&mut filtered_layer, filter);
+                    apply_filter_to_layer(&mut filtered_layer, filter, u8::from(coords.z) as f64);
                 }
 
                 let original_layer = filtered_layer.clone();
@@ -201,12 +222,15 @@ pub fn process_vector_tile<T: VectorTransferables, C: Context>(
                 match paint {
                     LayerPaint::Line(_) | LayerPaint::Fill(_) => {
                         let mut tessellator = ZeroTessellator::<IndexDataType>::default();
+                        tessellator.zoom = u8::from(coords.z) as f64;
                         match paint {
                             LayerPaint::Fill(p) => {
-                                tessellator.style_property = p.fill_color.clone()
+                                tessellator.style_property = p.fill_color.clone();
+                                tessellator.fill_paint = Some(p.clone());
                             }
                             LayerPaint::Line(p) => {
                                 tessellator.style_property = p.line_color.clone();
+                                tessellator.line_paint = Some(p.clone());
                                 tessellator.is_line_layer = true;
                             }
                             LayerPaint::Background(p) => {
@@ -225,6 +249,55 @@ pub fn process_vector_tile<T: VectorTransferables, C: Context>(
                                 tessellator.buffer.into(),
                                 tessellator.feature_indices,
                                 tessellator.feature_colors,
+                                tessellator.retained_feature_properties,
+                                original_layer,
+                                id.clone(),
+                            )?;
+                        }
+                    }
+                    LayerPaint::Circle(_) | LayerPaint::Heatmap(_) => {
+                        let zoom = u8::from(coords.z) as f64;
+                        // The kernels of heatmaps are centered at the same points as circles
+                        let mut tessellator = match paint {
+                            LayerPaint::Circle(p) => CircleTessellator::new(p, zoom),
+                            LayerPaint::Heatmap(p) => CircleTessellator::for_heatmap(p, zoom),
+                            _ => unreachable!(),
+                        };
+
+                        if let Err(e) = layer.process(&mut tessellator) {
+                            context.layer_missing(coords, source_layer)?;
+
+                            tracing::error!("tessellation for layer source {source_layer} at {coords} failed {e:?}");
+                        } else {
+                            let (buffer, feature_indices, feature_properties) =
+                                tessellator.finish();
+                            context.layer_tessellation_finished(
+                                coords,
+                                buffer.into(),
+                                feature_indices,
+                                Vec::new(),
+                                feature_properties,
+                                original_layer,
+                                id.clone(),
+                            )?;
+                        }
+                    }
+                    LayerPaint::FillExtrusion(fill_extrusion_paint) => {
+                        let mut tessellator = FillExtrusionTessellator::new(fill_extrusion_paint);
+
+                        if let Err(e) = layer.process(&mut tessellator) {
+                            context.layer_missing(coords, source_layer)?;
+
+                            tracing::error!("tessellation for layer source {source_layer} at {coords} failed {e:?}");
+                        } else {
+                            let (buffer, feature_indices, feature_properties) =
+                                tessellator.finish();
+                            context.layer_tessellation_finished(
+                                coords,
+                                buffer.into(),
+                                feature_indices,
+                                Vec::new(),
+                                feature_properties,
                                 original_layer,
                                 id.clone(),
                             )?;
@@ -232,11 +305,8 @@ pub fn process_vector_tile<T: VectorTransferables, C: Context>(
                     }
                     LayerPaint::Symbol(symbol_paint) => {
                         let mut tessellator = TextTessellator::<IndexDataType>::default();
-                        let text_field = symbol_paint
-                            .text_field
-                            .clone()
-                            .unwrap_or_else(|| "name".to_string());
-                        let mut tessellator_new = TextTessellatorNew::new(text_field);
+                        let mut tessellator_new =
+                            TextTessellatorNew::new(symbol_paint, u8::from(coords.z) as f64);
 
                         if let Err(e) = layer.process(&mut tessellator_new) {
                             context.layer_missing(coords, &source_layer)?;
@@ -294,10 +364,6 @@ pub fn process_vector_tile<T: VectorTransferables, C: Context>(
 
     context.layer_indexing_finished(&tile_request.coords, index.get_geometries())?;
 
-    // Report end
-    tracing::info!("tile tessellated at {coords} finished");
-    context.tile_finished(coords)?;
-
     Ok(())
 }
 
@@ -320,12 +386,6 @@ impl<T: VectorTransferables, C: Context> ProcessVectorContext<T, C> {
         self.context
     }
 
-    fn tile_finished(&mut self, coords: &WorldTileCoords) -> Result<(), ProcessVectorError> {
-        self.context
-            .send_back(T::TileTessellated::build_from(*coords))
-            .map_err(|e| ProcessVectorError::SendError(e))
-    }
-
     fn layer_missing(
         &mut self,
         coords: &WorldTileCoords,
@@ -336,12 +396,14 @@ impl<T: VectorTransferables, C: Context> ProcessVectorContext<T, C> {
             .map_err(|e| ProcessVectorError::SendError(e))
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn layer_tessellation_finished(
         &mut self,
         coords: &WorldTileCoords,
         buffer: OverAlignedVertexBuffer<ShaderVertex, IndexDataType>,
         feature_indices: Vec<u32>,
         feature_colors: Vec<[f32; 4]>,
+        feature_properties: Vec<FeatureProperties>,
         layer_data: tile::Layer,
         style_layer_id: String,
     ) -> Result<(), ProcessVectorError> {
@@ -351,6 +413,7 @@ impl<T: VectorTransferables, C: Context> ProcessVectorContext<T, C> {
                 buffer,
                 feature_indices,
                 feature_colors,
+                feature_properties,
                 layer_data,
                 style_layer_id,
             ))
@@ -394,9 +457,11 @@ impl<T: VectorTransferables, C: Context> ProcessVectorContext<T, C> {
 
 #[cfg(test)]
 mod tests {
-    use super::ProcessVectorContext;
+    use geozero::mvt::{tile, Message};
+
+    use super::{overzoom_tile, ProcessVectorContext};
     use crate::{
-        coords::ZoomLevel,
+        coords::{WorldTileCoords, ZoomLevel},
         io::apc::tests::DummyContext,
         vector::{
             process_vector::{process_vector_tile, VectorTileRequest},
@@ -416,4 +481,44 @@ mod tests {
             &mut ProcessVectorContext::<DefaultVectorTransferables, _>::new(DummyContext),
         );
     }
+
+    #[test]
+    fn test_overzoom_tile() {
+        let feature = |r#type: tile::GeomType, geometry: Vec<u32>| tile::Feature {
+            r#type: Some(r#type as i32),
+            geometry,
+            ..Default::default()
+        };
+        let tile = geozero::mvt::Tile {
+            layers: vec![tile::Layer {
+                version: 2,
+                name: "roads".to_string(),
+                extent: Some(4096),
+                features: vec![
+                    // A line from (1024, 1024) to (3072, 3072)
+                    feature(
+                        tile::GeomType::Linestring,
+                        vec![9, 2048, 2048, 10, 4096, 4096],
+                    ),
+                    // A point at (100, 100)
+                    feature(tile::GeomType::Point, vec![9, 200, 200]),
+                ],
+                ..Default::default()
+            }],
+        };
+
+        let data = overzoom_tile(
+            &tile.encode_to_vec(),
+            WorldTileCoords::from((0, 0, ZoomLevel::new(0))),
+            WorldTileCoords::from((1, 1, ZoomLevel::new(1))),
+        )
+        .unwrap();
+        let tile = geozero::mvt::Tile::decode(data.as_slice()).unwrap();
+
+        // The line reaches from (-2048, -2048) to (2048, 2048) in the bottom right tile, while the
+        // point lies outside of it
+        let features = &tile.layers[0].features;
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].geometry, vec![9, 4095, 4095, 10, 8192, 8192]);
+    }
 }
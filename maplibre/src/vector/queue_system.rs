@@ -17,7 +17,14 @@ use crate::{
     },
 };
 
-pub fn queue_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
+pub fn queue_system(
+    MapContext {
+        world,
+        style,
+        view_state,
+        ..
+    }: &mut MapContext,
+) -> SystemResult {
     let Some((
         Initialized(tile_view_pattern),
         Initialized(buffer_pool),
@@ -34,6 +41,7 @@ pub fn queue_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
     };
 
     let buffer_pool_index = buffer_pool.index();
+    let visible_layers = style.visible_layer_ids(view_state.zoom().level());
 
     for view_tile in tile_view_pattern.iter() {
         let coords = &view_tile.coords();
@@ -49,6 +57,10 @@ pub fn queue_system(MapContext { world, .. }: &mut MapContext) -> SystemResult {
 
             if let Some(layer_entries) = buffer_pool_index.get_layers(source_shape.coords()) {
                 for layer_entry in layer_entries {
+                    if !visible_layers.contains(layer_entry.style_layer.id.as_str()) {
+                        continue;
+                    }
+
                     // Choose fill vs line pipeline based on layer type
                     let is_line = layer_entry.style_layer.type_ == "line";
                     let draw_function: Box<dyn crate::render::render_phase::Draw<LayerItem>> =
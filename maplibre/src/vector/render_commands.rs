@@ -4,12 +4,12 @@ use crate::{
     render::{
         eventually::{Eventually, Eventually::Initialized},
         render_phase::{LayerItem, PhaseItem, RenderCommand, RenderCommandResult},
-        resource::TrackedRenderPass,
+        resource::{ImageAtlasTexture, TrackedRenderPass},
         tile_view_pattern::WgpuTileViewPattern,
         INDEX_FORMAT,
     },
     tcs::world::World,
-    vector::{LinePipeline, VectorBufferPool, VectorPipeline},
+    vector::{resource::LineAtlasTexture, LinePipeline, VectorBufferPool, VectorPipeline},
 };
 
 pub struct SetVectorTilePipeline;
@@ -19,11 +19,15 @@ impl<P: PhaseItem> RenderCommand<P> for SetVectorTilePipeline {
         _item: &P,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        let Some(Initialized(pipeline)) = world.resources.get::<Eventually<VectorPipeline>>()
+        let Some((Initialized(pipeline), Initialized(ImageAtlasTexture { bind_group, .. }))) =
+            world
+                .resources
+                .query::<(&Eventually<VectorPipeline>, &Eventually<ImageAtlasTexture>)>()
         else {
             return RenderCommandResult::Failure;
         };
 
+        pass.set_bind_group(0, bind_group, &[]);
         pass.set_render_pipeline(pipeline);
         RenderCommandResult::Success
     }
@@ -113,10 +117,21 @@ impl<P: PhaseItem> RenderCommand<P> for SetLineTilePipeline {
         _item: &P,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        let Some(Initialized(pipeline)) = world.resources.get::<Eventually<LinePipeline>>() else {
+        let Some((
+            Initialized(pipeline),
+            Initialized(LineAtlasTexture { bind_group, .. }),
+            Initialized(image_atlas_texture),
+        )) = world.resources.query::<(
+            &Eventually<LinePipeline>,
+            &Eventually<LineAtlasTexture>,
+            &Eventually<ImageAtlasTexture>,
+        )>()
+        else {
             return RenderCommandResult::Failure;
         };
 
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.set_bind_group(1, &image_atlas_texture.bind_group, &[]);
         pass.set_render_pipeline(pipeline);
         RenderCommandResult::Success
     }
@@ -1,22 +1,30 @@
 //! Requests tiles which are currently in view
 
-use std::{borrow::Cow, collections::HashSet, marker::PhantomData, rc::Rc};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    rc::Rc,
+};
 
 use crate::{
     context::MapContext,
+    coords::WorldTileCoords,
     environment::{Environment, OffscreenKernel},
     io::{
         apc::{AsyncProcedureCall, AsyncProcedureFuture, Context, Input, ProcedureError},
-        source_type::{SourceType, TessellateSource},
+        source_type::{SourceType, TemplateSource},
     },
     kernel::Kernel,
     render::{tile_view_pattern::DEFAULT_TILE_SIZE, view_state::ViewStatePadding},
     sdf::SymbolLayersDataComponent,
-    style::layer::StyleLayer,
+    style::{layer::StyleLayer, source::Source},
     tcs::system::{System, SystemResult},
     vector::{
-        process_vector::{process_vector_tile, ProcessVectorContext, VectorTileRequest},
-        transferables::{LayerMissing, VectorTransferables},
+        process_vector::{
+            overzoom_tile, process_vector_tile, ProcessVectorContext, VectorTileRequest,
+        },
+        transferables::{LayerMissing, TileTessellated, VectorTransferables},
         VectorLayerBucketComponent,
     },
 };
@@ -89,6 +97,7 @@ impl<E: Environment, T: VectorTransferables> System for RequestSystem<E, T> {
                             Input::TileRequest {
                                 coords,
                                 style: style.clone(), // TODO: Avoid cloning whole style
+                                source_backends: self.kernel.source_client().backends().clone(),
                             },
                             fetch_vector_apc::<
                                 E::OffscreenKernelEnvironment,
@@ -112,46 +121,272 @@ pub fn fetch_vector_apc<K: OffscreenKernel, T: VectorTransferables, C: Context +
     kernel: K,
 ) -> AsyncProcedureFuture {
     Box::pin(async move {
-        let Input::TileRequest { coords, style } = input else {
+        let Input::TileRequest {
+            coords,
+            style,
+            source_backends,
+        } = input
+        else {
             return Err(ProcedureError::IncompatibleInput);
         };
 
-        let requested_layers: HashSet<StyleLayer> = style.layers.iter().cloned().collect();
-
-        let client = kernel.source_client();
-
-        if !style.layers.is_empty() {
-            let context = context.clone();
-            let source = SourceType::Tessellate(TessellateSource::default());
-            match client.fetch(&coords, &source).await {
-                Ok(data) => {
-                    let data = data.into_boxed_slice();
-
-                    let mut pipeline_context = ProcessVectorContext::<T, C>::new(context);
-                    process_vector_tile(
-                        &data,
-                        VectorTileRequest {
-                            coords,
-                            layers: requested_layers,
-                        },
-                        &mut pipeline_context,
-                    )
-                    .map_err(|e| ProcedureError::Execution(Box::new(e)))?;
+        // Layers which are hidden by their visibility are still tessellated, such that they can
+        // be shown again without refetching the tile.
+        let mut requested_layers: HashMap<&str, HashSet<StyleLayer>> = HashMap::new();
+        let mut unsourced_layers = HashSet::new();
+        for layer in &style.layers {
+            if layer.source_layer.is_none() || !layer.is_in_zoom_range_of_tile(coords.z) {
+                continue;
+            }
+            let Some(source) = &layer.source else {
+                log::warn!("vector style layer {} does not define a source", layer.id);
+                unsourced_layers.insert(layer.clone());
+                continue;
+            };
+            requested_layers
+                .entry(source.as_str())
+                .or_default()
+                .insert(layer.clone());
+        }
+        send_layers_missing::<T, C>(&context, coords, &unsourced_layers)?;
+
+        let mut client = kernel.source_client();
+        client.register_backends(&source_backends);
+
+        // Every source is fetched once per tile, for all the layers which use it
+        for (source_id, layers) in requested_layers {
+            let source = match style.sources.get(source_id) {
+                Some(Source::Vector(source)) => source,
+                Some(_) => {
+                    log::warn!("source {source_id} of vector style layers is not a vector source");
+                    send_layers_missing::<T, C>(&context, coords, &layers)?;
+                    continue;
+                }
+                None => {
+                    log::warn!("vector source {source_id} is not defined in the style");
+                    send_layers_missing::<T, C>(&context, coords, &layers)?;
+                    continue;
                 }
+            };
+
+            if !source.has_tile(&coords) {
+                send_layers_missing::<T, C>(&context, coords, &layers)?;
+                continue;
+            }
+
+            let Some(template_source) = TemplateSource::from_vector_source(source) else {
+                log::warn!("vector source {source_id} does not define any tiles");
+                send_layers_missing::<T, C>(&context, coords, &layers)?;
+                continue;
+            };
+
+            // Tiles beyond the maximum zoom level of the source are cut from their ancestor
+            let source_coords = source.source_tile(&coords);
+            let data = match client
+                .fetch(&source_coords, &SourceType::Template(template_source))
+                .await
+            {
+                Ok(data) if source_coords == coords => Ok(data),
+                Ok(data) => overzoom_tile(&data, source_coords, coords).map_err(|e| {
+                    format!("overzooming the tile {source_coords} to {coords} failed: {e:?}")
+                }),
+                Err(e) => Err(format!("{e:?}")),
+            };
+            let data = match data {
+                Ok(data) => data.into_boxed_slice(),
                 Err(e) => {
-                    log::error!("{e:?}");
-                    for to_load in &requested_layers {
-                        context
-                            .send_back(<T as VectorTransferables>::LayerMissing::build_from(
-                                coords,
-                                to_load.id.clone(),
-                            ))
-                            .map_err(ProcedureError::Send)?;
-                    }
+                    log::error!("{e}");
+                    send_layers_missing::<T, C>(&context, coords, &layers)?;
+                    continue;
                 }
+            };
+
+            let mut pipeline_context = ProcessVectorContext::<T, C>::new(context.clone());
+            if let Err(e) = process_vector_tile(
+                &data,
+                VectorTileRequest {
+                    coords,
+                    layers: layers.clone(),
+                },
+                &mut pipeline_context,
+            ) {
+                log::error!(
+                    "processing the tile {coords} of vector source {source_id} failed: {e:?}"
+                );
+                send_layers_missing::<T, C>(&context, coords, &layers)?;
             }
         }
 
+        // The tile is finished once, after all of its sources have been processed or reported as
+        // missing
+        tracing::info!("tile tessellated at {coords} finished");
+        context
+            .send_back(<T as VectorTransferables>::TileTessellated::build_from(
+                coords,
+            ))
+            .map_err(ProcedureError::Send)?;
+
         Ok(())
     })
 }
+
+fn send_layers_missing<T: VectorTransferables, C: Context>(
+    context: &C,
+    coords: WorldTileCoords,
+    layers: &HashSet<StyleLayer>,
+) -> Result<(), ProcedureError> {
+    for layer in layers {
+        context
+            .send_back(<T as VectorTransferables>::LayerMissing::build_from(
+                coords,
+                layer.id.clone(),
+            ))
+            .map_err(ProcedureError::Send)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+
+    use super::fetch_vector_apc;
+    use crate::{
+        coords::{WorldTileCoords, ZoomLevel},
+        environment::{OffscreenKernel, OffscreenKernelConfig},
+        io::{
+            apc::{Context, Input, IntoMessage, Message, SendError},
+            source_client::{
+                HttpClient, HttpSourceClient, SourceBackend, SourceBackends, SourceClient,
+                SourceFetchError,
+            },
+            tile_json::{TileJson, TileJsonError},
+        },
+        style::Style,
+        vector::{
+            transferables::{LayerMissing, TileTessellated, VectorTransferables},
+            DefaultVectorTransferables,
+        },
+    };
+
+    #[derive(Clone)]
+    struct UnreachableHttpClient;
+
+    #[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+    #[cfg_attr(feature = "thread-safe-futures", async_trait)]
+    impl HttpClient for UnreachableHttpClient {
+        async fn fetch(&self, url: &str) -> Result<Vec<u8>, SourceFetchError> {
+            panic!("unexpected request to {url}")
+        }
+    }
+
+    struct TestKernel;
+
+    impl OffscreenKernel for TestKernel {
+        type HttpClient = UnreachableHttpClient;
+
+        fn create(_config: OffscreenKernelConfig) -> Self {
+            Self
+        }
+
+        fn source_client(&self) -> SourceClient<Self::HttpClient> {
+            SourceClient::new(HttpSourceClient::new(UnreachableHttpClient))
+        }
+    }
+
+    /// Serves an empty vector tile for every url.
+    struct EmptyTileBackend;
+
+    #[cfg_attr(not(feature = "thread-safe-futures"), async_trait(?Send))]
+    #[cfg_attr(feature = "thread-safe-futures", async_trait)]
+    impl SourceBackend for EmptyTileBackend {
+        async fn fetch_tile(
+            &self,
+            _url: &str,
+            _coords: &WorldTileCoords,
+        ) -> Result<Vec<u8>, SourceFetchError> {
+            Ok(Vec::new())
+        }
+
+        async fn fetch_tile_json(&self, _url: &str) -> Result<TileJson, TileJsonError> {
+            Err(TileJsonError::NoTiles)
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingContext {
+        messages: Arc<Mutex<Vec<Message>>>,
+    }
+
+    impl Context for RecordingContext {
+        fn send_back<T: IntoMessage>(&self, message: T) -> Result<(), SendError> {
+            self.messages.lock().unwrap().push(message.into());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tile_finished_once_for_all_sources() {
+        let style: Style = serde_json::from_value(serde_json::json!({
+            "version": 8,
+            "sources": {
+                "streets": {"type": "vector", "tiles": ["memory://streets/{z}/{x}/{y}"]},
+                "water": {"type": "vector", "tiles": ["memory://water/{z}/{x}/{y}"]},
+                "germany": {
+                    "type": "vector",
+                    "tiles": ["memory://germany/{z}/{x}/{y}"],
+                    "bounds": [5.8, 47.2, 15.1, 55.1]
+                }
+            },
+            "layers": [
+                {"id": "roads", "type": "line", "source": "streets", "source-layer": "roads"},
+                {"id": "ocean", "type": "fill", "source": "water", "source-layer": "ocean"},
+                {"id": "borders", "type": "line", "source": "germany", "source-layer": "borders"}
+            ]
+        }))
+        .unwrap();
+        let mut source_backends = SourceBackends::default();
+        source_backends.insert("memory", Arc::new(EmptyTileBackend));
+        let context = RecordingContext::default();
+
+        // The tile is outside of the bounds of the germany source, but the other sources are
+        // fetched
+        fetch_vector_apc::<TestKernel, DefaultVectorTransferables, _>(
+            Input::TileRequest {
+                coords: WorldTileCoords::from((0, 5, ZoomLevel::new(4))),
+                style,
+                source_backends,
+            },
+            context.clone(),
+            TestKernel,
+        )
+        .await
+        .unwrap();
+
+        let messages = std::mem::take(&mut *context.messages.lock().unwrap());
+        let (finished, messages): (Vec<_>, Vec<_>) = messages.into_iter().partition(|message| {
+            message.has_tag(
+                <DefaultVectorTransferables as VectorTransferables>::TileTessellated::message_tag(),
+            )
+        });
+        assert_eq!(finished.len(), 1);
+
+        let missing_layers: Vec<_> = messages
+            .into_iter()
+            .filter(|message| {
+                message.has_tag(
+                    <DefaultVectorTransferables as VectorTransferables>::LayerMissing::message_tag(),
+                )
+            })
+            .map(|message| {
+                message
+                    .into_transferable::<<DefaultVectorTransferables as VectorTransferables>::LayerMissing>()
+                    .layer_name()
+                    .to_string()
+            })
+            .collect();
+        assert!(missing_layers.contains(&"borders".to_string()));
+    }
+}
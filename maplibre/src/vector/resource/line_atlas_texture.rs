@@ -0,0 +1,85 @@
+use crate::vector::line_atlas::{LINE_ATLAS_HEIGHT, LINE_ATLAS_WIDTH};
+
+/// Bytes per texel of [`wgpu::TextureFormat::Rgba8Unorm`].
+const TEXEL_BYTES: u32 = 4;
+
+/// The texture of the [`LineAtlas`](crate::vector::line_atlas::LineAtlas) and its bind group.
+pub struct LineAtlasTexture {
+    texture: wgpu::Texture,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl LineAtlasTexture {
+    pub fn from_device(device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Line atlas texture"),
+            size: Self::size(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Line atlas sampler"),
+            // The dash patterns repeat along the line. Gradients are sampled at the centers of the
+            // texels, such that they do not wrap around.
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Line atlas bind group"),
+        });
+
+        Self {
+            texture,
+            bind_group,
+        }
+    }
+
+    /// Uploads the texels of the whole atlas.
+    pub fn upload(&self, queue: &wgpu::Queue, data: &[u8]) {
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(LINE_ATLAS_WIDTH * TEXEL_BYTES),
+                rows_per_image: Some(LINE_ATLAS_HEIGHT),
+            },
+            Self::size(),
+        );
+    }
+
+    fn size() -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: LINE_ATLAS_WIDTH,
+            height: LINE_ATLAS_HEIGHT,
+            depth_or_array_layers: 1,
+        }
+    }
+}
@@ -1,3 +1,5 @@
 pub use buffer_pool::*;
+pub use line_atlas_texture::*;
 
 mod buffer_pool;
+mod line_atlas_texture;
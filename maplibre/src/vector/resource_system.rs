@@ -9,7 +9,10 @@ use crate::{
         RenderResources, Renderer,
     },
     tcs::system::{SystemError, SystemResult},
-    vector::{resource::BufferPool, LinePipeline, VectorBufferPool, VectorPipeline},
+    vector::{
+        resource::{BufferPool, LineAtlasTexture},
+        LinePipeline, VectorBufferPool, VectorPipeline,
+    },
 };
 
 pub fn resource_system(
@@ -25,11 +28,14 @@ pub fn resource_system(
         ..
     }: &mut MapContext,
 ) -> SystemResult {
-    let Some((buffer_pool, vector_pipeline, line_pipeline)) = world.resources.query_mut::<(
-        &mut Eventually<VectorBufferPool>,
-        &mut Eventually<VectorPipeline>,
-        &mut Eventually<LinePipeline>,
-    )>() else {
+    let Some((buffer_pool, vector_pipeline, line_pipeline, line_atlas_texture)) =
+        world.resources.query_mut::<(
+            &mut Eventually<VectorBufferPool>,
+            &mut Eventually<VectorPipeline>,
+            &mut Eventually<LinePipeline>,
+            &mut Eventually<LineAtlasTexture>,
+        )>()
+    else {
         return Err(SystemError::Dependencies);
     };
 
@@ -50,7 +56,7 @@ pub fn resource_system(
             false,
             false,
             surface.is_multisampling_supported(settings.msaa),
-            false,
+            true, // The image atlas is bound like a raster texture
             false,
         )
         .describe_render_pipeline()
@@ -74,12 +80,17 @@ pub fn resource_system(
             false,
             false,
             surface.is_multisampling_supported(settings.msaa),
-            false,
+            true, // The line atlas and the image atlas are bound like raster textures
             false,
         )
+        .with_texture_bind_groups(2)
         .describe_render_pipeline()
         .initialize(device);
 
+        line_atlas_texture.initialize(|| {
+            LineAtlasTexture::from_device(device, &pipeline.get_bind_group_layout(0))
+        });
+
         LinePipeline(pipeline)
     });
 
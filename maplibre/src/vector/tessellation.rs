@@ -1,30 +1,52 @@
 //! Tessellation for lines and polygons is implemented here.
 
-use std::{cell::RefCell, collections::HashMap};
+use std::cell::RefCell;
 
 use bytemuck::Pod;
 use geozero::{ColumnValue, FeatureProcessor, GeomProcessor, PropertyProcessor};
 use lyon::{
     geom,
-    path::{path::Builder, Path},
+    path::{path::Builder, Path, PathEvent},
     tessellation::{
         geometry_builder::MaxIndex, BuffersBuilder, FillOptions, FillRule, FillTessellator,
-        FillVertex, FillVertexConstructor, StrokeOptions, StrokeTessellator, StrokeVertex,
-        StrokeVertexConstructor, VertexBuffers,
+        FillVertex, FillVertexConstructor, LineCap as StrokeLineCap, LineJoin as StrokeLineJoin,
+        Side, StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor,
+        VertexBuffers, VertexSource,
     },
 };
 
-use crate::render::ShaderVertex;
+use crate::{
+    render::ShaderVertex,
+    style::{
+        expression::{EvaluationContext, FeatureProperties, Value},
+        layer::{FillPaint, LineCap, LineJoin, LinePaint},
+    },
+};
 
 const DEFAULT_TOLERANCE: f32 = 0.02;
 
+/// Strokes are tessellated with a width of one tile unit and widened in the shader. Round caps
+/// and joins therefore need a much lower tolerance than fills to stay round at larger widths.
+const STROKE_TOLERANCE: f32 = 0.001;
+
+/// The default of `line-miter-limit`.
+const DEFAULT_MITER_LIMIT: f32 = 2.0;
+
 /// Vertex buffers index data type.
 pub type IndexDataType = u32; // Must match INDEX_FORMAT
 
 type GeoResult<T> = geozero::error::Result<T>;
 
 /// Constructor for Fill and Stroke vertices.
-pub struct VertexConstructor {}
+#[derive(Default)]
+pub struct VertexConstructor {
+    /// The distance at which the line of each endpoint starts and the length of that line,
+    /// indexed by endpoint. Empty if the progress along lines is not calculated.
+    line_extents: Vec<[f32; 2]>,
+    /// The distance along the feature at which the tessellated path starts, if the path is only
+    /// a piece of the lines of the feature.
+    advancement: f32,
+}
 
 impl FillVertexConstructor<ShaderVertex> for VertexConstructor {
     fn new_vertex(&mut self, vertex: FillVertex) -> ShaderVertex {
@@ -38,6 +60,28 @@ impl StrokeVertexConstructor<ShaderVertex> for VertexConstructor {
             vertex.position_on_path().to_array(),
             vertex.normal().to_array(),
         )
+        .with_line_distance(self.advancement + vertex.advancement())
+        .with_line_side(match vertex.side() {
+            Side::Positive => 1.0,
+            Side::Negative => -1.0,
+        })
+        .with_line_progress(self.line_progress(&vertex))
+    }
+}
+
+impl VertexConstructor {
+    fn line_progress(&self, vertex: &StrokeVertex) -> f32 {
+        let endpoint = match vertex.source() {
+            VertexSource::Endpoint { id } => id,
+            VertexSource::Edge { from, .. } => from,
+        };
+        let Some([start, length]) = self.line_extents.get(endpoint.to_usize()) else {
+            return 0.0;
+        };
+        if *length <= 0.0 {
+            return 0.0;
+        }
+        ((self.advancement + vertex.advancement() - start) / length).clamp(0.0, 1.0)
     }
 }
 
@@ -129,13 +173,27 @@ pub struct ZeroTessellator<I: std::ops::Add + From<lyon::tessellation::VertexId>
     pub buffer: VertexBuffers<ShaderVertex, I>,
 
     pub feature_indices: Vec<u32>,
-    pub feature_properties: HashMap<String, String>,
+    pub feature_properties: FeatureProperties,
     pub feature_colors: Vec<[f32; 4]>,
     pub fallback_color: [f32; 4],
     pub style_property: Option<crate::style::layer::StyleProperty<csscolorparser::Color>>,
+    /// Zoom level at which the `style_property` is evaluated.
+    pub zoom: f64,
+    /// Properties of each feature. Only retained if the `style_property` depends on both the zoom
+    /// level and the feature, because then it needs to be re-evaluated while zooming, or if other
+    /// paint properties of fills or lines depend on the feature, because they are evaluated while
+    /// uploading.
+    pub retained_feature_properties: Vec<FeatureProperties>,
     /// When true, polygon geometry is tessellated as strokes (outlines) instead of fills.
     /// This is used when a line-type style layer references polygon source geometry.
     pub is_line_layer: bool,
+    /// The paint of line layers, which determines the caps and joins of the strokes.
+    pub line_paint: Option<LinePaint>,
+    /// When true, the progress along each line is stored in the vertices of strokes.
+    pub line_metrics: bool,
+    /// The paint of fill layers, which determines whether the outlines of polygons are
+    /// tessellated as strokes in addition to their fills.
+    pub fill_paint: Option<FillPaint>,
     current_index: usize,
 }
 
@@ -147,11 +205,16 @@ impl<I: std::ops::Add + From<lyon::tessellation::VertexId> + MaxIndex> Default
             path_builder: RefCell::new(Path::builder()),
             buffer: VertexBuffers::new(),
             feature_indices: Vec::new(),
-            feature_properties: HashMap::new(),
+            feature_properties: FeatureProperties::new(),
             feature_colors: Vec::new(),
             fallback_color: [0.0, 0.0, 0.0, 1.0],
             style_property: None,
+            zoom: 0.0,
+            retained_feature_properties: Vec::new(),
             is_line_layer: false,
+            line_paint: None,
+            line_metrics: false,
+            fill_paint: None,
             current_index: 0,
             path_open: false,
             is_point: false,
@@ -169,14 +232,109 @@ impl<I: std::ops::Add + From<lyon::tessellation::VertexId> + MaxIndex> ZeroTesse
         self.current_index = next_index;
     }
 
+    /// Evaluates the layout properties of the line layer for the current feature.
+    fn stroke_options(&self) -> StrokeOptions {
+        let options = StrokeOptions::tolerance(STROKE_TOLERANCE);
+        let Some(paint) = &self.line_paint else {
+            return options;
+        };
+
+        let context = EvaluationContext::default()
+            .with_zoom(self.zoom)
+            .with_properties(&self.feature_properties);
+
+        let cap = match paint
+            .line_cap
+            .as_ref()
+            .and_then(|cap| cap.evaluate(&context))
+            .unwrap_or_default()
+        {
+            LineCap::Butt => StrokeLineCap::Butt,
+            LineCap::Round => StrokeLineCap::Round,
+            LineCap::Square => StrokeLineCap::Square,
+        };
+        let join = match paint
+            .line_join
+            .as_ref()
+            .and_then(|join| join.evaluate(&context))
+            .unwrap_or_default()
+        {
+            LineJoin::Bevel => StrokeLineJoin::Bevel,
+            LineJoin::Round => StrokeLineJoin::Round,
+            LineJoin::Miter => StrokeLineJoin::Miter,
+        };
+        let miter_limit = paint
+            .line_miter_limit
+            .as_ref()
+            .and_then(|limit| limit.evaluate(&context))
+            .unwrap_or(DEFAULT_MITER_LIMIT);
+
+        // maplibre-gl bevels joins whose miter is longer than the limit times the half width, lyon
+        // compares against the full width. lyon requires a limit of at least 1, so limits below 2
+        // behave like 2.
+        options
+            .with_line_cap(cap)
+            .with_line_join(join)
+            .with_miter_limit((miter_limit / 2.0).max(StrokeOptions::MINIMUM_MITER_LIMIT))
+    }
+
     fn tessellate_strokes(&mut self) {
         let path_builder = self.path_builder.replace(Path::builder());
+        let path = path_builder.build();
+
+        let options = self.stroke_options();
+        let line_extents = if self.line_metrics {
+            line_extents(&path)
+        } else {
+            Vec::new()
+        };
 
+        // lyon collapses miter joins of lines which turn back on themselves to a single point.
+        // Such joins exceed any miter limit, so maplibre-gl draws them as bevel joins, which are
+        // flat like butt caps when turning back. The lines are split at these joins, such that
+        // all other joins keep their miters.
+        if options.line_join == StrokeLineJoin::Miter {
+            if let Some(pieces) = split_at_turns(&path) {
+                for piece in pieces {
+                    let mut piece_options = options;
+                    if piece.starts_at_turn {
+                        piece_options.start_cap = StrokeLineCap::Butt;
+                    }
+                    if piece.ends_at_turn {
+                        piece_options.end_cap = StrokeLineCap::Butt;
+                    }
+                    let vertex_constructor = VertexConstructor {
+                        line_extents: piece
+                            .endpoints
+                            .iter()
+                            .filter_map(|endpoint| line_extents.get(*endpoint).copied())
+                            .collect(),
+                        advancement: piece.advancement,
+                    };
+                    self.tessellate_stroke(&piece.path, &piece_options, vertex_constructor);
+                }
+                return;
+            }
+        }
+
+        let vertex_constructor = VertexConstructor {
+            line_extents,
+            advancement: 0.0,
+        };
+        self.tessellate_stroke(&path, &options, vertex_constructor);
+    }
+
+    fn tessellate_stroke(
+        &mut self,
+        path: &Path,
+        options: &StrokeOptions,
+        vertex_constructor: VertexConstructor,
+    ) {
         StrokeTessellator::new()
             .tessellate_path(
-                &path_builder.build(),
-                &StrokeOptions::tolerance(DEFAULT_TOLERANCE),
-                &mut BuffersBuilder::new(&mut self.buffer, VertexConstructor {}),
+                path,
+                options,
+                &mut BuffersBuilder::new(&mut self.buffer, vertex_constructor),
             )
             .unwrap(); // TODO: Remove unwrap
     }
@@ -190,14 +348,213 @@ impl<I: std::ops::Add + From<lyon::tessellation::VertexId> + MaxIndex> ZeroTesse
 
     fn tessellate_fill(&mut self) {
         let path_builder = self.path_builder.replace(Path::builder());
+        let path = path_builder.build();
 
         FillTessellator::new()
             .tessellate_path(
-                &path_builder.build(),
+                &path,
                 &FillOptions::tolerance(DEFAULT_TOLERANCE).with_fill_rule(FillRule::NonZero),
-                &mut BuffersBuilder::new(&mut self.buffer, VertexConstructor {}),
+                &mut BuffersBuilder::new(&mut self.buffer, VertexConstructor::default()),
             )
             .unwrap(); // TODO: Remove unwrap
+
+        // The outline is told apart from the fill by its non-zero normals
+        if self.fill_paint.as_ref().is_some_and(FillPaint::has_outline) {
+            StrokeTessellator::new()
+                .tessellate_path(
+                    &path,
+                    &StrokeOptions::tolerance(STROKE_TOLERANCE)
+                        .with_line_join(StrokeLineJoin::Bevel),
+                    &mut BuffersBuilder::new(&mut self.buffer, VertexConstructor::default()),
+                )
+                .unwrap(); // TODO: Remove unwrap
+        }
+    }
+}
+
+/// Returns the distance from the start of the `path` at which the line of each endpoint starts
+/// and the length of that line. The distances match the advancement of lyon, which continues
+/// across the lines of a path.
+fn line_extents(path: &Path) -> Vec<[f32; 2]> {
+    let mut extents: Vec<[f32; 2]> = Vec::new();
+    let mut distance = 0.0;
+    let mut line_start = 0;
+    for event in path.iter() {
+        match event {
+            PathEvent::Begin { .. } => {
+                line_start = extents.len();
+                extents.push([distance, 0.0]);
+            }
+            PathEvent::Line { from, to } => {
+                distance += (to - from).length();
+                extents.push([extents[line_start][0], 0.0]);
+            }
+            PathEvent::End {
+                last, first, close, ..
+            } => {
+                if close {
+                    distance += (first - last).length();
+                }
+                let length = distance - extents[line_start][0];
+                for extent in &mut extents[line_start..] {
+                    extent[1] = length;
+                }
+            }
+            _ => {}
+        }
+    }
+    extents
+}
+
+/// A piece of the lines of a path, which is tessellated on its own.
+struct StrokePiece {
+    path: Path,
+    /// The endpoints of the original path, which are the endpoints of this piece.
+    endpoints: Vec<usize>,
+    /// The distance along the original path at which the piece starts.
+    advancement: f32,
+    starts_at_turn: bool,
+    ends_at_turn: bool,
+}
+
+/// A point of a line, with its endpoint and the distance along the path at which it lies.
+#[derive(Clone, Copy)]
+struct LinePoint {
+    position: geom::Point<f32>,
+    endpoint: usize,
+    advancement: f32,
+}
+
+/// Returns whether a line which passes `at` turns back by (almost) 180 degrees.
+fn turns_back(from: geom::Point<f32>, at: geom::Point<f32>, to: geom::Point<f32>) -> bool {
+    match ((at - from).try_normalize(), (to - at).try_normalize()) {
+        (Some(previous), Some(next)) => (previous + next).square_length() < 1e-4,
+        _ => false,
+    }
+}
+
+/// Splits the lines of the `path` at the joins which turn back by (almost) 180 degrees. Closed
+/// lines which turn back are opened at such a join. Returns `None` if no line turns back.
+fn split_at_turns(path: &Path) -> Option<Vec<StrokePiece>> {
+    let mut lines: Vec<(Vec<LinePoint>, bool)> = Vec::new();
+    let mut distance = 0.0;
+    let mut endpoint = 0;
+    for event in path.iter() {
+        let position = match event {
+            PathEvent::Begin { at } => {
+                lines.push((Vec::new(), false));
+                at
+            }
+            PathEvent::Line { from, to } => {
+                distance += (to - from).length();
+                to
+            }
+            PathEvent::End {
+                last, first, close, ..
+            } => {
+                if close {
+                    distance += (first - last).length();
+                    if let Some((_, closed)) = lines.last_mut() {
+                        *closed = true;
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some((points, _)) = lines.last_mut() {
+            points.push(LinePoint {
+                position,
+                endpoint,
+                advancement: distance,
+            });
+        }
+        endpoint += 1;
+    }
+
+    let turns_at = |points: &[LinePoint], i: usize| {
+        turns_back(
+            points[i - 1].position,
+            points[i].position,
+            points[i + 1].position,
+        )
+    };
+
+    let mut pieces = Vec::new();
+    let mut any_turns = false;
+    for (mut points, closed) in lines {
+        let n = points.len();
+        let opened_at_turn = if closed {
+            let first_turn = (0..n).find(|&i| {
+                turns_back(
+                    points[(i + n - 1) % n].position,
+                    points[i].position,
+                    points[(i + 1) % n].position,
+                )
+            });
+            let Some(first_turn) = first_turn else {
+                pieces.push(stroke_piece(&points, true, false, false));
+                continue;
+            };
+            // The line starts and ends at its first turn, and the distance continues to the end
+            points.rotate_left(first_turn);
+            let last = points[n - 1];
+            points.push(LinePoint {
+                advancement: last.advancement + (points[0].position - last.position).length(),
+                ..points[0]
+            });
+            true
+        } else {
+            if !(1..n.saturating_sub(1)).any(|i| turns_at(&points, i)) {
+                pieces.push(stroke_piece(&points, false, false, false));
+                continue;
+            }
+            false
+        };
+        any_turns = true;
+
+        let mut start = 0;
+        for i in 1..points.len() {
+            let is_end = i + 1 == points.len();
+            let is_turn = if is_end {
+                opened_at_turn
+            } else {
+                turns_at(&points, i)
+            };
+            if is_turn || is_end {
+                pieces.push(stroke_piece(
+                    &points[start..=i],
+                    false,
+                    start > 0 || opened_at_turn,
+                    is_turn,
+                ));
+                start = i;
+            }
+        }
+    }
+
+    any_turns.then_some(pieces)
+}
+
+fn stroke_piece(
+    points: &[LinePoint],
+    closed: bool,
+    starts_at_turn: bool,
+    ends_at_turn: bool,
+) -> StrokePiece {
+    let mut builder = Path::builder();
+    builder.begin(points[0].position);
+    for point in &points[1..] {
+        builder.line_to(point.position);
+    }
+    builder.end(closed);
+
+    StrokePiece {
+        path: builder.build(),
+        endpoints: points.iter().map(|point| point.endpoint).collect(),
+        advancement: points[0].advancement,
+        starts_at_turn,
+        ends_at_turn,
     }
 }
 
@@ -317,7 +674,7 @@ impl<I: std::ops::Add + From<lyon::tessellation::VertexId> + MaxIndex> PropertyP
         value: &ColumnValue,
     ) -> geozero::error::Result<bool> {
         self.feature_properties
-            .insert(name.to_string(), value.to_string());
+            .insert(name.to_string(), Value::from(value));
         Ok(false)
     }
 }
@@ -327,8 +684,28 @@ impl<I: std::ops::Add + From<lyon::tessellation::VertexId> + MaxIndex> FeaturePr
 {
     fn feature_end(&mut self, _idx: u64) -> geozero::error::Result<()> {
         self.update_feature_indices();
+        let retain_properties = self
+            .style_property
+            .as_ref()
+            .is_some_and(|style| !style.is_zoom_constant() && !style.is_feature_constant())
+            || self
+                .fill_paint
+                .as_ref()
+                .is_some_and(|paint| !paint.is_feature_constant())
+            || self
+                .line_paint
+                .as_ref()
+                .is_some_and(|paint| !paint.is_feature_constant());
+        if retain_properties {
+            self.retained_feature_properties
+                .push(self.feature_properties.clone());
+        }
+
         let color = if let Some(style) = &self.style_property {
-            if let Some(c) = style.evaluate(&self.feature_properties) {
+            let context = EvaluationContext::default()
+                .with_zoom(self.zoom)
+                .with_properties(&self.feature_properties);
+            if let Some(c) = style.evaluate(&context) {
                 [c.r as f32, c.g as f32, c.b as f32, c.a as f32]
             } else {
                 tracing::debug!(
@@ -347,3 +724,47 @@ impl<I: std::ops::Add + From<lyon::tessellation::VertexId> + MaxIndex> FeaturePr
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use geozero::GeomProcessor;
+
+    use super::{IndexDataType, ZeroTessellator};
+    use crate::style::layer::{LineJoin, LinePaint, StyleProperty};
+
+    fn tessellate_line(points: &[(f64, f64)]) -> ZeroTessellator<IndexDataType> {
+        let mut tessellator = ZeroTessellator::<IndexDataType> {
+            line_paint: Some(LinePaint {
+                line_join: Some(StyleProperty::Constant(LineJoin::Miter)),
+                ..LinePaint::default()
+            }),
+            ..ZeroTessellator::default()
+        };
+        tessellator.linestring_begin(true, points.len(), 0).unwrap();
+        for (i, (x, y)) in points.iter().enumerate() {
+            tessellator.xy(*x, *y, i).unwrap();
+        }
+        tessellator.linestring_end(true, 0).unwrap();
+        tessellator
+    }
+
+    #[test]
+    fn test_miter_join_next_to_turn() {
+        // The line turns right by 90 degrees and then turns back
+        let tessellator = tessellate_line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (10.0, 5.0)]);
+        let vertices = &tessellator.buffer.vertices;
+
+        // The miter of the right angle reaches sqrt(2) half widths from the join
+        assert!(vertices.iter().any(|vertex| vertex.position == [10.0, 0.0]
+            && (vertex.normal[0].hypot(vertex.normal[1]) - 2f32.sqrt()).abs() < 1e-3));
+
+        // The line is flat where it turns back, and the distance continues along the line
+        assert!(vertices
+            .iter()
+            .filter(|vertex| vertex.position == [10.0, 10.0])
+            .all(|vertex| (vertex.normal[0].hypot(vertex.normal[1]) - 1.0).abs() < 1e-3));
+        assert!(vertices
+            .iter()
+            .any(|vertex| vertex.position == [10.0, 5.0] && vertex.line_distance == 25.0));
+    }
+}
@@ -13,6 +13,7 @@ use crate::{
         ShaderVertex,
     },
     sdf::{Feature, SymbolLayerData},
+    style::expression::FeatureProperties,
     vector::{
         tessellation::{IndexDataType, OverAlignedVertexBuffer},
         AvailableVectorLayerBucket, MissingVectorLayerBucket,
@@ -66,6 +67,7 @@ pub trait LayerTessellated: IntoMessage + Debug + Send {
         buffer: OverAlignedVertexBuffer<ShaderVertex, IndexDataType>,
         feature_indices: Vec<u32>,
         feature_colors: Vec<[f32; 4]>,
+        feature_properties: Vec<FeatureProperties>,
         layer_data: Layer,
         style_layer_id: String,
     ) -> Self
@@ -193,6 +195,7 @@ pub struct DefaultLayerTessellated {
     /// Holds for each feature the count of indices.
     pub feature_indices: Vec<u32>,
     pub feature_colors: Vec<[f32; 4]>,
+    pub feature_properties: Vec<FeatureProperties>,
     pub layer_data: Layer, // FIXME (perf): Introduce a better structure for this
     pub style_layer_id: String,
 }
@@ -219,6 +222,7 @@ impl LayerTessellated for DefaultLayerTessellated {
         buffer: OverAlignedVertexBuffer<ShaderVertex, IndexDataType>,
         feature_indices: Vec<u32>,
         feature_colors: Vec<[f32; 4]>,
+        feature_properties: Vec<FeatureProperties>,
         layer_data: Layer,
         style_layer_id: String,
     ) -> Self {
@@ -227,6 +231,7 @@ impl LayerTessellated for DefaultLayerTessellated {
             buffer,
             feature_indices,
             feature_colors,
+            feature_properties,
             layer_data,
             style_layer_id,
         }
@@ -252,6 +257,7 @@ impl LayerTessellated for DefaultLayerTessellated {
             buffer: self.buffer,
             feature_indices: self.feature_indices,
             feature_colors: self.feature_colors,
+            feature_properties: self.feature_properties,
         }
     }
 }
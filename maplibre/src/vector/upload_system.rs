@@ -1,22 +1,31 @@
 //! Uploads data to the GPU which is needed for rendering.
 
+use csscolorparser::Color;
+
 use crate::{
     context::MapContext,
-    coords::ViewRegion,
+    coords::{ViewRegion, TILE_SIZE},
     render::{
         eventually::{Eventually, Eventually::Initialized},
-        shaders::{FillShaderFeatureMetadata, ShaderLayerMetadata, Vec4f32},
+        image_atlas::{ImageAtlas, ImagePosition},
+        resource::ImageAtlasTexture,
+        shaders::{FillShaderFeatureMetadata, ShaderLayerMetadata, Vec2f32, Vec4f32},
         tile_view_pattern::DEFAULT_TILE_SIZE,
         view_state::ViewStatePadding,
         Renderer,
     },
-    style::{layer::LayerPaint, Style},
+    style::{
+        expression::EvaluationContext,
+        layer::{LayerPaint, Reference, StyleLayer, StyleProperty},
+        Style,
+    },
     tcs::{
         system::{SystemError, SystemResult},
         tiles::Tiles,
     },
     vector::{
-        AvailableVectorLayerBucket, VectorBufferPool, VectorLayerBucket, VectorLayerBucketComponent,
+        line_atlas::LineAtlas, resource::LineAtlasTexture, AvailableVectorLayerBucket,
+        VectorBufferPool, VectorLayerBucket, VectorLayerBucketComponent,
     },
 };
 
@@ -29,23 +38,54 @@ pub fn upload_system(
         ..
     }: &mut MapContext,
 ) -> SystemResult {
-    let Some(Initialized(buffer_pool)) = world
-        .resources
-        .query_mut::<&mut Eventually<VectorBufferPool>>()
+    let zoom = view_state.zoom().level();
+
+    let zoom_changed = {
+        let evaluated_zoom = world.resources.get_or_init_mut::<EvaluatedZoom>();
+        let changed = evaluated_zoom.0 != Some(zoom);
+        evaluated_zoom.0 = Some(zoom);
+        changed
+    };
+
+    let Some((
+        Initialized(buffer_pool),
+        Initialized(line_atlas_texture),
+        line_atlas,
+        Initialized(image_atlas_texture),
+        image_atlas,
+    )) = world.resources.query_mut::<(
+        &mut Eventually<VectorBufferPool>,
+        &mut Eventually<LineAtlasTexture>,
+        &mut LineAtlas,
+        &mut Eventually<ImageAtlasTexture>,
+        &mut ImageAtlas,
+    )>()
     else {
         return Err(SystemError::Dependencies);
     };
 
+    if zoom_changed {
+        update_zoom_dependent_metadata(
+            buffer_pool,
+            line_atlas,
+            image_atlas,
+            queue,
+            &world.tiles,
+            style,
+            zoom,
+        );
+    }
+
     let view_region = view_state.create_view_region(
         view_state.zoom().zoom_level(DEFAULT_TILE_SIZE),
         ViewStatePadding::Loose,
     );
 
-    let zoom = view_state.zoom().level();
-
     if let Some(view_region) = &view_region {
         upload_tessellated_layer(
             buffer_pool,
+            line_atlas,
+            image_atlas,
             device,
             queue,
             &mut world.tiles,
@@ -55,11 +95,23 @@ pub fn upload_system(
         );
     }
 
+    // Dash patterns are added to the atlas while evaluating the layer metadata
+    if let Some(data) = line_atlas.take_changes() {
+        line_atlas_texture.upload(queue, data);
+    }
+    // Patterns are added to the atlas while evaluating the feature metadata
+    if let Some(data) = image_atlas.take_changes() {
+        image_atlas_texture.upload(queue, data);
+    }
+
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn upload_tessellated_layer(
     buffer_pool: &mut VectorBufferPool,
+    line_atlas: &mut LineAtlas,
+    image_atlas: &mut ImageAtlas,
     _device: &wgpu::Device,
     queue: &wgpu::Queue,
     tiles: &mut Tiles,
@@ -88,6 +140,14 @@ fn upload_tessellated_layer(
             .collect::<Vec<_>>();
 
         for style_layer in &style.layers {
+            // Circles, extrusions and heatmaps are uploaded by their own plugins
+            if let Some(
+                LayerPaint::Circle(_) | LayerPaint::FillExtrusion(_) | LayerPaint::Heatmap(_),
+            ) = style_layer.paint
+            {
+                continue;
+            }
+
             let layer_id = &style_layer.id;
             // GeoJSON sources have no source_layer; fall back to the layer id as a
             // virtual source-layer name (matches the name set in process_geojson_features).
@@ -99,66 +159,305 @@ fn upload_tessellated_layer(
                 }
             };
 
-            let Some(AvailableVectorLayerBucket {
-                coords,
-                feature_indices,
-                feature_colors,
-                buffer,
-                ..
-            }) = available_layers
+            let Some(bucket) = available_layers
                 .iter()
                 .find(|layer| style_layer.id.as_str() == layer.style_layer_id.as_str())
             else {
                 continue;
             };
 
-            let color: Option<Vec4f32> = style_layer
-                .paint
-                .as_ref()
-                .and_then(|paint| paint.get_color())
-                .map(|color| color.into());
-
-            // Assign every feature in the layer the color from the style if no parsed feature_color exist.
-            let fallback_color = color.unwrap_or([0.0, 0.0, 0.0, 1.0]);
-
-            let mut feature_metadata =
-                Vec::with_capacity(feature_indices.iter().sum::<u32>() as usize);
-            for (idx, &count) in feature_indices.iter().enumerate() {
-                let current_color = feature_colors.get(idx).copied().unwrap_or(fallback_color);
-                for _ in 0..count {
-                    feature_metadata.push(FillShaderFeatureMetadata {
-                        color: current_color,
-                    });
-                }
-            }
-
             // FIXME avoid uploading empty indices
-            if buffer.buffer.indices.is_empty() {
+            if bucket.buffer.buffer.indices.is_empty() {
                 continue;
             }
 
-            // Extract line-width from style paint (default 1.0px)
-            let line_width = match &style_layer.paint {
-                Some(LayerPaint::Line(paint)) => paint
-                    .line_width
-                    .as_ref()
-                    .map(|w| w.evaluate_at_zoom(zoom))
-                    .unwrap_or(1.0),
-                _ => 1.0,
-            };
+            let feature_metadata = feature_metadata(style_layer, bucket, zoom, image_atlas);
 
-            log::debug!("Allocating geometry at {coords}");
+            log::debug!("Allocating geometry at {}", bucket.coords);
             buffer_pool.allocate_layer_geometry(
                 queue,
-                *coords,
+                bucket.coords,
                 style_layer.clone(),
-                buffer,
-                ShaderLayerMetadata {
-                    z_index: style_layer.index as f32,
-                    line_width,
-                },
+                &bucket.buffer,
+                layer_metadata(style_layer, zoom, line_atlas),
                 &feature_metadata,
             );
         }
     }
 }
+
+/// The zoom level at which the metadata of the uploaded layers was evaluated.
+#[derive(Default)]
+struct EvaluatedZoom(Option<f32>);
+
+/// Re-evaluates the zoom-dependent line widths, dash patterns, colors and image patterns of all
+/// uploaded layers.
+fn update_zoom_dependent_metadata(
+    buffer_pool: &VectorBufferPool,
+    line_atlas: &mut LineAtlas,
+    image_atlas: &mut ImageAtlas,
+    queue: &wgpu::Queue,
+    tiles: &Tiles,
+    style: &Style,
+    zoom: f32,
+) {
+    for entry in buffer_pool.index().iter().flatten() {
+        let Some(style_layer) = style
+            .layers
+            .iter()
+            .find(|layer| layer.id == entry.style_layer.id)
+        else {
+            continue;
+        };
+        let Some(paint) = &style_layer.paint else {
+            continue;
+        };
+
+        let layer_zoom_constant = match paint {
+            LayerPaint::Line(line_paint) => line_paint.is_layer_zoom_constant(),
+            LayerPaint::Fill(fill_paint) => fill_paint.is_layer_zoom_constant(),
+            _ => true,
+        };
+        if !layer_zoom_constant {
+            buffer_pool.update_layer_metadata(
+                queue,
+                entry,
+                layer_metadata(style_layer, zoom, line_atlas),
+            );
+        }
+
+        let feature_zoom_constant = paint
+            .color_property()
+            .is_none_or(|color| color.is_zoom_constant())
+            && match paint {
+                LayerPaint::Fill(fill_paint) => fill_paint.is_feature_zoom_constant(),
+                LayerPaint::Line(line_paint) => line_paint.is_feature_zoom_constant(),
+                _ => true,
+            };
+        if feature_zoom_constant {
+            continue;
+        }
+        let Some(bucket) = tiles
+            .query::<&VectorLayerBucketComponent>(entry.coords)
+            .and_then(|component| {
+                component.layers.iter().find_map(|layer| match layer {
+                    VectorLayerBucket::AvailableLayer(bucket)
+                        if bucket.style_layer_id == style_layer.id =>
+                    {
+                        Some(bucket)
+                    }
+                    _ => None,
+                })
+            })
+        else {
+            continue;
+        };
+        buffer_pool.update_feature_metadata(
+            queue,
+            entry,
+            &feature_metadata(style_layer, bucket, zoom, image_atlas),
+        );
+    }
+}
+
+/// Evaluates the layer properties at `zoom`. Dash patterns are added to the `line_atlas`.
+fn layer_metadata(
+    style_layer: &StyleLayer,
+    zoom: f32,
+    line_atlas: &mut LineAtlas,
+) -> ShaderLayerMetadata {
+    let mut metadata = ShaderLayerMetadata {
+        z_index: style_layer.index as f32,
+        line_width: 1.0,
+        opacity: 1.0,
+        ..Default::default()
+    };
+
+    if let Some(LayerPaint::Fill(paint)) = &style_layer.paint {
+        (metadata.translate, metadata.translate_viewport) =
+            translate(&paint.fill_translate, paint.fill_translate_anchor, zoom);
+    }
+
+    if let Some(LayerPaint::Line(paint)) = &style_layer.paint {
+        let evaluate = |property: &Option<StyleProperty<f32>>, default: f32| {
+            property
+                .as_ref()
+                .and_then(|property| property.evaluate_at_zoom(zoom))
+                .unwrap_or(default)
+        };
+
+        // Extract line-width from style paint (default 1.0px)
+        metadata.line_width = evaluate(&paint.line_width, 1.0);
+        metadata.opacity = evaluate(&paint.line_opacity, 1.0);
+        metadata.line_blur = evaluate(&paint.line_blur, 0.0);
+        metadata.line_gap_width = evaluate(&paint.line_gap_width, 0.0);
+        metadata.line_offset = evaluate(&paint.line_offset, 0.0);
+        (metadata.translate, metadata.translate_viewport) =
+            translate(&paint.line_translate, paint.line_translate_anchor, zoom);
+
+        if let Some(dash) = paint
+            .line_dasharray
+            .as_ref()
+            .and_then(|dasharray| dasharray.evaluate_at_zoom(zoom))
+            .and_then(|dasharray| line_atlas.get_dash(&dasharray))
+        {
+            metadata.dash_y = dash.y;
+            metadata.dash_length = dash.length;
+        }
+
+        if let Some(gradient_y) = paint
+            .line_gradient
+            .as_ref()
+            .and_then(|gradient| line_atlas.get_gradient(&style_layer.id, gradient))
+        {
+            metadata.gradient_y = gradient_y;
+        }
+    }
+
+    metadata
+}
+
+/// Evaluates a translate property and returns the translation together with the flag whether it
+/// is relative to the viewport.
+fn translate(
+    translate: &Option<StyleProperty<[f32; 2]>>,
+    anchor: Option<Reference>,
+    zoom: f32,
+) -> (Vec2f32, f32) {
+    let translation = translate
+        .as_ref()
+        .and_then(|translate| translate.evaluate_at_zoom(zoom))
+        .unwrap_or([0.0, 0.0]);
+    let viewport = if anchor == Some(Reference::Viewport) {
+        1.0
+    } else {
+        0.0
+    };
+    (translation, viewport)
+}
+
+/// Builds the metadata for every index of the features. Colors which depend on the zoom level
+/// are evaluated at `zoom`, otherwise the colors evaluated during tessellation are used. The
+/// vertices of the outlines of fills get the outline color. Patterns are added to the
+/// `image_atlas`.
+fn feature_metadata(
+    style_layer: &StyleLayer,
+    bucket: &AvailableVectorLayerBucket,
+    zoom: f32,
+    image_atlas: &mut ImageAtlas,
+) -> Vec<FillShaderFeatureMetadata> {
+    let to_vec4 = |c: Color| [c.r as f32, c.g as f32, c.b as f32, c.a as f32];
+
+    let color: Option<Vec4f32> = style_layer
+        .paint
+        .as_ref()
+        .and_then(|paint| paint.get_color())
+        .map(|color| color.into());
+
+    // Assign every feature in the layer the color from the style if no parsed feature_color exist.
+    let fallback_color = color.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+
+    let zoom_dependent_color = style_layer
+        .paint
+        .as_ref()
+        .and_then(LayerPaint::color_property)
+        .filter(|color| !color.is_zoom_constant());
+    let layer_color = zoom_dependent_color
+        .filter(|color| color.is_feature_constant())
+        .and_then(|color| color.evaluate_at_zoom(zoom))
+        .map(to_vec4);
+
+    let fill_paint = match &style_layer.paint {
+        Some(LayerPaint::Fill(fill_paint)) => Some(fill_paint),
+        _ => None,
+    };
+    let pattern_property = match &style_layer.paint {
+        Some(LayerPaint::Fill(fill_paint)) => fill_paint.fill_pattern.as_ref(),
+        Some(LayerPaint::Line(line_paint)) => line_paint.line_pattern.as_ref(),
+        _ => None,
+    };
+    let mut vertices = bucket.buffer.buffer.vertices.iter();
+
+    let feature_indices = &bucket.feature_indices;
+    let mut feature_metadata = Vec::with_capacity(feature_indices.iter().sum::<u32>() as usize);
+    for (idx, &count) in feature_indices.iter().enumerate() {
+        let current_color = match (layer_color, zoom_dependent_color) {
+            (Some(layer_color), _) => Some(layer_color),
+            (None, Some(color)) => bucket.feature_properties.get(idx).and_then(|properties| {
+                color
+                    .evaluate(
+                        &EvaluationContext::default()
+                            .with_zoom(zoom as f64)
+                            .with_properties(properties),
+                    )
+                    .map(to_vec4)
+            }),
+            (None, None) => None,
+        }
+        .or_else(|| bucket.feature_colors.get(idx).copied())
+        .unwrap_or(fallback_color);
+
+        let context = EvaluationContext::default().with_zoom(zoom as f64);
+        let context = match bucket.feature_properties.get(idx) {
+            Some(properties) => context.with_properties(properties),
+            None => context,
+        };
+
+        let mut opacity = 1.0;
+        let mut outline_color = current_color;
+        if let Some(fill_paint) = fill_paint {
+            opacity = fill_paint
+                .fill_opacity
+                .as_ref()
+                .and_then(|opacity| opacity.evaluate(&context))
+                .unwrap_or(1.0);
+            if let Some(color) = fill_paint
+                .fill_outline_color
+                .as_ref()
+                .and_then(|color| color.evaluate(&context))
+            {
+                outline_color = to_vec4(color);
+            }
+        }
+
+        let pattern = pattern_property
+            .and_then(|pattern| pattern.evaluate(&context))
+            .and_then(|id| image_atlas.get_pattern(&id));
+        let pattern_placement = pattern
+            .map(|pattern| pattern_placement(&pattern, bucket))
+            .unwrap_or_default();
+        let pattern = pattern.map(|pattern| pattern.rect()).unwrap_or_default();
+
+        for _ in 0..count {
+            // The outline of fills is tessellated as a stroke, whose vertices have a normal
+            let is_outline = fill_paint.is_some()
+                && vertices
+                    .next()
+                    .is_some_and(|vertex| vertex.normal != [0.0, 0.0]);
+            feature_metadata.push(FillShaderFeatureMetadata {
+                color: if is_outline {
+                    outline_color
+                } else {
+                    current_color
+                },
+                opacity,
+                pattern,
+                pattern_placement,
+            });
+        }
+    }
+    feature_metadata
+}
+
+/// Returns the display size of the `pattern` and the offset of the origin of the tile of the
+/// `bucket` within the pattern, such that patterns continue seamlessly across tiles.
+fn pattern_placement(pattern: &ImagePosition, bucket: &AvailableVectorLayerBucket) -> Vec4f32 {
+    let [width, height] = pattern.display_size;
+    let offset = |tile: i32, size: f32| (tile as f64 * TILE_SIZE).rem_euclid(size as f64) as f32;
+    [
+        width,
+        height,
+        offset(bucket.coords.x, width),
+        offset(bucket.coords.y, height),
+    ]
+}
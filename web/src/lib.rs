@@ -133,7 +133,14 @@ pub async fn run_maplibre(new_worker: js_sys::Function) -> Result<(), JSError> {
             Box::<maplibre::background::BackgroundPlugin>::default(),
             Box::<maplibre::vector::VectorPlugin<platform::UsedVectorTransferables>>::default(),
             Box::new(maplibre::sdf::SdfPlugin::<platform::UsedVectorTransferables>::default()),
-            // Box::new(RasterPlugin::<platform::UsedRasterTransferables>::default()),
+            Box::<maplibre::circle::CirclePlugin>::default(),
+            Box::<maplibre::fill_extrusion::FillExtrusionPlugin>::default(),
+            Box::<maplibre::heatmap::HeatmapPlugin>::default(),
+            // Fetches the elevations of the hillshade, color-relief and terrain plugins
+            Box::<maplibre::raster::RasterPlugin<platform::UsedRasterTransferables>>::default(),
+            Box::<maplibre::hillshade::HillshadePlugin>::default(),
+            Box::<maplibre::color_relief::ColorReliefPlugin>::default(),
+            Box::<maplibre::terrain::TerrainPlugin>::default(),
             #[cfg(debug_assertions)]
             Box::<maplibre::debug::DebugPlugin>::default(),
         ],
@@ -1,6 +1,6 @@
 use std::fmt::{Debug, Formatter};
 
-use flatbuffers::FlatBufferBuilder;
+use flatbuffers::{FlatBufferBuilder, WIPOffset};
 use image::RgbaImage;
 use js_sys::{ArrayBuffer, Uint8Array};
 use maplibre::{
@@ -19,6 +19,7 @@ use maplibre::{
         ShaderVertex,
     },
     sdf::{Feature, SymbolLayerData},
+    style::expression::{FeatureProperties, Value},
     tile::Layer,
     vector::{
         AvailableVectorLayerBucket, LayerIndexed, LayerMissing, LayerTessellated,
@@ -206,6 +207,7 @@ impl LayerTessellated for FlatBufferTransferable {
         buffer: OverAlignedVertexBuffer<ShaderVertex, IndexDataType>,
         feature_indices: Vec<u32>,
         feature_colors: Vec<[f32; 4]>,
+        feature_properties: Vec<FeatureProperties>,
         layer_data: Layer,
         style_layer_id: String,
     ) -> Self {
@@ -216,7 +218,15 @@ impl LayerTessellated for FlatBufferTransferable {
                 .buffer
                 .vertices
                 .iter()
-                .map(|vertex| FlatShaderVertex::new(&vertex.position, &vertex.normal))
+                .map(|vertex| {
+                    FlatShaderVertex::new(
+                        &vertex.position,
+                        &vertex.normal,
+                        vertex.line_distance,
+                        vertex.line_side,
+                        vertex.line_progress,
+                    )
+                })
                 .collect::<Vec<_>>(),
         );
         let indices = inner_builder.create_vector(&buffer.buffer.indices);
@@ -229,6 +239,11 @@ impl LayerTessellated for FlatBufferTransferable {
             .flat_map(|c| c.iter().copied())
             .collect();
         let feature_colors_fb = inner_builder.create_vector(&flat_colors);
+        let feature_properties = feature_properties
+            .iter()
+            .map(|properties| create_feature_properties(&mut inner_builder, properties))
+            .collect::<Vec<_>>();
+        let feature_properties = inner_builder.create_vector(&feature_properties);
 
         let mut builder = FlatLayerTessellatedBuilder::new(&mut inner_builder);
 
@@ -244,6 +259,7 @@ impl LayerTessellated for FlatBufferTransferable {
         builder.add_usable_indices(buffer.usable_indices);
         builder.add_style_layer_id(style_layer_id_fb);
         builder.add_feature_colors(feature_colors_fb);
+        builder.add_feature_properties(feature_properties);
         let root = builder.finish();
 
         inner_builder.finish(root, None);
@@ -271,7 +287,12 @@ impl LayerTessellated for FlatBufferTransferable {
             .vertices()
             .unwrap()
             .iter()
-            .map(|vertex| ShaderVertex::new(vertex.position().into(), vertex.normal().into()));
+            .map(|vertex| {
+                ShaderVertex::new(vertex.position().into(), vertex.normal().into())
+                    .with_line_distance(vertex.line_distance())
+                    .with_line_side(vertex.line_side())
+                    .with_line_progress(vertex.line_progress())
+            });
 
         let indices = data.indices().unwrap();
         let feature_indices: Vec<u32> = data.feature_indices().unwrap().iter().collect();
@@ -292,6 +313,15 @@ impl LayerTessellated for FlatBufferTransferable {
                     .collect()
             })
             .unwrap_or_default();
+        let feature_properties = data
+            .feature_properties()
+            .map(|feature_properties| {
+                feature_properties
+                    .iter()
+                    .map(read_feature_properties)
+                    .collect()
+            })
+            .unwrap_or_default();
         AvailableVectorLayerBucket {
             coords: LayerTessellated::coords(&self),
             source_layer: layer_name,
@@ -299,10 +329,77 @@ impl LayerTessellated for FlatBufferTransferable {
             buffer: OverAlignedVertexBuffer::from_iters(vertices, indices, usable_indices),
             feature_indices,
             feature_colors,
+            feature_properties,
         }
     }
 }
 
+fn create_feature_properties<'a>(
+    builder: &mut FlatBufferBuilder<'a>,
+    properties: &FeatureProperties,
+) -> WIPOffset<FlatFeatureProperties<'a>> {
+    let properties = properties
+        .iter()
+        .map(|(key, value)| {
+            let key = builder.create_string(key);
+            let string = match value {
+                Value::String(string) => Some(builder.create_string(string)),
+                _ => None,
+            };
+            let json = match value {
+                Value::Color(_) | Value::Array(_) | Value::Object(_) => {
+                    Some(builder.create_string(&serde_json::Value::from(value).to_string()))
+                }
+                _ => None,
+            };
+
+            let mut property = FlatFeaturePropertyBuilder::new(builder);
+            property.add_key(key);
+            match value {
+                Value::Number(number) => property.add_number(*number),
+                Value::Boolean(boolean) => property.add_boolean(*boolean),
+                _ => {}
+            }
+            if let Some(string) = string {
+                property.add_string(string);
+            }
+            if let Some(json) = json {
+                property.add_json(json);
+            }
+            property.finish()
+        })
+        .collect::<Vec<_>>();
+    let properties = builder.create_vector(&properties);
+
+    let mut builder = FlatFeaturePropertiesBuilder::new(builder);
+    builder.add_properties(properties);
+    builder.finish()
+}
+
+fn read_feature_properties(properties: FlatFeatureProperties) -> FeatureProperties {
+    properties
+        .properties()
+        .into_iter()
+        .flatten()
+        .filter_map(|property| {
+            let value = if let Some(number) = property.number() {
+                Value::Number(number)
+            } else if let Some(boolean) = property.boolean() {
+                Value::Boolean(boolean)
+            } else if let Some(string) = property.string() {
+                Value::String(string.to_owned())
+            } else if let Some(json) = property.json() {
+                serde_json::from_str(json)
+                    .map(|json| Value::from_json(&json))
+                    .unwrap_or(Value::Null)
+            } else {
+                Value::Null
+            };
+            Some((property.key()?.to_owned(), value))
+        })
+        .collect()
+}
+
 impl LayerIndexed for FlatBufferTransferable {
     fn message_tag() -> &'static dyn MessageTag {
         &WebMessageTag::LayerIndexed
@@ -385,7 +482,7 @@ impl LayerRaster for FlatBufferTransferable {
         let image_data = data.image_data().unwrap().iter().collect();
         AvailableRasterLayerData {
             coords: LayerRaster::coords(&self),
-            source_layer: "raster".to_owned(),
+            style_layer_id: data.layer_name().expect("property must be set").to_owned(),
             image: RgbaImage::from_vec(data.width(), data.height(), image_data).unwrap(),
         }
     }
@@ -396,15 +493,17 @@ impl LayerRasterMissing for FlatBufferTransferable {
         &WebMessageTag::LayerRasterMissing
     }
 
-    fn build_from(coords: WorldTileCoords) -> Self {
+    fn build_from(coords: WorldTileCoords, layer_name: String) -> Self {
         let mut inner_builder = FlatBufferBuilder::with_capacity(1024);
-        let mut builder = FlatLayerIndexedBuilder::new(&mut inner_builder);
+        let layer_name = inner_builder.create_string(&layer_name);
 
+        let mut builder = FlatLayerMissingBuilder::new(&mut inner_builder);
         builder.add_coords(&FlatWorldTileCoords::new(
             coords.x,
             coords.y,
             coords.z.into(),
         ));
+        builder.add_layer_name(layer_name);
         let root = builder.finish();
         inner_builder.finish(root, None);
         let (data, start) = inner_builder.collapse();
@@ -421,10 +520,10 @@ impl LayerRasterMissing for FlatBufferTransferable {
     }
 
     fn to_layer(self) -> MissingRasterLayerData {
-        let _data = root_as_flat_layer_raster(&self.data[self.start..]).unwrap();
+        let data = root_as_flat_layer_missing(&self.data[self.start..]).unwrap();
         MissingRasterLayerData {
-            coords: LayerRaster::coords(&self),
-            source_layer: "raster".to_string(),
+            coords: LayerRasterMissing::coords(&self),
+            style_layer_id: data.layer_name().expect("property must be set").to_owned(),
         }
     }
 }